@@ -23,9 +23,16 @@ pub enum Tail {
         tail1: Box<Self>,
         tail2: Box<Self>,
     },
+    /// Runs the tail, which may jump back to its start by the label. The
+    /// label is local to the procedure.
+    r#loop {
+        label: utils::Label,
+        tail: Box<Self>,
+    },
+    /// Jumps to the trg, with the locs holding the values which it reads.
     jump {
-        trg: u8,
-        locs: Vec<u8>,
+        trg: Triv,
+        locs: Vec<Rloc>,
     },
 }
 
@@ -36,6 +43,7 @@ pub enum Pred {
         opand1: Opand,
         opand2: Opand,
     },
+    opand(Opand),
     r#true,
     r#false,
     not(Box<Self>),
@@ -56,12 +64,38 @@ pub enum Effect {
         aloc: utils::Aloc,
         value: Value,
     },
+    /// Reads an argument which the caller passed in the rloc.
+    set_aloc_rloc {
+        aloc: utils::Aloc,
+        rloc: Rloc,
+    },
+    /// Passes an argument to a procedure in the rloc.
+    set_rloc_opand {
+        rloc: Rloc,
+        opand: Opand,
+    },
+    mset {
+        opand1: Opand,
+        opand2: Opand,
+        opand3: Opand,
+    },
     begin(Vec<Effect>),
     r#if {
         pred: Pred,
         effect1: Box<Self>,
         effect2: Box<Self>,
     },
+    r#while {
+        pred: Pred,
+        effect: Box<Self>,
+    },
+    /// Executes the effect which the table maps the opand to, or the last
+    /// effect if the opand is outside the table.
+    switch {
+        opand: Opand,
+        table: Vec<usize>,
+        effects: Vec<Self>,
+    },
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
@@ -72,10 +106,19 @@ pub enum Value {
         opand1: Opand,
         opand2: Opand,
     },
+    alloc {
+        opand: Opand,
+    },
+    mref {
+        opand1: Opand,
+        opand2: Opand,
+    },
 }
 
 pub type Opand = super::target::Triv;
 
+pub type Rloc = super::target::Rloc;
+
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub enum Triv {
     opand(Opand),
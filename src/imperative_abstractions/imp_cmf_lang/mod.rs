@@ -2,7 +2,10 @@ pub mod data;
 #[cfg(test)]
 mod tests;
 
+use std::collections::HashMap;
+
 pub use self::data::*;
+use crate::imperative_abstractions::ssa_lang;
 use crate::register_allocation::asm_pred_lang as target;
 use crate::utils;
 
@@ -10,210 +13,653 @@ use crate::utils;
 pub struct ImpCmfLang(pub self::P);
 
 impl ImpCmfLang {
+    /// ConstructSsa: ImpCmfLang -> SsaLang
+    ///
     /// ### Purpose:
-    /// Compiles Imp-cmf-lang v3 to Asm-lang v2, selecting appropriate sequences
-    /// of abstract assembly instructions to implement the operations of the
-    /// source language.
-    pub fn select_instructions(self) -> target::AsmPredLang {
-        // let Self(p) = self;
-
-        // fn select_p(p: self::P) -> target::P {
-        //     match p {
-        //         self::P::module(tail) => {
-        //             let (mut effects, tail) = select_tail(tail);
-        //             let tail =
-        //                 make_begins!((effects, tail) => target::Tail::tail);
-        //             target::P::module {
-        //                 info: utils::Info::default(),
-        //                 tail,
-        //             }
-        //         },
-        //     }
-        // }
-
-        // fn select_tail(
-        //     tail: self::Tail,
-        // ) -> (Vec<target::Effect>, target::Tail) {
-        //     match tail {
-        //         self::Tail::value(value) => match value {
-        //             self::Value::triv(triv) => {
-        //                 (Vec::with_capacity(0), target::Tail::halt(triv))
-        //             },
-        //             self::Value::binop {
-        //                 binop,
-        //                 triv1,
-        //                 triv2,
-        //             } => {
-        //                 let aloc = utils::Aloc::fresh();
-        //                 let effect1 = target::Effect::set_aloc_triv {
-        //                     aloc: aloc.clone(),
-        //                     triv: triv1,
-        //                 };
-        //                 let effect2 =
-        //                     target::Effect::set_aloc_binop_aloc_triv {
-        //                         aloc: aloc.clone(),
-        //                         binop,
-        //                         triv: triv2,
-        //                     };
-        //                 let effects = vec![effect1, effect2];
-        //                 let triv = target::Triv::aloc(aloc);
-        //                 let tail = target::Tail::halt(triv);
-        //                 (effects, tail)
-        //             },
-        //         },
-        //         self::Tail::begin { effects, tail } => {
-        //             let mut effects = select_effects(effects);
-        //             let (tail_effects, tail) = select_tail(*tail);
-        //             effects.extend(tail_effects);
-        //             (effects, tail)
-        //         },
-        //         self::Tail::r#if { pred, tail1, tail2 } => {
-        //             let (effects, pred) = select_pred(pred);
-        //             let (mut effects1, tail1) = select_tail(*tail1);
-        //             let (mut effects2, tail2) = select_tail(*tail2);
-        //             let tail1 =
-        //                 make_begins!((effects1, tail1) =>
-        // target::Tail::tail);             let tail2 =
-        //                 make_begins!((effects2, tail2) =>
-        // target::Tail::tail);             let tail1 = Box::new(tail1);
-        //             let tail2 = Box::new(tail2);
-        //             let tail = target::Tail::r#if { pred, tail1, tail2 };
-        //             (effects, tail)
-        //         },
-        //     }
-        // }
-
-        // fn select_pred(
-        //     pred: self::Pred,
-        // ) -> (Vec<target::Effect>, target::Pred) {
-        //     match pred {
-        //         self::Pred::relop {
-        //             relop,
-        //             triv1,
-        //             triv2,
-        //         } => {
-        //             let aloc = utils::Aloc::fresh();
-        //             let effect = target::Effect::set_aloc_triv {
-        //                 aloc: aloc.clone(),
-        //                 triv: triv1,
-        //             };
-        //             let effects = vec![effect];
-        //             let pred = target::Pred::relop {
-        //                 relop,
-        //                 aloc,
-        //                 triv: triv2,
-        //             };
-        //             (effects, pred)
-        //         },
-        //         self::Pred::r#true => {
-        //             (Vec::with_capacity(0), target::Pred::r#true)
-        //         },
-        //         self::Pred::r#false => {
-        //             (Vec::with_capacity(0), target::Pred::r#false)
-        //         },
-        //         self::Pred::not(pred) => {
-        //             let (effects, pred) = select_pred(*pred);
-        //             let pred = Box::new(pred);
-        //             (effects, target::Pred::not(pred))
-        //         },
-        //         self::Pred::begin { effects, pred } => {
-        //             let mut effects = select_effects(effects);
-        //             let (pred_effects, pred) = select_pred(*pred);
-        //             effects.extend(pred_effects);
-        //             (effects, pred)
-        //         },
-        //         self::Pred::r#if {
-        //             pred1,
-        //             pred2,
-        //             pred3,
-        //         } => {
-        //             let (effects, pred1) = select_pred(*pred1);
-        //             let (mut effects2, pred2) = select_pred(*pred2);
-        //             let (mut effects3, pred3) = select_pred(*pred3);
-        //             let pred2 =
-        //                 make_begins!((effects2, pred2) =>
-        // target::Pred::pred);             let pred3 =
-        //                 make_begins!((effects3, pred3) =>
-        // target::Pred::pred);             let pred1 = Box::new(pred1);
-        //             let pred2 = Box::new(pred2);
-        //             let pred3 = Box::new(pred3);
-        //             (effects, target::Pred::r#if {
-        //                 pred1,
-        //                 pred2,
-        //                 pred3,
-        //             })
-        //         },
-        //     }
-        // }
-
-        // fn select_effects(effects: Vec<self::Effect>) -> Vec<target::Effect>
-        // {     effects
-        //         .into_iter()
-        //         .map(select_effect)
-        //         .flatten()
-        //         .collect::<Vec<_>>()
-        // }
-
-        // fn select_effect(effect: self::Effect) -> Vec<target::Effect> {
-        //     match effect {
-        //         self::Effect::set_aloc_value { aloc, value } => {
-        //             select_value(value, aloc)
-        //         },
-        //         self::Effect::begin(effects) => select_effects(effects),
-        //         self::Effect::r#if {
-        //             pred,
-        //             effect1,
-        //             effect2,
-        //         } => {
-        //             let (mut effects, pred) = select_pred(pred);
-        //             let effects1 = select_effect(*effect1);
-        //             let effects2 = select_effect(*effect2);
-        //             let effect1 = target::Effect::begin(effects1);
-        //             let effect2 = target::Effect::begin(effects2);
-        //             let effect1 = Box::new(effect1);
-        //             let effect2 = Box::new(effect2);
-        //             let effect = target::Effect::r#if {
-        //                 pred,
-        //                 effect1,
-        //                 effect2,
-        //             };
-        //             effects.push(effect);
-        //             effects
-        //         },
-        //     }
-        // }
-
-        // fn select_value(
-        //     value: self::Value,
-        //     aloc: utils::Aloc,
-        // ) -> Vec<target::Effect> {
-        //     match value {
-        //         self::Value::triv(triv) => {
-        //             let effect = target::Effect::set_aloc_triv { aloc, triv
-        // };             vec![effect]
-        //         },
-        //         self::Value::binop {
-        //             binop,
-        //             triv1,
-        //             triv2,
-        //         } => {
-        //             let effect1 = target::Effect::set_aloc_triv {
-        //                 aloc: aloc.clone(),
-        //                 triv: triv1,
-        //             };
-        //             let effect2 = target::Effect::set_aloc_binop_aloc_triv {
-        //                 aloc,
-        //                 binop,
-        //                 triv: triv2,
-        //             };
-        //             vec![effect1, effect2]
-        //         },
-        //     }
-        // }
-
-        // let p = select_p(p);
-        // target::AsmPredLang(p)
-
-        todo!()
+    /// Compiles Imp-cmf-lang v3 to Ssa-lang, splitting the program into basic
+    /// blocks and giving each assignment a fresh aloc, with phis wherever
+    /// control flow joins.
+    ///
+    /// ### Notes:
+    /// Assignments of trivs are propagated instead of renamed. A pred which is
+    /// neither a comparison nor a constant is computed into an aloc holding
+    /// true or false, on which the program then branches.
+    ///
+    /// Each procedure is constructed on its own. A loop is given a header with
+    /// phis for the alocs assigned in it, like a while, and each jump back to
+    /// it becomes a goto to the header.
+    pub fn construct_ssa(self) -> ssa_lang::SsaLang {
+        type Env = HashMap<utils::Aloc, ssa_lang::Opand>;
+
+        // The blocks built so far, and the block being built, with the opand
+        // which each aloc of the source holds at its end. Each loop being
+        // built maps to the alocs of its phis, and to the label and env of
+        // each block which jumps back to it.
+        struct Builder {
+            blocks: Vec<ssa_lang::Block>,
+            label: utils::Label,
+            phis: Vec<ssa_lang::Phi>,
+            stmts: Vec<ssa_lang::Stmt>,
+            env: Env,
+            loops: HashMap<utils::Label, (Vec<utils::Aloc>, Vec<Latch>)>,
+        }
+
+        type Latch = (utils::Label, Env);
+
+        impl Builder {
+            fn open(&mut self, label: utils::Label) {
+                self.label = label;
+            }
+
+            // Ends the current block with the jump, returning its label.
+            fn close(&mut self, jump: ssa_lang::Jump) -> utils::Label {
+                let label = self.label.clone();
+                let phis = std::mem::take(&mut self.phis);
+                let stmts = std::mem::take(&mut self.stmts);
+                self.blocks.push(ssa_lang::Block {
+                    label: label.clone(),
+                    phis,
+                    stmts,
+                    jump,
+                });
+                label
+            }
+        }
+
+        fn build_p(p: self::P) -> ssa_lang::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, tail }| {
+                            let blocks = build_blocks(tail);
+                            ssa_lang::Lambda { label, blocks }
+                        })
+                        .collect();
+                    let blocks = build_blocks(tail);
+                    ssa_lang::P::module { lambdas, blocks }
+                },
+            }
+        }
+
+        fn build_blocks(tail: self::Tail) -> Vec<ssa_lang::Block> {
+            let mut builder = Builder {
+                blocks: vec![],
+                label: utils::Label::new_with_name("entry"),
+                phis: vec![],
+                stmts: vec![],
+                env: Env::default(),
+                loops: HashMap::default(),
+            };
+            build_tail(tail, &mut builder);
+            builder.blocks
+        }
+
+        fn build_tail(tail: self::Tail, builder: &mut Builder) {
+            match tail {
+                self::Tail::value(self::Value::triv(triv)) => {
+                    let opand = build_triv(triv, builder);
+                    builder.close(ssa_lang::Jump::halt(opand));
+                },
+                self::Tail::value(value) => {
+                    let aloc = utils::Aloc::fresh();
+                    let value = build_value(value, builder);
+                    let stmt = ssa_lang::Stmt::set_aloc_value {
+                        aloc: aloc.clone(),
+                        value,
+                    };
+                    builder.stmts.push(stmt);
+                    let opand = ssa_lang::Opand::aloc(aloc);
+                    builder.close(ssa_lang::Jump::halt(opand));
+                },
+                self::Tail::begin { effects, tail } => {
+                    build_effects(effects, builder);
+                    build_tail(*tail, builder)
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let cond = build_pred(pred, builder);
+                    let label1 = utils::Label::new_with_name("then");
+                    let label2 = utils::Label::new_with_name("else");
+                    builder.close(ssa_lang::Jump::r#if {
+                        cond,
+                        label1: label1.clone(),
+                        label2: label2.clone(),
+                        join: None,
+                    });
+                    let env = builder.env.clone();
+                    builder.open(label1);
+                    build_tail(*tail1, builder);
+                    builder.env = env;
+                    builder.open(label2);
+                    build_tail(*tail2, builder)
+                },
+                // The phis of the header are only completed once every jump
+                // back to it is built.
+                self::Tail::r#loop { label, tail } => {
+                    let mut assigned = utils::AlocSet::default();
+                    assigned_tail(&tail, &mut assigned);
+                    let alocs = loop_alocs(assigned, builder);
+                    let jump = ssa_lang::Jump::r#loop {
+                        header: label.clone(),
+                    };
+                    let phis = open_loop(label.clone(), &alocs, jump, builder);
+                    builder.phis = phis;
+                    builder.loops.insert(label.clone(), (alocs, vec![]));
+                    build_tail(*tail, builder);
+                    let (alocs, latches) =
+                        builder.loops.remove(&label).unwrap();
+                    close_loop(&label, &alocs, latches, builder);
+                },
+                self::Tail::jump {
+                    trg: self::Triv::label(label),
+                    ..
+                } if builder.loops.contains_key(&label) => {
+                    let jump = ssa_lang::Jump::goto(label.clone());
+                    let from = builder.close(jump);
+                    let env = builder.env.clone();
+                    let (_, latches) = builder.loops.get_mut(&label).unwrap();
+                    latches.push((from, env));
+                },
+                self::Tail::jump { trg, locs } => {
+                    let trg = match trg {
+                        self::Triv::label(label) => ssa_lang::Trg::label(label),
+                        self::Triv::opand(opand) => {
+                            ssa_lang::Trg::opand(build_opand(opand, builder))
+                        },
+                    };
+                    builder.close(ssa_lang::Jump::jump { trg, locs });
+                },
+            }
+        }
+
+        // The alocs assigned in a loop which already hold an opand on entry
+        // to it, each of which is given a phi.
+        fn loop_alocs(
+            assigned: utils::AlocSet,
+            builder: &Builder,
+        ) -> Vec<utils::Aloc> {
+            let mut alocs = assigned
+                .into_iter()
+                .filter(|aloc| builder.env.contains_key(aloc))
+                .collect::<Vec<_>>();
+            alocs.sort();
+            alocs
+        }
+
+        // Ends the current block with the jump into the loop, and opens its
+        // header, returning the phis of the alocs with their opands on entry.
+        fn open_loop(
+            header: utils::Label,
+            alocs: &[utils::Aloc],
+            jump: ssa_lang::Jump,
+            builder: &mut Builder,
+        ) -> Vec<ssa_lang::Phi> {
+            let entry = builder.env.clone();
+            let label = builder.close(jump);
+            builder.open(header);
+            alocs
+                .iter()
+                .map(|aloc| {
+                    let phi = utils::Aloc::fresh_with_name(&aloc.name);
+                    let opand = ssa_lang::Opand::aloc(phi.clone());
+                    builder.env.insert(aloc.clone(), opand);
+                    let opands = vec![(label.clone(), entry[aloc].clone())];
+                    ssa_lang::Phi { aloc: phi, opands }
+                })
+                .collect()
+        }
+
+        // Adds the opand which each block jumping back to the header holds
+        // for each aloc to its phi.
+        fn close_loop(
+            header: &utils::Label,
+            alocs: &[utils::Aloc],
+            latches: Vec<Latch>,
+            builder: &mut Builder,
+        ) {
+            let phis = builder
+                .blocks
+                .iter_mut()
+                .filter(|block| block.label == *header)
+                .flat_map(|block| block.phis.iter_mut());
+            phis.zip(alocs).for_each(|(phi, aloc)| {
+                latches.iter().for_each(|(label, latch)| {
+                    let opand = latch[aloc].clone();
+                    phi.opands.push((label.clone(), opand))
+                })
+            });
+        }
+
+        fn build_pred(
+            pred: self::Pred,
+            builder: &mut Builder,
+        ) -> ssa_lang::Cond {
+            match pred {
+                self::Pred::relop {
+                    relop,
+                    opand1,
+                    opand2,
+                } => ssa_lang::Cond::relop {
+                    relop,
+                    opand1: build_opand(opand1, builder),
+                    opand2: build_opand(opand2, builder),
+                },
+                self::Pred::opand(opand) => ssa_lang::Cond::relop {
+                    relop: utils::Relop::neq,
+                    opand1: build_opand(opand, builder),
+                    opand2: ssa_lang::Opand::int64(utils::FALSE),
+                },
+                self::Pred::r#true => ssa_lang::Cond::r#true,
+                self::Pred::r#false => ssa_lang::Cond::r#false,
+                self::Pred::not(pred) => match build_pred(*pred, builder) {
+                    ssa_lang::Cond::relop {
+                        relop,
+                        opand1,
+                        opand2,
+                    } => ssa_lang::Cond::relop {
+                        relop: !relop,
+                        opand1,
+                        opand2,
+                    },
+                    ssa_lang::Cond::r#true => ssa_lang::Cond::r#false,
+                    ssa_lang::Cond::r#false => ssa_lang::Cond::r#true,
+                },
+                self::Pred::begin { effects, pred } => {
+                    build_effects(effects, builder);
+                    build_pred(*pred, builder)
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let cond = build_pred(*pred1, builder);
+                    let opands = build_branches(
+                        cond,
+                        vec![*pred2, *pred3],
+                        builder,
+                        |pred, builder| {
+                            let cond = build_pred(pred, builder);
+                            build_boolean(cond, builder)
+                        },
+                    );
+                    ssa_lang::Cond::relop {
+                        relop: utils::Relop::neq,
+                        opand1: build_phi(None, opands, builder),
+                        opand2: ssa_lang::Opand::int64(utils::FALSE),
+                    }
+                },
+            }
+        }
+
+        // Computes the cond into an opand holding true or false.
+        fn build_boolean(
+            cond: ssa_lang::Cond,
+            builder: &mut Builder,
+        ) -> ssa_lang::Opand {
+            match cond {
+                ssa_lang::Cond::r#true => ssa_lang::Opand::int64(utils::TRUE),
+                ssa_lang::Cond::r#false => {
+                    ssa_lang::Opand::int64(utils::FALSE)
+                },
+                cond => {
+                    let booleans = vec![utils::TRUE, utils::FALSE];
+                    let opands =
+                        build_branches(cond, booleans, builder, |int64, _| {
+                            ssa_lang::Opand::int64(int64)
+                        });
+                    build_phi(None, opands, builder)
+                },
+            }
+        }
+
+        // Branches on the cond to a block for each item, which is built by
+        // the function, and joins them again. What the function returns for
+        // each item is returned with the label of the last block of its arm.
+        fn build_branches<T, R>(
+            cond: ssa_lang::Cond,
+            items: Vec<T>,
+            builder: &mut Builder,
+            build: fn(T, &mut Builder) -> R,
+        ) -> Vec<(utils::Label, R)> {
+            let labels = ["then", "else"].map(utils::Label::new_with_name);
+            let [label1, label2] = labels.clone();
+            let join = utils::Label::new_with_name("join");
+            builder.close(ssa_lang::Jump::r#if {
+                cond,
+                label1,
+                label2,
+                join: Some(join.clone()),
+            });
+            build_arms(labels.into_iter().zip(items), join, builder, build)
+        }
+
+        fn build_arms<T, R>(
+            arms: impl Iterator<Item = (utils::Label, T)>,
+            join: utils::Label,
+            builder: &mut Builder,
+            build: fn(T, &mut Builder) -> R,
+        ) -> Vec<(utils::Label, R)> {
+            let env = builder.env.clone();
+            let (ends, results): (Vec<_>, Vec<_>) = arms
+                .map(|(label, item)| {
+                    builder.env = env.clone();
+                    builder.open(label);
+                    let result = build(item, builder);
+                    let jump = ssa_lang::Jump::goto(join.clone());
+                    let label = builder.close(jump);
+                    let env = std::mem::take(&mut builder.env);
+                    ((label.clone(), env), (label, result))
+                })
+                .unzip();
+            builder.open(join);
+            build_join(ends, builder);
+            results
+        }
+
+        // Sets the env to hold each aloc which is held in every env which
+        // joins, through a phi if they hold different opands.
+        fn build_join(ends: Vec<(utils::Label, Env)>, builder: &mut Builder) {
+            let mut alocs = ends
+                .first()
+                .map(|(_, env)| env.keys().cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            alocs.retain(|aloc| {
+                ends.iter().all(|(_, env)| env.contains_key(aloc))
+            });
+            alocs.sort();
+            builder.env = alocs
+                .into_iter()
+                .map(|aloc| {
+                    let opands = ends
+                        .iter()
+                        .map(|(label, env)| (label.clone(), env[&aloc].clone()))
+                        .collect();
+                    let opand = build_phi(Some(&aloc), opands, builder);
+                    (aloc, opand)
+                })
+                .collect();
+        }
+
+        // Adds a phi of the opands to the current block, unless they are all
+        // the same opand.
+        fn build_phi(
+            aloc: Option<&utils::Aloc>,
+            opands: Vec<(utils::Label, ssa_lang::Opand)>,
+            builder: &mut Builder,
+        ) -> ssa_lang::Opand {
+            match opands.split_first() {
+                Some(((_, opand), rest))
+                    if rest.iter().all(|(_, other)| other == opand) =>
+                {
+                    opand.clone()
+                },
+                _ => {
+                    let phi = match aloc {
+                        Some(aloc) => utils::Aloc::fresh_with_name(&aloc.name),
+                        None => utils::Aloc::fresh(),
+                    };
+                    builder.phis.push(ssa_lang::Phi {
+                        aloc: phi.clone(),
+                        opands,
+                    });
+                    ssa_lang::Opand::aloc(phi)
+                },
+            }
+        }
+
+        fn build_effects(effects: Vec<self::Effect>, builder: &mut Builder) {
+            effects
+                .into_iter()
+                .for_each(|effect| build_effect(effect, builder))
+        }
+
+        fn build_effect(effect: self::Effect, builder: &mut Builder) {
+            match effect {
+                self::Effect::set_aloc_value {
+                    aloc,
+                    value: self::Value::triv(self::Triv::opand(opand)),
+                } => {
+                    let opand = build_opand(opand, builder);
+                    builder.env.insert(aloc, opand);
+                },
+                self::Effect::set_aloc_value { aloc, value } => {
+                    let value = build_value(value, builder);
+                    let fresh = utils::Aloc::fresh_with_name(&aloc.name);
+                    let stmt = ssa_lang::Stmt::set_aloc_value {
+                        aloc: fresh.clone(),
+                        value,
+                    };
+                    builder.stmts.push(stmt);
+                    builder.env.insert(aloc, ssa_lang::Opand::aloc(fresh));
+                },
+                self::Effect::set_aloc_rloc { aloc, rloc } => {
+                    let fresh = utils::Aloc::fresh_with_name(&aloc.name);
+                    let stmt = ssa_lang::Stmt::set_aloc_rloc {
+                        aloc: fresh.clone(),
+                        rloc,
+                    };
+                    builder.stmts.push(stmt);
+                    builder.env.insert(aloc, ssa_lang::Opand::aloc(fresh));
+                },
+                self::Effect::set_rloc_opand { rloc, opand } => {
+                    let opand = build_opand(opand, builder);
+                    let stmt = ssa_lang::Stmt::set_rloc_opand { rloc, opand };
+                    builder.stmts.push(stmt);
+                },
+                self::Effect::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => {
+                    let stmt = ssa_lang::Stmt::mset {
+                        opand1: build_opand(opand1, builder),
+                        opand2: build_opand(opand2, builder),
+                        opand3: build_opand(opand3, builder),
+                    };
+                    builder.stmts.push(stmt);
+                },
+                self::Effect::begin(effects) => build_effects(effects, builder),
+                self::Effect::r#if {
+                    pred,
+                    effect1,
+                    effect2,
+                } => {
+                    let cond = build_pred(pred, builder);
+                    let effects = vec![*effect1, *effect2];
+                    build_branches(cond, effects, builder, build_effect);
+                },
+                // The alocs assigned by the loop are given phis at its
+                // header, whose second opands are only known once its body
+                // is built.
+                self::Effect::r#while { pred, effect } => {
+                    let mut assigned = utils::AlocSet::default();
+                    assigned_pred(&pred, &mut assigned);
+                    assigned_effect(&effect, &mut assigned);
+                    let alocs = loop_alocs(assigned, builder);
+                    let header = utils::Label::new_with_name("header");
+                    let jump = ssa_lang::Jump::goto(header.clone());
+                    let phis = open_loop(header.clone(), &alocs, jump, builder);
+                    builder.phis = phis;
+                    let cond = build_pred(pred, builder);
+                    let body = utils::Label::new_with_name("body");
+                    let exit = utils::Label::new_with_name("exit");
+                    builder.close(ssa_lang::Jump::r#while {
+                        header: header.clone(),
+                        cond,
+                        body: body.clone(),
+                        exit: exit.clone(),
+                    });
+                    let env = builder.env.clone();
+                    builder.open(body);
+                    build_effect(*effect, builder);
+                    let jump = ssa_lang::Jump::goto(header.clone());
+                    let label = builder.close(jump);
+                    let latch = std::mem::replace(&mut builder.env, env);
+                    close_loop(&header, &alocs, vec![(label, latch)], builder);
+                    builder.open(exit);
+                },
+                self::Effect::switch {
+                    opand,
+                    table,
+                    effects,
+                } => {
+                    let opand = build_opand(opand, builder);
+                    let labels = effects
+                        .iter()
+                        .map(|_| utils::Label::new_with_name("case"))
+                        .collect::<Vec<_>>();
+                    let join = utils::Label::new_with_name("join");
+                    builder.close(ssa_lang::Jump::switch {
+                        opand,
+                        table,
+                        labels: labels.clone(),
+                        join: join.clone(),
+                    });
+                    let arms = labels.into_iter().zip(effects);
+                    build_arms(arms, join, builder, build_effect);
+                },
+            }
+        }
+
+        fn assigned_tail(tail: &self::Tail, assigned: &mut utils::AlocSet) {
+            match tail {
+                self::Tail::value(..) | self::Tail::jump { .. } => (),
+                self::Tail::begin { effects, tail } => {
+                    effects
+                        .iter()
+                        .for_each(|effect| assigned_effect(effect, assigned));
+                    assigned_tail(tail, assigned)
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    assigned_pred(pred, assigned);
+                    assigned_tail(tail1, assigned);
+                    assigned_tail(tail2, assigned)
+                },
+                self::Tail::r#loop { tail, .. } => {
+                    assigned_tail(tail, assigned)
+                },
+            }
+        }
+
+        fn assigned_pred(pred: &self::Pred, assigned: &mut utils::AlocSet) {
+            match pred {
+                self::Pred::not(pred) => assigned_pred(pred, assigned),
+                self::Pred::begin { effects, pred } => {
+                    effects
+                        .iter()
+                        .for_each(|effect| assigned_effect(effect, assigned));
+                    assigned_pred(pred, assigned)
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    assigned_pred(pred1, assigned);
+                    assigned_pred(pred2, assigned);
+                    assigned_pred(pred3, assigned)
+                },
+                _ => (),
+            }
+        }
+
+        fn assigned_effect(
+            effect: &self::Effect,
+            assigned: &mut utils::AlocSet,
+        ) {
+            match effect {
+                self::Effect::set_aloc_value { aloc, .. }
+                | self::Effect::set_aloc_rloc { aloc, .. } => {
+                    assigned.insert(aloc.clone());
+                },
+                self::Effect::set_rloc_opand { .. }
+                | self::Effect::mset { .. } => (),
+                self::Effect::begin(effects)
+                | self::Effect::switch { effects, .. } => effects
+                    .iter()
+                    .for_each(|effect| assigned_effect(effect, assigned)),
+                self::Effect::r#if {
+                    pred,
+                    effect1,
+                    effect2,
+                } => {
+                    assigned_pred(pred, assigned);
+                    assigned_effect(effect1, assigned);
+                    assigned_effect(effect2, assigned)
+                },
+                self::Effect::r#while { pred, effect } => {
+                    assigned_pred(pred, assigned);
+                    assigned_effect(effect, assigned)
+                },
+            }
+        }
+
+        fn build_value(
+            value: self::Value,
+            builder: &mut Builder,
+        ) -> ssa_lang::Value {
+            match value {
+                self::Value::triv(self::Triv::opand(opand)) => {
+                    ssa_lang::Value::triv(build_opand(opand, builder))
+                },
+                self::Value::triv(self::Triv::label(label)) => {
+                    ssa_lang::Value::label(label)
+                },
+                self::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                } => ssa_lang::Value::binop {
+                    binop,
+                    opand1: build_opand(opand1, builder),
+                    opand2: build_opand(opand2, builder),
+                },
+                self::Value::alloc { opand } => ssa_lang::Value::alloc {
+                    opand: build_opand(opand, builder),
+                },
+                self::Value::mref { opand1, opand2 } => ssa_lang::Value::mref {
+                    opand1: build_opand(opand1, builder),
+                    opand2: build_opand(opand2, builder),
+                },
+            }
+        }
+
+        // A label is set to a fresh aloc, as it is not an opand.
+        fn build_triv(
+            triv: self::Triv,
+            builder: &mut Builder,
+        ) -> ssa_lang::Opand {
+            match triv {
+                self::Triv::opand(opand) => build_opand(opand, builder),
+                self::Triv::label(label) => {
+                    let aloc = utils::Aloc::fresh();
+                    let value = ssa_lang::Value::label(label);
+                    let stmt = ssa_lang::Stmt::set_aloc_value {
+                        aloc: aloc.clone(),
+                        value,
+                    };
+                    builder.stmts.push(stmt);
+                    ssa_lang::Opand::aloc(aloc)
+                },
+            }
+        }
+
+        fn build_opand(
+            opand: self::Opand,
+            builder: &mut Builder,
+        ) -> ssa_lang::Opand {
+            match opand {
+                target::Triv::aloc(aloc) => match builder.env.get(&aloc) {
+                    Some(opand) => opand.clone(),
+                    None => target::Triv::aloc(aloc),
+                },
+                opand => opand,
+            }
+        }
+
+        let Self(p) = self;
+        let p = build_p(p);
+        ssa_lang::SsaLang(p)
     }
 }
@@ -1,8 +1,5 @@
 use serial_test::serial;
 
-use crate::imperative_abstractions::imp_cmf_lang as source;
-use crate::register_allocation::asm_pred_lang as target;
-use crate::utils;
 
 #[test]
 #[serial]
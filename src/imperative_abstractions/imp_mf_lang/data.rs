@@ -37,6 +37,7 @@ pub enum Pred {
         opand1: Opand,
         opand2: Opand,
     },
+    opand(Opand),
     r#true,
     r#false,
     not(Box<Self>),
@@ -57,12 +58,28 @@ pub enum Effect {
         aloc: utils::Aloc,
         value: Value,
     },
+    mset {
+        opand1: Opand,
+        opand2: Opand,
+        opand3: Opand,
+    },
     begin(Vec<Effect>),
     r#if {
         pred: Pred,
         effect1: Box<Self>,
         effect2: Box<Self>,
     },
+    r#while {
+        pred: Pred,
+        effect: Box<Self>,
+    },
+    /// Executes the effect which the table maps the opand to, or the last
+    /// effect if the opand is outside the table.
+    switch {
+        opand: Opand,
+        table: Vec<usize>,
+        effects: Vec<Self>,
+    },
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
@@ -73,6 +90,13 @@ pub enum Value {
         opand1: Opand,
         opand2: Opand,
     },
+    alloc {
+        opand: Opand,
+    },
+    mref {
+        opand1: Opand,
+        opand2: Opand,
+    },
     begin {
         effects: Vec<Effect>,
         value: Box<Value>,
@@ -82,6 +106,13 @@ pub enum Value {
         value1: Box<Self>,
         value2: Box<Self>,
     },
+    /// Evaluates the value which the table maps the opand to, or the last
+    /// value if the opand is outside the table.
+    switch {
+        opand: Opand,
+        table: Vec<usize>,
+        values: Vec<Self>,
+    },
 }
 
 pub type Opand = super::target::Opand;
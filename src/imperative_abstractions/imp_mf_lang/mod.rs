@@ -95,6 +95,18 @@ impl ImpMfLang {
                                 target::Effect::set_aloc_value { aloc, value };
                             vec![effect]
                         },
+                        self::Value::alloc { opand } => {
+                            let value = target::Value::alloc { opand };
+                            let effect =
+                                target::Effect::set_aloc_value { aloc, value };
+                            vec![effect]
+                        },
+                        self::Value::mref { opand1, opand2 } => {
+                            let value = target::Value::mref { opand1, opand2 };
+                            let effect =
+                                target::Effect::set_aloc_value { aloc, value };
+                            vec![effect]
+                        },
                         self::Value::begin { effects, value } => {
                             let mut effects = normalize_effects(effects);
                             let effects_value =
@@ -128,6 +140,28 @@ impl ImpMfLang {
                             };
                             vec![effect]
                         },
+                        self::Value::switch {
+                            opand,
+                            table,
+                            values,
+                        } => {
+                            let effects = values
+                                .into_iter()
+                                .map(|value| {
+                                    let aloc = Some(aloc.clone());
+                                    let effects = normalize_value(value, aloc)
+                                        .right()
+                                        .unwrap();
+                                    target::Effect::begin(effects)
+                                })
+                                .collect();
+                            let effect = target::Effect::switch {
+                                opand,
+                                table,
+                                effects,
+                            };
+                            vec![effect]
+                        },
                     };
                     Either::Right(effects)
                 },
@@ -149,6 +183,14 @@ impl ImpMfLang {
                             };
                             target::Tail::value(value)
                         },
+                        self::Value::alloc { opand } => {
+                            let value = target::Value::alloc { opand };
+                            target::Tail::value(value)
+                        },
+                        self::Value::mref { opand1, opand2 } => {
+                            let value = target::Value::mref { opand1, opand2 };
+                            target::Tail::value(value)
+                        },
                         self::Value::begin { effects, value } => {
                             let effects = normalize_effects(effects);
                             let tail =
@@ -170,6 +212,20 @@ impl ImpMfLang {
                             let tail2 = Box::new(tail2);
                             target::Tail::r#if { pred, tail1, tail2 }
                         },
+                        // The switch sets a fresh aloc, which the tail returns.
+                        value @ self::Value::switch { .. } => {
+                            let aloc = utils::Aloc::fresh();
+                            let effects =
+                                normalize_value(value, Some(aloc.clone()))
+                                    .right()
+                                    .unwrap();
+                            let opand = target::Opand::aloc(aloc);
+                            let triv = target::Triv::opand(opand);
+                            let value = target::Value::triv(triv);
+                            let tail = target::Tail::value(value);
+                            let tail = Box::new(tail);
+                            target::Tail::begin { effects, tail }
+                        },
                     };
                     Either::Left(tail)
                 },
@@ -181,6 +237,18 @@ impl ImpMfLang {
                 self::Effect::set_aloc_value { aloc, value } => {
                     normalize_value(value, Some(aloc)).right().unwrap()
                 },
+                self::Effect::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => {
+                    let effect = target::Effect::mset {
+                        opand1,
+                        opand2,
+                        opand3,
+                    };
+                    vec![effect]
+                },
                 self::Effect::begin(effects) => normalize_effects(effects),
                 self::Effect::r#if {
                     pred,
@@ -201,6 +269,33 @@ impl ImpMfLang {
                     };
                     vec![effect]
                 },
+                self::Effect::r#while { pred, effect } => {
+                    let pred = normalize_pred(pred);
+                    let effects = normalize_effect(*effect);
+                    let effect = target::Effect::begin(effects);
+                    let effect = Box::new(effect);
+                    let effect = target::Effect::r#while { pred, effect };
+                    vec![effect]
+                },
+                self::Effect::switch {
+                    opand,
+                    table,
+                    effects,
+                } => {
+                    let effects = effects
+                        .into_iter()
+                        .map(|effect| {
+                            let effects = normalize_effect(effect);
+                            target::Effect::begin(effects)
+                        })
+                        .collect();
+                    let effect = target::Effect::switch {
+                        opand,
+                        table,
+                        effects,
+                    };
+                    vec![effect]
+                },
             }
         }
 
@@ -215,6 +310,7 @@ impl ImpMfLang {
                     opand1,
                     opand2,
                 },
+                self::Pred::opand(opand) => target::Pred::opand(opand),
                 self::Pred::r#true => target::Pred::r#true,
                 self::Pred::r#false => target::Pred::r#false,
                 self::Pred::not(pred) => {
@@ -253,8 +349,7 @@ impl ImpMfLang {
         ) -> Vec<target::Effect> {
             effects
                 .into_iter()
-                .map(normalize_effect)
-                .flatten()
+                .flat_map(normalize_effect)
                 .collect()
         }
 
@@ -1,9 +1,5 @@
 use serial_test::serial;
 
-use crate::imperative_abstractions::imp_cmf_lang as target;
-use crate::imperative_abstractions::imp_mf_lang as source;
-use crate::utils;
-
 #[test]
 #[serial]
 fn basic_with_tail() {
@@ -3,20 +3,87 @@ pub mod imp_mf_lang;
 pub mod proc_imp_cmf_lang;
 #[cfg(test)]
 mod tests;
+pub mod ssa_lang;
+pub mod syntax_lang;
 pub mod values_lang;
 pub mod values_unique_lang;
 
+use std::collections::HashMap;
+
+use crate::utils;
+
 pub type Source = values_lang::ValuesLang;
 
 pub type Target = crate::register_allocation::Source;
 
-pub fn compile(p: Source) -> Result<Target, String> {
-    p.check_values_lang().map(|p| {
-        p.uniquify()
-            .optimize_let_bindings()
-            .sequentialize_let()
-            .normalize_bind()
-            .impose_calling_conventions()
-            .select_instructions()
-    })
+/// ### Purpose:
+/// Summarises what the optimisations of the imperative abstractions removed
+/// from the program.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CompileStats {
+    /// The labels of the procedures which cannot be reached from the module
+    /// tail or the provided procedures, and were therefore removed, in the
+    /// order in which they were defined.
+    pub dead_procedures: Vec<utils::Label>,
+}
+
+pub fn compile(
+    p: Source,
+    config: &utils::TargetConfig,
+    options: &utils::CompileOptions,
+) -> Result<Target, String> {
+    compile_with_labels(p, &HashMap::default(), config, options)
+}
+
+/// ### Purpose:
+/// Compiles the program as compile does, but with the given labels for the
+/// procedures it provides to other modules and those it requires of them.
+pub fn compile_with_labels(
+    p: Source,
+    labels: &HashMap<utils::Name, utils::Label>,
+    config: &utils::TargetConfig,
+    options: &utils::CompileOptions,
+) -> Result<Target, String> {
+    compile_with_stats(p, labels, config, options).map(|(p, _)| p)
+}
+
+/// ### Purpose:
+/// Compiles the program as compile-with-labels does, and reports what was
+/// removed from it along the way.
+pub fn compile_with_stats(
+    p: Source,
+    labels: &HashMap<utils::Name, utils::Label>,
+    config: &utils::TargetConfig,
+    options: &utils::CompileOptions,
+) -> Result<(Target, CompileStats), String> {
+    p.check_values_lang()
+        .map(|p| p.expand_derived_forms())
+        .and_then(|p| match options.typing_mode {
+            utils::TypingMode::dynamic => Ok(p),
+            utils::TypingMode::r#static => p.check_types(),
+        })
+        .map(|p| {
+            let roots = labels.values().cloned().collect::<Vec<_>>();
+            let (p, dead_procedures) = p
+                .uniquify_with_labels(labels)
+                .inline_procedures(options.inline_budget)
+                .eliminate_dead_procedures(&roots);
+            let stats = CompileStats { dead_procedures };
+            let p = p
+                .convert_assignments()
+                .convert_closures()
+                .specify_representation()
+                .eliminate_common_subexpressions()
+                .optimize_let_bindings()
+                .sequentialize_let()
+                .normalize_bind()
+                .convert_self_tail_calls()
+                .impose_calling_conventions(config)
+                .construct_ssa()
+                .propagate_constants(options)
+                .simplify_binops(options)
+                .eliminate_dead_code()
+                .destruct_ssa();
+            (p, stats)
+        })
 }
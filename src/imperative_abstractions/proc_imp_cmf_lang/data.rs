@@ -28,6 +28,14 @@ pub enum Tail {
         triv: Triv,
         opands: Vec<Opand>,
     },
+    /// Runs the tail, which may jump back to its start by the label. The
+    /// label is local to the procedure.
+    r#loop {
+        label: utils::Label,
+        tail: Box<Self>,
+    },
+    /// Jumps back to the start of the enclosing loop with the label.
+    jump(utils::Label),
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
@@ -37,6 +45,7 @@ pub enum Pred {
         opand1: Opand,
         opand2: Opand,
     },
+    opand(Opand),
     r#true,
     r#false,
     not(Box<Self>),
@@ -57,12 +66,28 @@ pub enum Effect {
         aloc: utils::Aloc,
         value: Value,
     },
+    mset {
+        opand1: Opand,
+        opand2: Opand,
+        opand3: Opand,
+    },
     begin(Vec<Effect>),
     r#if {
         pred: Pred,
         effect1: Box<Self>,
         effect2: Box<Self>,
     },
+    r#while {
+        pred: Pred,
+        effect: Box<Self>,
+    },
+    /// Executes the effect which the table maps the opand to, or the last
+    /// effect if the opand is outside the table.
+    switch {
+        opand: Opand,
+        table: Vec<usize>,
+        effects: Vec<Self>,
+    },
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
@@ -73,6 +98,13 @@ pub enum Value {
         opand1: Opand,
         opand2: Opand,
     },
+    alloc {
+        opand: Opand,
+    },
+    mref {
+        opand1: Opand,
+        opand2: Opand,
+    },
 }
 
 pub type Opand = super::target::Opand;
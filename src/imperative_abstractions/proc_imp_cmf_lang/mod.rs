@@ -4,16 +4,359 @@ mod tests;
 
 pub use self::data::*;
 use crate::imperative_abstractions::imp_cmf_lang as target;
+use crate::utils;
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub struct ProcImpCmfLang(pub self::P);
 
 impl ProcImpCmfLang {
+    /// ConvertSelfTailCalls: ProcImpCmfLang -> ProcImpCmfLang
+    ///
+    /// ### Purpose:
+    /// Rewrites each call which a procedure makes to itself in tail position
+    /// as assignments to its arguments, followed by a jump to a loop around
+    /// its body. Recursive loops then cost no more than the parameter passing
+    /// protocol would for a single call.
+    ///
+    /// ### Notes:
+    /// The arguments are assigned in parallel: an opand which is itself an
+    /// argument is first copied into a fresh aloc, so that no argument is
+    /// overwritten before it is read. An opand which is the argument it is
+    /// assigned to is not assigned at all.
+    pub fn convert_self_tail_calls(self) -> Self {
+        fn convert_p(p: self::P) -> self::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let lambdas =
+                        lambdas.into_iter().map(convert_lambda).collect();
+                    self::P::module { lambdas, tail }
+                },
+            }
+        }
+
+        fn convert_lambda(
+            self::Lambda { label, args, tail }: self::Lambda,
+        ) -> self::Lambda {
+            let mut header = None;
+            let tail = convert_tail(tail, &label, &args, &mut header);
+            let tail = match header {
+                Some(header) => {
+                    let tail = Box::new(tail);
+                    self::Tail::r#loop {
+                        label: header,
+                        tail,
+                    }
+                },
+                None => tail,
+            };
+            self::Lambda { label, args, tail }
+        }
+
+        fn convert_tail(
+            tail: self::Tail,
+            label: &utils::Label,
+            args: &[utils::Aloc],
+            header: &mut Option<utils::Label>,
+        ) -> self::Tail {
+            match tail {
+                self::Tail::begin { effects, tail } => {
+                    let tail = convert_tail(*tail, label, args, header);
+                    let tail = Box::new(tail);
+                    self::Tail::begin { effects, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let tail1 = convert_tail(*tail1, label, args, header);
+                    let tail2 = convert_tail(*tail2, label, args, header);
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    self::Tail::r#if { pred, tail1, tail2 }
+                },
+                self::Tail::r#loop { label: inner, tail } => {
+                    let tail = convert_tail(*tail, label, args, header);
+                    let tail = Box::new(tail);
+                    self::Tail::r#loop { label: inner, tail }
+                },
+                self::Tail::call {
+                    triv: self::Triv::label(callee),
+                    opands,
+                } if callee == *label && opands.len() == args.len() => {
+                    let header = header.get_or_insert_with(|| {
+                        utils::Label::new_with_name("loop")
+                    });
+                    let effects = assign_args(args, opands);
+                    let tail = Box::new(self::Tail::jump(header.clone()));
+                    self::Tail::begin { effects, tail }
+                },
+                tail => tail,
+            }
+        }
+
+        fn assign_args(
+            args: &[utils::Aloc],
+            opands: Vec<self::Opand>,
+        ) -> Vec<self::Effect> {
+            let set = |aloc: &utils::Aloc, opand| {
+                let value = self::Value::triv(self::Triv::opand(opand));
+                let aloc = aloc.clone();
+                self::Effect::set_aloc_value { aloc, value }
+            };
+            let mut copies = vec![];
+            let mut effects = vec![];
+            args.iter().zip(opands).for_each(|(arg, opand)| match opand {
+                self::Opand::aloc(ref aloc) if aloc == arg => (),
+                self::Opand::aloc(ref aloc) if args.contains(aloc) => {
+                    let tmp = utils::Aloc::fresh_with_name(&aloc.name);
+                    copies.push(set(&tmp, opand));
+                    effects.push(set(arg, self::Opand::aloc(tmp)));
+                },
+                opand => effects.push(set(arg, opand)),
+            });
+            copies.extend(effects);
+            copies
+        }
+
+        let Self(p) = self;
+        let p = convert_p(p);
+        Self(p)
+    }
+
+    /// ImposeCallingConventions: ProcImpCmfLang -> ImpCmfLang
+    ///
     /// ### Purpose:
     /// Compiles Proc-imp-cmf-lang v5 to Imp-cmf-lang v5 by imposing calling
     /// conventions on all calls and procedure definitions. The parameter
-    /// registers are defined by the list current-parameter-registers.
-    pub fn impose_calling_conventions(self) -> target::ImpCmfLang {
-        todo!()
+    /// registers are defined by the parameter registers of the target
+    /// configuration.
+    ///
+    /// ### Notes:
+    /// The first arguments are passed in the parameter registers, in order,
+    /// and the rest in frame variables, from fv0 up. A call sets the frame
+    /// variables before the registers, and jumps with the frame base pointer
+    /// and each rloc which it sets. A procedure reads its arguments out of
+    /// the same rlocs on entry. A jump back to a loop passes nothing.
+    pub fn impose_calling_conventions(
+        self,
+        config: &utils::TargetConfig,
+    ) -> target::ImpCmfLang {
+        fn impose_p(p: self::P, config: &utils::TargetConfig) -> target::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|lambda| impose_lambda(lambda, config))
+                        .collect();
+                    let tail = impose_tail(tail, config);
+                    target::P::module { lambdas, tail }
+                },
+            }
+        }
+
+        fn impose_lambda(
+            self::Lambda { label, args, tail }: self::Lambda,
+            config: &utils::TargetConfig,
+        ) -> target::Lambda {
+            let mut effects = args
+                .into_iter()
+                .zip(rlocs(config))
+                .map(|(aloc, rloc)| {
+                    target::Effect::set_aloc_rloc { aloc, rloc }
+                })
+                .collect::<Vec<_>>();
+            let tail = impose_tail(tail, config);
+            let tail = make_begins!((effects, tail) => target::Tail::tail);
+            target::Lambda { label, tail }
+        }
+
+        // The rlocs in which the arguments of a call are passed, in order.
+        fn rlocs(
+            config: &utils::TargetConfig,
+        ) -> impl Iterator<Item = target::Rloc> + '_ {
+            let regs = config
+                .parameter_registers
+                .iter()
+                .copied()
+                .map(target::Rloc::reg);
+            let fvars = (0..).map(|index| {
+                target::Rloc::fvar(utils::Fvar(index))
+            });
+            regs.chain(fvars)
+        }
+
+        fn impose_tail(
+            tail: self::Tail,
+            config: &utils::TargetConfig,
+        ) -> target::Tail {
+            match tail {
+                self::Tail::value(value) => {
+                    target::Tail::value(impose_value(value))
+                },
+                self::Tail::begin { effects, tail } => {
+                    let effects = impose_effects(effects);
+                    let tail = impose_tail(*tail, config);
+                    let tail = Box::new(tail);
+                    target::Tail::begin { effects, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let pred = impose_pred(pred);
+                    let tail1 = impose_tail(*tail1, config);
+                    let tail2 = impose_tail(*tail2, config);
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    target::Tail::r#if { pred, tail1, tail2 }
+                },
+                self::Tail::call { triv, opands } => {
+                    let (regs, fvars): (Vec<_>, Vec<_>) = rlocs(config)
+                        .zip(opands)
+                        .partition(|(rloc, _)| {
+                            matches!(rloc, target::Rloc::reg(..))
+                        });
+                    let mut locs =
+                        vec![target::Rloc::reg(config.frame_base_pointer)];
+                    let mut effects = fvars
+                        .into_iter()
+                        .chain(regs)
+                        .map(|(rloc, opand)| {
+                            locs.push(rloc.clone());
+                            target::Effect::set_rloc_opand { rloc, opand }
+                        })
+                        .collect::<Vec<_>>();
+                    let tail = target::Tail::jump { trg: triv, locs };
+                    make_begins!((effects, tail) => target::Tail::tail)
+                },
+                self::Tail::r#loop { label, tail } => {
+                    let tail = impose_tail(*tail, config);
+                    let tail = Box::new(tail);
+                    target::Tail::r#loop { label, tail }
+                },
+                self::Tail::jump(label) => target::Tail::jump {
+                    trg: target::Triv::label(label),
+                    locs: vec![],
+                },
+            }
+        }
+
+        fn impose_pred(pred: self::Pred) -> target::Pred {
+            match pred {
+                self::Pred::relop {
+                    relop,
+                    opand1,
+                    opand2,
+                } => target::Pred::relop {
+                    relop,
+                    opand1,
+                    opand2,
+                },
+                self::Pred::opand(opand) => target::Pred::opand(opand),
+                self::Pred::r#true => target::Pred::r#true,
+                self::Pred::r#false => target::Pred::r#false,
+                self::Pred::not(pred) => {
+                    let pred = impose_pred(*pred);
+                    let pred = Box::new(pred);
+                    target::Pred::not(pred)
+                },
+                self::Pred::begin { effects, pred } => {
+                    let effects = impose_effects(effects);
+                    let pred = impose_pred(*pred);
+                    let pred = Box::new(pred);
+                    target::Pred::begin { effects, pred }
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let pred1 = impose_pred(*pred1);
+                    let pred2 = impose_pred(*pred2);
+                    let pred3 = impose_pred(*pred3);
+                    let pred1 = Box::new(pred1);
+                    let pred2 = Box::new(pred2);
+                    let pred3 = Box::new(pred3);
+                    target::Pred::r#if {
+                        pred1,
+                        pred2,
+                        pred3,
+                    }
+                },
+            }
+        }
+
+        fn impose_effects(effects: Vec<self::Effect>) -> Vec<target::Effect> {
+            effects.into_iter().map(impose_effect).collect()
+        }
+
+        fn impose_effect(effect: self::Effect) -> target::Effect {
+            match effect {
+                self::Effect::set_aloc_value { aloc, value } => {
+                    let value = impose_value(value);
+                    target::Effect::set_aloc_value { aloc, value }
+                },
+                self::Effect::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => target::Effect::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                },
+                self::Effect::begin(effects) => {
+                    target::Effect::begin(impose_effects(effects))
+                },
+                self::Effect::r#if {
+                    pred,
+                    effect1,
+                    effect2,
+                } => {
+                    let pred = impose_pred(pred);
+                    let effect1 = impose_effect(*effect1);
+                    let effect2 = impose_effect(*effect2);
+                    let effect1 = Box::new(effect1);
+                    let effect2 = Box::new(effect2);
+                    target::Effect::r#if {
+                        pred,
+                        effect1,
+                        effect2,
+                    }
+                },
+                self::Effect::r#while { pred, effect } => {
+                    let pred = impose_pred(pred);
+                    let effect = impose_effect(*effect);
+                    let effect = Box::new(effect);
+                    target::Effect::r#while { pred, effect }
+                },
+                self::Effect::switch {
+                    opand,
+                    table,
+                    effects,
+                } => target::Effect::switch {
+                    opand,
+                    table,
+                    effects: impose_effects(effects),
+                },
+            }
+        }
+
+        fn impose_value(value: self::Value) -> target::Value {
+            match value {
+                self::Value::triv(triv) => target::Value::triv(triv),
+                self::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                } => target::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                },
+                self::Value::alloc { opand } => target::Value::alloc { opand },
+                self::Value::mref { opand1, opand2 } => {
+                    target::Value::mref { opand1, opand2 }
+                },
+            }
+        }
+
+        let Self(p) = self;
+        let p = impose_p(p, config);
+        target::ImpCmfLang(p)
     }
 }
@@ -1 +1,139 @@
+use serial_test::serial;
 
+use crate::imperative_abstractions::imp_cmf_lang as target;
+use crate::imperative_abstractions::proc_imp_cmf_lang as source;
+use crate::utils;
+
+#[test]
+#[serial]
+fn convert_self_tail_call_to_loop() {
+    utils::reset_all_indices();
+
+    let f = utils::Label::new_with_name("f");
+    let a = utils::Aloc::fresh_with_name("a");
+    let b = utils::Aloc::fresh_with_name("b");
+    let aloc = |aloc: &utils::Aloc| source::Opand::aloc(aloc.clone());
+    let tail = |tail2| source::Tail::r#if {
+        pred: source::Pred::r#true,
+        tail1: Box::new(source::Tail::value(source::Value::triv(
+            source::Triv::opand(aloc(&a)),
+        ))),
+        tail2: Box::new(tail2),
+    };
+    let lambda = |tail| source::Lambda {
+        label: f.clone(),
+        args: vec![a.clone(), b.clone()],
+        tail,
+    };
+    let call = source::Tail::call {
+        triv: source::Triv::label(f.clone()),
+        opands: vec![aloc(&b), source::Opand::int64(1)],
+    };
+    let p = source::ProcImpCmfLang(source::P::module {
+        lambdas: vec![lambda(tail(call))],
+        tail: source::Tail::call {
+            triv: source::Triv::label(f.clone()),
+            opands: vec![source::Opand::int64(0), source::Opand::int64(0)],
+        },
+    });
+    let actual = p.convert_self_tail_calls();
+
+    let header = utils::Label("L.loop.1".into());
+    let tmp = utils::Aloc {
+        name: "b".into(),
+        index: 2,
+    };
+    let set = |aloc: &utils::Aloc, opand| source::Effect::set_aloc_value {
+        aloc: aloc.clone(),
+        value: source::Value::triv(source::Triv::opand(opand)),
+    };
+    let jump = source::Tail::begin {
+        effects: vec![
+            set(&tmp, aloc(&b)),
+            set(&a, aloc(&tmp)),
+            set(&b, source::Opand::int64(1)),
+        ],
+        tail: Box::new(source::Tail::jump(header.clone())),
+    };
+    let expected = source::ProcImpCmfLang(source::P::module {
+        lambdas: vec![lambda(source::Tail::r#loop {
+            label: header,
+            tail: Box::new(tail(jump)),
+        })],
+        tail: source::Tail::call {
+            triv: source::Triv::label(f.clone()),
+            opands: vec![source::Opand::int64(0), source::Opand::int64(0)],
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn impose_calling_conventions_with_configured_registers() {
+    utils::reset_all_indices();
+
+    let f = utils::Label::new_with_name("f");
+    let a = utils::Aloc::fresh_with_name("a");
+    let b = utils::Aloc::fresh_with_name("b");
+    let p = source::ProcImpCmfLang(source::P::module {
+        lambdas: vec![source::Lambda {
+            label: f.clone(),
+            args: vec![a.clone(), b.clone()],
+            tail: source::Tail::value(source::Value::triv(
+                source::Triv::opand(source::Opand::aloc(a.clone())),
+            )),
+        }],
+        tail: source::Tail::call {
+            triv: source::Triv::label(f.clone()),
+            opands: vec![source::Opand::int64(1), source::Opand::int64(2)],
+        },
+    });
+    let config = utils::TargetConfig {
+        parameter_registers: vec![utils::Reg::rsi],
+        ..Default::default()
+    };
+    let actual = p.impose_calling_conventions(&config);
+
+    let rsi = target::Rloc::reg(utils::Reg::rsi);
+    let fv0 = target::Rloc::fvar(utils::Fvar(0));
+    let expected = target::ImpCmfLang(target::P::module {
+        lambdas: vec![target::Lambda {
+            label: f.clone(),
+            tail: target::Tail::begin {
+                effects: vec![
+                    target::Effect::set_aloc_rloc {
+                        aloc: a.clone(),
+                        rloc: rsi.clone(),
+                    },
+                    target::Effect::set_aloc_rloc {
+                        aloc: b,
+                        rloc: fv0.clone(),
+                    },
+                ],
+                tail: Box::new(target::Tail::value(target::Value::triv(
+                    target::Triv::opand(target::Opand::aloc(a)),
+                ))),
+            },
+        }],
+        tail: target::Tail::begin {
+            effects: vec![
+                target::Effect::set_rloc_opand {
+                    rloc: fv0.clone(),
+                    opand: target::Opand::int64(2),
+                },
+                target::Effect::set_rloc_opand {
+                    rloc: rsi.clone(),
+                    opand: target::Opand::int64(1),
+                },
+            ],
+            tail: Box::new(target::Tail::jump {
+                trg: target::Triv::label(f),
+                locs: vec![target::Rloc::reg(utils::Reg::rbp), fv0, rsi],
+            }),
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
@@ -0,0 +1,142 @@
+use crate::utils;
+
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum P {
+    /// The first block is the entry of the module.
+    module {
+        lambdas: Vec<Lambda>,
+        blocks: Vec<Block>,
+    },
+}
+
+/// A procedure, whose first block is its entry.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct Lambda {
+    pub label: utils::Label,
+    pub blocks: Vec<Block>,
+}
+
+/// A basic block: its phis are set on entry, in parallel, after which its
+/// stmts are run in order and it jumps.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct Block {
+    pub label: utils::Label,
+    pub phis: Vec<Phi>,
+    pub stmts: Vec<Stmt>,
+    pub jump: Jump,
+}
+
+/// Sets the aloc to the opand given for the block which was jumped from.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct Phi {
+    pub aloc: utils::Aloc,
+    pub opands: Vec<(utils::Label, Opand)>,
+}
+
+/// Each aloc is set by exactly one stmt or phi, which dominates its uses.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum Stmt {
+    set_aloc_value {
+        aloc: utils::Aloc,
+        value: Value,
+    },
+    /// Reads an argument which the caller passed in the rloc.
+    set_aloc_rloc {
+        aloc: utils::Aloc,
+        rloc: Rloc,
+    },
+    /// Passes an argument to a procedure in the rloc.
+    set_rloc_opand {
+        rloc: Rloc,
+        opand: Opand,
+    },
+    mset {
+        opand1: Opand,
+        opand2: Opand,
+        opand3: Opand,
+    },
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum Value {
+    triv(Opand),
+    /// The address of the label.
+    label(utils::Label),
+    binop {
+        binop: utils::Binop,
+        opand1: Opand,
+        opand2: Opand,
+    },
+    alloc {
+        opand: Opand,
+    },
+    mref {
+        opand1: Opand,
+        opand2: Opand,
+    },
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum Cond {
+    relop {
+        relop: utils::Relop,
+        opand1: Opand,
+        opand2: Opand,
+    },
+    r#true,
+    r#false,
+}
+
+/// The jumps keep the structure of the program they were built from, so that
+/// it can be recovered when leaving SSA.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum Jump {
+    halt(Opand),
+    goto(utils::Label),
+    /// Jumps to the trg, with the locs holding the values which it reads.
+    jump {
+        trg: Trg,
+        locs: Vec<Rloc>,
+    },
+    /// Enters the loop which starts at the header, and which the rest of the
+    /// procedure runs in. Each jump back to it is a goto to the header.
+    r#loop {
+        header: utils::Label,
+    },
+    /// Jumps to the first label if the cond holds, and otherwise to the
+    /// second. If there is a join, both branches end by jumping to it.
+    r#if {
+        cond: Cond,
+        label1: utils::Label,
+        label2: utils::Label,
+        join: Option<utils::Label>,
+    },
+    /// Ends the pred of the loop which starts at the header: jumps to the
+    /// body, which ends by jumping back to the header, if the cond holds, and
+    /// otherwise to the exit.
+    r#while {
+        header: utils::Label,
+        cond: Cond,
+        body: utils::Label,
+        exit: utils::Label,
+    },
+    /// Jumps to the label which the table maps the opand to, or to the last
+    /// label if the opand is outside the table. Each label ends by jumping to
+    /// the join.
+    switch {
+        opand: Opand,
+        table: Vec<usize>,
+        labels: Vec<utils::Label>,
+        join: utils::Label,
+    },
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum Trg {
+    label(utils::Label),
+    opand(Opand),
+}
+
+pub type Opand = super::target::Triv;
+
+pub type Rloc = super::target::Rloc;
@@ -0,0 +1,1265 @@
+pub mod data;
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+pub use self::data::*;
+use crate::register_allocation::asm_pred_lang as target;
+use crate::utils;
+
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct SsaLang(pub self::P);
+
+impl SsaLang {
+    /// PropagateConstants: SsaLang -> SsaLang
+    ///
+    /// ### Purpose:
+    /// Replaces each aloc which always holds the same integer with that
+    /// integer, and each jump which can then only take one of its labels with
+    /// a jump to that label, removing the blocks which can never be reached.
+    ///
+    /// ### Notes:
+    /// This is sparse conditional constant propagation: the opands of a phi
+    /// are only met along the edges which may be taken, so that a branch
+    /// which is never taken does not keep an aloc from being constant. A loop
+    /// whose cond always holds keeps its exit, as a loop cannot be recovered
+    /// without one.
+    ///
+    /// In checked overflow mode, a binop which overflows is not folded, so
+    /// that it still traps when the program is run.
+    ///
+    /// Each procedure is analysed on its own, so an argument, which may be
+    /// passed anything, holds more than one integer.
+    pub fn propagate_constants(self, options: &utils::CompileOptions) -> Self {
+        // The integers which an aloc is found to hold: none as yet, exactly
+        // one, or more than one.
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum Lattice {
+            top,
+            int64(i64),
+            bottom,
+        }
+
+        struct Facts {
+            defined: utils::AlocSet,
+            values: HashMap<utils::Aloc, Lattice>,
+            edges: HashSet<(utils::Label, utils::Label)>,
+            reached: HashSet<utils::Label>,
+        }
+
+        fn propagate_p(p: self::P, options: &utils::CompileOptions) -> self::P {
+            match p {
+                self::P::module { lambdas, blocks } => {
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, blocks }| {
+                            let blocks = propagate_blocks(blocks, options);
+                            self::Lambda { label, blocks }
+                        })
+                        .collect();
+                    let blocks = propagate_blocks(blocks, options);
+                    self::P::module { lambdas, blocks }
+                },
+            }
+        }
+
+        fn propagate_blocks(
+            blocks: Vec<self::Block>,
+            options: &utils::CompileOptions,
+        ) -> Vec<self::Block> {
+            let facts = analyse(&blocks, options);
+            blocks
+                .into_iter()
+                .filter(|block| facts.reached.contains(&block.label))
+                .map(|block| rewrite_block(block, &facts))
+                .collect()
+        }
+
+        // Evaluates every reached block until nothing more is learned.
+        fn analyse(
+            blocks: &[self::Block],
+            options: &utils::CompileOptions,
+        ) -> Facts {
+            let defined = blocks
+                .iter()
+                .flat_map(|block| {
+                    let phis = block.phis.iter().map(|phi| &phi.aloc);
+                    let stmts =
+                        block.stmts.iter().filter_map(|stmt| match stmt {
+                            self::Stmt::set_aloc_value { aloc, .. }
+                            | self::Stmt::set_aloc_rloc { aloc, .. } => {
+                                Some(aloc)
+                            },
+                            self::Stmt::set_rloc_opand { .. }
+                            | self::Stmt::mset { .. } => None,
+                        });
+                    phis.chain(stmts)
+                })
+                .cloned()
+                .collect();
+            let reached = blocks
+                .first()
+                .map(|block| block.label.clone())
+                .into_iter()
+                .collect();
+            let mut facts = Facts {
+                defined,
+                values: HashMap::default(),
+                edges: HashSet::default(),
+                reached,
+            };
+            let mut changed = true;
+            while changed {
+                changed = false;
+                blocks.iter().for_each(|block| {
+                    if facts.reached.contains(&block.label) {
+                        changed |= analyse_block(block, &mut facts, options)
+                    }
+                });
+            }
+            facts
+        }
+
+        fn analyse_block(
+            block: &self::Block,
+            facts: &mut Facts,
+            options: &utils::CompileOptions,
+        ) -> bool {
+            let mut changed = false;
+            block.phis.iter().for_each(|phi| {
+                let value = phi
+                    .opands
+                    .iter()
+                    .filter(|(label, _)| {
+                        let edge = (label.clone(), block.label.clone());
+                        facts.edges.contains(&edge)
+                    })
+                    .map(|(_, opand)| eval_opand(opand, facts))
+                    .fold(Lattice::top, meet);
+                changed |= update(&phi.aloc, value, facts);
+            });
+            block.stmts.iter().for_each(|stmt| match stmt {
+                self::Stmt::set_aloc_value { aloc, value } => {
+                    let value = eval_value(value, facts, options);
+                    changed |= update(aloc, value, facts);
+                },
+                self::Stmt::set_aloc_rloc { aloc, .. } => {
+                    changed |= update(aloc, Lattice::bottom, facts);
+                },
+                self::Stmt::set_rloc_opand { .. }
+                | self::Stmt::mset { .. } => (),
+            });
+            successors(&block.jump, facts)
+                .into_iter()
+                .for_each(|label| {
+                    let edge = (block.label.clone(), label.clone());
+                    changed |= facts.edges.insert(edge);
+                    facts.reached.insert(label);
+                });
+            changed
+        }
+
+        // The labels which the jump may take, given what is known so far.
+        fn successors(
+            jump: &self::Jump,
+            facts: &Facts,
+        ) -> Vec<utils::Label> {
+            match jump {
+                self::Jump::halt(..) | self::Jump::jump { .. } => vec![],
+                self::Jump::goto(label)
+                | self::Jump::r#loop { header: label } => vec![label.clone()],
+                self::Jump::r#if {
+                    cond,
+                    label1,
+                    label2,
+                    ..
+                } => match eval_cond(cond, facts) {
+                    Lattice::top => vec![],
+                    Lattice::int64(0) => vec![label2.clone()],
+                    Lattice::int64(_) => vec![label1.clone()],
+                    Lattice::bottom => vec![label1.clone(), label2.clone()],
+                },
+                self::Jump::r#while {
+                    cond, body, exit, ..
+                } => match eval_cond(cond, facts) {
+                    Lattice::top => vec![],
+                    Lattice::int64(0) => vec![exit.clone()],
+                    _ => vec![body.clone(), exit.clone()],
+                },
+                self::Jump::switch {
+                    opand,
+                    table,
+                    labels,
+                    ..
+                } => match eval_opand(opand, facts) {
+                    Lattice::top => vec![],
+                    Lattice::int64(int64) => {
+                        vec![select_case(int64, table, labels).clone()]
+                    },
+                    Lattice::bottom => labels.clone(),
+                },
+            }
+        }
+
+        fn select_case<'a>(
+            int64: i64,
+            table: &[usize],
+            labels: &'a [utils::Label],
+        ) -> &'a utils::Label {
+            usize::try_from(int64)
+                .ok()
+                .and_then(|index| table.get(index))
+                .and_then(|&index| labels.get(index))
+                .or_else(|| labels.last())
+                .unwrap()
+        }
+
+        fn meet(value1: Lattice, value2: Lattice) -> Lattice {
+            match (value1, value2) {
+                (Lattice::top, value) | (value, Lattice::top) => value,
+                (Lattice::int64(int1), Lattice::int64(int2))
+                    if int1 == int2 =>
+                {
+                    value1
+                },
+                _ => Lattice::bottom,
+            }
+        }
+
+        fn update(
+            aloc: &utils::Aloc,
+            value: Lattice,
+            facts: &mut Facts,
+        ) -> bool {
+            let old = facts.values.get(aloc).copied().unwrap_or(Lattice::top);
+            let new = meet(old, value);
+            facts.values.insert(aloc.clone(), new);
+            old != new
+        }
+
+        // An aloc which is never set, such as one which is used before it is
+        // set, may hold anything.
+        fn eval_opand(opand: &self::Opand, facts: &Facts) -> Lattice {
+            match opand {
+                self::Opand::int64(int64) => Lattice::int64(*int64),
+                self::Opand::aloc(aloc) if facts.defined.contains(aloc) => {
+                    facts.values.get(aloc).copied().unwrap_or(Lattice::top)
+                },
+                self::Opand::aloc(..) => Lattice::bottom,
+            }
+        }
+
+        // A binop which overflows in checked overflow mode may hold
+        // anything, as it is left in place to trap.
+        fn eval_value(
+            value: &self::Value,
+            facts: &Facts,
+            options: &utils::CompileOptions,
+        ) -> Lattice {
+            match value {
+                self::Value::triv(opand) => eval_opand(opand, facts),
+                self::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                } => {
+                    let value1 = eval_opand(opand1, facts);
+                    let value2 = eval_opand(opand2, facts);
+                    eval_binary(value1, value2, |int1, int2| {
+                        match options.overflow_mode {
+                            utils::OverflowMode::wrapping => {
+                                Some(binop.eval(int1, int2))
+                            },
+                            utils::OverflowMode::checked => {
+                                binop.checked_eval(int1, int2)
+                            },
+                        }
+                    })
+                },
+                self::Value::label(..)
+                | self::Value::alloc { .. }
+                | self::Value::mref { .. } => Lattice::bottom,
+            }
+        }
+
+        fn eval_binary(
+            value1: Lattice,
+            value2: Lattice,
+            eval: impl FnOnce(i64, i64) -> Option<i64>,
+        ) -> Lattice {
+            match (value1, value2) {
+                (Lattice::int64(int1), Lattice::int64(int2)) => {
+                    eval(int1, int2).map_or(Lattice::bottom, Lattice::int64)
+                },
+                (Lattice::bottom, _) | (_, Lattice::bottom) => Lattice::bottom,
+                _ => Lattice::top,
+            }
+        }
+
+        // Evaluates the cond to one if it holds and zero if it does not.
+        fn eval_cond(cond: &self::Cond, facts: &Facts) -> Lattice {
+            match cond {
+                self::Cond::relop {
+                    relop,
+                    opand1,
+                    opand2,
+                } => {
+                    let value1 = eval_opand(opand1, facts);
+                    let value2 = eval_opand(opand2, facts);
+                    eval_binary(value1, value2, |int1, int2| {
+                        Some(relop.eval(int1, int2) as i64)
+                    })
+                },
+                self::Cond::r#true => Lattice::int64(1),
+                self::Cond::r#false => Lattice::int64(0),
+            }
+        }
+
+        fn rewrite_block(block: self::Block, facts: &Facts) -> self::Block {
+            let self::Block {
+                label,
+                phis,
+                stmts,
+                jump,
+            } = block;
+            let phis = phis
+                .into_iter()
+                .filter(|phi| !is_constant(&phi.aloc, facts))
+                .map(|self::Phi { aloc, opands }| {
+                    let opands = opands
+                        .into_iter()
+                        .filter(|(from, _)| {
+                            let edge = (from.clone(), label.clone());
+                            facts.edges.contains(&edge)
+                        })
+                        .map(|(from, opand)| {
+                            (from, rewrite_opand(opand, facts))
+                        })
+                        .collect();
+                    self::Phi { aloc, opands }
+                })
+                .collect();
+            let stmts = stmts
+                .into_iter()
+                .filter_map(|stmt| rewrite_stmt(stmt, facts))
+                .collect();
+            let jump = rewrite_jump(jump, facts);
+            self::Block {
+                label,
+                phis,
+                stmts,
+                jump,
+            }
+        }
+
+        fn rewrite_stmt(
+            stmt: self::Stmt,
+            facts: &Facts,
+        ) -> Option<self::Stmt> {
+            match stmt {
+                self::Stmt::set_aloc_value { aloc, .. }
+                    if is_constant(&aloc, facts) =>
+                {
+                    None
+                },
+                self::Stmt::set_aloc_value { aloc, value } => {
+                    let value = match value {
+                        self::Value::triv(opand) => {
+                            self::Value::triv(rewrite_opand(opand, facts))
+                        },
+                        self::Value::binop {
+                            binop,
+                            opand1,
+                            opand2,
+                        } => self::Value::binop {
+                            binop,
+                            opand1: rewrite_opand(opand1, facts),
+                            opand2: rewrite_opand(opand2, facts),
+                        },
+                        self::Value::alloc { opand } => self::Value::alloc {
+                            opand: rewrite_opand(opand, facts),
+                        },
+                        self::Value::mref { opand1, opand2 } => {
+                            self::Value::mref {
+                                opand1: rewrite_opand(opand1, facts),
+                                opand2: rewrite_opand(opand2, facts),
+                            }
+                        },
+                        value @ self::Value::label(..) => value,
+                    };
+                    Some(self::Stmt::set_aloc_value { aloc, value })
+                },
+                self::Stmt::set_aloc_rloc { aloc, rloc } => {
+                    Some(self::Stmt::set_aloc_rloc { aloc, rloc })
+                },
+                self::Stmt::set_rloc_opand { rloc, opand } => {
+                    let opand = rewrite_opand(opand, facts);
+                    Some(self::Stmt::set_rloc_opand { rloc, opand })
+                },
+                self::Stmt::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => Some(self::Stmt::mset {
+                    opand1: rewrite_opand(opand1, facts),
+                    opand2: rewrite_opand(opand2, facts),
+                    opand3: rewrite_opand(opand3, facts),
+                }),
+            }
+        }
+
+        fn rewrite_jump(jump: self::Jump, facts: &Facts) -> self::Jump {
+            match jump {
+                self::Jump::halt(opand) => {
+                    self::Jump::halt(rewrite_opand(opand, facts))
+                },
+                self::Jump::r#if {
+                    cond,
+                    label1,
+                    label2,
+                    join,
+                } => match eval_cond(&cond, facts) {
+                    Lattice::int64(0) => self::Jump::goto(label2),
+                    Lattice::int64(_) => self::Jump::goto(label1),
+                    _ => self::Jump::r#if {
+                        cond: rewrite_cond(cond, facts),
+                        label1,
+                        label2,
+                        join,
+                    },
+                },
+                self::Jump::r#while {
+                    header,
+                    cond,
+                    body,
+                    exit,
+                } => match eval_cond(&cond, facts) {
+                    Lattice::int64(0) => self::Jump::goto(exit),
+                    value => {
+                        let cond = match value {
+                            Lattice::int64(_) => self::Cond::r#true,
+                            _ => rewrite_cond(cond, facts),
+                        };
+                        self::Jump::r#while {
+                            header,
+                            cond,
+                            body,
+                            exit,
+                        }
+                    },
+                },
+                self::Jump::switch {
+                    opand,
+                    table,
+                    labels,
+                    join,
+                } => match eval_opand(&opand, facts) {
+                    Lattice::int64(int64) => {
+                        let label = select_case(int64, &table, &labels);
+                        self::Jump::goto(label.clone())
+                    },
+                    _ => self::Jump::switch {
+                        opand,
+                        table,
+                        labels,
+                        join,
+                    },
+                },
+                jump => jump,
+            }
+        }
+
+        fn rewrite_cond(cond: self::Cond, facts: &Facts) -> self::Cond {
+            match cond {
+                self::Cond::relop {
+                    relop,
+                    opand1,
+                    opand2,
+                } => self::Cond::relop {
+                    relop,
+                    opand1: rewrite_opand(opand1, facts),
+                    opand2: rewrite_opand(opand2, facts),
+                },
+                cond => cond,
+            }
+        }
+
+        fn rewrite_opand(opand: self::Opand, facts: &Facts) -> self::Opand {
+            match eval_opand(&opand, facts) {
+                Lattice::int64(int64) => self::Opand::int64(int64),
+                _ => opand,
+            }
+        }
+
+        fn is_constant(aloc: &utils::Aloc, facts: &Facts) -> bool {
+            matches!(facts.values.get(aloc), Some(Lattice::int64(_)))
+        }
+
+        let Self(p) = self;
+        let p = propagate_p(p, options);
+        Self(p)
+    }
+
+    /// SimplifyBinops: SsaLang -> SsaLang
+    ///
+    /// ### Purpose:
+    /// Rewrites binops by constants into cheaper ones: multiplications by
+    /// powers of two into shifts, and binops which leave their opand as it is,
+    /// or which always give zero, into copies.
+    ///
+    /// ### Notes:
+    /// A chain of additions, or of multiplications, by constants, such as
+    /// (+ (+ x 1) 2), is first reassociated into a single binop by the
+    /// constants combined. This is exact, as binops wrap. The binops which
+    /// are left unused are removed by eliminate-dead-code.
+    ///
+    /// In checked overflow mode, chains are not reassociated and
+    /// multiplications are not turned into shifts, as either would change
+    /// which binops overflow; constants which overflow are not folded.
+    pub fn simplify_binops(self, options: &utils::CompileOptions) -> Self {
+        // The binop by a constant which sets each aloc, before it is reduced.
+        type Chains = HashMap<utils::Aloc, (utils::Binop, self::Opand, i64)>;
+
+        fn simplify_p(p: self::P, options: &utils::CompileOptions) -> self::P {
+            match p {
+                self::P::module { lambdas, blocks } => {
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, blocks }| {
+                            let blocks = simplify_blocks(blocks, options);
+                            self::Lambda { label, blocks }
+                        })
+                        .collect();
+                    let blocks = simplify_blocks(blocks, options);
+                    self::P::module { lambdas, blocks }
+                },
+            }
+        }
+
+        fn simplify_blocks(
+            blocks: Vec<self::Block>,
+            options: &utils::CompileOptions,
+        ) -> Vec<self::Block> {
+            let mut chains = Chains::default();
+            blocks
+                .into_iter()
+                .map(|mut block| {
+                    block.stmts = block
+                        .stmts
+                        .into_iter()
+                        .map(|stmt| simplify_stmt(stmt, &mut chains, options))
+                        .collect();
+                    block
+                })
+                .collect()
+        }
+
+        // The blocks are in an order in which each aloc is set before it is
+        // used, so the chain which sets an opand is known by its use.
+        fn simplify_stmt(
+            stmt: self::Stmt,
+            chains: &mut Chains,
+            options: &utils::CompileOptions,
+        ) -> self::Stmt {
+            let wrapping =
+                options.overflow_mode == utils::OverflowMode::wrapping;
+            match stmt {
+                self::Stmt::set_aloc_value {
+                    aloc,
+                    value:
+                        self::Value::binop {
+                            binop,
+                            opand1,
+                            opand2,
+                        },
+                } => {
+                    let reassociated =
+                        reassociate(binop, opand1, opand2, chains, wrapping);
+                    let value = match reassociated {
+                        Ok((binop, opand, int64)) => {
+                            let chained = matches!(
+                                binop,
+                                utils::Binop::plus | utils::Binop::multiply
+                            );
+                            if wrapping && chained {
+                                let chain = (binop, opand.clone(), int64);
+                                chains.insert(aloc.clone(), chain);
+                            }
+                            reduce(binop, opand, int64, wrapping)
+                        },
+                        Err(value) => value,
+                    };
+                    self::Stmt::set_aloc_value { aloc, value }
+                },
+                stmt => stmt,
+            }
+        }
+
+        // Puts the binop in the form of an opand by a constant, folding in
+        // the constant of the chain which sets the opand, if it has one of
+        // the same binop. A binop which has no such form is returned as it
+        // is, or folded if both its opands are constant and it does not
+        // overflow where overflow traps.
+        fn reassociate(
+            binop: utils::Binop,
+            opand1: self::Opand,
+            opand2: self::Opand,
+            chains: &Chains,
+            wrapping: bool,
+        ) -> Result<(utils::Binop, self::Opand, i64), self::Value> {
+            let (binop, opand, int64) = match (binop, opand1, opand2) {
+                (binop, self::Opand::int64(int1), self::Opand::int64(int2)) => {
+                    let int64 = match wrapping {
+                        true => Some(binop.eval(int1, int2)),
+                        false => binop.checked_eval(int1, int2),
+                    };
+                    return Err(match int64 {
+                        Some(int64) => {
+                            self::Value::triv(self::Opand::int64(int64))
+                        },
+                        None => self::Value::binop {
+                            binop,
+                            opand1: self::Opand::int64(int1),
+                            opand2: self::Opand::int64(int2),
+                        },
+                    });
+                },
+                (utils::Binop::minus, opand, self::Opand::int64(int64))
+                    if int64 != i64::MIN =>
+                {
+                    (utils::Binop::plus, opand, -int64)
+                },
+                (binop, opand, self::Opand::int64(int64))
+                | (binop, self::Opand::int64(int64), opand)
+                    if is_commutative(binop) =>
+                {
+                    (binop, opand, int64)
+                },
+                (binop, opand, self::Opand::int64(int64)) => {
+                    return Ok((binop, opand, int64))
+                },
+                (binop, opand1, opand2) => {
+                    return Err(self::Value::binop {
+                        binop,
+                        opand1,
+                        opand2,
+                    })
+                },
+            };
+            let chain = match &opand {
+                self::Opand::aloc(aloc) => chains.get(aloc),
+                self::Opand::int64(..) => None,
+            };
+            match chain {
+                Some((chain_binop, chain_opand, chain_int64))
+                    if *chain_binop == binop =>
+                {
+                    let int64 = binop.eval(*chain_int64, int64);
+                    Ok((binop, chain_opand.clone(), int64))
+                },
+                _ => Ok((binop, opand, int64)),
+            }
+        }
+
+        fn reduce(
+            binop: utils::Binop,
+            opand: self::Opand,
+            int64: i64,
+            wrapping: bool,
+        ) -> self::Value {
+            match (binop, int64) {
+                (utils::Binop::multiply | utils::Binop::bitwise_and, 0) => {
+                    self::Value::triv(self::Opand::int64(0))
+                },
+                (
+                    utils::Binop::plus
+                    | utils::Binop::minus
+                    | utils::Binop::bitwise_ior
+                    | utils::Binop::bitwise_xor
+                    | utils::Binop::arithmetic_shift_right
+                    | utils::Binop::shift_left,
+                    0,
+                )
+                | (utils::Binop::multiply, 1) => self::Value::triv(opand),
+                (utils::Binop::multiply, int64)
+                    if wrapping && (int64 as u64).is_power_of_two() =>
+                {
+                    let shift = (int64 as u64).trailing_zeros() as i64;
+                    self::Value::binop {
+                        binop: utils::Binop::shift_left,
+                        opand1: opand,
+                        opand2: self::Opand::int64(shift),
+                    }
+                },
+                (utils::Binop::plus, int64)
+                    if int64 < 0 && int64 != i64::MIN =>
+                {
+                    self::Value::binop {
+                        binop: utils::Binop::minus,
+                        opand1: opand,
+                        opand2: self::Opand::int64(-int64),
+                    }
+                },
+                (binop, int64) => self::Value::binop {
+                    binop,
+                    opand1: opand,
+                    opand2: self::Opand::int64(int64),
+                },
+            }
+        }
+
+        fn is_commutative(binop: utils::Binop) -> bool {
+            matches!(
+                binop,
+                utils::Binop::plus
+                    | utils::Binop::multiply
+                    | utils::Binop::bitwise_and
+                    | utils::Binop::bitwise_ior
+                    | utils::Binop::bitwise_xor
+            )
+        }
+
+        let Self(p) = self;
+        let p = simplify_p(p, options);
+        Self(p)
+    }
+
+    /// EliminateDeadCode: SsaLang -> SsaLang
+    ///
+    /// ### Purpose:
+    /// Removes each stmt and phi which sets an aloc that no jump, memory
+    /// store or argument uses, whether directly or through the values of other
+    /// alocs.
+    pub fn eliminate_dead_code(self) -> Self {
+        fn eliminate_p(p: self::P) -> self::P {
+            match p {
+                self::P::module { lambdas, blocks } => {
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, blocks }| {
+                            let blocks = eliminate_blocks(blocks);
+                            self::Lambda { label, blocks }
+                        })
+                        .collect();
+                    let blocks = eliminate_blocks(blocks);
+                    self::P::module { lambdas, blocks }
+                },
+            }
+        }
+
+        fn eliminate_blocks(blocks: Vec<self::Block>) -> Vec<self::Block> {
+            let live = live_alocs(&blocks);
+            blocks
+                .into_iter()
+                .map(|mut block| {
+                    block.phis.retain(|phi| live.contains(&phi.aloc));
+                    block.stmts.retain(|stmt| match stmt {
+                        self::Stmt::set_aloc_value { aloc, .. }
+                        | self::Stmt::set_aloc_rloc { aloc, .. } => {
+                            live.contains(aloc)
+                        },
+                        self::Stmt::set_rloc_opand { .. }
+                        | self::Stmt::mset { .. } => true,
+                    });
+                    block
+                })
+                .collect()
+        }
+
+        fn live_alocs(blocks: &[self::Block]) -> utils::AlocSet {
+            let mut uses = HashMap::<&utils::Aloc, Vec<&self::Opand>>::new();
+            let mut pending = vec![];
+            blocks.iter().for_each(|block| {
+                block.phis.iter().for_each(|phi| {
+                    let opands = phi.opands.iter().map(|(_, opand)| opand);
+                    uses.insert(&phi.aloc, opands.collect());
+                });
+                block.stmts.iter().for_each(|stmt| match stmt {
+                    self::Stmt::set_aloc_value { aloc, value } => {
+                        uses.insert(aloc, value_opands(value));
+                    },
+                    self::Stmt::set_aloc_rloc { aloc, .. } => {
+                        uses.insert(aloc, vec![]);
+                    },
+                    self::Stmt::set_rloc_opand { opand, .. } => {
+                        pending.push(opand)
+                    },
+                    self::Stmt::mset {
+                        opand1,
+                        opand2,
+                        opand3,
+                    } => pending.extend([opand1, opand2, opand3]),
+                });
+                pending.extend(jump_opands(&block.jump));
+            });
+            let mut live = utils::AlocSet::default();
+            while let Some(opand) = pending.pop() {
+                if let self::Opand::aloc(aloc) = opand {
+                    if live.insert(aloc.clone()) {
+                        let opands = uses.remove(aloc).unwrap_or_default();
+                        pending.extend(opands);
+                    }
+                }
+            }
+            live
+        }
+
+        fn value_opands(value: &self::Value) -> Vec<&self::Opand> {
+            match value {
+                self::Value::triv(opand) | self::Value::alloc { opand } => {
+                    vec![opand]
+                },
+                self::Value::binop { opand1, opand2, .. }
+                | self::Value::mref { opand1, opand2 } => vec![opand1, opand2],
+                self::Value::label(..) => vec![],
+            }
+        }
+
+        fn jump_opands(jump: &self::Jump) -> Vec<&self::Opand> {
+            match jump {
+                self::Jump::halt(opand)
+                | self::Jump::switch { opand, .. }
+                | self::Jump::jump {
+                    trg: self::Trg::opand(opand),
+                    ..
+                } => vec![opand],
+                self::Jump::r#if {
+                    cond: self::Cond::relop { opand1, opand2, .. },
+                    ..
+                }
+                | self::Jump::r#while {
+                    cond: self::Cond::relop { opand1, opand2, .. },
+                    ..
+                } => vec![opand1, opand2],
+                _ => vec![],
+            }
+        }
+
+        let Self(p) = self;
+        let p = eliminate_p(p);
+        Self(p)
+    }
+
+    /// DestructSsa: SsaLang -> AsmPredLang
+    ///
+    /// ### Purpose:
+    /// Compiles Ssa-lang to Asm-pred-lang v2, replacing each phi with copies
+    /// at the end of the blocks which jump to its block, and recovering the
+    /// nesting of the program from its jumps, and selecting sequences of
+    /// abstract assembly instructions to implement its statements.
+    ///
+    /// ### Notes:
+    /// The copies into the phis of a block are made in parallel: an opand
+    /// which is also the aloc of one of the phis is first copied into a fresh
+    /// aloc. The jump tables are allocated once, at the entry of the program
+    /// or procedure which uses them.
+    pub fn destruct_ssa(self) -> target::AsmPredLang {
+        // How a region of blocks ends: by halting, by jumping to the label at
+        // which it stops, or by branching into a loop and out of it again.
+        enum End {
+            halt(target::Tail),
+            stop,
+            r#while {
+                cond: self::Cond,
+                body: utils::Label,
+                exit: utils::Label,
+            },
+        }
+
+        // The blocks which are yet to be destructed, the headers of the
+        // whiles and of the loops among them, and the jump tables destructed
+        // so far.
+        struct Blocks {
+            blocks: HashMap<utils::Label, (Vec<target::Effect>, self::Jump)>,
+            headers: HashSet<utils::Label>,
+            loops: HashSet<utils::Label>,
+            tables: Vec<target::Effect>,
+        }
+
+        fn destruct_p(p: self::P) -> target::P {
+            match p {
+                self::P::module { lambdas, blocks } => {
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, blocks }| {
+                            let info = utils::Info::default();
+                            let tail = destruct_blocks(blocks);
+                            target::Lambda { label, info, tail }
+                        })
+                        .collect();
+                    let tail = destruct_blocks(blocks);
+                    target::P::module {
+                        info: utils::Info::default(),
+                        lambdas,
+                        tail,
+                    }
+                },
+            }
+        }
+
+        fn destruct_blocks(blocks: Vec<self::Block>) -> target::Tail {
+            let entry = blocks.first().unwrap().label.clone();
+            let mut blocks = destruct_phis(blocks);
+            let tail = destruct_tail(entry, None, &mut blocks);
+            let mut tables = blocks.tables;
+            make_begins!((tables, tail) => target::Tail::tail)
+        }
+
+        // Selects the stmts of each block, followed by the copies into the
+        // phis of the block it jumps to.
+        fn destruct_phis(blocks: Vec<self::Block>) -> Blocks {
+            let mut copies =
+                HashMap::<utils::Label, Vec<(utils::Aloc, self::Opand)>>::new();
+            blocks.iter().for_each(|block| {
+                block.phis.iter().for_each(|phi| {
+                    phi.opands.iter().for_each(|(label, opand)| {
+                        let copy = (phi.aloc.clone(), opand.clone());
+                        copies.entry(label.clone()).or_default().push(copy)
+                    })
+                })
+            });
+            let headers = blocks
+                .iter()
+                .filter_map(|block| match &block.jump {
+                    self::Jump::r#while { header, .. } => Some(header.clone()),
+                    _ => None,
+                })
+                .collect();
+            let loops = blocks
+                .iter()
+                .filter_map(|block| match &block.jump {
+                    self::Jump::r#loop { header } => Some(header.clone()),
+                    _ => None,
+                })
+                .collect();
+            let blocks = blocks
+                .into_iter()
+                .map(|block| {
+                    let mut effects = block
+                        .stmts
+                        .into_iter()
+                        .flat_map(select_stmt)
+                        .collect::<Vec<_>>();
+                    let copies =
+                        copies.remove(&block.label).unwrap_or_default();
+                    effects.extend(sequentialize_copies(copies));
+                    (block.label, (effects, block.jump))
+                })
+                .collect();
+            let tables = vec![];
+            Blocks {
+                blocks,
+                headers,
+                loops,
+                tables,
+            }
+        }
+
+        fn sequentialize_copies(
+            copies: Vec<(utils::Aloc, self::Opand)>,
+        ) -> Vec<target::Effect> {
+            let alocs = copies
+                .iter()
+                .map(|(aloc, _)| aloc.clone())
+                .collect::<utils::AlocSet>();
+            let mut saves = vec![];
+            let mut effects = vec![];
+            copies.into_iter().for_each(|(aloc, triv)| match triv {
+                target::Triv::aloc(ref source) if *source == aloc => (),
+                target::Triv::aloc(ref source) if alocs.contains(source) => {
+                    let tmp = utils::Aloc::fresh_with_name(&source.name);
+                    saves.push(target::Effect::set_aloc_triv {
+                        aloc: tmp.clone(),
+                        triv,
+                    });
+                    let triv = target::Triv::aloc(tmp);
+                    effects.push(target::Effect::set_aloc_triv { aloc, triv });
+                },
+                triv => {
+                    effects.push(target::Effect::set_aloc_triv { aloc, triv })
+                },
+            });
+            saves.extend(effects);
+            saves
+        }
+
+        // Destructs the blocks from the label on, following their jumps until
+        // the region ends.
+        fn destruct_region(
+            label: utils::Label,
+            stop: Option<&utils::Label>,
+            blocks: &mut Blocks,
+        ) -> (Vec<target::Effect>, End) {
+            let mut effects = vec![];
+            let mut label = label;
+            loop {
+                let (block_effects, jump) =
+                    blocks.blocks.remove(&label).unwrap();
+                effects.extend(block_effects);
+                label = match jump {
+                    self::Jump::halt(triv) => {
+                        return (effects, End::halt(target::Tail::halt(triv)))
+                    },
+                    self::Jump::goto(next) if Some(&next) == stop => {
+                        return (effects, End::stop)
+                    },
+                    self::Jump::goto(next) if blocks.loops.contains(&next) => {
+                        let trg = target::Trg::label(next);
+                        let tail = target::Tail::jump { trg, locs: vec![] };
+                        return (effects, End::halt(tail));
+                    },
+                    self::Jump::goto(next)
+                        if blocks.headers.contains(&next) =>
+                    {
+                        let (effect, exit) = destruct_loop(next, blocks);
+                        effects.push(effect);
+                        exit
+                    },
+                    self::Jump::goto(next) => next,
+                    self::Jump::jump { trg, locs } => {
+                        let trg = match trg {
+                            self::Trg::label(label) => {
+                                target::Trg::label(label)
+                            },
+                            self::Trg::opand(opand) => {
+                                let (trg_effects, aloc) = select_aloc(opand);
+                                effects.extend(trg_effects);
+                                target::Trg::aloc(aloc)
+                            },
+                        };
+                        let tail = target::Tail::jump { trg, locs };
+                        return (effects, End::halt(tail));
+                    },
+                    self::Jump::r#loop { header } => {
+                        let tail = destruct_tail(header.clone(), stop, blocks);
+                        let tail = Box::new(tail);
+                        let tail = target::Tail::r#loop {
+                            label: header,
+                            tail,
+                        };
+                        return (effects, End::halt(tail));
+                    },
+                    self::Jump::r#if {
+                        cond,
+                        label1,
+                        label2,
+                        join: None,
+                    } => {
+                        let (cond_effects, pred) = select_cond(cond);
+                        effects.extend(cond_effects);
+                        let tail1 = destruct_tail(label1, stop, blocks);
+                        let tail2 = destruct_tail(label2, stop, blocks);
+                        let tail1 = Box::new(tail1);
+                        let tail2 = Box::new(tail2);
+                        let tail = target::Tail::r#if { pred, tail1, tail2 };
+                        return (effects, End::halt(tail));
+                    },
+                    self::Jump::r#if {
+                        cond,
+                        label1,
+                        label2,
+                        join: Some(join),
+                    } => {
+                        let (cond_effects, pred) = select_cond(cond);
+                        effects.extend(cond_effects);
+                        let (effects1, _) =
+                            destruct_region(label1, Some(&join), blocks);
+                        let (effects2, _) =
+                            destruct_region(label2, Some(&join), blocks);
+                        let effect1 = target::Effect::begin(effects1);
+                        let effect2 = target::Effect::begin(effects2);
+                        let effect1 = Box::new(effect1);
+                        let effect2 = Box::new(effect2);
+                        effects.push(target::Effect::r#if {
+                            pred,
+                            effect1,
+                            effect2,
+                        });
+                        join
+                    },
+                    self::Jump::r#while {
+                        cond, body, exit, ..
+                    } => return (effects, End::r#while { cond, body, exit }),
+                    // The index is copied, as the jump through the table
+                    // overwrites it.
+                    self::Jump::switch {
+                        opand,
+                        table,
+                        labels,
+                        join,
+                    } => {
+                        let aloc = utils::Aloc::fresh();
+                        let jump_table = utils::Aloc::fresh();
+                        let length = table.len();
+                        blocks.tables.push(target::Effect::set_aloc_jump_table {
+                            aloc: jump_table.clone(),
+                            labels: table
+                                .into_iter()
+                                .map(|index| labels[index].clone())
+                                .collect(),
+                        });
+                        effects.push(target::Effect::set_aloc_triv {
+                            aloc: aloc.clone(),
+                            triv: opand,
+                        });
+                        let cases = labels
+                            .iter()
+                            .map(|label| {
+                                let label = label.clone();
+                                let (effects, _) =
+                                    destruct_region(label, Some(&join), blocks);
+                                target::Effect::begin(effects)
+                            })
+                            .collect();
+                        effects.push(target::Effect::switch {
+                            aloc,
+                            table: jump_table,
+                            length,
+                            labels,
+                            effects: cases,
+                        });
+                        join
+                    },
+                };
+            }
+        }
+
+        fn destruct_tail(
+            label: utils::Label,
+            stop: Option<&utils::Label>,
+            blocks: &mut Blocks,
+        ) -> target::Tail {
+            let (mut effects, end) = destruct_region(label, stop, blocks);
+            let End::halt(tail) = end else {
+                unreachable!("A region in tail position always halts.")
+            };
+            make_begins!((effects, tail) => target::Tail::tail)
+        }
+
+        // The blocks from the header up to its while make up the pred of the
+        // loop, and are run on every iteration.
+        fn destruct_loop(
+            header: utils::Label,
+            blocks: &mut Blocks,
+        ) -> (target::Effect, utils::Label) {
+            let (mut effects, end) =
+                destruct_region(header.clone(), None, blocks);
+            let End::r#while { cond, body, exit } = end else {
+                unreachable!("The header of a loop reaches its while.")
+            };
+            let (cond_effects, pred) = select_cond(cond);
+            effects.extend(cond_effects);
+            let pred = make_begins!((effects, pred) => target::Pred::pred);
+            let (effects, _) = destruct_region(body, Some(&header), blocks);
+            let effect = Box::new(target::Effect::begin(effects));
+            (target::Effect::r#while { pred, effect }, exit)
+        }
+
+        fn select_cond(
+            cond: self::Cond,
+        ) -> (Vec<target::Effect>, target::Pred) {
+            match cond {
+                self::Cond::relop {
+                    relop,
+                    opand1,
+                    opand2,
+                } => {
+                    let (effects, aloc) = select_aloc(opand1);
+                    let pred = target::Pred::relop {
+                        relop,
+                        aloc,
+                        triv: opand2,
+                    };
+                    (effects, pred)
+                },
+                self::Cond::r#true => (vec![], target::Pred::r#true),
+                self::Cond::r#false => (vec![], target::Pred::r#false),
+            }
+        }
+
+        fn select_stmt(stmt: self::Stmt) -> Vec<target::Effect> {
+            match stmt {
+                self::Stmt::set_aloc_value { aloc, value } => match value {
+                    self::Value::triv(triv) => {
+                        vec![target::Effect::set_aloc_triv { aloc, triv }]
+                    },
+                    self::Value::label(label) => {
+                        vec![target::Effect::set_aloc_label { aloc, label }]
+                    },
+                    self::Value::binop {
+                        binop,
+                        opand1,
+                        opand2,
+                    } => {
+                        let effect1 = target::Effect::set_aloc_triv {
+                            aloc: aloc.clone(),
+                            triv: opand1,
+                        };
+                        let effect2 = target::Effect::set_aloc_binop_aloc_triv {
+                            aloc,
+                            binop,
+                            triv: opand2,
+                        };
+                        vec![effect1, effect2]
+                    },
+                    self::Value::alloc { opand } => {
+                        vec![target::Effect::set_aloc_alloc_triv {
+                            aloc,
+                            triv: opand,
+                        }]
+                    },
+                    self::Value::mref { opand1, opand2 } => {
+                        let (mut effects, base) = select_aloc(opand1);
+                        effects.push(target::Effect::set_aloc_mref_aloc_triv {
+                            aloc,
+                            base,
+                            index: opand2,
+                        });
+                        effects
+                    },
+                },
+                self::Stmt::set_aloc_rloc { aloc, rloc } => {
+                    vec![target::Effect::set_aloc_rloc { aloc, rloc }]
+                },
+                self::Stmt::set_rloc_opand { rloc, opand } => {
+                    vec![target::Effect::set_rloc_triv { rloc, triv: opand }]
+                },
+                self::Stmt::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => {
+                    let (mut effects, aloc) = select_aloc(opand1);
+                    effects.push(target::Effect::mset_aloc_triv_triv {
+                        aloc,
+                        index: opand2,
+                        triv: opand3,
+                    });
+                    effects
+                },
+            }
+        }
+
+        fn select_aloc(
+            opand: self::Opand,
+        ) -> (Vec<target::Effect>, utils::Aloc) {
+            match opand {
+                target::Triv::aloc(aloc) => (vec![], aloc),
+                triv => {
+                    let aloc = utils::Aloc::fresh();
+                    let effect = target::Effect::set_aloc_triv {
+                        aloc: aloc.clone(),
+                        triv,
+                    };
+                    (vec![effect], aloc)
+                },
+            }
+        }
+
+        let Self(p) = self;
+        let p = destruct_p(p);
+        target::AsmPredLang(p)
+    }
+}
@@ -0,0 +1,505 @@
+use serial_test::serial;
+
+use crate::imperative_abstractions::imp_cmf_lang as source;
+use crate::imperative_abstractions::ssa_lang as ssa;
+use crate::register_allocation::asm_pred_lang as target;
+use crate::utils;
+
+fn aloc(aloc: &utils::Aloc) -> target::Triv {
+    target::Triv::aloc(aloc.clone())
+}
+
+fn label(name: &str, index: usize) -> utils::Label {
+    utils::Label(format!("L.{}.{}", name, index))
+}
+
+// Sets y to one or two depending on whether the value of x is negative, and
+// halts with y.
+fn branch_on(value: source::Value) -> source::ImpCmfLang {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh_with_name("x");
+    let y = utils::Aloc::fresh_with_name("y");
+    let set = |aloc: &utils::Aloc, value| source::Effect::set_aloc_value {
+        aloc: aloc.clone(),
+        value,
+    };
+    let int64 = |int64| source::Value::triv(source::Triv::opand(int64));
+    let effects = vec![
+        set(&x, value),
+        source::Effect::r#if {
+            pred: source::Pred::relop {
+                relop: utils::Relop::lt,
+                opand1: aloc(&x),
+                opand2: target::Triv::int64(0),
+            },
+            effect1: Box::new(set(&y, int64(target::Triv::int64(1)))),
+            effect2: Box::new(set(&y, int64(target::Triv::int64(2)))),
+        },
+    ];
+    source::ImpCmfLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects,
+            tail: Box::new(source::Tail::value(source::Value::triv(
+                source::Triv::opand(aloc(&y)),
+            ))),
+        },
+    })
+}
+
+#[test]
+#[serial]
+fn construct_phi_at_join() {
+    let p = branch_on(source::Value::alloc {
+        opand: target::Triv::int64(16),
+    });
+    let actual = p.construct_ssa();
+
+    let x = utils::Aloc {
+        name: "x".into(),
+        index: 2,
+    };
+    let y = utils::Aloc {
+        name: "y".into(),
+        index: 3,
+    };
+    let [entry, then, r#else, join] = [
+        label("entry", 0),
+        label("then", 1),
+        label("else", 2),
+        label("join", 3),
+    ];
+    let block = |label: &utils::Label, phis, stmts, jump| ssa::Block {
+        label: label.clone(),
+        phis,
+        stmts,
+        jump,
+    };
+    let expected = ssa::SsaLang(ssa::P::module {
+        lambdas: vec![],
+        blocks: vec![
+            block(
+                &entry,
+                vec![],
+                vec![ssa::Stmt::set_aloc_value {
+                    aloc: x.clone(),
+                    value: ssa::Value::alloc {
+                        opand: target::Triv::int64(16),
+                    },
+                }],
+                ssa::Jump::r#if {
+                    cond: ssa::Cond::relop {
+                        relop: utils::Relop::lt,
+                        opand1: aloc(&x),
+                        opand2: target::Triv::int64(0),
+                    },
+                    label1: then.clone(),
+                    label2: r#else.clone(),
+                    join: Some(join.clone()),
+                },
+            ),
+            block(&then, vec![], vec![], ssa::Jump::goto(join.clone())),
+            block(&r#else, vec![], vec![], ssa::Jump::goto(join.clone())),
+            block(
+                &join,
+                vec![ssa::Phi {
+                    aloc: y.clone(),
+                    opands: vec![
+                        (then.clone(), target::Triv::int64(1)),
+                        (r#else.clone(), target::Triv::int64(2)),
+                    ],
+                }],
+                vec![],
+                ssa::Jump::halt(aloc(&y)),
+            ),
+        ],
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn fold_constant_branch() {
+    let p = branch_on(source::Value::triv(source::Triv::opand(
+        target::Triv::int64(5),
+    )));
+    let actual = p
+        .construct_ssa()
+        .propagate_constants(&utils::CompileOptions::default())
+        .eliminate_dead_code()
+        .destruct_ssa();
+
+    let expected = target::AsmPredLang(target::P::module {
+        info: utils::Info::default(),
+        lambdas: vec![],
+        tail: target::Tail::halt(target::Triv::int64(2)),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn fold_overflowing_binop_only_when_wrapping() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh_with_name("x");
+    let entry = utils::Label::new_with_name("entry");
+    let p = |stmts| {
+        ssa::SsaLang(ssa::P::module {
+            lambdas: vec![],
+            blocks: vec![ssa::Block {
+                label: entry.clone(),
+                phis: vec![],
+                stmts,
+                jump: ssa::Jump::halt(aloc(&x)),
+            }],
+        })
+    };
+    let overflowing = || {
+        p(vec![ssa::Stmt::set_aloc_value {
+            aloc: x.clone(),
+            value: ssa::Value::binop {
+                binop: utils::Binop::plus,
+                opand1: target::Triv::int64(i64::MAX),
+                opand2: target::Triv::int64(1),
+            },
+        }])
+    };
+
+    let wrapping = utils::CompileOptions::default();
+    let checked = utils::CompileOptions {
+        overflow_mode: utils::OverflowMode::checked,
+        ..Default::default()
+    };
+
+    let expected = ssa::SsaLang(ssa::P::module {
+        lambdas: vec![],
+        blocks: vec![ssa::Block {
+            label: entry.clone(),
+            phis: vec![],
+            stmts: vec![],
+            jump: ssa::Jump::halt(target::Triv::int64(i64::MIN)),
+        }],
+    });
+    assert_eq!(overflowing().propagate_constants(&wrapping), expected);
+    assert_eq!(overflowing().propagate_constants(&checked), overflowing());
+}
+
+#[test]
+#[serial]
+fn destruct_loop_in_procedure() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh_with_name("x");
+    let f = utils::Label::new_with_name("f");
+    let header = utils::Label::new_with_name("loop");
+    let rdi = target::Rloc::reg(utils::Reg::rdi);
+    let rbp = target::Rloc::reg(utils::Reg::rbp);
+    let p = source::ImpCmfLang(source::P::module {
+        lambdas: vec![source::Lambda {
+            label: f.clone(),
+            tail: source::Tail::begin {
+                effects: vec![source::Effect::set_aloc_rloc {
+                    aloc: x.clone(),
+                    rloc: rdi.clone(),
+                }],
+                tail: Box::new(source::Tail::r#loop {
+                    label: header.clone(),
+                    tail: Box::new(source::Tail::r#if {
+                        pred: source::Pred::relop {
+                            relop: utils::Relop::gt,
+                            opand1: aloc(&x),
+                            opand2: target::Triv::int64(0),
+                        },
+                        tail1: Box::new(source::Tail::begin {
+                            effects: vec![source::Effect::set_aloc_value {
+                                aloc: x.clone(),
+                                value: source::Value::binop {
+                                    binop: utils::Binop::minus,
+                                    opand1: aloc(&x),
+                                    opand2: target::Triv::int64(1),
+                                },
+                            }],
+                            tail: Box::new(source::Tail::jump {
+                                trg: source::Triv::label(header.clone()),
+                                locs: vec![],
+                            }),
+                        }),
+                        tail2: Box::new(source::Tail::value(
+                            source::Value::triv(source::Triv::opand(aloc(&x))),
+                        )),
+                    }),
+                }),
+            },
+        }],
+        tail: source::Tail::begin {
+            effects: vec![source::Effect::set_rloc_opand {
+                rloc: rdi.clone(),
+                opand: target::Triv::int64(5),
+            }],
+            tail: Box::new(source::Tail::jump {
+                trg: source::Triv::label(f.clone()),
+                locs: vec![rbp.clone(), rdi.clone()],
+            }),
+        },
+    });
+    let actual = p
+        .construct_ssa()
+        .propagate_constants(&utils::CompileOptions::default())
+        .eliminate_dead_code()
+        .destruct_ssa();
+
+    let [x1, x2, x3] = [1, 2, 3].map(|index| utils::Aloc {
+        name: "x".into(),
+        index,
+    });
+    let set = |aloc: &utils::Aloc, triv| target::Effect::set_aloc_triv {
+        aloc: aloc.clone(),
+        triv,
+    };
+    let expected = target::AsmPredLang(target::P::module {
+        info: utils::Info::default(),
+        lambdas: vec![target::Lambda {
+            label: f.clone(),
+            info: utils::Info::default(),
+            tail: target::Tail::begin {
+                effects: vec![
+                    target::Effect::set_aloc_rloc {
+                        aloc: x1.clone(),
+                        rloc: rdi.clone(),
+                    },
+                    set(&x2, aloc(&x1)),
+                ],
+                tail: Box::new(target::Tail::r#loop {
+                    label: header.clone(),
+                    tail: Box::new(target::Tail::r#if {
+                        pred: target::Pred::relop {
+                            relop: utils::Relop::gt,
+                            aloc: x2.clone(),
+                            triv: target::Triv::int64(0),
+                        },
+                        tail1: Box::new(target::Tail::begin {
+                            effects: vec![
+                                set(&x3, aloc(&x2)),
+                                target::Effect::set_aloc_binop_aloc_triv {
+                                    aloc: x3.clone(),
+                                    binop: utils::Binop::minus,
+                                    triv: target::Triv::int64(1),
+                                },
+                                set(&x2, aloc(&x3)),
+                            ],
+                            tail: Box::new(target::Tail::jump {
+                                trg: target::Trg::label(header.clone()),
+                                locs: vec![],
+                            }),
+                        }),
+                        tail2: Box::new(target::Tail::halt(aloc(&x2))),
+                    }),
+                }),
+            },
+        }],
+        tail: target::Tail::begin {
+            effects: vec![target::Effect::set_rloc_triv {
+                rloc: rdi.clone(),
+                triv: target::Triv::int64(5),
+            }],
+            tail: Box::new(target::Tail::jump {
+                trg: target::Trg::label(f),
+                locs: vec![rbp, rdi],
+            }),
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn destruct_loop() {
+    utils::reset_all_indices();
+
+    let i = utils::Aloc::fresh_with_name("i");
+    let p = source::ImpCmfLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![
+                source::Effect::set_aloc_value {
+                    aloc: i.clone(),
+                    value: source::Value::triv(source::Triv::opand(
+                        target::Triv::int64(0),
+                    )),
+                },
+                source::Effect::r#while {
+                    pred: source::Pred::relop {
+                        relop: utils::Relop::lt,
+                        opand1: aloc(&i),
+                        opand2: target::Triv::int64(10),
+                    },
+                    effect: Box::new(source::Effect::set_aloc_value {
+                        aloc: i.clone(),
+                        value: source::Value::binop {
+                            binop: utils::Binop::plus,
+                            opand1: aloc(&i),
+                            opand2: target::Triv::int64(1),
+                        },
+                    }),
+                },
+            ],
+            tail: Box::new(source::Tail::value(source::Value::triv(
+                source::Triv::opand(aloc(&i)),
+            ))),
+        },
+    });
+    let actual = p
+        .construct_ssa()
+        .propagate_constants(&utils::CompileOptions::default())
+        .eliminate_dead_code()
+        .destruct_ssa();
+
+    let [i1, i2] = [1, 2].map(|index| utils::Aloc {
+        name: "i".into(),
+        index,
+    });
+    let set = |aloc: &utils::Aloc, triv| target::Effect::set_aloc_triv {
+        aloc: aloc.clone(),
+        triv,
+    };
+    let expected = target::AsmPredLang(target::P::module {
+        info: utils::Info::default(),
+        lambdas: vec![],
+        tail: target::Tail::begin {
+            effects: vec![
+                set(&i1, target::Triv::int64(0)),
+                target::Effect::r#while {
+                    pred: target::Pred::relop {
+                        relop: utils::Relop::lt,
+                        aloc: i1.clone(),
+                        triv: target::Triv::int64(10),
+                    },
+                    effect: Box::new(target::Effect::begin(vec![
+                        set(&i2, aloc(&i1)),
+                        target::Effect::set_aloc_binop_aloc_triv {
+                            aloc: i2.clone(),
+                            binop: utils::Binop::plus,
+                            triv: target::Triv::int64(1),
+                        },
+                        set(&i1, aloc(&i2)),
+                    ])),
+                },
+            ],
+            tail: Box::new(target::Tail::halt(aloc(&i1))),
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn simplify_binops_by_constants() {
+    utils::reset_all_indices();
+
+    let [x, a, b, c, d, e, f] = ["x", "a", "b", "c", "d", "e", "f"]
+        .map(utils::Aloc::fresh_with_name);
+    let int64 = target::Triv::int64;
+    let binop = |aloc: &utils::Aloc, binop, opand1, opand2| {
+        ssa::Stmt::set_aloc_value {
+            aloc: aloc.clone(),
+            value: ssa::Value::binop {
+                binop,
+                opand1,
+                opand2,
+            },
+        }
+    };
+    let entry = utils::Label::new_with_name("entry");
+    let p = |stmts| {
+        ssa::SsaLang(ssa::P::module {
+            lambdas: vec![],
+            blocks: vec![ssa::Block {
+                label: entry.clone(),
+                phis: vec![],
+                stmts,
+                jump: ssa::Jump::halt(aloc(&e)),
+            }],
+        })
+    };
+    let actual = p(vec![
+        binop(&a, utils::Binop::plus, aloc(&x), int64(1)),
+        binop(&b, utils::Binop::plus, aloc(&a), int64(2)),
+        binop(&c, utils::Binop::multiply, int64(4), aloc(&b)),
+        binop(&d, utils::Binop::multiply, aloc(&c), int64(2)),
+        binop(&e, utils::Binop::minus, aloc(&d), int64(1)),
+        binop(&f, utils::Binop::multiply, aloc(&e), int64(0)),
+    ])
+    .simplify_binops(&utils::CompileOptions::default());
+
+    let expected = p(vec![
+        binop(&a, utils::Binop::plus, aloc(&x), int64(1)),
+        binop(&b, utils::Binop::plus, aloc(&x), int64(3)),
+        binop(&c, utils::Binop::shift_left, aloc(&b), int64(2)),
+        binop(&d, utils::Binop::shift_left, aloc(&b), int64(3)),
+        binop(&e, utils::Binop::minus, aloc(&d), int64(1)),
+        ssa::Stmt::set_aloc_value {
+            aloc: f.clone(),
+            value: ssa::Value::triv(int64(0)),
+        },
+    ]);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn simplify_binops_preserves_overflow_when_checked() {
+    utils::reset_all_indices();
+
+    let [x, a, b, c, d] =
+        ["x", "a", "b", "c", "d"].map(utils::Aloc::fresh_with_name);
+    let int64 = target::Triv::int64;
+    let binop = |aloc: &utils::Aloc, binop, opand1, opand2| {
+        ssa::Stmt::set_aloc_value {
+            aloc: aloc.clone(),
+            value: ssa::Value::binop {
+                binop,
+                opand1,
+                opand2,
+            },
+        }
+    };
+    let entry = utils::Label::new_with_name("entry");
+    let p = |stmts| {
+        ssa::SsaLang(ssa::P::module {
+            lambdas: vec![],
+            blocks: vec![ssa::Block {
+                label: entry.clone(),
+                phis: vec![],
+                stmts,
+                jump: ssa::Jump::halt(aloc(&c)),
+            }],
+        })
+    };
+    let checked = utils::CompileOptions {
+        overflow_mode: utils::OverflowMode::checked,
+        ..Default::default()
+    };
+    let actual = p(vec![
+        binop(&a, utils::Binop::plus, aloc(&x), int64(5)),
+        binop(&b, utils::Binop::plus, aloc(&a), int64(-5)),
+        binop(&c, utils::Binop::multiply, int64(4), aloc(&b)),
+        binop(&d, utils::Binop::plus, int64(i64::MAX), int64(1)),
+    ])
+    .simplify_binops(&checked);
+
+    let expected = p(vec![
+        binop(&a, utils::Binop::plus, aloc(&x), int64(5)),
+        binop(&b, utils::Binop::minus, aloc(&a), int64(5)),
+        binop(&c, utils::Binop::multiply, aloc(&b), int64(4)),
+        binop(&d, utils::Binop::plus, int64(i64::MAX), int64(1)),
+    ]);
+
+    assert_eq!(actual, expected);
+}
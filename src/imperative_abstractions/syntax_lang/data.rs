@@ -0,0 +1,35 @@
+use crate::utils;
+
+/// ### Purpose:
+/// A module of the surface language is a list of top-level forms, each either
+/// a define-syntax, a define of a procedure, or (last) the tail of the
+/// module.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum P {
+    module(Vec<Datum>),
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum Datum {
+    int64(i64),
+    boolean(bool),
+    ascii_char(u8),
+    symbol(utils::Name),
+    /// A symbol which the expansion of a macro introduced, marked with the
+    /// expansions it came out of, innermost last.
+    introduced {
+        name: utils::Name,
+        marks: Vec<usize>,
+    },
+    list(Vec<Self>),
+}
+
+/// ### Purpose:
+/// The rules of a syntax-rules transformer.
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct Rules {
+    pub literals: Vec<utils::Name>,
+    pub rules: Vec<(Datum, Datum)>,
+}
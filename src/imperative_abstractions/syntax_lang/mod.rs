@@ -0,0 +1,981 @@
+pub mod data;
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+
+pub use self::data::*;
+use crate::imperative_abstractions::values_lang as target;
+use crate::utils;
+
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct SyntaxLang(pub self::P);
+
+impl SyntaxLang {
+    /// ### Purpose:
+    /// Expands every use of a macro in the module, and parses the expanded
+    /// module into Values-lang v3.
+    ///
+    /// ### Notes:
+    /// Macros are defined by define-syntax and syntax-rules, and are hygienic.
+    /// Each expansion marks the symbols which its template introduces, so a
+    /// binder which an expansion introduces is renamed to a gensym, and
+    /// only binds the symbols introduced by the same expansion. An introduced
+    /// symbol which the expansion does not bind refers to its binding at the
+    /// top level, where the macro was defined, even where the use of the
+    /// macro shadows it.
+    ///
+    /// Macros can only be defined at the top level, and every macro and
+    /// procedure is in scope in the whole module. A symbol is a keyword of the
+    /// core language wherever it is not bound.
+    pub fn expand_macros(self) -> Result<target::ValuesLang, String> {
+        // A symbol, and the marks of the expansions which introduced it.
+        type Id = (utils::Name, Vec<usize>);
+        type Env = utils::LevelledEnv<Id, Binding>;
+        type Matches = HashMap<utils::Name, Match>;
+
+        enum Binding {
+            variable(utils::Ident),
+            r#macro(self::Rules),
+        }
+
+        // What a pattern variable matched, once for each level of ellipsis
+        // which follows it.
+        #[derive(Clone)]
+        enum Match {
+            one(self::Datum),
+            many(Vec<Self>),
+        }
+
+        fn expand_p(p: self::P) -> Result<target::P, String> {
+            match p {
+                self::P::module(mut forms) => {
+                    let tail = forms
+                        .pop()
+                        .ok_or("A module must end in a tail.".to_string())?;
+                    let (env, defines) = forms.into_iter().try_fold(
+                        (Env::default().add_level(), vec![]),
+                        |(env, mut defines), form| {
+                            let env = match define(form)? {
+                                (name, Ok(rules)) => env.insert(
+                                    (name, vec![]),
+                                    Binding::r#macro(rules),
+                                ),
+                                (name, Err(lambda)) => {
+                                    defines.push((name, lambda));
+                                    env.insert(
+                                        (name, vec![]),
+                                        Binding::variable(name.into()),
+                                    )
+                                },
+                            };
+                            Ok::<_, String>((env, defines))
+                        },
+                    )?;
+                    let mut next_mark = usize::default();
+                    let (lambdas, env) = defines.into_iter().try_fold(
+                        (vec![], env),
+                        |(mut lambdas, env), (name, lambda)| {
+                            let (value, env) =
+                                expand_value(lambda, env, &mut next_mark)?;
+                            match value {
+                                target::Value::lambda { args, tail } => {
+                                    let tail = *tail;
+                                    let lambda =
+                                        target::Lambda { name, args, tail };
+                                    lambdas.push(lambda);
+                                    Ok((lambdas, env))
+                                },
+                                _ => Err(format!(
+                                    "The define of '{}' must bind a lambda.",
+                                    name
+                                )),
+                            }
+                        },
+                    )?;
+                    let (tail, _) = expand_tail(tail, env, &mut next_mark)?;
+                    Ok(target::P::module { lambdas, tail })
+                },
+            }
+        }
+
+        // The name a top-level form defines, and either the rules of the macro
+        // or the (unexpanded) lambda it binds.
+        fn define(
+            form: self::Datum,
+        ) -> Result<(utils::Name, Result<self::Rules, self::Datum>), String>
+        {
+            let datums = match form {
+                self::Datum::list(datums) => datums,
+                _ => vec![],
+            };
+            match datums.first() {
+                Some(self::Datum::symbol("define-syntax")) => {
+                    let [_, name, rules] = parts("define-syntax", datums)?;
+                    let name = top_level_name(name)?;
+                    syntax_rules(rules).map(|rules| (name, Ok(rules)))
+                },
+                Some(self::Datum::symbol("define")) => {
+                    let [_, name, lambda] = parts("define", datums)?;
+                    let name = top_level_name(name)?;
+                    Ok((name, Err(lambda)))
+                },
+                _ => Err("Only the last form of a module can be other than a \
+                          define or a define-syntax."
+                    .into()),
+            }
+        }
+
+        fn top_level_name(datum: self::Datum) -> Result<utils::Name, String> {
+            match datum {
+                self::Datum::symbol(name) => Ok(name),
+                _ => Err("A top-level define must name a symbol.".into()),
+            }
+        }
+
+        fn syntax_rules(datum: self::Datum) -> Result<self::Rules, String> {
+            let mut datums = match datum {
+                self::Datum::list(datums) => datums.into_iter(),
+                _ => Err(bad("syntax-rules"))?,
+            };
+            match (datums.next(), datums.next()) {
+                (
+                    Some(self::Datum::symbol("syntax-rules")),
+                    Some(self::Datum::list(literals)),
+                ) => {
+                    let literals = literals
+                        .into_iter()
+                        .map(|literal| match literal {
+                            self::Datum::symbol(name) => Ok(name),
+                            _ => Err(bad("syntax-rules")),
+                        })
+                        .collect::<Result<_, _>>()?;
+                    let rules = datums
+                        .map(|rule| match rule {
+                            self::Datum::list(rule) => {
+                                let [pattern, template] =
+                                    parts("syntax-rules", rule)?;
+                                Ok((pattern, template))
+                            },
+                            _ => Err(bad("syntax-rules")),
+                        })
+                        .collect::<Result<_, _>>()?;
+                    Ok(self::Rules { literals, rules })
+                },
+                _ => Err(bad("syntax-rules")),
+            }
+        }
+
+        fn expand_tail(
+            datum: self::Datum,
+            env: Env,
+            next_mark: &mut usize,
+        ) -> Result<(target::Tail, Env), String> {
+            let datum = expand_uses(datum, &env, next_mark)?;
+            match (head_keyword(&datum, &env), datum) {
+                (Some("let"), self::Datum::list(datums)) => {
+                    let [_, bindings, tail] = parts("let", datums)?;
+                    let (bindings, env) =
+                        expand_bindings(bindings, env.add_level(), next_mark)?;
+                    let (tail, env) = expand_tail(tail, env, next_mark)?;
+                    let tail = Box::new(tail);
+                    let tail = target::Tail::r#let { bindings, tail };
+                    Ok((tail, env.remove_level()))
+                },
+                (Some("if"), self::Datum::list(datums)) => {
+                    let [_, pred, tail1, tail2] = parts("if", datums)?;
+                    let (pred, env) = expand_pred(pred, env, next_mark)?;
+                    let (tail1, env) = expand_tail(tail1, env, next_mark)?;
+                    let (tail2, env) = expand_tail(tail2, env, next_mark)?;
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    Ok((target::Tail::r#if { pred, tail1, tail2 }, env))
+                },
+                (None, self::Datum::list(datums)) if !datums.is_empty() => {
+                    let mut datums = datums.into_iter();
+                    let name = expand_name(datums.next().unwrap(), &env)?;
+                    let args = datums
+                        .map(|datum| expand_triv(datum, &env))
+                        .collect::<Result<_, _>>()?;
+                    Ok((target::Tail::call { name, args }, env))
+                },
+                (_, datum) => {
+                    let (value, env) = expand_value(datum, env, next_mark)?;
+                    Ok((target::Tail::value(value), env))
+                },
+            }
+        }
+
+        fn expand_pred(
+            datum: self::Datum,
+            env: Env,
+            next_mark: &mut usize,
+        ) -> Result<(target::Pred, Env), String> {
+            let datum = expand_uses(datum, &env, next_mark)?;
+            match (head_keyword(&datum, &env), datum) {
+                (Some("let"), self::Datum::list(datums)) => {
+                    let [_, bindings, pred] = parts("let", datums)?;
+                    let (bindings, env) =
+                        expand_bindings(bindings, env.add_level(), next_mark)?;
+                    let (pred, env) = expand_pred(pred, env, next_mark)?;
+                    let pred = Box::new(pred);
+                    let pred = target::Pred::r#let { bindings, pred };
+                    Ok((pred, env.remove_level()))
+                },
+                (Some("if"), self::Datum::list(datums)) => {
+                    let [_, pred1, pred2, pred3] = parts("if", datums)?;
+                    let (pred1, env) = expand_pred(pred1, env, next_mark)?;
+                    let (pred2, env) = expand_pred(pred2, env, next_mark)?;
+                    let (pred3, env) = expand_pred(pred3, env, next_mark)?;
+                    let pred1 = Box::new(pred1);
+                    let pred2 = Box::new(pred2);
+                    let pred3 = Box::new(pred3);
+                    Ok((target::Pred::r#if { pred1, pred2, pred3 }, env))
+                },
+                (Some("not"), self::Datum::list(datums)) => {
+                    let [_, pred] = parts("not", datums)?;
+                    let (pred, env) = expand_pred(pred, env, next_mark)?;
+                    Ok((target::Pred::not(Box::new(pred)), env))
+                },
+                (Some(keyword @ ("and" | "or")), self::Datum::list(datums)) => {
+                    let (preds, env) = datums.into_iter().skip(1).try_fold(
+                        (vec![], env),
+                        |(mut preds, env), pred| {
+                            let (pred, env) =
+                                expand_pred(pred, env, next_mark)?;
+                            preds.push(pred);
+                            Ok::<_, String>((preds, env))
+                        },
+                    )?;
+                    let pred = match keyword {
+                        "and" => target::Pred::and(preds),
+                        _ => target::Pred::or(preds),
+                    };
+                    Ok((pred, env))
+                },
+                (Some(keyword), self::Datum::list(datums))
+                    if relop(keyword).is_some() =>
+                {
+                    let [_, triv1, triv2] = parts(keyword, datums)?;
+                    let relop = relop(keyword).unwrap();
+                    let triv1 = expand_triv(triv1, &env)?;
+                    let triv2 = expand_triv(triv2, &env)?;
+                    Ok((target::Pred::relop { relop, triv1, triv2 }, env))
+                },
+                (_, self::Datum::boolean(true)) => {
+                    Ok((target::Pred::r#true, env))
+                },
+                (_, self::Datum::boolean(false)) => {
+                    Ok((target::Pred::r#false, env))
+                },
+                (_, datum) => {
+                    let triv = expand_triv(datum, &env)?;
+                    Ok((target::Pred::triv(triv), env))
+                },
+            }
+        }
+
+        fn expand_value(
+            datum: self::Datum,
+            env: Env,
+            next_mark: &mut usize,
+        ) -> Result<(target::Value, Env), String> {
+            let datum = expand_uses(datum, &env, next_mark)?;
+            match (head_keyword(&datum, &env), datum) {
+                (Some("let"), self::Datum::list(datums)) => {
+                    let [_, bindings, value] = parts("let", datums)?;
+                    let (bindings, env) =
+                        expand_bindings(bindings, env.add_level(), next_mark)?;
+                    let (value, env) = expand_value(value, env, next_mark)?;
+                    let value = Box::new(value);
+                    let value = target::Value::r#let { bindings, value };
+                    Ok((value, env.remove_level()))
+                },
+                (Some("if"), self::Datum::list(datums)) => {
+                    let [_, pred, value1, value2] = parts("if", datums)?;
+                    let (pred, env) = expand_pred(pred, env, next_mark)?;
+                    let (value1, env) = expand_value(value1, env, next_mark)?;
+                    let (value2, env) = expand_value(value2, env, next_mark)?;
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    Ok((target::Value::r#if { pred, value1, value2 }, env))
+                },
+                (Some("lambda"), self::Datum::list(datums)) => {
+                    let [_, args, tail] = parts("lambda", datums)?;
+                    let args = match args {
+                        self::Datum::list(args) => args,
+                        _ => Err(bad("lambda"))?,
+                    };
+                    let (args, env) = args.into_iter().try_fold(
+                        (vec![], env.add_level()),
+                        |(mut args, env), arg| {
+                            let (arg, env) = bind(arg, env)?;
+                            args.push(arg);
+                            Ok::<_, String>((args, env))
+                        },
+                    )?;
+                    let (tail, env) = expand_tail(tail, env, next_mark)?;
+                    let tail = Box::new(tail);
+                    let value = target::Value::lambda { args, tail };
+                    Ok((value, env.remove_level()))
+                },
+                (Some("set!"), self::Datum::list(datums)) => {
+                    let [_, name, value] = parts("set!", datums)?;
+                    let name = expand_name(name, &env)?;
+                    let (value, env) = expand_value(value, env, next_mark)?;
+                    let value = Box::new(value);
+                    Ok((target::Value::set { name, value }, env))
+                },
+                (Some("begin"), self::Datum::list(datums)) => {
+                    let (mut values, env) =
+                        expand_values(datums, env, next_mark)?;
+                    let value = values
+                        .pop()
+                        .ok_or("A 'begin' form must end in a value.")?;
+                    let value = Box::new(value);
+                    Ok((target::Value::begin { values, value }, env))
+                },
+                (Some("while"), self::Datum::list(datums)) => {
+                    let [_, pred, value] = parts("while", datums)?;
+                    let (pred, env) = expand_pred(pred, env, next_mark)?;
+                    let (value, env) = expand_value(value, env, next_mark)?;
+                    let value = Box::new(value);
+                    Ok((target::Value::r#while { pred, value }, env))
+                },
+                (
+                    Some(keyword @ ("when" | "unless")),
+                    self::Datum::list(datums),
+                ) => {
+                    let [_, pred, value] = parts(keyword, datums)?;
+                    let (pred, env) = expand_pred(pred, env, next_mark)?;
+                    let (value, env) = expand_value(value, env, next_mark)?;
+                    let value = Box::new(value);
+                    let value = match keyword {
+                        "when" => target::Value::when { pred, value },
+                        _ => target::Value::unless { pred, value },
+                    };
+                    Ok((value, env))
+                },
+                (Some("cond"), self::Datum::list(datums)) => {
+                    let (clauses, r#else) = clauses("cond", datums, &env)?;
+                    let (clauses, env) = clauses.into_iter().try_fold(
+                        (vec![], env),
+                        |(mut clauses, env), (pred, value)| {
+                            let (pred, env) =
+                                expand_pred(pred, env, next_mark)?;
+                            let (value, env) =
+                                expand_value(value, env, next_mark)?;
+                            clauses.push((pred, value));
+                            Ok::<_, String>((clauses, env))
+                        },
+                    )?;
+                    let (r#else, env) = expand_value(r#else, env, next_mark)?;
+                    let r#else = Box::new(r#else);
+                    Ok((target::Value::cond { clauses, r#else }, env))
+                },
+                (Some("case"), self::Datum::list(mut datums))
+                    if datums.len() > 1 =>
+                {
+                    let triv = datums.remove(1);
+                    let triv = expand_triv(triv, &env)?;
+                    let (clauses, r#else) = clauses("case", datums, &env)?;
+                    let (clauses, env) = clauses.into_iter().try_fold(
+                        (vec![], env),
+                        |(mut clauses, env), (ints, value)| {
+                            let ints = match ints {
+                                self::Datum::list(ints) => ints
+                                    .into_iter()
+                                    .map(|int| match int {
+                                        self::Datum::int64(int) => Ok(int),
+                                        _ => Err(bad("case")),
+                                    })
+                                    .collect::<Result<_, _>>()?,
+                                _ => Err(bad("case"))?,
+                            };
+                            let (value, env) =
+                                expand_value(value, env, next_mark)?;
+                            clauses.push((ints, value));
+                            Ok::<_, String>((clauses, env))
+                        },
+                    )?;
+                    let (r#else, env) = expand_value(r#else, env, next_mark)?;
+                    let r#else = Box::new(r#else);
+                    Ok((target::Value::case { triv, clauses, r#else }, env))
+                },
+                (Some(keyword), self::Datum::list(datums))
+                    if binop(keyword).is_some() =>
+                {
+                    let [_, triv1, triv2] = parts(keyword, datums)?;
+                    let binop = binop(keyword).unwrap();
+                    let triv1 = expand_triv(triv1, &env)?;
+                    let triv2 = expand_triv(triv2, &env)?;
+                    let value = target::Value::binop_triv_triv {
+                        binop,
+                        triv1,
+                        triv2,
+                    };
+                    Ok((value, env))
+                },
+                (Some(keyword), self::Datum::list(datums))
+                    if relop(keyword).is_some() =>
+                {
+                    let [_, triv1, triv2] = parts(keyword, datums)?;
+                    let relop = relop(keyword).unwrap();
+                    let triv1 = expand_triv(triv1, &env)?;
+                    let triv2 = expand_triv(triv2, &env)?;
+                    let value = target::Value::relop_triv_triv {
+                        relop,
+                        triv1,
+                        triv2,
+                    };
+                    Ok((value, env))
+                },
+                (Some(keyword), self::Datum::list(datums))
+                    if primop(keyword).is_some() =>
+                {
+                    let primop = primop(keyword).unwrap();
+                    let trivs = datums
+                        .into_iter()
+                        .skip(1)
+                        .map(|datum| expand_triv(datum, &env))
+                        .collect::<Result<_, _>>()?;
+                    Ok((target::Value::primop_trivs { primop, trivs }, env))
+                },
+                (None, self::Datum::list(datums)) if !datums.is_empty() => {
+                    Err("A call can only be in tail position.".into())
+                },
+                (_, datum) => {
+                    let triv = expand_triv(datum, &env)?;
+                    Ok((target::Value::triv(triv), env))
+                },
+            }
+        }
+
+        // The values of the rest of the form, after its keyword.
+        fn expand_values(
+            datums: Vec<self::Datum>,
+            env: Env,
+            next_mark: &mut usize,
+        ) -> Result<(Vec<target::Value>, Env), String> {
+            datums.into_iter().skip(1).try_fold(
+                (vec![], env),
+                |(mut values, env), value| {
+                    let (value, env) = expand_value(value, env, next_mark)?;
+                    values.push(value);
+                    Ok((values, env))
+                },
+            )
+        }
+
+        // Each binding is in scope in the values of the bindings after it.
+        fn expand_bindings(
+            bindings: self::Datum,
+            env: Env,
+            next_mark: &mut usize,
+        ) -> Result<(target::Bindings, Env), String> {
+            let bindings = match bindings {
+                self::Datum::list(bindings) => bindings,
+                _ => Err(bad("let"))?,
+            };
+            bindings.into_iter().try_fold(
+                (vec![], env),
+                |(mut bindings, env), binding| {
+                    let [name, value] = match binding {
+                        self::Datum::list(binding) => parts("let", binding)?,
+                        _ => Err(bad("let"))?,
+                    };
+                    let (value, env) = expand_value(value, env, next_mark)?;
+                    let (name, env) = bind(name, env)?;
+                    bindings.push((name, value));
+                    Ok((bindings, env))
+                },
+            )
+        }
+
+        fn expand_triv(
+            datum: self::Datum,
+            env: &Env,
+        ) -> Result<target::Triv, String> {
+            match (head_keyword(&datum, env), datum) {
+                (_, self::Datum::int64(int64)) => {
+                    Ok(target::Triv::int64(int64))
+                },
+                (_, self::Datum::boolean(boolean)) => {
+                    Ok(target::Triv::boolean(boolean))
+                },
+                (_, self::Datum::ascii_char(ascii_char)) => {
+                    Ok(target::Triv::ascii_char(ascii_char))
+                },
+                (Some("void"), self::Datum::list(datums)) => {
+                    let [_] = parts("void", datums)?;
+                    Ok(target::Triv::void)
+                },
+                (Some("error"), self::Datum::list(datums)) => {
+                    match parts("error", datums)? {
+                        [_, self::Datum::int64(int64)] => {
+                            u8::try_from(int64)
+                                .map(target::Triv::error)
+                                .map_err(|_| "An error code is a byte.".into())
+                        },
+                        _ => Err(bad("error")),
+                    }
+                },
+                (Some(keyword), self::Datum::list(..)) => {
+                    Err(bad(keyword))
+                },
+                (None, self::Datum::list(..)) => {
+                    Err("A call can only be in tail position.".into())
+                },
+                (_, datum) if keyword(&datum, env) == Some("empty") => {
+                    Ok(target::Triv::empty)
+                },
+                (_, datum) => expand_name(datum, env).map(target::Triv::name),
+            }
+        }
+
+        // The name of the variable which the symbol refers to.
+        fn expand_name(
+            datum: self::Datum,
+            env: &Env,
+        ) -> Result<utils::Ident, String> {
+            let (name, marks) =
+                id(&datum).ok_or("Expected a name.".to_string())?;
+            match resolve(&(name, marks), env) {
+                Some(Binding::variable(ident)) => Ok(*ident),
+                Some(Binding::r#macro(..)) => Err(format!(
+                    "The macro, '{}', can only be at the head of a form.",
+                    name
+                )),
+                None => Err(format!("The name, '{}', is unbound.", name)),
+            }
+        }
+
+        // Binds the symbol in the innermost level of the env, renaming it to a
+        // gensym if an expansion introduced it.
+        fn bind(
+            datum: self::Datum,
+            env: Env,
+        ) -> Result<(utils::Ident, Env), String> {
+            let (name, marks) =
+                id(&datum).ok_or("Expected a name.".to_string())?;
+            let ident = match marks.is_empty() {
+                true => utils::Ident::symbol(name),
+                false => utils::Ident::fresh(name),
+            };
+            let env = env.insert((name, marks), Binding::variable(ident));
+            Ok((ident, env))
+        }
+
+        // The clauses of a cond or case form, and the value of its else
+        // clause, which must be last.
+        fn clauses(
+            form: utils::Name,
+            datums: Vec<self::Datum>,
+            env: &Env,
+        ) -> Result<(Vec<(self::Datum, self::Datum)>, self::Datum), String> {
+            let mut clauses = datums
+                .into_iter()
+                .skip(1)
+                .map(|clause| match clause {
+                    self::Datum::list(clause) => parts(form, clause)
+                        .map(|[test, value]| (test, value)),
+                    _ => Err(bad(form)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            match clauses.pop() {
+                Some((test, r#else))
+                    if keyword(&test, env) == Some("else") =>
+                {
+                    Ok((clauses, r#else))
+                },
+                _ => Err(format!(
+                    "A '{}' form must end in an else clause.",
+                    form
+                )),
+            }
+        }
+
+        // Transcribes the form for as long as its head is a macro.
+        fn expand_uses(
+            datum: self::Datum,
+            env: &Env,
+            next_mark: &mut usize,
+        ) -> Result<self::Datum, String> {
+            let rules = match &datum {
+                self::Datum::list(datums) => datums
+                    .first()
+                    .and_then(id)
+                    .and_then(|id| resolve(&id, env)),
+                _ => None,
+            };
+            match rules {
+                Some(Binding::r#macro(rules)) => {
+                    let datum = transcribe(rules, &datum, next_mark)?;
+                    expand_uses(datum, env, next_mark)
+                },
+                _ => Ok(datum),
+            }
+        }
+
+        // Instantiates the template of the first rule whose pattern matches
+        // the form, marking the symbols it introduces with a fresh mark. The
+        // keyword of the form is not matched.
+        fn transcribe(
+            rules: &self::Rules,
+            datum: &self::Datum,
+            next_mark: &mut usize,
+        ) -> Result<self::Datum, String> {
+            let mark = *next_mark;
+            *next_mark += 1;
+            let datums = match datum {
+                self::Datum::list(datums) => &datums[1..],
+                _ => &[],
+            };
+            rules
+                .rules
+                .iter()
+                .find_map(|(pattern, template)| match pattern {
+                    self::Datum::list(patterns) if !patterns.is_empty() => {
+                        let mut matches = Matches::new();
+                        match_list(
+                            &patterns[1..],
+                            datums,
+                            &rules.literals,
+                            &mut matches,
+                        )
+                        .then(|| instantiate(template, &matches, mark))
+                    },
+                    _ => None,
+                })
+                .unwrap_or_else(|| {
+                    Err("No rule of the macro matches its use.".into())
+                })
+        }
+
+        fn match_datum(
+            pattern: &self::Datum,
+            datum: &self::Datum,
+            literals: &[utils::Name],
+            matches: &mut Matches,
+        ) -> bool {
+            match (pattern, id(pattern), datum) {
+                (_, Some(("_", _)), _) => true,
+                (_, Some((literal, _)), _) if literals.contains(&literal) => {
+                    id(datum).is_some_and(|(name, _)| name == literal)
+                },
+                (_, Some((name, _)), _) => {
+                    matches.insert(name, Match::one(datum.clone()));
+                    true
+                },
+                (self::Datum::list(patterns), _, self::Datum::list(datums)) => {
+                    match_list(patterns, datums, literals, matches)
+                },
+                (self::Datum::int64(int1), _, self::Datum::int64(int2)) => {
+                    int1 == int2
+                },
+                (
+                    self::Datum::boolean(boolean1),
+                    _,
+                    self::Datum::boolean(boolean2),
+                ) => boolean1 == boolean2,
+                (
+                    self::Datum::ascii_char(char1),
+                    _,
+                    self::Datum::ascii_char(char2),
+                ) => char1 == char2,
+                _ => false,
+            }
+        }
+
+        // A pattern followed by an ellipsis matches any number of datums, as
+        // long as enough are left for the patterns after the ellipsis.
+        fn match_list(
+            patterns: &[self::Datum],
+            datums: &[self::Datum],
+            literals: &[utils::Name],
+            matches: &mut Matches,
+        ) -> bool {
+            let ellipsis = patterns.iter().position(is_ellipsis);
+            match ellipsis {
+                Some(index) if index > 0 => {
+                    let before = &patterns[..index - 1];
+                    let repeated = &patterns[index - 1];
+                    let after = &patterns[index + 1..];
+                    match datums.len().checked_sub(before.len() + after.len()) {
+                        Some(count) => {
+                            let (datums_before, rest) =
+                                datums.split_at(before.len());
+                            let (repeats, datums_after) = rest.split_at(count);
+                            let repeats = repeats
+                                .iter()
+                                .map(|datum| {
+                                    let mut repeat_matches = Matches::new();
+                                    match_datum(
+                                        repeated,
+                                        datum,
+                                        literals,
+                                        &mut repeat_matches,
+                                    )
+                                    .then_some(repeat_matches)
+                                })
+                                .collect::<Option<Vec<_>>>();
+                            match repeats {
+                                Some(repeats) => {
+                                    pattern_vars(repeated, literals)
+                                        .into_iter()
+                                        .for_each(|var| {
+                                            let many = repeats
+                                                .iter()
+                                                .filter_map(|repeat| {
+                                                    repeat.get(var).cloned()
+                                                })
+                                                .collect();
+                                            matches
+                                                .insert(var, Match::many(many));
+                                        });
+                                    match_list(
+                                        before,
+                                        datums_before,
+                                        literals,
+                                        matches,
+                                    ) && match_list(
+                                        after,
+                                        datums_after,
+                                        literals,
+                                        matches,
+                                    )
+                                },
+                                None => false,
+                            }
+                        },
+                        None => false,
+                    }
+                },
+                _ => {
+                    patterns.len() == datums.len()
+                        && patterns.iter().zip(datums).all(|(pattern, datum)| {
+                            match_datum(pattern, datum, literals, matches)
+                        })
+                },
+            }
+        }
+
+        fn pattern_vars(
+            pattern: &self::Datum,
+            literals: &[utils::Name],
+        ) -> Vec<utils::Name> {
+            match (id(pattern), pattern) {
+                (Some(("_" | "...", _)), _) => vec![],
+                (Some((name, _)), _) if literals.contains(&name) => vec![],
+                (Some((name, _)), _) => vec![name],
+                (_, self::Datum::list(patterns)) => patterns
+                    .iter()
+                    .flat_map(|pattern| pattern_vars(pattern, literals))
+                    .collect(),
+                _ => vec![],
+            }
+        }
+
+        // A template followed by an ellipsis is instantiated once for each
+        // match of the pattern variables in it which were followed by one.
+        fn instantiate(
+            template: &self::Datum,
+            matches: &Matches,
+            mark: usize,
+        ) -> Result<self::Datum, String> {
+            match (id(template), template) {
+                (Some((name, marks)), _) => match matches.get(name) {
+                    Some(Match::one(datum)) => Ok(datum.clone()),
+                    Some(Match::many(..)) => Err(format!(
+                        "The pattern variable, '{}', must be followed by an \
+                         ellipsis.",
+                        name
+                    )),
+                    None => {
+                        let mut marks = marks;
+                        marks.push(mark);
+                        Ok(self::Datum::introduced { name, marks })
+                    },
+                },
+                (_, self::Datum::list(templates)) => {
+                    let mut datums = vec![];
+                    let mut templates = templates.iter().peekable();
+                    while let Some(template) = templates.next() {
+                        match templates.next_if(|next| is_ellipsis(next)) {
+                            Some(_) => {
+                                datums.extend(instantiate_repeats(
+                                    template, matches, mark,
+                                )?)
+                            },
+                            None => {
+                                datums.push(instantiate(
+                                    template, matches, mark,
+                                )?)
+                            },
+                        }
+                    }
+                    Ok(self::Datum::list(datums))
+                },
+                (_, datum) => Ok(datum.clone()),
+            }
+        }
+
+        fn instantiate_repeats(
+            template: &self::Datum,
+            matches: &Matches,
+            mark: usize,
+        ) -> Result<Vec<self::Datum>, String> {
+            let repeated = template_vars(template)
+                .into_iter()
+                .filter_map(|var| match matches.get(var) {
+                    Some(Match::many(repeats)) => Some((var, repeats)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let count = repeated.first().map(|(_, repeats)| repeats.len());
+            match count {
+                Some(count)
+                    if repeated
+                        .iter()
+                        .all(|(_, repeats)| repeats.len() == count) =>
+                {
+                    (0..count)
+                        .map(|index| {
+                            let mut matches = matches.clone();
+                            repeated.iter().for_each(|(var, repeats)| {
+                                matches.insert(var, repeats[index].clone());
+                            });
+                            instantiate(template, &matches, mark)
+                        })
+                        .collect()
+                },
+                Some(_) => Err("Pattern variables under the same ellipsis \
+                                matched different numbers of datums."
+                    .into()),
+                None => Err("An ellipsis must follow a pattern variable \
+                             which was followed by one."
+                    .into()),
+            }
+        }
+
+        fn template_vars(template: &self::Datum) -> Vec<utils::Name> {
+            match (id(template), template) {
+                (Some((name, _)), _) => vec![name],
+                (_, self::Datum::list(templates)) => {
+                    templates.iter().flat_map(template_vars).collect()
+                },
+                _ => vec![],
+            }
+        }
+
+        fn is_ellipsis(datum: &self::Datum) -> bool {
+            matches!(datum, self::Datum::symbol("..."))
+        }
+
+        fn bad(keyword: utils::Name) -> String {
+            format!("Bad syntax in a '{}' form.", keyword)
+        }
+
+        // The destructured parts of a form of the given keyword.
+        fn parts<const N: usize>(
+            keyword: utils::Name,
+            datums: Vec<self::Datum>,
+        ) -> Result<[self::Datum; N], String> {
+            datums
+                .try_into()
+                .map_err(|_| bad(keyword))
+        }
+
+        fn id(datum: &self::Datum) -> Option<Id> {
+            match datum {
+                self::Datum::symbol(name) => Some((*name, vec![])),
+                self::Datum::introduced { name, marks } => {
+                    Some((*name, marks.clone()))
+                },
+                _ => None,
+            }
+        }
+
+        // A symbol which an expansion introduced, and which the expansion does
+        // not bind, is resolved at the top level.
+        fn resolve<'a>(id: &Id, env: &'a Env) -> Option<&'a Binding> {
+            let (name, marks) = id;
+            env.get(id).or_else(|| match marks.is_empty() {
+                true => None,
+                false => env.get_outermost(&(*name, vec![])),
+            })
+        }
+
+        // The name of the symbol, if it is a keyword here.
+        fn keyword(datum: &self::Datum, env: &Env) -> Option<utils::Name> {
+            id(datum)
+                .filter(|id| resolve(id, env).is_none())
+                .map(|(name, _)| name)
+        }
+
+        fn head_keyword(datum: &self::Datum, env: &Env) -> Option<utils::Name> {
+            match datum {
+                self::Datum::list(datums) => {
+                    datums.first().and_then(|head| keyword(head, env))
+                },
+                _ => None,
+            }
+        }
+
+        fn binop(keyword: utils::Name) -> Option<utils::Binop> {
+            match keyword {
+                "+" => Some(utils::Binop::plus),
+                "-" => Some(utils::Binop::minus),
+                "*" => Some(utils::Binop::multiply),
+                "quotient" => Some(utils::Binop::quotient),
+                "remainder" => Some(utils::Binop::remainder),
+                "bitwise-and" => Some(utils::Binop::bitwise_and),
+                "bitwise-ior" => Some(utils::Binop::bitwise_ior),
+                "bitwise-xor" => Some(utils::Binop::bitwise_xor),
+                "arithmetic-shift-right" => {
+                    Some(utils::Binop::arithmetic_shift_right)
+                },
+                "shift-left" => Some(utils::Binop::shift_left),
+                _ => None,
+            }
+        }
+
+        fn relop(keyword: utils::Name) -> Option<utils::Relop> {
+            match keyword {
+                ">" => Some(utils::Relop::gt),
+                ">=" => Some(utils::Relop::gte),
+                "<" => Some(utils::Relop::lt),
+                "<=" => Some(utils::Relop::lte),
+                "=" | "eq?" => Some(utils::Relop::eq),
+                "!=" => Some(utils::Relop::neq),
+                _ => None,
+            }
+        }
+
+        fn primop(keyword: utils::Name) -> Option<utils::Primop> {
+            match keyword {
+                "fixnum?" => Some(utils::Primop::fixnum_p),
+                "boolean?" => Some(utils::Primop::boolean_p),
+                "empty?" => Some(utils::Primop::empty_p),
+                "void?" => Some(utils::Primop::void_p),
+                "ascii-char?" => Some(utils::Primop::ascii_char_p),
+                "error?" => Some(utils::Primop::error_p),
+                "pair?" => Some(utils::Primop::pair_p),
+                "vector?" => Some(utils::Primop::vector_p),
+                "procedure?" => Some(utils::Primop::procedure_p),
+                "not" => Some(utils::Primop::not),
+                "cons" => Some(utils::Primop::cons),
+                "car" => Some(utils::Primop::car),
+                "cdr" => Some(utils::Primop::cdr),
+                "make-vector" => Some(utils::Primop::make_vector),
+                "vector-length" => Some(utils::Primop::vector_length),
+                "vector-ref" => Some(utils::Primop::vector_ref),
+                "vector-set!" => Some(utils::Primop::vector_set),
+                _ => None,
+            }
+        }
+
+        let Self(p) = self;
+        expand_p(p).map(target::ValuesLang)
+    }
+}
@@ -0,0 +1,231 @@
+use serial_test::serial;
+
+use crate::imperative_abstractions::syntax_lang as source;
+use crate::imperative_abstractions::values_lang as target;
+use crate::utils;
+
+fn list(datums: Vec<source::Datum>) -> source::Datum {
+    source::Datum::list(datums)
+}
+
+fn symbol(name: utils::Name) -> source::Datum {
+    source::Datum::symbol(name)
+}
+
+fn name(name: impl Into<utils::Ident>) -> target::Value {
+    target::Value::triv(target::Triv::name(name.into()))
+}
+
+// (define-syntax swap!
+//   (syntax-rules ()
+//     [(_ a b) (let ([tmp a]) (begin (set! a b) (set! b tmp)))]))
+fn swap() -> source::Datum {
+    list(vec![
+        symbol("define-syntax"),
+        symbol("swap!"),
+        list(vec![
+            symbol("syntax-rules"),
+            list(vec![]),
+            list(vec![
+                list(vec![symbol("_"), symbol("a"), symbol("b")]),
+                list(vec![
+                    symbol("let"),
+                    list(vec![list(vec![symbol("tmp"), symbol("a")])]),
+                    list(vec![
+                        symbol("begin"),
+                        list(vec![symbol("set!"), symbol("a"), symbol("b")]),
+                        list(vec![symbol("set!"), symbol("b"), symbol("tmp")]),
+                    ]),
+                ]),
+            ]),
+        ]),
+    ])
+}
+
+// (let ([x 1] [y 2]) (begin (swap! x y) x))
+fn swap_x_y(x: utils::Name) -> source::Datum {
+    list(vec![
+        symbol("let"),
+        list(vec![
+            list(vec![symbol(x), source::Datum::int64(1)]),
+            list(vec![symbol("y"), source::Datum::int64(2)]),
+        ]),
+        list(vec![
+            symbol("begin"),
+            list(vec![symbol("swap!"), symbol(x), symbol("y")]),
+            symbol(x),
+        ]),
+    ])
+}
+
+// (let ([x 1] [y 2]) (begin (let ([tmp x]) (begin (set! x y) (set! y tmp))) x))
+fn swapped_x_y(x: utils::Name) -> target::ValuesLang {
+    let tmp = utils::Ident::gensym {
+        name: "tmp",
+        index: 0,
+    };
+    let set = |name1: utils::Ident, name2: utils::Ident| target::Value::set {
+        name: name1,
+        value: Box::new(name(name2)),
+    };
+    target::ValuesLang(target::P::module {
+        lambdas: vec![],
+        tail: target::Tail::r#let {
+            bindings: vec![
+                (x.into(), target::Value::triv(target::Triv::int64(1))),
+                ("y".into(), target::Value::triv(target::Triv::int64(2))),
+            ],
+            tail: Box::new(target::Tail::value(target::Value::begin {
+                values: vec![target::Value::r#let {
+                    bindings: vec![(tmp, name(x))],
+                    value: Box::new(target::Value::begin {
+                        values: vec![set(x.into(), "y".into())],
+                        value: Box::new(set("y".into(), tmp)),
+                    }),
+                }],
+                value: Box::new(name(x)),
+            })),
+        },
+    })
+}
+
+#[test]
+#[serial]
+fn swap_does_not_capture_tmp() {
+    let tail = swap_x_y("tmp");
+    let p = source::SyntaxLang(source::P::module(vec![swap(), tail]));
+    let actual = p.expand_macros();
+
+    assert_eq!(actual, Ok(swapped_x_y("tmp")));
+    utils::reset_all_indices();
+}
+
+#[test]
+#[serial]
+fn swap_does_not_capture_symbol_spelled_like_gensym() {
+    utils::reset_all_indices();
+
+    let tail = swap_x_y("tmp.0");
+    let p = source::SyntaxLang(source::P::module(vec![swap(), tail]));
+    let actual = p.expand_macros();
+
+    assert_eq!(actual, Ok(swapped_x_y("tmp.0")));
+    utils::reset_all_indices();
+}
+
+#[test]
+#[serial]
+fn introduced_plus_is_not_shadowed() {
+    // (define-syntax inc (syntax-rules () [(_ x) (+ x 1)]))
+    // (let ([+ 5]) (inc +))
+    let inc = list(vec![
+        symbol("define-syntax"),
+        symbol("inc"),
+        list(vec![
+            symbol("syntax-rules"),
+            list(vec![]),
+            list(vec![
+                list(vec![symbol("_"), symbol("x")]),
+                list(vec![symbol("+"), symbol("x"), source::Datum::int64(1)]),
+            ]),
+        ]),
+    ]);
+    let tail = list(vec![
+        symbol("let"),
+        list(vec![list(vec![symbol("+"), source::Datum::int64(5)])]),
+        list(vec![symbol("inc"), symbol("+")]),
+    ]);
+    let p = source::SyntaxLang(source::P::module(vec![inc, tail]));
+    let actual = p.expand_macros();
+
+    let expected = target::ValuesLang(target::P::module {
+        lambdas: vec![],
+        tail: target::Tail::r#let {
+            bindings: vec![(
+                "+".into(),
+                target::Value::triv(target::Triv::int64(5)),
+            )],
+            tail: Box::new(target::Tail::value(
+                target::Value::binop_triv_triv {
+                    binop: utils::Binop::plus,
+                    triv1: target::Triv::name("+".into()),
+                    triv2: target::Triv::int64(1),
+                },
+            )),
+        },
+    });
+
+    assert_eq!(actual, Ok(expected));
+}
+
+#[test]
+#[serial]
+fn ellipsis_expands_recursively() {
+    // (define-syntax seq
+    //   (syntax-rules ()
+    //     [(_ e) e]
+    //     [(_ e1 e2 ...) (begin e1 (seq e2 ...))]))
+    // (define f (lambda (x) (seq (set! x 1) (set! x 2) x)))
+    // (f 0)
+    let seq = list(vec![
+        symbol("define-syntax"),
+        symbol("seq"),
+        list(vec![
+            symbol("syntax-rules"),
+            list(vec![]),
+            list(vec![list(vec![symbol("_"), symbol("e")]), symbol("e")]),
+            list(vec![
+                list(vec![
+                    symbol("_"),
+                    symbol("e1"),
+                    symbol("e2"),
+                    symbol("..."),
+                ]),
+                list(vec![
+                    symbol("begin"),
+                    symbol("e1"),
+                    list(vec![symbol("seq"), symbol("e2"), symbol("...")]),
+                ]),
+            ]),
+        ]),
+    ]);
+    let set = |int64| {
+        list(vec![symbol("set!"), symbol("x"), source::Datum::int64(int64)])
+    };
+    let f = list(vec![
+        symbol("define"),
+        symbol("f"),
+        list(vec![
+            symbol("lambda"),
+            list(vec![symbol("x")]),
+            list(vec![symbol("seq"), set(1), set(2), symbol("x")]),
+        ]),
+    ]);
+    let tail = list(vec![symbol("f"), source::Datum::int64(0)]);
+    let p = source::SyntaxLang(source::P::module(vec![seq, f, tail]));
+    let actual = p.expand_macros();
+
+    let set = |int64| target::Value::set {
+        name: "x".into(),
+        value: Box::new(target::Value::triv(target::Triv::int64(int64))),
+    };
+    let expected = target::ValuesLang(target::P::module {
+        lambdas: vec![target::Lambda {
+            name: "f",
+            args: vec!["x".into()],
+            tail: target::Tail::value(target::Value::begin {
+                values: vec![set(1)],
+                value: Box::new(target::Value::begin {
+                    values: vec![set(2)],
+                    value: Box::new(name("x")),
+                }),
+            }),
+        }],
+        tail: target::Tail::call {
+            name: "f".into(),
+            args: vec![target::Triv::int64(0)],
+        },
+    });
+
+    assert_eq!(actual, Ok(expected));
+}
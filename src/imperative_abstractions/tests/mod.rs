@@ -12,10 +12,16 @@ fn basic() {
         lambdas: vec![],
         tail: source::Tail::value(source::Value::triv(source::Triv::int64(5))),
     });
-    let actual = compile(p).unwrap();
+    let actual = compile(
+        p,
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    )
+    .unwrap();
     let expected = target::AsmPredLang(target::P::module {
         info: utils::Info::default(),
-        tail: target::Tail::halt(target::Triv::int64(5)),
+        lambdas: vec![],
+        tail: target::Tail::halt(target::Triv::int64(40)),
     });
     assert_eq!(actual, expected);
 }
@@ -39,26 +45,16 @@ fn basic_if_condition() {
             ))),
         },
     });
-    let actual = compile(p).unwrap();
-    utils::reset_all_indices();
-    let aloc = utils::Aloc::fresh();
+    let actual = compile(
+        p,
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    )
+    .unwrap();
     let expected = target::AsmPredLang(target::P::module {
         info: utils::Info::default(),
-        tail: target::Tail::begin {
-            effects: vec![target::Effect::set_aloc_triv {
-                aloc: aloc.clone(),
-                triv: target::Triv::int64(5),
-            }],
-            tail: Box::new(target::Tail::r#if {
-                pred: target::Pred::relop {
-                    relop: utils::Relop::gt,
-                    aloc,
-                    triv: target::Triv::int64(6),
-                },
-                tail1: Box::new(target::Tail::halt(target::Triv::int64(7))),
-                tail2: Box::new(target::Tail::halt(target::Triv::int64(8))),
-            }),
-        },
+        lambdas: vec![],
+        tail: target::Tail::halt(target::Triv::int64(64)),
     });
     assert_eq!(actual, expected);
 }
@@ -74,25 +70,16 @@ fn basic_operation() {
             triv2: source::Triv::int64(11),
         }),
     });
-    let actual = compile(p).unwrap();
-    utils::reset_all_indices();
-    let aloc = utils::Aloc::fresh();
+    let actual = compile(
+        p,
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    )
+    .unwrap();
     let expected = target::AsmPredLang(target::P::module {
         info: utils::Info::default(),
-        tail: target::Tail::begin {
-            effects: vec![
-                target::Effect::set_aloc_triv {
-                    aloc: aloc.clone(),
-                    triv: target::Triv::int64(10),
-                },
-                target::Effect::set_aloc_binop_aloc_triv {
-                    aloc: aloc.clone(),
-                    binop: utils::Binop::plus,
-                    triv: target::Triv::int64(11),
-                },
-            ],
-            tail: Box::new(target::Tail::halt(target::Triv::aloc(aloc))),
-        },
+        lambdas: vec![],
+        tail: target::Tail::halt(target::Triv::int64(168)),
     });
     assert_eq!(actual, expected);
 }
@@ -116,26 +103,16 @@ fn not_if_condition() {
             ))),
         },
     });
-    let actual = compile(p).unwrap();
-    utils::reset_all_indices();
-    let aloc = utils::Aloc::fresh();
+    let actual = compile(
+        p,
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    )
+    .unwrap();
     let expected = target::AsmPredLang(target::P::module {
         info: utils::Info::default(),
-        tail: target::Tail::begin {
-            effects: vec![target::Effect::set_aloc_triv {
-                aloc: aloc.clone(),
-                triv: target::Triv::int64(5),
-            }],
-            tail: Box::new(target::Tail::r#if {
-                pred: target::Pred::not(Box::new(target::Pred::relop {
-                    relop: utils::Relop::gt,
-                    aloc,
-                    triv: target::Triv::int64(6),
-                })),
-                tail1: Box::new(target::Tail::halt(target::Triv::int64(7))),
-                tail2: Box::new(target::Tail::halt(target::Triv::int64(8))),
-            }),
-        },
+        lambdas: vec![],
+        tail: target::Tail::halt(target::Triv::int64(56)),
     });
     assert_eq!(actual, expected);
 }
@@ -152,10 +129,16 @@ fn empty_bindings_in_tail() {
             ))),
         },
     });
-    let actual = compile(p).unwrap();
+    let actual = compile(
+        p,
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    )
+    .unwrap();
     let expected = target::AsmPredLang(target::P::module {
         info: utils::Info::default(),
-        tail: target::Tail::halt(target::Triv::int64(5)),
+        lambdas: vec![],
+        tail: target::Tail::halt(target::Triv::int64(40)),
     });
     assert_eq!(actual, expected);
 }
@@ -175,10 +158,16 @@ fn nested_empty_bindings_in_tail() {
             }),
         },
     });
-    let actual = compile(p).unwrap();
+    let actual = compile(
+        p,
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    )
+    .unwrap();
     let expected = target::AsmPredLang(target::P::module {
         info: utils::Info::default(),
-        tail: target::Tail::halt(target::Triv::int64(5)),
+        lambdas: vec![],
+        tail: target::Tail::halt(target::Triv::int64(40)),
     });
     assert_eq!(actual, expected);
 }
@@ -208,26 +197,16 @@ fn nested_if_condition_in_tail() {
             }),
         },
     });
-    let actual = compile(p).unwrap();
-    utils::reset_all_indices();
-    let aloc = utils::Aloc::fresh();
+    let actual = compile(
+        p,
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    )
+    .unwrap();
     let expected = target::AsmPredLang(target::P::module {
         info: utils::Info::default(),
-        tail: target::Tail::begin {
-            effects: vec![target::Effect::set_aloc_triv {
-                aloc: aloc.clone(),
-                triv: target::Triv::int64(0),
-            }],
-            tail: Box::new(target::Tail::r#if {
-                pred: target::Pred::relop {
-                    relop: utils::Relop::eq,
-                    aloc,
-                    triv: target::Triv::int64(0),
-                },
-                tail1: Box::new(target::Tail::halt(target::Triv::int64(1))),
-                tail2: Box::new(target::Tail::halt(target::Triv::int64(2))),
-            }),
-        },
+        lambdas: vec![],
+        tail: target::Tail::halt(target::Triv::int64(8)),
     });
     assert_eq!(actual, expected);
 }
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use crate::utils;
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
@@ -5,10 +7,21 @@ pub enum P {
     module { lambdas: Vec<Lambda>, tail: Tail },
 }
 
+/// ### Purpose:
+/// A module of a program of several modules. It provides some of its
+/// top-level procedures to the other modules, and requires some of theirs.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct Module {
+    pub name: utils::Name,
+    pub provides: Vec<utils::Name>,
+    pub requires: Vec<utils::Name>,
+    pub p: P,
+}
+
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub struct Lambda {
     pub name: utils::Name,
-    pub args: Vec<utils::Name>,
+    pub args: Vec<utils::Ident>,
     pub tail: Tail,
 }
 
@@ -19,6 +32,7 @@ pub enum Pred {
         triv1: Triv,
         triv2: Triv,
     },
+    triv(Triv),
     r#true,
     r#false,
     not(Box<Self>),
@@ -31,6 +45,12 @@ pub enum Pred {
         pred2: Box<Self>,
         pred3: Box<Self>,
     },
+    /// Holds if every pred holds, evaluating them in order only until one
+    /// does not.
+    and(Vec<Self>),
+    /// Holds if any pred holds, evaluating them in order only until one
+    /// does.
+    or(Vec<Self>),
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
@@ -46,7 +66,7 @@ pub enum Tail {
         tail2: Box<Self>,
     },
     call {
-        name: utils::Name,
+        name: utils::Ident,
         args: Vec<Triv>,
     },
 }
@@ -59,6 +79,15 @@ pub enum Value {
         triv1: Triv,
         triv2: Triv,
     },
+    relop_triv_triv {
+        relop: utils::Relop,
+        triv1: Triv,
+        triv2: Triv,
+    },
+    primop_trivs {
+        primop: utils::Primop,
+        trivs: Vec<Triv>,
+    },
     r#let {
         bindings: Bindings,
         value: Box<Self>,
@@ -68,12 +97,104 @@ pub enum Value {
         value1: Box<Self>,
         value2: Box<Self>,
     },
+    lambda {
+        args: Vec<utils::Ident>,
+        tail: Box<Tail>,
+    },
+    set {
+        name: utils::Ident,
+        value: Box<Self>,
+    },
+    begin {
+        values: Vec<Self>,
+        value: Box<Self>,
+    },
+    r#while {
+        pred: Pred,
+        value: Box<Self>,
+    },
+    /// Evaluates the value of the first clause whose pred holds, or the else
+    /// value if none does.
+    cond {
+        clauses: Vec<(Pred, Self)>,
+        r#else: Box<Self>,
+    },
+    /// Evaluates the value of the first clause which lists the triv among its
+    /// integers, or the else value if none does.
+    case {
+        triv: Triv,
+        clauses: Vec<(Vec<i64>, Self)>,
+        r#else: Box<Self>,
+    },
+    /// Evaluates to the value if the pred holds, and to void otherwise.
+    when {
+        pred: Pred,
+        value: Box<Self>,
+    },
+    /// Evaluates to the value unless the pred holds, and to void otherwise.
+    unless {
+        pred: Pred,
+        value: Box<Self>,
+    },
+    /// Evaluates the value which the table maps the triv minus lo to, or the
+    /// last value if the triv is not a fixnum within the table.
+    ///
+    /// Produced by expand-derived-forms for a case over a dense range.
+    switch {
+        triv: Triv,
+        lo: i64,
+        table: Vec<usize>,
+        values: Vec<Self>,
+    },
 }
 
+#[derive(Clone)]
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub enum Triv {
     int64(i64),
-    name(utils::Name),
+    boolean(bool),
+    empty,
+    void,
+    ascii_char(u8),
+    error(u8),
+    name(utils::Ident),
 }
 
-pub type Bindings = Vec<(utils::Name, Value)>;
+pub type Bindings = Vec<(utils::Ident, Value)>;
+
+/// ### Purpose:
+/// The static types of Values-lang v3, as inferred by check-types. A type
+/// variable stands for a type which is not yet known.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum Type {
+    int,
+    bool,
+    void,
+    empty,
+    char,
+    pair(Box<Self>, Box<Self>),
+    vector(Box<Self>),
+    procedure { args: Vec<Self>, ret: Box<Self> },
+    var(usize),
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::int => f.write_str("int"),
+            Self::bool => f.write_str("bool"),
+            Self::void => f.write_str("void"),
+            Self::empty => f.write_str("empty"),
+            Self::char => f.write_str("char"),
+            Self::pair(car, cdr) => write!(f, "(pair {} {})", car, cdr),
+            Self::vector(r#type) => write!(f, "(vector {})", r#type),
+            Self::procedure { args, ret } => {
+                f.write_str("(->")?;
+                args.iter().try_for_each(|arg| write!(f, " {}", arg))?;
+                write!(f, " {})", ret)
+            },
+            Self::var(index) => write!(f, "t.{}", index),
+        }
+    }
+}
@@ -1,8 +1,10 @@
 pub mod data;
+pub mod prelude;
 #[cfg(test)]
 mod tests;
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 
 pub use self::data::*;
 use crate::imperative_abstractions::values_unique_lang as target;
@@ -13,75 +15,1291 @@ pub struct ValuesLang(pub self::P);
 
 impl ValuesLang {
     /// ### Purpose:
-    /// ...
+    /// Checks that the program is well-formed, before any of its forms are
+    /// expanded.
+    ///
+    /// ### Notes:
+    /// An integer literal must fit in a fixnum, which has 61 bits. A primop
+    /// must be given as many arguments as it takes.
+    ///
+    /// Only a name bound by a let or as an argument can be assigned. A call
+    /// to a top-level procedure jumps straight to its label, so assigning
+    /// the name of one would have no effect on the calls.
     pub fn check_values_lang(self) -> Result<Self, String> {
+        // Whether each name in scope can be assigned.
+        type NameEnv = utils::LevelledEnv<utils::Ident, bool>;
+
+        fn check_p(p: &self::P) -> Result<(), String> {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let env = NameEnv::default().add_level();
+                    let env = lambdas.iter().fold(env, |env, lambda| {
+                        env.insert(lambda.name.into(), false)
+                    });
+                    let env = lambdas.iter().try_fold(
+                        env,
+                        |env, Lambda { args, tail, .. }| {
+                            check_procedure(args, tail, env)
+                        },
+                    )?;
+                    check_tail(tail, env).map(|_| ())
+                },
+            }
+        }
+
+        fn check_procedure(
+            args: &[utils::Ident],
+            tail: &self::Tail,
+            env: NameEnv,
+        ) -> Result<NameEnv, String> {
+            let env = args
+                .iter()
+                .fold(env.add_level(), |env, arg| env.insert(*arg, true));
+            let env = check_tail(tail, env)?;
+            Ok(env.remove_level())
+        }
+
+        fn check_tail(
+            tail: &self::Tail,
+            env: NameEnv,
+        ) -> Result<NameEnv, String> {
+            match tail {
+                self::Tail::value(value) => check_value(value, env),
+                self::Tail::r#let { bindings, tail } => {
+                    let env = check_bindings(bindings, env.add_level())?;
+                    let env = check_tail(tail, env)?;
+                    Ok(env.remove_level())
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let env = check_pred(pred, env)?;
+                    let env = check_tail(tail1, env)?;
+                    check_tail(tail2, env)
+                },
+                self::Tail::call { args, .. } => {
+                    args.iter().try_for_each(check_triv)?;
+                    Ok(env)
+                },
+            }
+        }
+
+        fn check_pred(
+            pred: &self::Pred,
+            env: NameEnv,
+        ) -> Result<NameEnv, String> {
+            match pred {
+                self::Pred::relop { triv1, triv2, .. } => {
+                    check_triv(triv1)?;
+                    check_triv(triv2)?;
+                    Ok(env)
+                },
+                self::Pred::triv(triv) => {
+                    check_triv(triv)?;
+                    Ok(env)
+                },
+                self::Pred::r#true | self::Pred::r#false => Ok(env),
+                self::Pred::not(pred) => check_pred(pred, env),
+                self::Pred::r#let { bindings, pred } => {
+                    let env = check_bindings(bindings, env.add_level())?;
+                    let env = check_pred(pred, env)?;
+                    Ok(env.remove_level())
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let env = check_pred(pred1, env)?;
+                    let env = check_pred(pred2, env)?;
+                    check_pred(pred3, env)
+                },
+                self::Pred::and(preds) | self::Pred::or(preds) => {
+                    preds
+                        .iter()
+                        .try_fold(env, |env, pred| check_pred(pred, env))
+                },
+            }
+        }
+
+        fn check_value(
+            value: &self::Value,
+            env: NameEnv,
+        ) -> Result<NameEnv, String> {
+            match value {
+                self::Value::triv(triv) => {
+                    check_triv(triv)?;
+                    Ok(env)
+                },
+                self::Value::binop_triv_triv { triv1, triv2, .. }
+                | self::Value::relop_triv_triv { triv1, triv2, .. } => {
+                    check_triv(triv1)?;
+                    check_triv(triv2)?;
+                    Ok(env)
+                },
+                self::Value::primop_trivs { primop, trivs } => {
+                    match primop.arity() == trivs.len() {
+                        true => trivs.iter().try_for_each(check_triv)?,
+                        false => Err(format!(
+                            "The primop, '{:?}', takes {} arguments, but is \
+                             given {}.",
+                            primop,
+                            primop.arity(),
+                            trivs.len()
+                        ))?,
+                    };
+                    Ok(env)
+                },
+                self::Value::r#let { bindings, value } => {
+                    let env = check_bindings(bindings, env.add_level())?;
+                    let env = check_value(value, env)?;
+                    Ok(env.remove_level())
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let env = check_pred(pred, env)?;
+                    let env = check_value(value1, env)?;
+                    check_value(value2, env)
+                },
+                self::Value::lambda { args, tail } => {
+                    check_procedure(args, tail, env)
+                },
+                self::Value::set { name, value } => {
+                    let env = check_value(value, env)?;
+                    match env.get(name) {
+                        Some(true) => Ok(env),
+                        Some(false) => Err(format!(
+                            "The top-level procedure, '{}', cannot be \
+                             assigned.",
+                            name
+                        )),
+                        None => {
+                            Err(format!("The name, '{}', is unbound.", name))
+                        },
+                    }
+                },
+                self::Value::begin { values, value } => {
+                    let env = values
+                        .iter()
+                        .try_fold(env, |env, value| check_value(value, env))?;
+                    check_value(value, env)
+                },
+                self::Value::r#while { pred, value }
+                | self::Value::when { pred, value }
+                | self::Value::unless { pred, value } => {
+                    let env = check_pred(pred, env)?;
+                    check_value(value, env)
+                },
+                self::Value::cond { clauses, r#else } => {
+                    let env = clauses.iter().try_fold(
+                        env,
+                        |env, (pred, value)| {
+                            let env = check_pred(pred, env)?;
+                            check_value(value, env)
+                        },
+                    )?;
+                    check_value(r#else, env)
+                },
+                self::Value::case {
+                    triv,
+                    clauses,
+                    r#else,
+                } => {
+                    check_triv(triv)?;
+                    let env = clauses.iter().try_fold(
+                        env,
+                        |env, (int64s, value)| {
+                            int64s.iter().try_for_each(check_int64)?;
+                            check_value(value, env)
+                        },
+                    )?;
+                    check_value(r#else, env)
+                },
+                self::Value::switch { triv, values, .. } => {
+                    check_triv(triv)?;
+                    values
+                        .iter()
+                        .try_fold(env, |env, value| check_value(value, env))
+                },
+            }
+        }
+
+        // Each binding is in scope in the bindings after it.
+        fn check_bindings(
+            bindings: &self::Bindings,
+            env: NameEnv,
+        ) -> Result<NameEnv, String> {
+            bindings.iter().try_fold(env, |env, (name, value)| {
+                let env = check_value(value, env)?;
+                Ok(env.insert(*name, true))
+            })
+        }
+
+        fn check_triv(triv: &self::Triv) -> Result<(), String> {
+            match triv {
+                self::Triv::int64(int64) => check_int64(int64),
+                _ => Ok(()),
+            }
+        }
+
+        fn check_int64(int64: &i64) -> Result<(), String> {
+            let fixnum = (int64 << utils::FIXNUM_SHIFT) >> utils::FIXNUM_SHIFT;
+            match fixnum == *int64 {
+                true => Ok(()),
+                false => Err(format!(
+                    "The integer, '{}', does not fit in a fixnum.",
+                    int64
+                )),
+            }
+        }
+
+        let Self(p) = &self;
+        check_p(p)?;
         Ok(self)
     }
 
+    /// ### Purpose:
+    /// Adds the procedures of the prelude to the program, after its own.
+    ///
+    /// ### Notes:
+    /// A procedure of the program shadows the prelude procedure of the same
+    /// name, which is then left out.
+    pub fn link_prelude(self) -> Self {
+        let Self(self::P::module { mut lambdas, tail }) = self;
+        let prelude = self::prelude::prelude()
+            .into_iter()
+            .filter(|procedure| {
+                lambdas.iter().all(|lambda| lambda.name != procedure.name)
+            })
+            .collect::<Vec<_>>();
+        lambdas.extend(prelude);
+        Self(self::P::module { lambdas, tail })
+    }
+
+    /// ### Purpose:
+    /// Checks that the program is well-typed, inferring the type of every
+    /// name by Hindley-Milner style unification. The program must have been
+    /// expanded by expand-derived-forms.
+    ///
+    /// ### Notes:
+    /// Binops, and relops other than eq and neq, take ints; eq and neq take
+    /// two values of the same type. Preds must be bools. The type of a
+    /// procedure records its arity, so a call with the wrong number of
+    /// arguments is rejected. An error literal has any type.
+    ///
+    /// A lambda bound by a let, or a top-level procedure, is polymorphic in
+    /// the body of the let or in the tail of the module; the top-level
+    /// procedures are monomorphic among themselves. A polymorphic name cannot
+    /// be assigned.
+    pub fn check_types(self) -> Result<Self, String> {
+        // The quantified type variables and the type.
+        type Scheme = (Vec<usize>, self::Type);
+        type TypeEnv = utils::LevelledEnv<utils::Ident, Scheme>;
+        // The type bound to each type variable, once it is known.
+        type Subst = Vec<Option<self::Type>>;
+
+        fn check_p(p: &self::P, subst: &mut Subst) -> Result<(), String> {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let env = TypeEnv::default().add_level();
+                    let (env, types) = lambdas.iter().fold(
+                        (env, vec![]),
+                        |(env, mut types), Lambda { name, .. }| {
+                            let r#type = fresh(subst);
+                            types.push(r#type.clone());
+                            let name = (*name).into();
+                            let env = env.insert(name, (vec![], r#type));
+                            (env, types)
+                        },
+                    );
+                    let env = lambdas.iter().zip(&types).try_fold(
+                        env,
+                        |env, (Lambda { args, tail, .. }, r#type)| {
+                            let (procedure, env) =
+                                check_procedure(args, tail, env, subst)?;
+                            unify(r#type.clone(), procedure, subst)?;
+                            Ok::<_, String>(env)
+                        },
+                    )?;
+                    let env = lambdas.iter().zip(types).fold(
+                        env,
+                        |env, (Lambda { name, .. }, r#type)| {
+                            let r#type = resolve(&r#type, subst);
+                            let vars = free_vars(&r#type);
+                            env.insert((*name).into(), (vars, r#type))
+                        },
+                    );
+                    check_tail(tail, env, subst).map(|_| ())
+                },
+            }
+        }
+
+        fn check_procedure(
+            args: &[utils::Ident],
+            tail: &self::Tail,
+            env: TypeEnv,
+            subst: &mut Subst,
+        ) -> Result<(self::Type, TypeEnv), String> {
+            let (env, args) = args.iter().fold(
+                (env.add_level(), vec![]),
+                |(env, mut args), name| {
+                    let r#type = fresh(subst);
+                    args.push(r#type.clone());
+                    let env = env.insert(*name, (vec![], r#type));
+                    (env, args)
+                },
+            );
+            let (ret, env) = check_tail(tail, env, subst)?;
+            let env = env.remove_level();
+            let ret = Box::new(ret);
+            Ok((self::Type::procedure { args, ret }, env))
+        }
+
+        fn check_tail(
+            tail: &self::Tail,
+            env: TypeEnv,
+            subst: &mut Subst,
+        ) -> Result<(self::Type, TypeEnv), String> {
+            match tail {
+                self::Tail::value(value) => check_value(value, env, subst),
+                self::Tail::r#let { bindings, tail } => {
+                    let env = check_bindings(bindings, env.add_level(), subst)?;
+                    let (r#type, env) = check_tail(tail, env, subst)?;
+                    Ok((r#type, env.remove_level()))
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let env = check_pred(pred, env, subst)?;
+                    let (type1, env) = check_tail(tail1, env, subst)?;
+                    let (type2, env) = check_tail(tail2, env, subst)?;
+                    unify(type1.clone(), type2, subst)?;
+                    Ok((type1, env))
+                },
+                self::Tail::call { name, args } => {
+                    let name = self::Triv::name(*name);
+                    let r#type = check_triv(&name, &env, subst)?;
+                    let args = args
+                        .iter()
+                        .map(|arg| check_triv(arg, &env, subst))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let ret = fresh(subst);
+                    let procedure = self::Type::procedure {
+                        args,
+                        ret: Box::new(ret.clone()),
+                    };
+                    unify(procedure, r#type, subst)?;
+                    Ok((ret, env))
+                },
+            }
+        }
+
+        fn check_pred(
+            pred: &self::Pred,
+            env: TypeEnv,
+            subst: &mut Subst,
+        ) -> Result<TypeEnv, String> {
+            match pred {
+                self::Pred::relop {
+                    relop,
+                    triv1,
+                    triv2,
+                } => {
+                    check_relop(relop, triv1, triv2, &env, subst)?;
+                    Ok(env)
+                },
+                self::Pred::triv(triv) => {
+                    let r#type = check_triv(triv, &env, subst)?;
+                    unify(self::Type::bool, r#type, subst)?;
+                    Ok(env)
+                },
+                self::Pred::r#true | self::Pred::r#false => Ok(env),
+                self::Pred::not(pred) => check_pred(pred, env, subst),
+                self::Pred::r#let { bindings, pred } => {
+                    let env = check_bindings(bindings, env.add_level(), subst)?;
+                    let env = check_pred(pred, env, subst)?;
+                    Ok(env.remove_level())
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let env = check_pred(pred1, env, subst)?;
+                    let env = check_pred(pred2, env, subst)?;
+                    check_pred(pred3, env, subst)
+                },
+                self::Pred::and(..) | self::Pred::or(..) => {
+                    unreachable!(
+                        "Ands and ors are expanded by expand-derived-forms."
+                    )
+                },
+            }
+        }
+
+        fn check_value(
+            value: &self::Value,
+            env: TypeEnv,
+            subst: &mut Subst,
+        ) -> Result<(self::Type, TypeEnv), String> {
+            match value {
+                self::Value::triv(triv) => {
+                    let r#type = check_triv(triv, &env, subst)?;
+                    Ok((r#type, env))
+                },
+                self::Value::binop_triv_triv { triv1, triv2, .. } => {
+                    let type1 = check_triv(triv1, &env, subst)?;
+                    let type2 = check_triv(triv2, &env, subst)?;
+                    unify(self::Type::int, type1, subst)?;
+                    unify(self::Type::int, type2, subst)?;
+                    Ok((self::Type::int, env))
+                },
+                self::Value::relop_triv_triv {
+                    relop,
+                    triv1,
+                    triv2,
+                } => {
+                    check_relop(relop, triv1, triv2, &env, subst)?;
+                    Ok((self::Type::bool, env))
+                },
+                self::Value::primop_trivs { primop, trivs } => {
+                    let (args, ret) = primop_type(primop, subst);
+                    match args.len() == trivs.len() {
+                        true => args.into_iter().zip(trivs).try_for_each(
+                            |(arg, triv)| {
+                                let r#type = check_triv(triv, &env, subst)?;
+                                unify(arg, r#type, subst)
+                            },
+                        )?,
+                        false => Err(format!(
+                            "The primop, '{:?}', takes {} arguments, but is \
+                             given {}.",
+                            primop,
+                            args.len(),
+                            trivs.len()
+                        ))?,
+                    };
+                    Ok((ret, env))
+                },
+                self::Value::r#let { bindings, value } => {
+                    let env = check_bindings(bindings, env.add_level(), subst)?;
+                    let (r#type, env) = check_value(value, env, subst)?;
+                    Ok((r#type, env.remove_level()))
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let env = check_pred(pred, env, subst)?;
+                    let (type1, env) = check_value(value1, env, subst)?;
+                    let (type2, env) = check_value(value2, env, subst)?;
+                    unify(type1.clone(), type2, subst)?;
+                    Ok((type1, env))
+                },
+                self::Value::lambda { args, tail } => {
+                    check_procedure(args, tail, env, subst)
+                },
+                self::Value::set { name, value } => {
+                    let (r#type, env) = check_value(value, env, subst)?;
+                    match env.get(name) {
+                        Some((vars, _)) if !vars.is_empty() => Err(format!(
+                            "The polymorphic name, '{}', cannot be assigned.",
+                            name
+                        )),
+                        Some((_, name_type)) => {
+                            unify(name_type.clone(), r#type, subst)?;
+                            Ok((self::Type::void, env))
+                        },
+                        None => {
+                            Err(format!("The name, '{}', is unbound.", name))
+                        },
+                    }
+                },
+                self::Value::begin { values, value } => {
+                    let env = values.iter().try_fold(env, |env, value| {
+                        check_value(value, env, subst).map(|(_, env)| env)
+                    })?;
+                    check_value(value, env, subst)
+                },
+                self::Value::r#while { pred, value } => {
+                    let env = check_pred(pred, env, subst)?;
+                    let (_, env) = check_value(value, env, subst)?;
+                    Ok((self::Type::void, env))
+                },
+                self::Value::switch { triv, values, .. } => {
+                    let r#type = check_triv(triv, &env, subst)?;
+                    unify(self::Type::int, r#type, subst)?;
+                    let r#type = fresh(subst);
+                    let env = values.iter().try_fold(env, |env, value| {
+                        let (value_type, env) = check_value(value, env, subst)?;
+                        unify(r#type.clone(), value_type, subst)?;
+                        Ok::<_, String>(env)
+                    })?;
+                    Ok((r#type, env))
+                },
+                self::Value::cond { .. }
+                | self::Value::case { .. }
+                | self::Value::when { .. }
+                | self::Value::unless { .. } => {
+                    unreachable!(
+                        "Derived forms are expanded by expand-derived-forms."
+                    )
+                },
+            }
+        }
+
+        // Each bound lambda is generalized over the type variables which are
+        // not free in the env.
+        fn check_bindings(
+            bindings: &self::Bindings,
+            env: TypeEnv,
+            subst: &mut Subst,
+        ) -> Result<TypeEnv, String> {
+            bindings.iter().try_fold(env, |env, (name, value)| {
+                let (r#type, env) = check_value(value, env, subst)?;
+                let r#type = resolve(&r#type, subst);
+                let vars = match value {
+                    self::Value::lambda { .. } => {
+                        let env_vars = env
+                            .values()
+                            .flat_map(|(_, r#type)| {
+                                free_vars(&resolve(r#type, subst))
+                            })
+                            .collect::<HashSet<_>>();
+                        free_vars(&r#type)
+                            .into_iter()
+                            .filter(|var| !env_vars.contains(var))
+                            .collect()
+                    },
+                    _ => vec![],
+                };
+                Ok(env.insert(*name, (vars, r#type)))
+            })
+        }
+
+        // Relops other than eq and neq compare ints.
+        fn check_relop(
+            relop: &utils::Relop,
+            triv1: &self::Triv,
+            triv2: &self::Triv,
+            env: &TypeEnv,
+            subst: &mut Subst,
+        ) -> Result<(), String> {
+            let type1 = check_triv(triv1, env, subst)?;
+            let type2 = check_triv(triv2, env, subst)?;
+            match relop {
+                utils::Relop::eq | utils::Relop::neq => {
+                    unify(type1, type2, subst)
+                },
+                _ => {
+                    unify(self::Type::int, type1, subst)?;
+                    unify(self::Type::int, type2, subst)
+                },
+            }
+        }
+
+        fn check_triv(
+            triv: &self::Triv,
+            env: &TypeEnv,
+            subst: &mut Subst,
+        ) -> Result<self::Type, String> {
+            match triv {
+                self::Triv::int64(..) => Ok(self::Type::int),
+                self::Triv::boolean(..) => Ok(self::Type::bool),
+                self::Triv::empty => Ok(self::Type::empty),
+                self::Triv::void => Ok(self::Type::void),
+                self::Triv::ascii_char(..) => Ok(self::Type::char),
+                self::Triv::error(..) => Ok(fresh(subst)),
+                self::Triv::name(name) => match env.get(name) {
+                    Some(scheme) => Ok(instantiate(scheme, subst)),
+                    None => Err(format!("The name, '{}', is unbound.", name)),
+                },
+            }
+        }
+
+        // The types of the arguments and of the result of the primop.
+        fn primop_type(
+            primop: &utils::Primop,
+            subst: &mut Subst,
+        ) -> (Vec<self::Type>, self::Type) {
+            match primop {
+                utils::Primop::fixnum_p
+                | utils::Primop::boolean_p
+                | utils::Primop::empty_p
+                | utils::Primop::void_p
+                | utils::Primop::ascii_char_p
+                | utils::Primop::error_p
+                | utils::Primop::pair_p
+                | utils::Primop::vector_p
+                | utils::Primop::procedure_p
+                | utils::Primop::not => (vec![fresh(subst)], self::Type::bool),
+                utils::Primop::cons => {
+                    let car = fresh(subst);
+                    let cdr = fresh(subst);
+                    let pair = pair(car.clone(), cdr.clone());
+                    (vec![car, cdr], pair)
+                },
+                utils::Primop::car => {
+                    let car = fresh(subst);
+                    let cdr = fresh(subst);
+                    (vec![pair(car.clone(), cdr)], car)
+                },
+                utils::Primop::cdr => {
+                    let car = fresh(subst);
+                    let cdr = fresh(subst);
+                    (vec![pair(car, cdr.clone())], cdr)
+                },
+                utils::Primop::make_vector => {
+                    let element = fresh(subst);
+                    (vec![self::Type::int], vector(element))
+                },
+                utils::Primop::vector_length => {
+                    let element = fresh(subst);
+                    (vec![vector(element)], self::Type::int)
+                },
+                utils::Primop::vector_ref => {
+                    let element = fresh(subst);
+                    let args = vec![vector(element.clone()), self::Type::int];
+                    (args, element)
+                },
+                utils::Primop::vector_set => {
+                    let element = fresh(subst);
+                    let args =
+                        vec![vector(element.clone()), self::Type::int, element];
+                    (args, self::Type::void)
+                },
+            }
+        }
+
+        fn pair(car: self::Type, cdr: self::Type) -> self::Type {
+            self::Type::pair(Box::new(car), Box::new(cdr))
+        }
+
+        fn vector(element: self::Type) -> self::Type {
+            self::Type::vector(Box::new(element))
+        }
+
+        fn fresh(subst: &mut Subst) -> self::Type {
+            subst.push(None);
+            self::Type::var(subst.len() - 1)
+        }
+
+        // Replaces the quantified type variables with fresh ones.
+        fn instantiate(
+            (vars, r#type): &Scheme,
+            subst: &mut Subst,
+        ) -> self::Type {
+            let fresh_vars = vars
+                .iter()
+                .map(|var| (*var, fresh(subst)))
+                .collect::<HashMap<_, _>>();
+            rename(r#type, &fresh_vars)
+        }
+
+        fn rename(
+            r#type: &self::Type,
+            vars: &HashMap<usize, self::Type>,
+        ) -> self::Type {
+            map_type(r#type, &mut |var| vars.get(&var).cloned())
+        }
+
+        // Replaces the bound type variables with their types, throughout.
+        fn resolve(r#type: &self::Type, subst: &Subst) -> self::Type {
+            map_type(r#type, &mut |var| {
+                subst[var].as_ref().map(|r#type| resolve(r#type, subst))
+            })
+        }
+
+        fn map_type(
+            r#type: &self::Type,
+            var_type: &mut impl FnMut(usize) -> Option<self::Type>,
+        ) -> self::Type {
+            match r#type {
+                self::Type::pair(car, cdr) => {
+                    pair(map_type(car, var_type), map_type(cdr, var_type))
+                },
+                self::Type::vector(element) => {
+                    vector(map_type(element, var_type))
+                },
+                self::Type::procedure { args, ret } => {
+                    let args = args
+                        .iter()
+                        .map(|arg| map_type(arg, var_type))
+                        .collect();
+                    let ret = map_type(ret, var_type);
+                    let ret = Box::new(ret);
+                    self::Type::procedure { args, ret }
+                },
+                self::Type::var(var) => {
+                    var_type(*var).unwrap_or(self::Type::var(*var))
+                },
+                r#type => r#type.clone(),
+            }
+        }
+
+        fn free_vars(r#type: &self::Type) -> Vec<usize> {
+            match r#type {
+                self::Type::pair(car, cdr) => {
+                    let mut vars = free_vars(car);
+                    vars.extend(free_vars(cdr));
+                    vars
+                },
+                self::Type::vector(element) => free_vars(element),
+                self::Type::procedure { args, ret } => {
+                    let mut vars =
+                        args.iter().flat_map(free_vars).collect::<Vec<_>>();
+                    vars.extend(free_vars(ret));
+                    vars
+                },
+                self::Type::var(var) => vec![*var],
+                _ => vec![],
+            }
+        }
+
+        // The expected type is the first, and the type found is the second.
+        fn unify(
+            expected: self::Type,
+            found: self::Type,
+            subst: &mut Subst,
+        ) -> Result<(), String> {
+            let expected = resolve(&expected, subst);
+            let found = resolve(&found, subst);
+            match (expected, found) {
+                (self::Type::var(var1), self::Type::var(var2))
+                    if var1 == var2 =>
+                {
+                    Ok(())
+                },
+                (self::Type::var(var), r#type)
+                | (r#type, self::Type::var(var)) => {
+                    match free_vars(&r#type).contains(&var) {
+                        true => Err(format!(
+                            "The type, '{}', cannot contain itself.",
+                            r#type
+                        )),
+                        false => {
+                            subst[var] = Some(r#type);
+                            Ok(())
+                        },
+                    }
+                },
+                (
+                    self::Type::pair(car1, cdr1),
+                    self::Type::pair(car2, cdr2),
+                ) => {
+                    unify(*car1, *car2, subst)?;
+                    unify(*cdr1, *cdr2, subst)
+                },
+                (
+                    self::Type::vector(element1),
+                    self::Type::vector(element2),
+                ) => {
+                    unify(*element1, *element2, subst)
+                },
+                (
+                    self::Type::procedure {
+                        args: args1,
+                        ret: ret1,
+                    },
+                    self::Type::procedure {
+                        args: args2,
+                        ret: ret2,
+                    },
+                ) if args1.len() == args2.len() => {
+                    args1
+                        .into_iter()
+                        .zip(args2)
+                        .try_for_each(|(arg1, arg2)| unify(arg1, arg2, subst))?;
+                    unify(*ret1, *ret2, subst)
+                },
+                (expected, found) if expected == found => Ok(()),
+                (expected, found) => Err(format!(
+                    "Type mismatch: expected '{}', but found '{}'.",
+                    expected, found
+                )),
+            }
+        }
+
+        let Self(p) = &self;
+        let mut subst = Subst::default();
+        check_p(p, &mut subst).map(|()| self)
+    }
+
+    /// ### Purpose:
+    /// Expands the derived forms of Values-lang v3 into its core forms, so
+    /// that uniquify need not know about them.
+    ///
+    /// ### Notes:
+    /// A cond, when or unless becomes a chain of ifs, and an and or or
+    /// becomes a chain of ifs over its preds.
+    ///
+    /// A case whose integers cover a dense range becomes a switch through a
+    /// jump table, indexed by the triv minus the lowest integer. Any other
+    /// case becomes a chain of ifs, comparing the triv to each integer in
+    /// turn.
+    pub fn expand_derived_forms(self) -> Self {
+        let Self(p) = self;
+
+        // The fewest integers for which a case is worth a jump table, and the
+        // sparsest range of integers it may cover, as a multiple of their
+        // count.
+        const MIN_SWITCH_KEYS: usize = 4;
+        const MAX_SWITCH_SPREAD: i128 = 2;
+
+        fn expand_p(p: self::P) -> self::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let lambdas = lambdas.into_iter().map(expand_lambda);
+                    let lambdas = lambdas.collect();
+                    let tail = expand_tail(tail);
+                    self::P::module { lambdas, tail }
+                },
+            }
+        }
+
+        fn expand_lambda(Lambda { name, args, tail }: self::Lambda) -> Lambda {
+            let tail = expand_tail(tail);
+            self::Lambda { name, args, tail }
+        }
+
+        fn expand_tail(tail: self::Tail) -> self::Tail {
+            match tail {
+                self::Tail::value(value) => {
+                    let value = expand_value(value);
+                    self::Tail::value(value)
+                },
+                self::Tail::r#let { bindings, tail } => {
+                    let bindings = expand_bindings(bindings);
+                    let tail = expand_tail(*tail);
+                    let tail = Box::new(tail);
+                    self::Tail::r#let { bindings, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let pred = expand_pred(pred);
+                    let tail1 = expand_tail(*tail1);
+                    let tail2 = expand_tail(*tail2);
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    self::Tail::r#if { pred, tail1, tail2 }
+                },
+                tail @ self::Tail::call { .. } => tail,
+            }
+        }
+
+        fn expand_pred(pred: self::Pred) -> self::Pred {
+            match pred {
+                self::Pred::not(pred) => {
+                    let pred = expand_pred(*pred);
+                    let pred = Box::new(pred);
+                    self::Pred::not(pred)
+                },
+                self::Pred::r#let { bindings, pred } => {
+                    let bindings = expand_bindings(bindings);
+                    let pred = expand_pred(*pred);
+                    let pred = Box::new(pred);
+                    self::Pred::r#let { bindings, pred }
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let pred1 = expand_pred(*pred1);
+                    let pred2 = expand_pred(*pred2);
+                    let pred3 = expand_pred(*pred3);
+                    let pred1 = Box::new(pred1);
+                    let pred2 = Box::new(pred2);
+                    let pred3 = Box::new(pred3);
+                    self::Pred::r#if {
+                        pred1,
+                        pred2,
+                        pred3,
+                    }
+                },
+                // and p1 ... pn = if p1 (and p2 ... pn) false
+                self::Pred::and(preds) => preds
+                    .into_iter()
+                    .map(expand_pred)
+                    .rev()
+                    .reduce(|pred2, pred1| {
+                        make_if(pred1, pred2, self::Pred::r#false)
+                    })
+                    .unwrap_or(self::Pred::r#true),
+                // or p1 ... pn = if p1 true (or p2 ... pn)
+                self::Pred::or(preds) => preds
+                    .into_iter()
+                    .map(expand_pred)
+                    .rev()
+                    .reduce(|pred3, pred1| {
+                        make_if(pred1, self::Pred::r#true, pred3)
+                    })
+                    .unwrap_or(self::Pred::r#false),
+                pred => pred,
+            }
+        }
+
+        fn make_if(
+            pred1: self::Pred,
+            pred2: self::Pred,
+            pred3: self::Pred,
+        ) -> self::Pred {
+            let pred1 = Box::new(pred1);
+            let pred2 = Box::new(pred2);
+            let pred3 = Box::new(pred3);
+            self::Pred::r#if {
+                pred1,
+                pred2,
+                pred3,
+            }
+        }
+
+        fn expand_value(value: self::Value) -> self::Value {
+            match value {
+                self::Value::r#let { bindings, value } => {
+                    let bindings = expand_bindings(bindings);
+                    let value = expand_value(*value);
+                    let value = Box::new(value);
+                    self::Value::r#let { bindings, value }
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let pred = expand_pred(pred);
+                    let value1 = expand_value(*value1);
+                    let value2 = expand_value(*value2);
+                    make_if_value(pred, value1, value2)
+                },
+                self::Value::lambda { args, tail } => {
+                    let tail = expand_tail(*tail);
+                    let tail = Box::new(tail);
+                    self::Value::lambda { args, tail }
+                },
+                self::Value::set { name, value } => {
+                    let value = expand_value(*value);
+                    let value = Box::new(value);
+                    self::Value::set { name, value }
+                },
+                self::Value::begin { values, value } => {
+                    let values = values.into_iter().map(expand_value);
+                    let values = values.collect();
+                    let value = expand_value(*value);
+                    let value = Box::new(value);
+                    self::Value::begin { values, value }
+                },
+                self::Value::r#while { pred, value } => {
+                    let pred = expand_pred(pred);
+                    let value = expand_value(*value);
+                    let value = Box::new(value);
+                    self::Value::r#while { pred, value }
+                },
+                // cond [p1 v1] ... [pn vn] else v = if p1 v1 ... if pn vn v
+                self::Value::cond { clauses, r#else } => {
+                    let r#else = expand_value(*r#else);
+                    clauses.into_iter().rev().fold(
+                        r#else,
+                        |value2, (pred, value1)| {
+                            let pred = expand_pred(pred);
+                            let value1 = expand_value(value1);
+                            make_if_value(pred, value1, value2)
+                        },
+                    )
+                },
+                self::Value::case {
+                    triv,
+                    clauses,
+                    r#else,
+                } => {
+                    let clauses = clauses
+                        .into_iter()
+                        .map(|(ints, value)| (ints, expand_value(value)))
+                        .collect::<Vec<_>>();
+                    let r#else = expand_value(*r#else);
+                    match dense_range(&clauses) {
+                        Some((lo, length)) => {
+                            expand_switch(triv, lo, length, clauses, r#else)
+                        },
+                        None => expand_case(triv, clauses, r#else),
+                    }
+                },
+                self::Value::when { pred, value } => {
+                    let pred = expand_pred(pred);
+                    let value = expand_value(*value);
+                    let void = self::Value::triv(self::Triv::void);
+                    make_if_value(pred, value, void)
+                },
+                self::Value::unless { pred, value } => {
+                    let pred = expand_pred(pred);
+                    let value = expand_value(*value);
+                    let void = self::Value::triv(self::Triv::void);
+                    make_if_value(pred, void, value)
+                },
+                self::Value::switch {
+                    triv,
+                    lo,
+                    table,
+                    values,
+                } => {
+                    let values = values.into_iter().map(expand_value);
+                    let values = values.collect();
+                    self::Value::switch {
+                        triv,
+                        lo,
+                        table,
+                        values,
+                    }
+                },
+                value => value,
+            }
+        }
+
+        fn make_if_value(
+            pred: self::Pred,
+            value1: self::Value,
+            value2: self::Value,
+        ) -> self::Value {
+            let value1 = Box::new(value1);
+            let value2 = Box::new(value2);
+            self::Value::r#if {
+                pred,
+                value1,
+                value2,
+            }
+        }
+
+        // The lowest integer of the case and the length of the range it
+        // covers, if there are enough integers and they are dense enough.
+        fn dense_range(
+            clauses: &[(Vec<i64>, self::Value)],
+        ) -> Option<(i64, usize)> {
+            let ints = clauses
+                .iter()
+                .flat_map(|(ints, _)| ints.iter().copied())
+                .collect::<HashSet<_>>();
+            let lo = ints.iter().copied().min()?;
+            let hi = ints.iter().copied().max()?;
+            let length = hi as i128 - lo as i128 + 1;
+            let count = ints.len();
+            match count >= MIN_SWITCH_KEYS
+                && length <= MAX_SWITCH_SPREAD * count as i128
+            {
+                true => Some((lo, length as usize)),
+                false => None,
+            }
+        }
+
+        // An integer listed by several clauses selects the first of them.
+        fn expand_switch(
+            triv: self::Triv,
+            lo: i64,
+            length: usize,
+            clauses: Vec<(Vec<i64>, self::Value)>,
+            r#else: self::Value,
+        ) -> self::Value {
+            let default = clauses.len();
+            let mut table = vec![default; length];
+            let values = clauses
+                .into_iter()
+                .enumerate()
+                .map(|(index, (ints, value))| {
+                    ints.into_iter().for_each(|int| {
+                        let entry = &mut table[(int - lo) as usize];
+                        if *entry == default {
+                            *entry = index;
+                        }
+                    });
+                    value
+                })
+                .chain([r#else])
+                .collect();
+            self::Value::switch {
+                triv,
+                lo,
+                table,
+                values,
+            }
+        }
+
+        // case t [(k1 ... km) v] ... else v' = if (or (= t k1) ...) v ... v'
+        fn expand_case(
+            triv: self::Triv,
+            clauses: Vec<(Vec<i64>, self::Value)>,
+            r#else: self::Value,
+        ) -> self::Value {
+            clauses
+                .into_iter()
+                .rev()
+                .fold(r#else, |value2, (ints, value1)| {
+                    let preds = ints
+                        .into_iter()
+                        .map(|int| self::Pred::relop {
+                            relop: utils::Relop::eq,
+                            triv1: triv.clone(),
+                            triv2: self::Triv::int64(int),
+                        })
+                        .collect();
+                    let pred = expand_pred(self::Pred::or(preds));
+                    make_if_value(pred, value1, value2)
+                })
+        }
+
+        fn expand_bindings(bindings: self::Bindings) -> self::Bindings {
+            bindings
+                .into_iter()
+                .map(|(name, value)| (name, expand_value(value)))
+                .collect()
+        }
+
+        let p = expand_p(p);
+        Self(p)
+    }
+
     /// ### Purpose:
     /// Compiles Values-lang v3 to Values-unique-lang v3 by resolving all
     /// lexical identifiers to abstract locations.
+    ///
+    /// ### Notes:
+    /// Literals are replaced by their runtime representation, and a relop used
+    /// as a value becomes an if that produces true or false.
+    ///
+    /// A begin becomes a sequence of lets, each of which binds a value to a
+    /// fresh aloc only for its effect.
+    ///
+    /// Each top-level procedure is also named by an aloc, which is bound to
+    /// its label in every body that uses the procedure as a value. A call to a
+    /// top-level procedure is direct; a call to any other name is through the
+    /// value it is bound to.
     pub fn uniquify(self) -> target::ValuesUniqueLang {
-        type ValueEnv = utils::LevelledEnv<utils::Name, utils::Aloc>;
-        type LambdaEnv = HashMap<utils::Name, utils::Label>;
+        self.uniquify_with_labels(&HashMap::default())
+    }
+
+    /// ### Purpose:
+    /// Uniquifies the program as uniquify does, but gives each top-level
+    /// procedure named in the labels its label there, rather than a fresh
+    /// one.
+    ///
+    /// ### Notes:
+    /// Every other name in the labels is a procedure which another module
+    /// provides, and is bound at the top level like a procedure of this one.
+    pub fn uniquify_with_labels(
+        self,
+        labels: &HashMap<utils::Name, utils::Label>,
+    ) -> target::ValuesUniqueLang {
+        type ValueEnv = utils::LevelledEnv<utils::Ident, utils::Aloc>;
+        type ProcEnv = HashMap<utils::Aloc, utils::Label>;
         let Self(p) = self;
 
-        fn uniquify_p(p: self::P) -> target::P {
+        fn uniquify_p(
+            p: self::P,
+            labels: &HashMap<utils::Name, utils::Label>,
+        ) -> target::P {
             match p {
                 self::P::module { lambdas, tail } => {
-                    let env = ValueEnv::default();
-                    let lambda_env = collect_lambdas(&lambdas);
-                    let lambdas = uniquify_lambdas(lambdas, &lambda_env);
-                    let (tail, _) = uniquify_tail(tail, env, &lambda_env);
+                    let env = ValueEnv::default().add_level();
+                    let (env, proc_env) =
+                        collect_lambdas(&lambdas, labels, env);
+                    let (lambdas, env) =
+                        uniquify_lambdas(lambdas, env, &proc_env);
+                    let (tail, _) = uniquify_tail(tail, env, &proc_env);
+                    let tail = bind_procs(tail, &proc_env);
                     target::P::module { lambdas, tail }
                 },
             }
         }
 
-        fn collect_lambdas(lambdas: &Vec<self::Lambda>) -> LambdaEnv {
-            let length = lambdas.len();
-            lambdas.iter().fold(
-                LambdaEnv::with_capacity(length),
-                |mut lambda_env, Lambda { name, .. }| {
-                    let label = utils::Label::new_with_name(*name);
-                    lambda_env.insert(name.clone(), label);
-                    lambda_env
+        // The imported procedures are collected before those of the module,
+        // in the order of their names.
+        fn collect_lambdas(
+            lambdas: &[self::Lambda],
+            labels: &HashMap<utils::Name, utils::Label>,
+            env: ValueEnv,
+        ) -> (ValueEnv, ProcEnv) {
+            let length = lambdas.len() + labels.len();
+            let mut imports = labels
+                .keys()
+                .filter(|name| {
+                    lambdas.iter().all(|lambda| lambda.name != **name)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            imports.sort();
+            let names = imports
+                .into_iter()
+                .chain(lambdas.iter().map(|Lambda { name, .. }| *name));
+            names.fold(
+                (env, ProcEnv::with_capacity(length)),
+                |(env, mut proc_env), name| {
+                    let label = labels
+                        .get(name)
+                        .cloned()
+                        .unwrap_or_else(|| utils::Label::new_with_name(name));
+                    let aloc = utils::Aloc::fresh_with_name(name);
+                    proc_env.insert(aloc.clone(), label);
+                    let env = env.insert(name.into(), aloc);
+                    (env, proc_env)
                 },
             )
         }
 
+        // Binds the top-level procedures used as values in the body to their
+        // labels.
+        fn bind_procs(tail: target::Tail, proc_env: &ProcEnv) -> target::Tail {
+            let bindings = tail
+                .free_alocs()
+                .into_iter()
+                .filter_map(|aloc| {
+                    proc_env.get(&aloc).map(|label| {
+                        let triv = target::Triv::label(label.clone());
+                        (aloc, target::Value::triv(triv))
+                    })
+                })
+                .collect::<target::Bindings>();
+            match bindings.is_empty() {
+                true => tail,
+                false => {
+                    let tail = Box::new(tail);
+                    target::Tail::r#let { bindings, tail }
+                },
+            }
+        }
+
         fn uniquify_tail(
             tail: self::Tail,
             env: ValueEnv,
-            lambda_env: &LambdaEnv,
+            proc_env: &ProcEnv,
         ) -> (target::Tail, ValueEnv) {
             match tail {
                 self::Tail::value(value) => {
-                    let (value, env) = uniquify_value(value, env);
+                    let (value, env) = uniquify_value(value, env, proc_env);
                     let tail = target::Tail::value(value);
                     (tail, env)
                 },
                 self::Tail::r#let { bindings, tail } => {
                     let env = env.add_level();
-                    let (bindings, env) = uniquify_bindings(bindings, env);
-                    let (tail, env) = uniquify_tail(*tail, env, lambda_env);
+                    let (bindings, env) =
+                        uniquify_bindings(bindings, env, proc_env);
+                    let (tail, env) = uniquify_tail(*tail, env, proc_env);
                     let tail = Box::new(tail);
                     let tail = target::Tail::r#let { bindings, tail };
                     let env = env.remove_level();
                     (tail, env)
                 },
                 self::Tail::r#if { pred, tail1, tail2 } => {
-                    let (pred, env) = uniquify_pred(pred, env);
-                    let (tail1, env) = uniquify_tail(*tail1, env, lambda_env);
-                    let (tail2, env) = uniquify_tail(*tail2, env, lambda_env);
+                    let (pred, env) = uniquify_pred(pred, env, proc_env);
+                    let (tail1, env) = uniquify_tail(*tail1, env, proc_env);
+                    let (tail2, env) = uniquify_tail(*tail2, env, proc_env);
                     let tail1 = Box::new(tail1);
                     let tail2 = Box::new(tail2);
                     let tail = target::Tail::r#if { pred, tail1, tail2 };
                     (tail, env)
                 },
                 self::Tail::call { name, args } => {
-                    let label = lambda_env.get(&name).unwrap().clone();
-                    let triv = target::Triv::label(label);
+                    let aloc = env.get(&name).cloned().unwrap();
+                    let triv = match proc_env.get(&aloc) {
+                        Some(label) => target::Triv::label(label.clone()),
+                        None => target::Triv::opand(target::Opand::aloc(aloc)),
+                    };
                     let (opands, env) = uniquify_trivs(args, env);
                     let tail = target::Tail::call { triv, opands };
                     (tail, env)
@@ -91,12 +1309,18 @@ impl ValuesLang {
 
         fn uniquify_lambdas(
             lambdas: Vec<self::Lambda>,
-            lambda_env: &LambdaEnv,
-        ) -> Vec<target::Lambda> {
-            lambdas
-                .into_iter()
-                .map(|lambda| uniquify_lambda(lambda, lambda_env))
-                .collect()
+            env: ValueEnv,
+            proc_env: &ProcEnv,
+        ) -> (Vec<target::Lambda>, ValueEnv) {
+            let length = lambdas.len();
+            lambdas.into_iter().fold(
+                (Vec::with_capacity(length), env),
+                |(mut lambdas, env), lambda| {
+                    let (lambda, env) = uniquify_lambda(lambda, env, proc_env);
+                    lambdas.push(lambda);
+                    (lambdas, env)
+                },
+            )
         }
 
         fn uniquify_lambda(
@@ -105,11 +1329,28 @@ impl ValuesLang {
                 tail,
                 args: names,
             }: self::Lambda,
-            lambda_env: &LambdaEnv,
-        ) -> target::Lambda {
+            env: ValueEnv,
+            proc_env: &ProcEnv,
+        ) -> (target::Lambda, ValueEnv) {
+            let aloc = env.get(&name.into()).unwrap();
+            let label = proc_env.get(aloc).unwrap().clone();
+            let (args, tail, env) =
+                uniquify_procedure(names, tail, env, proc_env);
+            let tail = bind_procs(tail, proc_env);
+            let lambda = target::Lambda { label, args, tail };
+            (lambda, env)
+        }
+
+        // Binds the arguments to fresh alocs, in a new level of the env.
+        fn uniquify_procedure(
+            names: Vec<utils::Ident>,
+            tail: self::Tail,
+            env: ValueEnv,
+            proc_env: &ProcEnv,
+        ) -> (Vec<utils::Aloc>, target::Tail, ValueEnv) {
             let length = names.len();
             let (env, alocs) = names.into_iter().fold(
-                (ValueEnv::with_capacity(length), vec![]),
+                (env.add_level(), Vec::with_capacity(length)),
                 |(env, mut alocs), name| {
                     let aloc = utils::Aloc::fresh();
                     let env = env.insert(name, aloc.clone());
@@ -117,18 +1358,15 @@ impl ValuesLang {
                     (env, alocs)
                 },
             );
-            let label = lambda_env.get(&name).unwrap().clone();
-            let (tail, _) = uniquify_tail(tail, env, lambda_env);
-            target::Lambda {
-                label,
-                args: alocs,
-                tail,
-            }
+            let (tail, env) = uniquify_tail(tail, env, proc_env);
+            let env = env.remove_level();
+            (alocs, tail, env)
         }
 
         fn uniquify_pred(
             pred: self::Pred,
             env: ValueEnv,
+            proc_env: &ProcEnv,
         ) -> (target::Pred, ValueEnv) {
             match pred {
                 self::Pred::relop {
@@ -145,6 +1383,11 @@ impl ValuesLang {
                     };
                     (pred, env)
                 },
+                self::Pred::triv(triv) => {
+                    let (opand, env) = uniquify_triv(triv, env);
+                    let pred = target::Pred::opand(opand);
+                    (pred, env)
+                },
                 self::Pred::r#true => {
                     let pred = target::Pred::r#true;
                     (pred, env)
@@ -154,15 +1397,16 @@ impl ValuesLang {
                     (pred, env)
                 },
                 self::Pred::not(pred) => {
-                    let (pred, env) = uniquify_pred(*pred, env);
+                    let (pred, env) = uniquify_pred(*pred, env, proc_env);
                     let pred = Box::new(pred);
                     let pred = target::Pred::not(pred);
                     (pred, env)
                 },
                 self::Pred::r#let { bindings, pred } => {
                     let env = env.add_level();
-                    let (bindings, env) = uniquify_bindings(bindings, env);
-                    let (pred, env) = uniquify_pred(*pred, env);
+                    let (bindings, env) =
+                        uniquify_bindings(bindings, env, proc_env);
+                    let (pred, env) = uniquify_pred(*pred, env, proc_env);
                     let pred = Box::new(pred);
                     let pred = target::Pred::r#let { bindings, pred };
                     let env = env.remove_level();
@@ -173,9 +1417,9 @@ impl ValuesLang {
                     pred2,
                     pred3,
                 } => {
-                    let (pred1, env) = uniquify_pred(*pred1, env);
-                    let (pred2, env) = uniquify_pred(*pred2, env);
-                    let (pred3, env) = uniquify_pred(*pred3, env);
+                    let (pred1, env) = uniquify_pred(*pred1, env, proc_env);
+                    let (pred2, env) = uniquify_pred(*pred2, env, proc_env);
+                    let (pred3, env) = uniquify_pred(*pred3, env, proc_env);
                     let pred1 = Box::new(pred1);
                     let pred2 = Box::new(pred2);
                     let pred3 = Box::new(pred3);
@@ -186,12 +1430,18 @@ impl ValuesLang {
                     };
                     (pred, env)
                 },
+                self::Pred::and(..) | self::Pred::or(..) => {
+                    unreachable!(
+                        "Ands and ors are expanded by expand-derived-forms."
+                    )
+                },
             }
         }
 
         fn uniquify_value(
             value: self::Value,
             env: ValueEnv,
+            proc_env: &ProcEnv,
         ) -> (target::Value, ValueEnv) {
             match value {
                 self::Value::triv(triv) => {
@@ -214,10 +1464,39 @@ impl ValuesLang {
                     };
                     (value, env)
                 },
+                self::Value::relop_triv_triv {
+                    relop,
+                    triv1,
+                    triv2,
+                } => {
+                    let (opand1, env) = uniquify_triv(triv1, env);
+                    let (opand2, env) = uniquify_triv(triv2, env);
+                    let pred = target::Pred::relop {
+                        relop,
+                        opand1,
+                        opand2,
+                    };
+                    let value1 = uniquify_boolean(true);
+                    let value2 = uniquify_boolean(false);
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    let value = target::Value::r#if {
+                        pred,
+                        value1,
+                        value2,
+                    };
+                    (value, env)
+                },
+                self::Value::primop_trivs { primop, trivs } => {
+                    let (opands, env) = uniquify_trivs(trivs, env);
+                    let value = target::Value::primop { primop, opands };
+                    (value, env)
+                },
                 self::Value::r#let { bindings, value } => {
                     let env = env.add_level();
-                    let (bindings, env) = uniquify_bindings(bindings, env);
-                    let (value, env) = uniquify_value(*value, env);
+                    let (bindings, env) =
+                        uniquify_bindings(bindings, env, proc_env);
+                    let (value, env) = uniquify_value(*value, env, proc_env);
                     let value = Box::new(value);
                     let value = target::Value::r#let { bindings, value };
                     let env = env.remove_level();
@@ -228,9 +1507,9 @@ impl ValuesLang {
                     value1,
                     value2,
                 } => {
-                    let (pred, env) = uniquify_pred(pred, env);
-                    let (value1, env) = uniquify_value(*value1, env);
-                    let (value2, env) = uniquify_value(*value2, env);
+                    let (pred, env) = uniquify_pred(pred, env, proc_env);
+                    let (value1, env) = uniquify_value(*value1, env, proc_env);
+                    let (value2, env) = uniquify_value(*value2, env, proc_env);
                     let value1 = Box::new(value1);
                     let value2 = Box::new(value2);
                     let value = target::Value::r#if {
@@ -240,6 +1519,116 @@ impl ValuesLang {
                     };
                     (value, env)
                 },
+                self::Value::lambda { args, tail } => {
+                    let (args, tail, env) =
+                        uniquify_procedure(args, *tail, env, proc_env);
+                    let tail = Box::new(tail);
+                    let value = target::Value::lambda { args, tail };
+                    (value, env)
+                },
+                self::Value::set { name, value } => {
+                    let aloc = env.get(&name).cloned().unwrap();
+                    let (value, env) = uniquify_value(*value, env, proc_env);
+                    let value = Box::new(value);
+                    let value = target::Value::set { aloc, value };
+                    (value, env)
+                },
+                // begin v1 ... vn v = let tmp1 = v1 in ... let tmpn = vn in v
+                self::Value::begin { values, value } => {
+                    let (values, env) = values.into_iter().fold(
+                        (vec![], env),
+                        |(mut values, env), value| {
+                            let (value, env) =
+                                uniquify_value(value, env, proc_env);
+                            values.push(value);
+                            (values, env)
+                        },
+                    );
+                    let (value, env) = uniquify_value(*value, env, proc_env);
+                    let value =
+                        values.into_iter().rev().fold(value, |body, value| {
+                            let aloc = utils::Aloc::fresh();
+                            let bindings =
+                                [(aloc, value)].into_iter().collect();
+                            let value = Box::new(body);
+                            target::Value::r#let { bindings, value }
+                        });
+                    (value, env)
+                },
+                self::Value::r#while { pred, value } => {
+                    let (pred, env) = uniquify_pred(pred, env, proc_env);
+                    let (value, env) = uniquify_value(*value, env, proc_env);
+                    let value = Box::new(value);
+                    let value = target::Value::r#while { pred, value };
+                    (value, env)
+                },
+                // switch t lo ... =
+                //     let i = if (fixnum? t) (- t lo) -1 in switch i ...
+                self::Value::switch {
+                    triv,
+                    lo,
+                    table,
+                    values,
+                } => {
+                    let (opand, env) = uniquify_triv(triv, env);
+                    let (values, env) = values.into_iter().fold(
+                        (vec![], env),
+                        |(mut values, env), value| {
+                            let (value, env) =
+                                uniquify_value(value, env, proc_env);
+                            values.push(value);
+                            (values, env)
+                        },
+                    );
+                    let is_fixnum = utils::Aloc::fresh();
+                    let index = utils::Aloc::fresh();
+                    let primop = utils::Primop::fixnum_p;
+                    let opands = vec![opand.clone()];
+                    let bindings = [(
+                        is_fixnum.clone(),
+                        target::Value::primop { primop, opands },
+                    )]
+                    .into_iter()
+                    .collect();
+                    let pred =
+                        target::Pred::opand(target::Opand::aloc(is_fixnum));
+                    let pred = Box::new(pred);
+                    let pred = target::Pred::r#let { bindings, pred };
+                    let value1 = target::Value::binop {
+                        binop: utils::Binop::minus,
+                        opand1: opand,
+                        opand2: uniquify_datum(utils::Datum::fixnum(lo)),
+                    };
+                    let value2 = target::Value::triv(target::Triv::opand(
+                        uniquify_datum(utils::Datum::fixnum(-1)),
+                    ));
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    let value = target::Value::r#if {
+                        pred,
+                        value1,
+                        value2,
+                    };
+                    let bindings =
+                        [(index.clone(), value)].into_iter().collect();
+                    let opand = target::Opand::aloc(index);
+                    let value = target::Value::switch {
+                        opand,
+                        table,
+                        values,
+                    };
+                    let value = Box::new(value);
+                    let value = target::Value::r#let { bindings, value };
+                    (value, env)
+                },
+                self::Value::cond { .. }
+                | self::Value::case { .. }
+                | self::Value::when { .. }
+                | self::Value::unless { .. } => {
+                    unreachable!(
+                        "Derived forms are expanded by expand-derived-forms."
+                    )
+                },
             }
         }
 
@@ -262,26 +1651,57 @@ impl ValuesLang {
         ) -> (target::Opand, ValueEnv) {
             match triv {
                 self::Triv::int64(int64) => {
-                    let opand = target::Opand::int64(int64);
+                    let opand = uniquify_datum(utils::Datum::fixnum(int64));
+                    (opand, env)
+                },
+                self::Triv::boolean(boolean) => {
+                    let opand = uniquify_datum(utils::Datum::boolean(boolean));
+                    (opand, env)
+                },
+                self::Triv::empty => {
+                    let opand = uniquify_datum(utils::Datum::empty);
+                    (opand, env)
+                },
+                self::Triv::void => {
+                    let opand = uniquify_datum(utils::Datum::void);
+                    (opand, env)
+                },
+                self::Triv::ascii_char(ascii) => {
+                    let opand = uniquify_datum(utils::Datum::ascii_char(ascii));
+                    (opand, env)
+                },
+                self::Triv::error(code) => {
+                    let opand = uniquify_datum(utils::Datum::error(code));
                     (opand, env)
                 },
                 self::Triv::name(name) => {
-                    let aloc = env.get(&name).map(utils::Aloc::clone).unwrap();
+                    let aloc = env.get(&name).cloned().unwrap();
                     let opand = target::Opand::aloc(aloc);
                     (opand, env)
                 },
             }
         }
 
+        fn uniquify_boolean(boolean: bool) -> target::Value {
+            let opand = uniquify_datum(utils::Datum::boolean(boolean));
+            let triv = target::Triv::opand(opand);
+            target::Value::triv(triv)
+        }
+
+        fn uniquify_datum(datum: utils::Datum) -> target::Opand {
+            target::Opand::int64(datum.repr())
+        }
+
         fn uniquify_bindings(
             bindings: self::Bindings,
             env: ValueEnv,
+            proc_env: &ProcEnv,
         ) -> (target::Bindings, ValueEnv) {
             let length = bindings.len();
             bindings.into_iter().fold(
                 (HashMap::with_capacity(length), env),
                 |(mut bindings, env), (name, value)| {
-                    let (value, env) = uniquify_value(value, env);
+                    let (value, env) = uniquify_value(value, env, proc_env);
                     let aloc = utils::Aloc::fresh();
                     bindings.insert(aloc.clone(), value);
                     let env = env.insert(name, aloc);
@@ -290,7 +1710,47 @@ impl ValuesLang {
             )
         }
 
-        let p = uniquify_p(p);
+        let p = uniquify_p(p, labels);
         target::ValuesUniqueLang(p)
     }
 }
+
+impl Module {
+    /// ### Purpose:
+    /// Ensures that the module provides only its own top-level procedures,
+    /// requires none of them, and names no procedure twice.
+    pub fn check_module(self) -> Result<Self, String> {
+        let self::P::module { lambdas, .. } = &self.p;
+        let is_defined = |name: &utils::Name| {
+            lambdas.iter().any(|lambda| lambda.name == *name)
+        };
+        let mut names = HashSet::new();
+        self.provides.iter().try_for_each(|name| {
+            match (is_defined(name), names.insert(*name)) {
+                (true, true) => Ok(()),
+                (false, _) => Err(format!(
+                    "The module, '{}', provides '{}', but does not define it.",
+                    self.name, name
+                )),
+                (_, false) => Err(format!(
+                    "The module, '{}', provides '{}' twice.",
+                    self.name, name
+                )),
+            }
+        })?;
+        self.requires.iter().try_for_each(|name| {
+            match (is_defined(name), names.insert(*name)) {
+                (false, true) => Ok(()),
+                (true, _) => Err(format!(
+                    "The module, '{}', requires '{}', which it defines.",
+                    self.name, name
+                )),
+                (_, false) => Err(format!(
+                    "The module, '{}', requires '{}' twice.",
+                    self.name, name
+                )),
+            }
+        })?;
+        Ok(self)
+    }
+}
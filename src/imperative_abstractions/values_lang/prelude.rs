@@ -0,0 +1,209 @@
+use crate::imperative_abstractions::values_lang::Lambda;
+use crate::imperative_abstractions::values_lang::Pred;
+use crate::imperative_abstractions::values_lang::Tail;
+use crate::imperative_abstractions::values_lang::Triv;
+use crate::imperative_abstractions::values_lang::Value;
+use crate::utils;
+
+/// ### Purpose:
+/// The procedures of the prelude, which link-prelude adds to every program.
+///
+/// ### Notes:
+/// The procedures only make tail calls, so the recursive ones (expt, gcd and
+/// fib) are written as while loops over their arguments.
+pub fn prelude() -> Vec<Lambda> {
+    vec![abs(), max(), min(), expt(), gcd(), fib()]
+}
+
+// (define (abs x) (if (< x 0) (- 0 x) x))
+fn abs() -> Lambda {
+    procedure(
+        "abs",
+        vec!["x"],
+        Value::r#if {
+            pred: relop(utils::Relop::lt, name("x"), int64(0)),
+            value1: Box::new(binop(utils::Binop::minus, int64(0), name("x"))),
+            value2: Box::new(triv(name("x"))),
+        },
+    )
+}
+
+// (define (max x y) (if (> x y) x y))
+fn max() -> Lambda {
+    procedure(
+        "max",
+        vec!["x", "y"],
+        Value::r#if {
+            pred: relop(utils::Relop::gt, name("x"), name("y")),
+            value1: Box::new(triv(name("x"))),
+            value2: Box::new(triv(name("y"))),
+        },
+    )
+}
+
+// (define (min x y) (if (< x y) x y))
+fn min() -> Lambda {
+    procedure(
+        "min",
+        vec!["x", "y"],
+        Value::r#if {
+            pred: relop(utils::Relop::lt, name("x"), name("y")),
+            value1: Box::new(triv(name("x"))),
+            value2: Box::new(triv(name("y"))),
+        },
+    )
+}
+
+// (define (expt x n)
+//   (let ([r 1])
+//     (begin
+//       (while (> n 0) (begin (set! r (* r x)) (set! n (- n 1))))
+//       r)))
+fn expt() -> Lambda {
+    procedure(
+        "expt",
+        vec!["x", "n"],
+        Value::r#let {
+            bindings: vec![("r".into(), triv(int64(1)))],
+            value: Box::new(begin(
+                vec![Value::r#while {
+                    pred: relop(utils::Relop::gt, name("n"), int64(0)),
+                    value: Box::new(begin(
+                        vec![set(
+                            "r",
+                            binop(utils::Binop::multiply, name("r"), name("x")),
+                        )],
+                        set(
+                            "n",
+                            binop(utils::Binop::minus, name("n"), int64(1)),
+                        ),
+                    )),
+                }],
+                triv(name("r")),
+            )),
+        },
+    )
+}
+
+// (define (gcd x y)
+//   (let ([_ (while (!= y 0)
+//              (let ([r (remainder x y)]) (begin (set! x y) (set! y r))))])
+//     (abs x)))
+fn gcd() -> Lambda {
+    let tail = Tail::call {
+        name: "abs".into(),
+        args: vec![name("x")],
+    };
+    let r#while = Value::r#while {
+        pred: relop(utils::Relop::neq, name("y"), int64(0)),
+        value: Box::new(Value::r#let {
+            bindings: vec![(
+                "r".into(),
+                binop(utils::Binop::remainder, name("x"), name("y")),
+            )],
+            value: Box::new(begin(
+                vec![set("x", triv(name("y")))],
+                set("y", triv(name("r"))),
+            )),
+        }),
+    };
+    Lambda {
+        name: "gcd",
+        args: vec!["x".into(), "y".into()],
+        tail: Tail::r#let {
+            bindings: vec![("_".into(), r#while)],
+            tail: Box::new(tail),
+        },
+    }
+}
+
+// (define (fib n)
+//   (let ([a 0] [b 1])
+//     (begin
+//       (while (> n 0)
+//         (let ([c (+ a b)])
+//           (begin (set! a b) (set! b c) (set! n (- n 1)))))
+//       a)))
+fn fib() -> Lambda {
+    procedure(
+        "fib",
+        vec!["n"],
+        Value::r#let {
+            bindings: vec![
+                ("a".into(), triv(int64(0))),
+                ("b".into(), triv(int64(1))),
+            ],
+            value: Box::new(begin(
+                vec![Value::r#while {
+                    pred: relop(utils::Relop::gt, name("n"), int64(0)),
+                    value: Box::new(Value::r#let {
+                        bindings: vec![(
+                            "c".into(),
+                            binop(utils::Binop::plus, name("a"), name("b")),
+                        )],
+                        value: Box::new(begin(
+                            vec![
+                                set("a", triv(name("b"))),
+                                set("b", triv(name("c"))),
+                            ],
+                            set(
+                                "n",
+                                binop(utils::Binop::minus, name("n"), int64(1)),
+                            ),
+                        )),
+                    }),
+                }],
+                triv(name("a")),
+            )),
+        },
+    )
+}
+
+fn procedure(
+    name: utils::Name,
+    args: Vec<utils::Name>,
+    value: Value,
+) -> Lambda {
+    let args = args.into_iter().map(utils::Ident::from).collect();
+    let tail = Tail::value(value);
+    Lambda { name, args, tail }
+}
+
+fn begin(values: Vec<Value>, value: Value) -> Value {
+    let value = Box::new(value);
+    Value::begin { values, value }
+}
+
+fn set(name: utils::Name, value: Value) -> Value {
+    let name = name.into();
+    let value = Box::new(value);
+    Value::set { name, value }
+}
+
+fn binop(binop: utils::Binop, triv1: Triv, triv2: Triv) -> Value {
+    Value::binop_triv_triv {
+        binop,
+        triv1,
+        triv2,
+    }
+}
+
+fn relop(relop: utils::Relop, triv1: Triv, triv2: Triv) -> Pred {
+    Pred::relop {
+        relop,
+        triv1,
+        triv2,
+    }
+}
+
+fn triv(triv: Triv) -> Value {
+    Value::triv(triv)
+}
+
+fn name(name: utils::Name) -> Triv {
+    Triv::name(name.into())
+}
+
+fn int64(int64: i64) -> Triv {
+    Triv::int64(int64)
+}
@@ -15,7 +15,7 @@ fn book_example_1() {
             triv2: source::Triv::int64(2),
         }),
     });
-    let actual = p.uniquify();
+    let _actual = p.uniquify();
     // let expected = target::ValuesUniqueLang(target::P::module(
     //     target::Tail::value(target::Value::binop_triv_triv {
     //         binop: utils::Binop::plus,
@@ -45,7 +45,7 @@ fn book_example_2() {
             ))),
         },
     });
-    let actual = p.uniquify();
+    let _actual = p.uniquify();
     // let aloc = utils::Aloc::fresh();
     // let expected =
     //     target::ValuesUniqueLang(target::P::module(target::Tail::r#let {
@@ -72,17 +72,20 @@ fn same_scope_reference() {
         lambdas: vec![],
         tail: source::Tail::r#let {
             bindings: vec![
-                ("a", source::Value::triv(source::Triv::int64(10))),
-                ("b", source::Value::triv(source::Triv::name("a"))),
+                ("a".into(), source::Value::triv(source::Triv::int64(10))),
+                (
+                    "b".into(),
+                    source::Value::triv(source::Triv::name("a".into())),
+                ),
             ]
             .into_iter()
             .collect(),
             tail: Box::new(source::Tail::value(source::Value::triv(
-                source::Triv::name("a"),
+                source::Triv::name("a".into()),
             ))),
         },
     });
-    let actual = p.uniquify();
+    let _actual = p.uniquify();
 }
 
 #[test]
@@ -92,17 +95,17 @@ fn shadowing() {
         lambdas: vec![],
         tail: source::Tail::r#let {
             bindings: vec![
-                ("a", source::Value::triv(source::Triv::int64(10))),
-                ("a", source::Value::triv(source::Triv::int64(101))),
+                ("a".into(), source::Value::triv(source::Triv::int64(10))),
+                ("a".into(), source::Value::triv(source::Triv::int64(101))),
             ]
             .into_iter()
             .collect(),
             tail: Box::new(source::Tail::value(source::Value::triv(
-                source::Triv::name("a"),
+                source::Triv::name("a".into()),
             ))),
         },
     });
-    let actual = p.uniquify();
+    let _actual = p.uniquify();
 }
 
 #[test]
@@ -114,7 +117,7 @@ fn invalid_identifier() {
         tail: source::Tail::r#let {
             bindings: vec![].into_iter().collect(),
             tail: Box::new(source::Tail::value(source::Value::triv(
-                source::Triv::name("x"),
+                source::Triv::name("x".into()),
             ))),
         },
     });
@@ -128,21 +131,345 @@ fn invalid_nested_identifier() {
     let program = source::ValuesLang(source::P::module {
         lambdas: vec![],
         tail: source::Tail::r#let {
-            bindings: vec![("z", source::Value::r#let {
+            bindings: vec![("z".into(), source::Value::r#let {
                 bindings: vec![(
-                    "x",
+                    "x".into(),
                     source::Value::triv(source::Triv::int64(5)),
                 )]
                 .into_iter()
                 .collect(),
-                value: Box::new(source::Value::triv(source::Triv::name("x"))),
+                value: Box::new(source::Value::triv(source::Triv::name(
+                    "x".into(),
+                ))),
             })]
             .into_iter()
             .collect(),
             tail: Box::new(source::Tail::value(source::Value::triv(
-                source::Triv::name("x"),
+                source::Triv::name("x".into()),
             ))),
         },
     });
     program.uniquify();
 }
+
+#[test]
+#[serial]
+fn boolean_values() {
+    utils::reset_all_indices();
+
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::r#let {
+            bindings: vec![("b".into(), source::Value::relop_triv_triv {
+                relop: utils::Relop::lt,
+                triv1: source::Triv::int64(1),
+                triv2: source::Triv::int64(2),
+            })],
+            tail: Box::new(source::Tail::r#if {
+                pred: source::Pred::triv(source::Triv::name("b".into())),
+                tail1: Box::new(source::Tail::value(source::Value::triv(
+                    source::Triv::boolean(false),
+                ))),
+                tail2: Box::new(source::Tail::value(source::Value::triv(
+                    source::Triv::int64(5),
+                ))),
+            }),
+        },
+    });
+    let actual = p.uniquify();
+
+    utils::reset_all_indices();
+
+    let aloc = utils::Aloc::fresh();
+    let boolean = |boolean| {
+        target::Value::triv(target::Triv::opand(target::Opand::int64(
+            utils::Datum::boolean(boolean).repr(),
+        )))
+    };
+    let expected = target::ValuesUniqueLang(target::P::module {
+        lambdas: vec![],
+        tail: target::Tail::r#let {
+            bindings: vec![(aloc.clone(), target::Value::r#if {
+                pred: target::Pred::relop {
+                    relop: utils::Relop::lt,
+                    opand1: target::Opand::int64(8),
+                    opand2: target::Opand::int64(16),
+                },
+                value1: Box::new(boolean(true)),
+                value2: Box::new(boolean(false)),
+            })]
+            .into_iter()
+            .collect(),
+            tail: Box::new(target::Tail::r#if {
+                pred: target::Pred::opand(target::Opand::aloc(aloc)),
+                tail1: Box::new(target::Tail::value(boolean(false))),
+                tail2: Box::new(target::Tail::value(target::Value::triv(
+                    target::Triv::opand(target::Opand::int64(40)),
+                ))),
+            }),
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn dense_case_becomes_switch() {
+    let int64 = |int64| source::Value::triv(source::Triv::int64(int64));
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::case {
+            triv: source::Triv::name("x".into()),
+            clauses: vec![
+                (vec![1], int64(10)),
+                (vec![2, 3], int64(20)),
+                (vec![4, 1], int64(30)),
+                (vec![6], int64(40)),
+            ],
+            r#else: Box::new(int64(50)),
+        }),
+    });
+    let actual = p.expand_derived_forms();
+
+    let expected = source::ValuesLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::switch {
+            triv: source::Triv::name("x".into()),
+            lo: 1,
+            table: vec![0, 1, 1, 2, 4, 3],
+            values: vec![int64(10), int64(20), int64(30), int64(40), int64(50)],
+        }),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn sparse_case_becomes_ifs() {
+    let int64 = |int64| source::Value::triv(source::Triv::int64(int64));
+    let eq = |int64| source::Pred::relop {
+        relop: utils::Relop::eq,
+        triv1: source::Triv::name("x".into()),
+        triv2: source::Triv::int64(int64),
+    };
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::case {
+            triv: source::Triv::name("x".into()),
+            clauses: vec![(vec![1], int64(10)), (vec![100, 200], int64(20))],
+            r#else: Box::new(int64(30)),
+        }),
+    });
+    let actual = p.expand_derived_forms();
+
+    let expected = source::ValuesLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::r#if {
+            pred: eq(1),
+            value1: Box::new(int64(10)),
+            value2: Box::new(source::Value::r#if {
+                pred: source::Pred::r#if {
+                    pred1: Box::new(eq(100)),
+                    pred2: Box::new(source::Pred::r#true),
+                    pred3: Box::new(eq(200)),
+                },
+                value1: Box::new(int64(20)),
+                value2: Box::new(int64(30)),
+            }),
+        }),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn adding_a_procedure_is_rejected() {
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![source::Lambda {
+            name: "f",
+            args: vec!["x".into()],
+            tail: source::Tail::value(source::Value::triv(
+                source::Triv::name("x".into()),
+            )),
+        }],
+        tail: source::Tail::value(source::Value::binop_triv_triv {
+            binop: utils::Binop::plus,
+            triv1: source::Triv::name("f".into()),
+            triv2: source::Triv::int64(1),
+        }),
+    });
+
+    assert!(p.check_types().is_err());
+}
+
+#[test]
+#[serial]
+fn polymorphic_identity_is_accepted() {
+    let id = source::Value::lambda {
+        args: vec!["x".into()],
+        tail: Box::new(source::Tail::value(source::Value::triv(
+            source::Triv::name("x".into()),
+        ))),
+    };
+    let f = source::Value::lambda {
+        args: vec![],
+        tail: Box::new(source::Tail::call {
+            name: "id".into(),
+            args: vec![source::Triv::boolean(true)],
+        }),
+    };
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::r#let {
+            bindings: vec![("id".into(), id), ("f".into(), f)],
+            tail: Box::new(source::Tail::call {
+                name: "id".into(),
+                args: vec![source::Triv::int64(1)],
+            }),
+        },
+    });
+
+    assert!(p.check_types().is_ok());
+}
+
+#[test]
+#[serial]
+fn arity_mismatch_is_rejected() {
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![source::Lambda {
+            name: "f",
+            args: vec!["x".into(), "y".into()],
+            tail: source::Tail::value(source::Value::binop_triv_triv {
+                binop: utils::Binop::plus,
+                triv1: source::Triv::name("x".into()),
+                triv2: source::Triv::name("y".into()),
+            }),
+        }],
+        tail: source::Tail::call {
+            name: "f".into(),
+            args: vec![source::Triv::int64(1)],
+        },
+    });
+
+    assert!(p.check_types().is_err());
+}
+
+#[test]
+#[serial]
+fn program_shadows_prelude() {
+    let abs = source::Lambda {
+        name: "abs",
+        args: vec!["x".into()],
+        tail: source::Tail::value(source::Value::triv(source::Triv::name(
+            "x".into(),
+        ))),
+    };
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![abs],
+        tail: source::Tail::call {
+            name: "abs".into(),
+            args: vec![source::Triv::int64(-1)],
+        },
+    });
+    let source::ValuesLang(source::P::module { lambdas, .. }) =
+        p.link_prelude();
+    let names = lambdas
+        .iter()
+        .map(|lambda| (lambda.name, lambda.args.len()))
+        .collect::<Vec<_>>();
+
+    let expected = vec![
+        ("abs", 1),
+        ("max", 2),
+        ("min", 2),
+        ("expt", 2),
+        ("gcd", 2),
+        ("fib", 1),
+    ];
+
+    assert_eq!(names, expected);
+    assert_eq!(
+        lambdas[0].tail,
+        source::Tail::value(source::Value::triv(source::Triv::name("x".into())))
+    );
+}
+
+#[test]
+#[serial]
+fn prelude_is_well_typed() {
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::call {
+            name: "gcd".into(),
+            args: vec![source::Triv::int64(12), source::Triv::int64(18)],
+        },
+    });
+    let actual = p.link_prelude().expand_derived_forms().check_types();
+
+    assert!(actual.is_ok());
+}
+
+#[test]
+#[serial]
+fn out_of_range_integer_is_rejected() {
+    let program = |int64| {
+        source::ValuesLang(source::P::module {
+            lambdas: vec![],
+            tail: source::Tail::value(source::Value::binop_triv_triv {
+                binop: utils::Binop::plus,
+                triv1: source::Triv::int64(int64),
+                triv2: source::Triv::int64(1),
+            }),
+        })
+    };
+
+    assert!(program((1 << 60) - 1).check_values_lang().is_ok());
+    assert!(program(-(1 << 60)).check_values_lang().is_ok());
+    assert!(program(1 << 60).check_values_lang().is_err());
+    assert!(program(-(1 << 60) - 1).check_values_lang().is_err());
+}
+
+#[test]
+#[serial]
+fn primop_arity_mismatch_is_rejected() {
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::primop_trivs {
+            primop: utils::Primop::cons,
+            trivs: vec![source::Triv::int64(1)],
+        }),
+    });
+
+    assert!(p.check_values_lang().is_err());
+}
+
+#[test]
+#[serial]
+fn assigning_a_procedure_is_rejected() {
+    let lambda = |name| source::Lambda {
+        name,
+        args: vec![],
+        tail: source::Tail::value(source::Value::triv(source::Triv::int64(0))),
+    };
+    let program = |bindings| {
+        source::ValuesLang(source::P::module {
+            lambdas: vec![lambda("f"), lambda("g")],
+            tail: source::Tail::r#let {
+                bindings,
+                tail: Box::new(source::Tail::value(source::Value::set {
+                    name: "f".into(),
+                    value: Box::new(source::Value::triv(source::Triv::name(
+                        "g".into(),
+                    ))),
+                })),
+            },
+        })
+    };
+    let shadow = ("f".into(), source::Value::triv(source::Triv::int64(1)));
+
+    assert!(program(vec![]).check_values_lang().is_err());
+    assert!(program(vec![shadow]).check_values_lang().is_ok());
+}
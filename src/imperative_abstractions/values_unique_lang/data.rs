@@ -39,6 +39,7 @@ pub enum Pred {
         opand1: Opand,
         opand2: Opand,
     },
+    opand(Opand),
     r#true,
     r#false,
     not(Box<Self>),
@@ -61,6 +62,23 @@ pub enum Value {
         opand1: Opand,
         opand2: Opand,
     },
+    primop {
+        primop: utils::Primop,
+        opands: Vec<Opand>,
+    },
+    alloc {
+        opand: Opand,
+    },
+    mref {
+        opand1: Opand,
+        opand2: Opand,
+    },
+    /// Stores opand3 at opand1 + opand2 and evaluates to void.
+    mset {
+        opand1: Opand,
+        opand2: Opand,
+        opand3: Opand,
+    },
     r#let {
         bindings: Bindings,
         value: Box<Self>,
@@ -70,6 +88,39 @@ pub enum Value {
         value1: Box<Self>,
         value2: Box<Self>,
     },
+    lambda {
+        args: Vec<utils::Aloc>,
+        tail: Box<Tail>,
+    },
+    /// Assigns the value to the aloc and evaluates to void.
+    set {
+        aloc: utils::Aloc,
+        value: Box<Self>,
+    },
+    /// Evaluates the value for as long as the pred holds, and evaluates to
+    /// void.
+    r#while {
+        pred: Pred,
+        value: Box<Self>,
+    },
+    /// Evaluates the value which the table maps the opand to, or the last
+    /// value if the opand is outside the table.
+    switch {
+        opand: Opand,
+        table: Vec<usize>,
+        values: Vec<Self>,
+    },
+    /// Allocates a closure of the labelled procedure, capturing the opands.
+    make_closure {
+        label: utils::Label,
+        arity: usize,
+        opands: Vec<Opand>,
+    },
+    /// Reads the captured value at the index out of the closure.
+    closure_ref {
+        opand: Opand,
+        index: usize,
+    },
 }
 
 pub type Opand = super::target::Opand;
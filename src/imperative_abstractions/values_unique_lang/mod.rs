@@ -2,13 +2,2400 @@ pub mod data;
 #[cfg(test)]
 mod tests;
 
+use std::collections::HashMap;
+use std::collections::HashSet;
+
 pub use self::data::*;
 use crate::imperative_abstractions::imp_mf_lang as target;
+use crate::utils;
+
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct ValuesUniqueLang(pub self::P);
+
+impl ValuesUniqueLang {
+    /// EliminateDeadProcedures: ValuesUniqueLang -> ValuesUniqueLang
+    ///
+    /// ### Purpose:
+    /// Removes the top-level procedures which cannot be reached through the
+    /// call graph from the tail of the module, or from any of the roots.
+    /// Returns the labels of the procedures removed, in the order in which
+    /// they were defined.
+    ///
+    /// ### Notes:
+    /// A procedure is reached wherever its label is referenced, whether it is
+    /// called or used as a value. The roots are the procedures which other
+    /// modules may call.
+    pub fn eliminate_dead_procedures(
+        self,
+        roots: &[utils::Label],
+    ) -> (Self, Vec<utils::Label>) {
+        let Self(self::P::module { lambdas, tail }) = self;
+        let calls = lambdas
+            .iter()
+            .map(|lambda| (lambda.label.clone(), lambda.tail.labels()))
+            .collect::<HashMap<_, _>>();
+        let mut work = tail.labels().into_iter().collect::<Vec<_>>();
+        work.extend(roots.iter().cloned());
+        let mut reached = HashSet::new();
+        while let Some(label) = work.pop() {
+            if reached.insert(label.clone()) {
+                work.extend(calls.get(&label).into_iter().flatten().cloned());
+            }
+        }
+        let (lambdas, removed): (Vec<_>, Vec<_>) = lambdas
+            .into_iter()
+            .partition(|lambda| reached.contains(&lambda.label));
+        let removed = removed.into_iter().map(|lambda| lambda.label).collect();
+        (Self(self::P::module { lambdas, tail }), removed)
+    }
+
+    /// InlineProcedures: ValuesUniqueLang -> ValuesUniqueLang
+    ///
+    /// ### Purpose:
+    /// Replaces each direct call to a top-level procedure, whose body is no
+    /// larger than the budget, with a copy of the body in which the arguments
+    /// are bound to the opands of the call.
+    ///
+    /// ### Notes:
+    /// Every aloc of the copy is renamed to a fresh aloc, so that the alocs of
+    /// the program stay unique. The body of each procedure is inlined into
+    /// before it is copied, and its size is counted afterwards, in nodes of
+    /// the tail. A procedure is never inlined while its own body is being
+    /// inlined into, and a procedure whose body still calls itself is never
+    /// inlined at all, so that recursion cannot be unrolled without limit.
+    pub fn inline_procedures(self, budget: usize) -> Self {
+        type AlocMap = HashMap<utils::Aloc, utils::Aloc>;
+
+        // The procedures which are yet to be inlined into, and those which
+        // have been.
+        struct Procs {
+            pending: HashMap<utils::Label, self::Lambda>,
+            inlined: HashMap<utils::Label, self::Lambda>,
+        }
+
+        fn inline_p(p: self::P, budget: usize) -> self::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let order = lambdas
+                        .iter()
+                        .map(|lambda| lambda.label.clone())
+                        .collect::<Vec<_>>();
+                    let pending = lambdas
+                        .into_iter()
+                        .map(|lambda| (lambda.label.clone(), lambda))
+                        .collect();
+                    let inlined = HashMap::default();
+                    let mut procs = Procs { pending, inlined };
+                    order.iter().for_each(|label| {
+                        inline_proc(label, &mut procs, budget)
+                    });
+                    let tail = inline_tail(tail, &mut procs, budget);
+                    let lambdas = order
+                        .iter()
+                        .filter_map(|label| procs.inlined.remove(label))
+                        .collect();
+                    self::P::module { lambdas, tail }
+                },
+            }
+        }
+
+        fn inline_proc(label: &utils::Label, procs: &mut Procs, budget: usize) {
+            if let Some(lambda) = procs.pending.remove(label) {
+                let self::Lambda { label, args, tail } = lambda;
+                let tail = inline_tail(tail, procs, budget);
+                let lambda = self::Lambda {
+                    label: label.clone(),
+                    args,
+                    tail,
+                };
+                procs.inlined.insert(label, lambda);
+            }
+        }
+
+        fn inline_tail(
+            tail: self::Tail,
+            procs: &mut Procs,
+            budget: usize,
+        ) -> self::Tail {
+            match tail {
+                self::Tail::value(value) => {
+                    self::Tail::value(inline_value(value, procs, budget))
+                },
+                self::Tail::r#let { bindings, tail } => {
+                    let bindings = inline_bindings(bindings, procs, budget);
+                    let tail = inline_tail(*tail, procs, budget);
+                    let tail = Box::new(tail);
+                    self::Tail::r#let { bindings, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let pred = inline_pred(pred, procs, budget);
+                    let tail1 = inline_tail(*tail1, procs, budget);
+                    let tail2 = inline_tail(*tail2, procs, budget);
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    self::Tail::r#if { pred, tail1, tail2 }
+                },
+                self::Tail::call {
+                    triv: self::Triv::label(label),
+                    opands,
+                } => {
+                    inline_proc(&label, procs, budget);
+                    let callee = procs.inlined.get(&label).filter(|callee| {
+                        callee.args.len() == opands.len()
+                            && size_tail(&callee.tail) <= budget
+                            && !callee.tail.labels().contains(&label)
+                    });
+                    match callee {
+                        Some(callee) => inline_call(callee, opands),
+                        None => {
+                            let triv = self::Triv::label(label);
+                            self::Tail::call { triv, opands }
+                        },
+                    }
+                },
+                self::Tail::call { .. } => tail,
+            }
+        }
+
+        // Binds fresh copies of the arguments of the callee to the opands,
+        // around a fresh copy of its body.
+        fn inline_call(
+            callee: &self::Lambda,
+            opands: Vec<self::Opand>,
+        ) -> self::Tail {
+            let mut alocs = AlocMap::default();
+            let bindings = callee
+                .args
+                .iter()
+                .zip(opands)
+                .map(|(arg, opand)| {
+                    let aloc = rename_aloc(arg, &mut alocs);
+                    let value = self::Value::triv(self::Triv::opand(opand));
+                    (aloc, value)
+                })
+                .collect::<self::Bindings>();
+            let tail = rename_tail(&callee.tail, &mut alocs);
+            match bindings.is_empty() {
+                true => tail,
+                false => {
+                    let tail = Box::new(tail);
+                    self::Tail::r#let { bindings, tail }
+                },
+            }
+        }
+
+        fn inline_pred(
+            pred: self::Pred,
+            procs: &mut Procs,
+            budget: usize,
+        ) -> self::Pred {
+            match pred {
+                self::Pred::not(pred) => {
+                    let pred = inline_pred(*pred, procs, budget);
+                    self::Pred::not(Box::new(pred))
+                },
+                self::Pred::r#let { bindings, pred } => {
+                    let bindings = inline_bindings(bindings, procs, budget);
+                    let pred = inline_pred(*pred, procs, budget);
+                    let pred = Box::new(pred);
+                    self::Pred::r#let { bindings, pred }
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let pred1 = inline_pred(*pred1, procs, budget);
+                    let pred2 = inline_pred(*pred2, procs, budget);
+                    let pred3 = inline_pred(*pred3, procs, budget);
+                    let pred1 = Box::new(pred1);
+                    let pred2 = Box::new(pred2);
+                    let pred3 = Box::new(pred3);
+                    self::Pred::r#if {
+                        pred1,
+                        pred2,
+                        pred3,
+                    }
+                },
+                pred => pred,
+            }
+        }
+
+        fn inline_value(
+            value: self::Value,
+            procs: &mut Procs,
+            budget: usize,
+        ) -> self::Value {
+            match value {
+                self::Value::r#let { bindings, value } => {
+                    let bindings = inline_bindings(bindings, procs, budget);
+                    let value = inline_value(*value, procs, budget);
+                    let value = Box::new(value);
+                    self::Value::r#let { bindings, value }
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let pred = inline_pred(pred, procs, budget);
+                    let value1 = inline_value(*value1, procs, budget);
+                    let value2 = inline_value(*value2, procs, budget);
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    self::Value::r#if {
+                        pred,
+                        value1,
+                        value2,
+                    }
+                },
+                self::Value::lambda { args, tail } => {
+                    let tail = inline_tail(*tail, procs, budget);
+                    let tail = Box::new(tail);
+                    self::Value::lambda { args, tail }
+                },
+                self::Value::set { aloc, value } => {
+                    let value = inline_value(*value, procs, budget);
+                    let value = Box::new(value);
+                    self::Value::set { aloc, value }
+                },
+                self::Value::r#while { pred, value } => {
+                    let pred = inline_pred(pred, procs, budget);
+                    let value = inline_value(*value, procs, budget);
+                    let value = Box::new(value);
+                    self::Value::r#while { pred, value }
+                },
+                self::Value::switch {
+                    opand,
+                    table,
+                    values,
+                } => {
+                    let values = values
+                        .into_iter()
+                        .map(|value| inline_value(value, procs, budget))
+                        .collect();
+                    self::Value::switch {
+                        opand,
+                        table,
+                        values,
+                    }
+                },
+                value => value,
+            }
+        }
+
+        fn inline_bindings(
+            bindings: self::Bindings,
+            procs: &mut Procs,
+            budget: usize,
+        ) -> self::Bindings {
+            bindings
+                .into_iter()
+                .map(|(aloc, value)| (aloc, inline_value(value, procs, budget)))
+                .collect()
+        }
+
+        fn size_tail(tail: &self::Tail) -> usize {
+            match tail {
+                self::Tail::value(value) => 1 + size_value(value),
+                self::Tail::r#let { bindings, tail } => {
+                    1 + size_bindings(bindings) + size_tail(tail)
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    1 + size_pred(pred) + size_tail(tail1) + size_tail(tail2)
+                },
+                self::Tail::call { .. } => 1,
+            }
+        }
+
+        fn size_pred(pred: &self::Pred) -> usize {
+            match pred {
+                self::Pred::not(pred) => 1 + size_pred(pred),
+                self::Pred::r#let { bindings, pred } => {
+                    1 + size_bindings(bindings) + size_pred(pred)
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => 1 + size_pred(pred1) + size_pred(pred2) + size_pred(pred3),
+                _ => 1,
+            }
+        }
+
+        fn size_value(value: &self::Value) -> usize {
+            match value {
+                self::Value::r#let { bindings, value } => {
+                    1 + size_bindings(bindings) + size_value(value)
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    1 + size_pred(pred)
+                        + size_value(value1)
+                        + size_value(value2)
+                },
+                self::Value::lambda { tail, .. } => 1 + size_tail(tail),
+                self::Value::set { value, .. } => 1 + size_value(value),
+                self::Value::r#while { pred, value } => {
+                    1 + size_pred(pred) + size_value(value)
+                },
+                self::Value::switch { values, .. } => {
+                    1 + values.iter().map(size_value).sum::<usize>()
+                },
+                _ => 1,
+            }
+        }
+
+        fn size_bindings(bindings: &self::Bindings) -> usize {
+            bindings.values().map(size_value).sum()
+        }
+
+        fn rename_tail(tail: &self::Tail, alocs: &mut AlocMap) -> self::Tail {
+            match tail {
+                self::Tail::value(value) => {
+                    self::Tail::value(rename_value(value, alocs))
+                },
+                self::Tail::r#let { bindings, tail } => {
+                    let bindings = rename_bindings(bindings, alocs);
+                    let tail = Box::new(rename_tail(tail, alocs));
+                    self::Tail::r#let { bindings, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let pred = rename_pred(pred, alocs);
+                    let tail1 = Box::new(rename_tail(tail1, alocs));
+                    let tail2 = Box::new(rename_tail(tail2, alocs));
+                    self::Tail::r#if { pred, tail1, tail2 }
+                },
+                self::Tail::call { triv, opands } => {
+                    let triv = rename_triv(triv, alocs);
+                    let opands = rename_opands(opands, alocs);
+                    self::Tail::call { triv, opands }
+                },
+            }
+        }
+
+        fn rename_pred(pred: &self::Pred, alocs: &mut AlocMap) -> self::Pred {
+            match pred {
+                self::Pred::relop {
+                    relop,
+                    opand1,
+                    opand2,
+                } => self::Pred::relop {
+                    relop: *relop,
+                    opand1: rename_opand(opand1, alocs),
+                    opand2: rename_opand(opand2, alocs),
+                },
+                self::Pred::opand(opand) => {
+                    self::Pred::opand(rename_opand(opand, alocs))
+                },
+                self::Pred::r#true => self::Pred::r#true,
+                self::Pred::r#false => self::Pred::r#false,
+                self::Pred::not(pred) => {
+                    self::Pred::not(Box::new(rename_pred(pred, alocs)))
+                },
+                self::Pred::r#let { bindings, pred } => {
+                    let bindings = rename_bindings(bindings, alocs);
+                    let pred = Box::new(rename_pred(pred, alocs));
+                    self::Pred::r#let { bindings, pred }
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => self::Pred::r#if {
+                    pred1: Box::new(rename_pred(pred1, alocs)),
+                    pred2: Box::new(rename_pred(pred2, alocs)),
+                    pred3: Box::new(rename_pred(pred3, alocs)),
+                },
+            }
+        }
+
+        fn rename_value(
+            value: &self::Value,
+            alocs: &mut AlocMap,
+        ) -> self::Value {
+            match value {
+                self::Value::triv(triv) => {
+                    self::Value::triv(rename_triv(triv, alocs))
+                },
+                self::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                } => self::Value::binop {
+                    binop: *binop,
+                    opand1: rename_opand(opand1, alocs),
+                    opand2: rename_opand(opand2, alocs),
+                },
+                self::Value::primop { primop, opands } => self::Value::primop {
+                    primop: *primop,
+                    opands: rename_opands(opands, alocs),
+                },
+                self::Value::alloc { opand } => self::Value::alloc {
+                    opand: rename_opand(opand, alocs),
+                },
+                self::Value::mref { opand1, opand2 } => self::Value::mref {
+                    opand1: rename_opand(opand1, alocs),
+                    opand2: rename_opand(opand2, alocs),
+                },
+                self::Value::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => self::Value::mset {
+                    opand1: rename_opand(opand1, alocs),
+                    opand2: rename_opand(opand2, alocs),
+                    opand3: rename_opand(opand3, alocs),
+                },
+                self::Value::r#let { bindings, value } => {
+                    let bindings = rename_bindings(bindings, alocs);
+                    let value = Box::new(rename_value(value, alocs));
+                    self::Value::r#let { bindings, value }
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => self::Value::r#if {
+                    pred: rename_pred(pred, alocs),
+                    value1: Box::new(rename_value(value1, alocs)),
+                    value2: Box::new(rename_value(value2, alocs)),
+                },
+                self::Value::lambda { args, tail } => self::Value::lambda {
+                    args: args
+                        .iter()
+                        .map(|arg| rename_aloc(arg, alocs))
+                        .collect(),
+                    tail: Box::new(rename_tail(tail, alocs)),
+                },
+                self::Value::set { aloc, value } => self::Value::set {
+                    aloc: rename_aloc(aloc, alocs),
+                    value: Box::new(rename_value(value, alocs)),
+                },
+                self::Value::r#while { pred, value } => self::Value::r#while {
+                    pred: rename_pred(pred, alocs),
+                    value: Box::new(rename_value(value, alocs)),
+                },
+                self::Value::switch {
+                    opand,
+                    table,
+                    values,
+                } => self::Value::switch {
+                    opand: rename_opand(opand, alocs),
+                    table: table.clone(),
+                    values: values
+                        .iter()
+                        .map(|value| rename_value(value, alocs))
+                        .collect(),
+                },
+                self::Value::make_closure {
+                    label,
+                    arity,
+                    opands,
+                } => self::Value::make_closure {
+                    label: label.clone(),
+                    arity: *arity,
+                    opands: rename_opands(opands, alocs),
+                },
+                self::Value::closure_ref { opand, index } => {
+                    self::Value::closure_ref {
+                        opand: rename_opand(opand, alocs),
+                        index: *index,
+                    }
+                },
+            }
+        }
+
+        fn rename_bindings(
+            bindings: &self::Bindings,
+            alocs: &mut AlocMap,
+        ) -> self::Bindings {
+            bindings
+                .iter()
+                .map(|(aloc, value)| {
+                    let aloc = rename_aloc(aloc, alocs);
+                    (aloc, rename_value(value, alocs))
+                })
+                .collect()
+        }
+
+        fn rename_triv(triv: &self::Triv, alocs: &mut AlocMap) -> self::Triv {
+            match triv {
+                self::Triv::opand(opand) => {
+                    self::Triv::opand(rename_opand(opand, alocs))
+                },
+                self::Triv::label(label) => self::Triv::label(label.clone()),
+            }
+        }
+
+        fn rename_opands(
+            opands: &[self::Opand],
+            alocs: &mut AlocMap,
+        ) -> Vec<self::Opand> {
+            opands
+                .iter()
+                .map(|opand| rename_opand(opand, alocs))
+                .collect()
+        }
+
+        fn rename_opand(
+            opand: &self::Opand,
+            alocs: &mut AlocMap,
+        ) -> self::Opand {
+            match opand {
+                self::Opand::aloc(aloc) => {
+                    self::Opand::aloc(rename_aloc(aloc, alocs))
+                },
+                self::Opand::int64(int64) => self::Opand::int64(*int64),
+            }
+        }
+
+        // Every aloc of a procedure body is bound in the body, so each is
+        // renamed, wherever it is first seen.
+        fn rename_aloc(aloc: &utils::Aloc, alocs: &mut AlocMap) -> utils::Aloc {
+            alocs
+                .entry(aloc.clone())
+                .or_insert_with(|| utils::Aloc::fresh_with_name(&aloc.name))
+                .clone()
+        }
+
+        let Self(p) = self;
+        let p = inline_p(p, budget);
+        Self(p)
+    }
+
+    /// ConvertAssignments: ValuesUniqueLang -> ValuesUniqueLang
+    ///
+    /// ### Purpose:
+    /// Boxes every aloc that is both assigned and captured by a lambda, so
+    /// that closures share its value instead of copying it. Afterwards, no
+    /// assigned aloc is captured.
+    ///
+    /// ### Notes:
+    /// A box is an untagged 8-byte heap cell, which is never exposed as a
+    /// value. Each reference to a boxed aloc reads its cell, and each
+    /// assignment writes it. Alocs that are assigned but never captured are
+    /// left to be assigned directly.
+    pub fn convert_assignments(self) -> Self {
+        let Self(p) = self;
+
+        fn convert_p(p: self::P) -> self::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let boxed = boxed_alocs(&lambdas, &tail);
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|Lambda { label, args, tail }| {
+                            let tail = convert_tail(tail, &boxed);
+                            let (args, tail) = box_args(args, tail, &boxed);
+                            Lambda { label, args, tail }
+                        })
+                        .collect();
+                    let tail = convert_tail(tail, &boxed);
+                    self::P::module { lambdas, tail }
+                },
+            }
+        }
+
+        fn boxed_alocs(
+            lambdas: &[Lambda],
+            tail: &self::Tail,
+        ) -> utils::AlocSet {
+            let mut assigned = utils::AlocSet::default();
+            let mut captured = utils::AlocSet::default();
+            lambdas
+                .iter()
+                .map(|Lambda { tail, .. }| tail)
+                .chain([tail])
+                .for_each(|tail| scan_tail(tail, &mut assigned, &mut captured));
+            assigned.intersection(&captured).cloned().collect()
+        }
+
+        fn scan_tail(
+            tail: &self::Tail,
+            assigned: &mut utils::AlocSet,
+            captured: &mut utils::AlocSet,
+        ) {
+            match tail {
+                self::Tail::value(value) => {
+                    scan_value(value, assigned, captured)
+                },
+                self::Tail::r#let { bindings, tail } => {
+                    scan_bindings(bindings, assigned, captured);
+                    scan_tail(tail, assigned, captured)
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    scan_pred(pred, assigned, captured);
+                    scan_tail(tail1, assigned, captured);
+                    scan_tail(tail2, assigned, captured)
+                },
+                self::Tail::call { .. } => (),
+            }
+        }
+
+        fn scan_pred(
+            pred: &self::Pred,
+            assigned: &mut utils::AlocSet,
+            captured: &mut utils::AlocSet,
+        ) {
+            match pred {
+                self::Pred::not(pred) => scan_pred(pred, assigned, captured),
+                self::Pred::r#let { bindings, pred } => {
+                    scan_bindings(bindings, assigned, captured);
+                    scan_pred(pred, assigned, captured)
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    scan_pred(pred1, assigned, captured);
+                    scan_pred(pred2, assigned, captured);
+                    scan_pred(pred3, assigned, captured)
+                },
+                _ => (),
+            }
+        }
+
+        fn scan_value(
+            value: &self::Value,
+            assigned: &mut utils::AlocSet,
+            captured: &mut utils::AlocSet,
+        ) {
+            match value {
+                self::Value::set { aloc, value } => {
+                    assigned.insert(aloc.clone());
+                    scan_value(value, assigned, captured)
+                },
+                self::Value::lambda { args, tail } => {
+                    let free = tail.free_alocs();
+                    captured.extend(
+                        free.into_iter().filter(|aloc| !args.contains(aloc)),
+                    );
+                    scan_tail(tail, assigned, captured)
+                },
+                self::Value::r#let { bindings, value } => {
+                    scan_bindings(bindings, assigned, captured);
+                    scan_value(value, assigned, captured)
+                },
+                self::Value::r#while { pred, value } => {
+                    scan_pred(pred, assigned, captured);
+                    scan_value(value, assigned, captured)
+                },
+                self::Value::switch { values, .. } => values
+                    .iter()
+                    .for_each(|value| scan_value(value, assigned, captured)),
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    scan_pred(pred, assigned, captured);
+                    scan_value(value1, assigned, captured);
+                    scan_value(value2, assigned, captured)
+                },
+                _ => (),
+            }
+        }
+
+        fn scan_bindings(
+            bindings: &self::Bindings,
+            assigned: &mut utils::AlocSet,
+            captured: &mut utils::AlocSet,
+        ) {
+            bindings
+                .values()
+                .for_each(|value| scan_value(value, assigned, captured))
+        }
+
+        fn convert_tail(
+            tail: self::Tail,
+            boxed: &utils::AlocSet,
+        ) -> self::Tail {
+            match tail {
+                self::Tail::value(value) => {
+                    let value = convert_value(value, boxed);
+                    self::Tail::value(value)
+                },
+                self::Tail::r#let { bindings, tail } => {
+                    let bindings = convert_bindings(bindings, boxed);
+                    let tail = convert_tail(*tail, boxed);
+                    let tail = Box::new(tail);
+                    self::Tail::r#let { bindings, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let pred = convert_pred(pred, boxed);
+                    let tail1 = convert_tail(*tail1, boxed);
+                    let tail2 = convert_tail(*tail2, boxed);
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    self::Tail::r#if { pred, tail1, tail2 }
+                },
+                self::Tail::call { triv, opands } => {
+                    let mut bindings = self::Bindings::default();
+                    let triv = match triv {
+                        self::Triv::opand(opand) => {
+                            let opand = unbox(opand, boxed, &mut bindings);
+                            self::Triv::opand(opand)
+                        },
+                        triv => triv,
+                    };
+                    let opands = opands
+                        .into_iter()
+                        .map(|opand| unbox(opand, boxed, &mut bindings))
+                        .collect();
+                    let tail = self::Tail::call { triv, opands };
+                    match bindings.is_empty() {
+                        true => tail,
+                        false => {
+                            let tail = Box::new(tail);
+                            self::Tail::r#let { bindings, tail }
+                        },
+                    }
+                },
+            }
+        }
+
+        fn convert_pred(
+            pred: self::Pred,
+            boxed: &utils::AlocSet,
+        ) -> self::Pred {
+            let mut bindings = self::Bindings::default();
+            let pred = match pred {
+                self::Pred::relop {
+                    relop,
+                    opand1,
+                    opand2,
+                } => {
+                    let opand1 = unbox(opand1, boxed, &mut bindings);
+                    let opand2 = unbox(opand2, boxed, &mut bindings);
+                    self::Pred::relop {
+                        relop,
+                        opand1,
+                        opand2,
+                    }
+                },
+                self::Pred::opand(opand) => {
+                    self::Pred::opand(unbox(opand, boxed, &mut bindings))
+                },
+                self::Pred::not(pred) => {
+                    let pred = convert_pred(*pred, boxed);
+                    let pred = Box::new(pred);
+                    self::Pred::not(pred)
+                },
+                self::Pred::r#let { bindings, pred } => {
+                    let bindings = convert_bindings(bindings, boxed);
+                    let pred = convert_pred(*pred, boxed);
+                    let pred = Box::new(pred);
+                    self::Pred::r#let { bindings, pred }
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let pred1 = convert_pred(*pred1, boxed);
+                    let pred2 = convert_pred(*pred2, boxed);
+                    let pred3 = convert_pred(*pred3, boxed);
+                    let pred1 = Box::new(pred1);
+                    let pred2 = Box::new(pred2);
+                    let pred3 = Box::new(pred3);
+                    self::Pred::r#if {
+                        pred1,
+                        pred2,
+                        pred3,
+                    }
+                },
+                pred => pred,
+            };
+            match bindings.is_empty() {
+                true => pred,
+                false => {
+                    let pred = Box::new(pred);
+                    self::Pred::r#let { bindings, pred }
+                },
+            }
+        }
+
+        fn convert_value(
+            value: self::Value,
+            boxed: &utils::AlocSet,
+        ) -> self::Value {
+            let mut bindings = self::Bindings::default();
+            let value = match value {
+                self::Value::triv(self::Triv::opand(self::Opand::aloc(aloc)))
+                    if boxed.contains(&aloc) =>
+                {
+                    read_box(aloc)
+                },
+                self::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                } => {
+                    let opand1 = unbox(opand1, boxed, &mut bindings);
+                    let opand2 = unbox(opand2, boxed, &mut bindings);
+                    self::Value::binop {
+                        binop,
+                        opand1,
+                        opand2,
+                    }
+                },
+                self::Value::primop { primop, opands } => {
+                    let opands = opands
+                        .into_iter()
+                        .map(|opand| unbox(opand, boxed, &mut bindings))
+                        .collect();
+                    self::Value::primop { primop, opands }
+                },
+                self::Value::alloc { opand } => {
+                    let opand = unbox(opand, boxed, &mut bindings);
+                    self::Value::alloc { opand }
+                },
+                self::Value::mref { opand1, opand2 } => {
+                    let opand1 = unbox(opand1, boxed, &mut bindings);
+                    let opand2 = unbox(opand2, boxed, &mut bindings);
+                    self::Value::mref { opand1, opand2 }
+                },
+                self::Value::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => {
+                    let opand1 = unbox(opand1, boxed, &mut bindings);
+                    let opand2 = unbox(opand2, boxed, &mut bindings);
+                    let opand3 = unbox(opand3, boxed, &mut bindings);
+                    self::Value::mset {
+                        opand1,
+                        opand2,
+                        opand3,
+                    }
+                },
+                self::Value::r#let { bindings, value } => {
+                    let bindings = convert_bindings(bindings, boxed);
+                    let value = convert_value(*value, boxed);
+                    let value = Box::new(value);
+                    self::Value::r#let { bindings, value }
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let pred = convert_pred(pred, boxed);
+                    let value1 = convert_value(*value1, boxed);
+                    let value2 = convert_value(*value2, boxed);
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    self::Value::r#if {
+                        pred,
+                        value1,
+                        value2,
+                    }
+                },
+                self::Value::r#while { pred, value } => {
+                    let pred = convert_pred(pred, boxed);
+                    let value = convert_value(*value, boxed);
+                    let value = Box::new(value);
+                    self::Value::r#while { pred, value }
+                },
+                self::Value::switch {
+                    opand,
+                    table,
+                    values,
+                } => {
+                    let opand = unbox(opand, boxed, &mut bindings);
+                    let values = values
+                        .into_iter()
+                        .map(|value| convert_value(value, boxed))
+                        .collect();
+                    self::Value::switch {
+                        opand,
+                        table,
+                        values,
+                    }
+                },
+                self::Value::lambda { args, tail } => {
+                    let tail = convert_tail(*tail, boxed);
+                    let (args, tail) = box_args(args, tail, boxed);
+                    let tail = Box::new(tail);
+                    self::Value::lambda { args, tail }
+                },
+                self::Value::set { aloc, value } => {
+                    let value = convert_value(*value, boxed);
+                    match boxed.contains(&aloc) {
+                        true => bind(value, |opand| self::Value::mset {
+                            opand1: self::Opand::aloc(aloc),
+                            opand2: self::Opand::int64(0),
+                            opand3: opand,
+                        }),
+                        false => {
+                            let value = Box::new(value);
+                            self::Value::set { aloc, value }
+                        },
+                    }
+                },
+                value => value,
+            };
+            match bindings.is_empty() {
+                true => value,
+                false => {
+                    let value = Box::new(value);
+                    self::Value::r#let { bindings, value }
+                },
+            }
+        }
+
+        fn convert_bindings(
+            bindings: self::Bindings,
+            boxed: &utils::AlocSet,
+        ) -> self::Bindings {
+            bindings
+                .into_iter()
+                .map(|(aloc, value)| {
+                    let value = convert_value(value, boxed);
+                    match boxed.contains(&aloc) {
+                        true => (aloc, make_box(value)),
+                        false => (aloc, value),
+                    }
+                })
+                .collect()
+        }
+
+        // A boxed argument is passed as its value, and boxed on entry.
+        fn box_args(
+            args: Vec<utils::Aloc>,
+            tail: self::Tail,
+            boxed: &utils::AlocSet,
+        ) -> (Vec<utils::Aloc>, self::Tail) {
+            let (args, bindings) = args.into_iter().fold(
+                (vec![], self::Bindings::default()),
+                |(mut args, mut bindings), aloc| {
+                    match boxed.contains(&aloc) {
+                        true => {
+                            let arg = utils::Aloc::fresh();
+                            let value = self::Value::triv(self::Triv::opand(
+                                self::Opand::aloc(arg.clone()),
+                            ));
+                            bindings.insert(aloc, make_box(value));
+                            args.push(arg);
+                        },
+                        false => args.push(aloc),
+                    };
+                    (args, bindings)
+                },
+            );
+            let tail = match bindings.is_empty() {
+                true => tail,
+                false => {
+                    let tail = Box::new(tail);
+                    self::Tail::r#let { bindings, tail }
+                },
+            };
+            (args, tail)
+        }
+
+        // Reads a boxed aloc into a fresh aloc, which is bound in the
+        // bindings.
+        fn unbox(
+            opand: self::Opand,
+            boxed: &utils::AlocSet,
+            bindings: &mut self::Bindings,
+        ) -> self::Opand {
+            match opand {
+                self::Opand::aloc(aloc) if boxed.contains(&aloc) => {
+                    let value = utils::Aloc::fresh();
+                    bindings.insert(value.clone(), read_box(aloc));
+                    self::Opand::aloc(value)
+                },
+                opand => opand,
+            }
+        }
+
+        // let value = value
+        //     box = alloc(8)
+        // in mset(box, 0, value); box
+        fn make_box(value: self::Value) -> self::Value {
+            bind(value, |value| {
+                let size = self::Opand::int64(8);
+                bind(self::Value::alloc { opand: size }, |cell| {
+                    let set = self::Value::mset {
+                        opand1: cell.clone(),
+                        opand2: self::Opand::int64(0),
+                        opand3: value,
+                    };
+                    let cell = self::Value::triv(self::Triv::opand(cell));
+                    bind(set, |_| cell)
+                })
+            })
+        }
+
+        fn read_box(aloc: utils::Aloc) -> self::Value {
+            self::Value::mref {
+                opand1: self::Opand::aloc(aloc),
+                opand2: self::Opand::int64(0),
+            }
+        }
+
+        // Binds the value to a fresh aloc for the body, unless it is an opand.
+        fn bind(
+            value: self::Value,
+            body: impl FnOnce(self::Opand) -> self::Value,
+        ) -> self::Value {
+            match value {
+                self::Value::triv(self::Triv::opand(opand)) => body(opand),
+                value => {
+                    let aloc = utils::Aloc::fresh();
+                    let body = body(self::Opand::aloc(aloc.clone()));
+                    let bindings = [(aloc, value)].into_iter().collect();
+                    let value = Box::new(body);
+                    self::Value::r#let { bindings, value }
+                },
+            }
+        }
+
+        let p = convert_p(p);
+        Self(p)
+    }
+
+    /// ConvertClosures: ValuesUniqueLang -> ValuesUniqueLang
+    ///
+    /// ### Purpose:
+    /// Converts every lambda into a closure, lifting its body to a top-level
+    /// procedure. Afterwards, no lambda remains and labels are only used as
+    /// the targets of direct calls.
+    ///
+    /// ### Notes:
+    /// Every procedure takes its closure as an extra first argument, and a
+    /// lifted procedure reads the free variables of its body out of it on
+    /// entry, binding them to the same alocs. A label used as a value becomes
+    /// a closure that captures nothing. Direct calls pass void as the closure,
+    /// since a top-level procedure has no free variables.
+    pub fn convert_closures(self) -> Self {
+        type ArityEnv = HashMap<utils::Label, usize>;
+        let Self(p) = self;
+
+        fn convert_p(p: self::P) -> self::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let arities = lambdas
+                        .iter()
+                        .map(|Lambda { label, args, .. }| {
+                            (label.clone(), args.len())
+                        })
+                        .collect::<ArityEnv>();
+                    let mut lifted = vec![];
+                    let mut lambdas = lambdas
+                        .into_iter()
+                        .map(|Lambda { label, args, tail }| {
+                            let tail =
+                                convert_tail(tail, &arities, &mut lifted);
+                            let args = with_closure(args);
+                            Lambda { label, args, tail }
+                        })
+                        .collect::<Vec<_>>();
+                    let tail = convert_tail(tail, &arities, &mut lifted);
+                    lambdas.extend(lifted);
+                    self::P::module { lambdas, tail }
+                },
+            }
+        }
+
+        fn convert_tail(
+            tail: self::Tail,
+            arities: &ArityEnv,
+            lifted: &mut Vec<Lambda>,
+        ) -> self::Tail {
+            match tail {
+                self::Tail::value(value) => {
+                    let value = convert_value(value, arities, lifted);
+                    self::Tail::value(value)
+                },
+                self::Tail::r#let { bindings, tail } => {
+                    let bindings = convert_bindings(bindings, arities, lifted);
+                    let tail = convert_tail(*tail, arities, lifted);
+                    let tail = Box::new(tail);
+                    self::Tail::r#let { bindings, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let pred = convert_pred(pred, arities, lifted);
+                    let tail1 = convert_tail(*tail1, arities, lifted);
+                    let tail2 = convert_tail(*tail2, arities, lifted);
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    self::Tail::r#if { pred, tail1, tail2 }
+                },
+                self::Tail::call { triv, opands } => {
+                    let closure = match &triv {
+                        self::Triv::label(..) => {
+                            self::Opand::int64(utils::VOID)
+                        },
+                        self::Triv::opand(opand) => opand.clone(),
+                    };
+                    let opands =
+                        std::iter::once(closure).chain(opands).collect();
+                    self::Tail::call { triv, opands }
+                },
+            }
+        }
+
+        fn convert_pred(
+            pred: self::Pred,
+            arities: &ArityEnv,
+            lifted: &mut Vec<Lambda>,
+        ) -> self::Pred {
+            match pred {
+                self::Pred::not(pred) => {
+                    let pred = convert_pred(*pred, arities, lifted);
+                    let pred = Box::new(pred);
+                    self::Pred::not(pred)
+                },
+                self::Pred::r#let { bindings, pred } => {
+                    let bindings = convert_bindings(bindings, arities, lifted);
+                    let pred = convert_pred(*pred, arities, lifted);
+                    let pred = Box::new(pred);
+                    self::Pred::r#let { bindings, pred }
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let pred1 = convert_pred(*pred1, arities, lifted);
+                    let pred2 = convert_pred(*pred2, arities, lifted);
+                    let pred3 = convert_pred(*pred3, arities, lifted);
+                    let pred1 = Box::new(pred1);
+                    let pred2 = Box::new(pred2);
+                    let pred3 = Box::new(pred3);
+                    self::Pred::r#if {
+                        pred1,
+                        pred2,
+                        pred3,
+                    }
+                },
+                pred => pred,
+            }
+        }
+
+        fn convert_value(
+            value: self::Value,
+            arities: &ArityEnv,
+            lifted: &mut Vec<Lambda>,
+        ) -> self::Value {
+            match value {
+                self::Value::triv(self::Triv::label(label)) => {
+                    let arity = arities.get(&label).copied().unwrap();
+                    let opands = vec![];
+                    self::Value::make_closure {
+                        label,
+                        arity,
+                        opands,
+                    }
+                },
+                self::Value::r#let { bindings, value } => {
+                    let bindings = convert_bindings(bindings, arities, lifted);
+                    let value = convert_value(*value, arities, lifted);
+                    let value = Box::new(value);
+                    self::Value::r#let { bindings, value }
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let pred = convert_pred(pred, arities, lifted);
+                    let value1 = convert_value(*value1, arities, lifted);
+                    let value2 = convert_value(*value2, arities, lifted);
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    self::Value::r#if {
+                        pred,
+                        value1,
+                        value2,
+                    }
+                },
+                self::Value::lambda { args, tail } => {
+                    let mut free = tail
+                        .free_alocs()
+                        .into_iter()
+                        .filter(|aloc| !args.contains(aloc))
+                        .collect::<Vec<_>>();
+                    free.sort();
+                    let arity = args.len();
+                    let label = utils::Label::new_with_name("lambda");
+                    let args = with_closure(args);
+                    let closure = self::Opand::aloc(args[0].clone());
+                    let bindings = free
+                        .iter()
+                        .cloned()
+                        .enumerate()
+                        .map(|(index, aloc)| {
+                            let opand = closure.clone();
+                            (aloc, self::Value::closure_ref { opand, index })
+                        })
+                        .collect::<self::Bindings>();
+                    let tail = convert_tail(*tail, arities, lifted);
+                    let tail = match bindings.is_empty() {
+                        true => tail,
+                        false => {
+                            let tail = Box::new(tail);
+                            self::Tail::r#let { bindings, tail }
+                        },
+                    };
+                    lifted.push(Lambda {
+                        label: label.clone(),
+                        args,
+                        tail,
+                    });
+                    let opands =
+                        free.into_iter().map(self::Opand::aloc).collect();
+                    self::Value::make_closure {
+                        label,
+                        arity,
+                        opands,
+                    }
+                },
+                self::Value::set { aloc, value } => {
+                    let value = convert_value(*value, arities, lifted);
+                    let value = Box::new(value);
+                    self::Value::set { aloc, value }
+                },
+                self::Value::r#while { pred, value } => {
+                    let pred = convert_pred(pred, arities, lifted);
+                    let value = convert_value(*value, arities, lifted);
+                    let value = Box::new(value);
+                    self::Value::r#while { pred, value }
+                },
+                self::Value::switch {
+                    opand,
+                    table,
+                    values,
+                } => {
+                    let values = values
+                        .into_iter()
+                        .map(|value| convert_value(value, arities, lifted))
+                        .collect();
+                    self::Value::switch {
+                        opand,
+                        table,
+                        values,
+                    }
+                },
+                value => value,
+            }
+        }
+
+        fn convert_bindings(
+            bindings: self::Bindings,
+            arities: &ArityEnv,
+            lifted: &mut Vec<Lambda>,
+        ) -> self::Bindings {
+            bindings
+                .into_iter()
+                .map(|(aloc, value)| {
+                    (aloc, convert_value(value, arities, lifted))
+                })
+                .collect()
+        }
+
+        fn with_closure(args: Vec<utils::Aloc>) -> Vec<utils::Aloc> {
+            let closure = utils::Aloc::fresh_with_name("closure");
+            std::iter::once(closure).chain(args).collect()
+        }
+
+        let p = convert_p(p);
+        Self(p)
+    }
+
+    /// SpecifyRepresentation: ValuesUniqueLang -> ValuesUniqueLang
+    ///
+    /// ### Purpose:
+    /// Specifies the representation of data, lowering fixnum arithmetic and
+    /// the primops to operations on 64-bit words. Afterwards, every binop
+    /// operates on words and no primop remains.
+    ///
+    /// ### Notes:
+    /// Each opand of a binop is checked to be a fixnum, and the binop produces
+    /// the type-error value otherwise. Literal opands are checked, and binops
+    /// on two literals are folded unless they overflow, at compile time.
+    /// Relops compare the representations, which orders fixnums correctly.
+    /// The fixnum index of a switch is untagged to a word. Pairs and vectors
+    /// are allocated on the heap and tagged; accessing a vector outside its
+    /// length produces the out-of-bounds value.
+    ///
+    /// Closures are allocated on the heap and tagged as procedures. They hold
+    /// the label and arity of their procedure, followed by the captured
+    /// values. A call through a closure checks its tag and arity, and then
+    /// jumps to its label.
+    pub fn specify_representation(self) -> Self {
+        let Self(p) = self;
+
+        fn specify_p(p: self::P) -> self::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let lambdas =
+                        lambdas.into_iter().map(specify_lambda).collect();
+                    let tail = specify_tail(tail);
+                    self::P::module { lambdas, tail }
+                },
+            }
+        }
+
+        fn specify_lambda(Lambda { label, args, tail }: Lambda) -> Lambda {
+            let tail = specify_tail(tail);
+            Lambda { label, args, tail }
+        }
+
+        fn specify_tail(tail: self::Tail) -> self::Tail {
+            match tail {
+                self::Tail::value(value) => {
+                    let value = specify_value(value);
+                    self::Tail::value(value)
+                },
+                self::Tail::r#let { bindings, tail } => {
+                    let bindings = specify_bindings(bindings);
+                    let tail = specify_tail(*tail);
+                    let tail = Box::new(tail);
+                    self::Tail::r#let { bindings, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let pred = specify_pred(pred);
+                    let tail1 = specify_tail(*tail1);
+                    let tail2 = specify_tail(*tail2);
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    self::Tail::r#if { pred, tail1, tail2 }
+                },
+                self::Tail::call {
+                    triv: self::Triv::opand(opand),
+                    opands,
+                } => specify_call(opand, opands),
+                tail @ self::Tail::call { .. } => tail,
+            }
+        }
+
+        fn specify_pred(pred: self::Pred) -> self::Pred {
+            match pred {
+                self::Pred::not(pred) => {
+                    let pred = specify_pred(*pred);
+                    let pred = Box::new(pred);
+                    self::Pred::not(pred)
+                },
+                self::Pred::r#let { bindings, pred } => {
+                    let bindings = specify_bindings(bindings);
+                    let pred = specify_pred(*pred);
+                    let pred = Box::new(pred);
+                    self::Pred::r#let { bindings, pred }
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let pred1 = specify_pred(*pred1);
+                    let pred2 = specify_pred(*pred2);
+                    let pred3 = specify_pred(*pred3);
+                    let pred1 = Box::new(pred1);
+                    let pred2 = Box::new(pred2);
+                    let pred3 = Box::new(pred3);
+                    self::Pred::r#if {
+                        pred1,
+                        pred2,
+                        pred3,
+                    }
+                },
+                pred => pred,
+            }
+        }
+
+        fn specify_value(value: self::Value) -> self::Value {
+            match value {
+                self::Value::triv(triv) => self::Value::triv(triv),
+                self::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                } => {
+                    let value =
+                        specify_binop(binop, opand1.clone(), opand2.clone());
+                    let value = check_fixnum(opand2, value);
+                    check_fixnum(opand1, value)
+                },
+                self::Value::primop { primop, opands } => {
+                    specify_primop(primop, opands)
+                },
+                value @ (self::Value::alloc { .. }
+                | self::Value::mref { .. }
+                | self::Value::mset { .. }) => value,
+                self::Value::make_closure {
+                    label,
+                    arity,
+                    opands,
+                } => specify_make_closure(label, arity, opands),
+                self::Value::closure_ref { opand, index } => {
+                    let offset = utils::PROCEDURE_ENVIRONMENT_DISPLACEMENT
+                        - utils::PROCEDURE_TAG
+                        + 8 * index as i64;
+                    mref(opand, self::Opand::int64(offset))
+                },
+                self::Value::lambda { .. } => {
+                    unreachable!("Lambdas are converted by convert-closures.")
+                },
+                self::Value::set { aloc, value } => {
+                    let value = specify_value(*value);
+                    let value = Box::new(value);
+                    self::Value::set { aloc, value }
+                },
+                self::Value::r#while { pred, value } => {
+                    let pred = specify_pred(pred);
+                    let value = specify_value(*value);
+                    let value = Box::new(value);
+                    self::Value::r#while { pred, value }
+                },
+                // The fixnum index is shifted to a word index.
+                self::Value::switch {
+                    opand,
+                    table,
+                    values,
+                } => {
+                    let values =
+                        values.into_iter().map(specify_value).collect();
+                    let shift = self::Opand::int64(utils::FIXNUM_SHIFT);
+                    let binop = utils::Binop::arithmetic_shift_right;
+                    bind(fold(binop, opand, shift), |opand| {
+                        self::Value::switch {
+                            opand,
+                            table,
+                            values,
+                        }
+                    })
+                },
+                self::Value::r#let { bindings, value } => {
+                    let bindings = specify_bindings(bindings);
+                    let value = specify_value(*value);
+                    let value = Box::new(value);
+                    self::Value::r#let { bindings, value }
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let pred = specify_pred(pred);
+                    let value1 = specify_value(*value1);
+                    let value2 = specify_value(*value2);
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    self::Value::r#if {
+                        pred,
+                        value1,
+                        value2,
+                    }
+                },
+            }
+        }
+
+        fn specify_bindings(bindings: self::Bindings) -> self::Bindings {
+            bindings
+                .into_iter()
+                .map(|(aloc, value)| (aloc, specify_value(value)))
+                .collect()
+        }
+
+        // Both opands are fixnums, 8x and 8y, and the result must be the
+        // fixnum of x binop y.
+        fn specify_binop(
+            binop: utils::Binop,
+            opand1: self::Opand,
+            opand2: self::Opand,
+        ) -> self::Value {
+            let shift = self::Opand::int64(utils::FIXNUM_SHIFT);
+            match binop {
+                // (8x >> 3) * 8y
+                utils::Binop::multiply => bind(
+                    fold(utils::Binop::arithmetic_shift_right, opand1, shift),
+                    |x| fold(binop, x, opand2),
+                ),
+                // (8x / 8y) << 3
+                utils::Binop::quotient => {
+                    bind(fold(binop, opand1, opand2), |quotient| {
+                        fold(utils::Binop::shift_left, quotient, shift)
+                    })
+                },
+                // (8x >> (8y >> 3)) & ~7
+                utils::Binop::arithmetic_shift_right => {
+                    bind(fold(binop, opand2, shift), |y| {
+                        bind(fold(binop, opand1, y), |shifted| {
+                            let mask = self::Opand::int64(!utils::FIXNUM_MASK);
+                            fold(utils::Binop::bitwise_and, shifted, mask)
+                        })
+                    })
+                },
+                // 8x << (8y >> 3)
+                utils::Binop::shift_left => bind(
+                    fold(utils::Binop::arithmetic_shift_right, opand2, shift),
+                    |y| fold(binop, opand1, y),
+                ),
+                // The tags are zero, so these are unaffected by them.
+                utils::Binop::plus
+                | utils::Binop::minus
+                | utils::Binop::remainder
+                | utils::Binop::bitwise_and
+                | utils::Binop::bitwise_ior
+                | utils::Binop::bitwise_xor => fold(binop, opand1, opand2),
+            }
+        }
+
+        fn specify_primop(
+            primop: utils::Primop,
+            opands: Vec<self::Opand>,
+        ) -> self::Value {
+            assert_eq!(
+                opands.len(),
+                primop.arity(),
+                "Wrong number of opands for {primop:?}."
+            );
+            let mut opands = opands.into_iter();
+            let mut opand = || opands.next().unwrap();
+            let r#true = datum(utils::Datum::boolean(true));
+            let r#false = datum(utils::Datum::boolean(false));
+            match primop {
+                utils::Primop::fixnum_p => check_tag(
+                    opand(),
+                    utils::FIXNUM_MASK,
+                    utils::FIXNUM_TAG,
+                    r#true,
+                    r#false,
+                ),
+                utils::Primop::boolean_p => check_tag(
+                    opand(),
+                    utils::BOOLEAN_MASK,
+                    utils::BOOLEAN_TAG,
+                    r#true,
+                    r#false,
+                ),
+                utils::Primop::ascii_char_p => check_tag(
+                    opand(),
+                    utils::ASCII_CHAR_MASK,
+                    utils::ASCII_CHAR_TAG,
+                    r#true,
+                    r#false,
+                ),
+                utils::Primop::error_p => check_tag(
+                    opand(),
+                    utils::ERROR_MASK,
+                    utils::ERROR_TAG,
+                    r#true,
+                    r#false,
+                ),
+                utils::Primop::empty_p => {
+                    check_eq(opand(), utils::EMPTY, r#true, r#false)
+                },
+                utils::Primop::void_p => {
+                    check_eq(opand(), utils::VOID, r#true, r#false)
+                },
+                utils::Primop::not => {
+                    check_eq(opand(), utils::FALSE, r#true, r#false)
+                },
+                utils::Primop::pair_p => check_tag(
+                    opand(),
+                    utils::PAIR_MASK,
+                    utils::PAIR_TAG,
+                    r#true,
+                    r#false,
+                ),
+                utils::Primop::vector_p => check_tag(
+                    opand(),
+                    utils::VECTOR_MASK,
+                    utils::VECTOR_TAG,
+                    r#true,
+                    r#false,
+                ),
+                utils::Primop::procedure_p => check_tag(
+                    opand(),
+                    utils::PROCEDURE_MASK,
+                    utils::PROCEDURE_TAG,
+                    r#true,
+                    r#false,
+                ),
+                utils::Primop::cons => specify_cons(opand(), opand()),
+                utils::Primop::car => {
+                    let pair = opand();
+                    let car = utils::CAR_DISPLACEMENT - utils::PAIR_TAG;
+                    let value = mref(pair.clone(), self::Opand::int64(car));
+                    check_pair(pair, value)
+                },
+                utils::Primop::cdr => {
+                    let pair = opand();
+                    let cdr = utils::CDR_DISPLACEMENT - utils::PAIR_TAG;
+                    let value = mref(pair.clone(), self::Opand::int64(cdr));
+                    check_pair(pair, value)
+                },
+                utils::Primop::make_vector => specify_make_vector(opand()),
+                utils::Primop::vector_length => {
+                    let vector = opand();
+                    let length = utils::VECTOR_LENGTH_DISPLACEMENT
+                        - utils::VECTOR_TAG;
+                    let value =
+                        mref(vector.clone(), self::Opand::int64(length));
+                    check_vector(vector, value)
+                },
+                utils::Primop::vector_ref => {
+                    let vector = opand();
+                    let index = opand();
+                    let body = |offset| mref(vector.clone(), offset);
+                    let value = index_vector(vector.clone(), index, body);
+                    check_vector(vector, value)
+                },
+                utils::Primop::vector_set => {
+                    let vector = opand();
+                    let index = opand();
+                    let element = opand();
+                    let body = |offset| self::Value::mset {
+                        opand1: vector.clone(),
+                        opand2: offset,
+                        opand3: element,
+                    };
+                    let value = index_vector(vector.clone(), index, body);
+                    check_vector(vector, value)
+                },
+            }
+        }
+
+        // let ptr = alloc(16)
+        //     pair = ptr + 1
+        // in mset(pair, -1, car); mset(pair, 7, cdr); pair
+        fn specify_cons(car: self::Opand, cdr: self::Opand) -> self::Value {
+            let size = self::Opand::int64(utils::PAIR_SIZE);
+            bind(self::Value::alloc { opand: size }, |ptr| {
+                let tag = self::Opand::int64(utils::PAIR_TAG);
+                bind(fold(utils::Binop::plus, ptr, tag), |pair| {
+                    let car_offset = utils::CAR_DISPLACEMENT - utils::PAIR_TAG;
+                    let cdr_offset = utils::CDR_DISPLACEMENT - utils::PAIR_TAG;
+                    let set_car = self::Value::mset {
+                        opand1: pair.clone(),
+                        opand2: self::Opand::int64(car_offset),
+                        opand3: car,
+                    };
+                    let set_cdr = self::Value::mset {
+                        opand1: pair.clone(),
+                        opand2: self::Opand::int64(cdr_offset),
+                        opand3: cdr,
+                    };
+                    let pair = self::Value::triv(self::Triv::opand(pair));
+                    then(set_car, then(set_cdr, pair))
+                })
+            })
+        }
+
+        // The length is the fixnum 8n, which is also the size in bytes of n
+        // elements.
+        //
+        // let ptr = alloc(8n + 8)
+        //     vector = ptr + 3
+        // in mset(vector, -3, 8n); vector
+        fn specify_make_vector(length: self::Opand) -> self::Value {
+            let header = self::Opand::int64(utils::VECTOR_BASE_DISPLACEMENT);
+            let size = fold(utils::Binop::plus, length.clone(), header);
+            let value = bind(size, |size| {
+                bind(self::Value::alloc { opand: size }, |ptr| {
+                    let tag = self::Opand::int64(utils::VECTOR_TAG);
+                    bind(fold(utils::Binop::plus, ptr, tag), |vector| {
+                        let offset = utils::VECTOR_LENGTH_DISPLACEMENT
+                            - utils::VECTOR_TAG;
+                        let set_length = self::Value::mset {
+                            opand1: vector.clone(),
+                            opand2: self::Opand::int64(offset),
+                            opand3: length.clone(),
+                        };
+                        let vector =
+                            self::Value::triv(self::Triv::opand(vector));
+                        then(set_length, vector)
+                    })
+                })
+            });
+            let value = check_relop(
+                utils::Relop::lt,
+                length.clone(),
+                self::Opand::int64(0),
+                error(utils::RuntimeError::out_of_bounds),
+                value,
+            );
+            check_fixnum(length, value)
+        }
+
+        // The index is the fixnum 8i, which is also the offset in bytes of
+        // the element from the base of the vector.
+        //
+        // if 0 <= 8i < length(vector)
+        // then body(8i + 5)
+        // else out-of-bounds
+        fn index_vector(
+            vector: self::Opand,
+            index: self::Opand,
+            body: impl FnOnce(self::Opand) -> self::Value,
+        ) -> self::Value {
+            let offset = utils::VECTOR_LENGTH_DISPLACEMENT - utils::VECTOR_TAG;
+            let value = bind(mref(vector, self::Opand::int64(offset)), |length| {
+                let offset = utils::VECTOR_BASE_DISPLACEMENT - utils::VECTOR_TAG;
+                let offset = self::Opand::int64(offset);
+                let value = bind(
+                    fold(utils::Binop::plus, index.clone(), offset),
+                    body,
+                );
+                let value = check_relop(
+                    utils::Relop::lt,
+                    index.clone(),
+                    length,
+                    value,
+                    error(utils::RuntimeError::out_of_bounds),
+                );
+                check_relop(
+                    utils::Relop::lt,
+                    index.clone(),
+                    self::Opand::int64(0),
+                    error(utils::RuntimeError::out_of_bounds),
+                    value,
+                )
+            });
+            check_fixnum(index, value)
+        }
+
+        // let ptr = alloc(16 + 8n)
+        //     closure = ptr + 2
+        //     label = label
+        // in mset(closure, -2, label); mset(closure, 6, arity);
+        //    mset(closure, 14 + 8i, opand_i) ...; closure
+        fn specify_make_closure(
+            label: utils::Label,
+            arity: usize,
+            opands: Vec<self::Opand>,
+        ) -> self::Value {
+            let size = utils::PROCEDURE_ENVIRONMENT_DISPLACEMENT
+                + 8 * opands.len() as i64;
+            let size = self::Opand::int64(size);
+            bind(self::Value::alloc { opand: size }, |ptr| {
+                let tag = self::Opand::int64(utils::PROCEDURE_TAG);
+                bind(fold(utils::Binop::plus, ptr, tag), |closure| {
+                    let label = self::Value::triv(self::Triv::label(label));
+                    bind(label, |label| {
+                        let offset = |displacement| {
+                            self::Opand::int64(
+                                displacement - utils::PROCEDURE_TAG,
+                            )
+                        };
+                        let set_label = mset(
+                            closure.clone(),
+                            offset(utils::PROCEDURE_LABEL_DISPLACEMENT),
+                            label,
+                        );
+                        let set_arity = mset(
+                            closure.clone(),
+                            offset(utils::PROCEDURE_ARITY_DISPLACEMENT),
+                            self::Opand::int64(arity as i64),
+                        );
+                        let set_opands = opands.into_iter().enumerate().map(
+                            |(index, opand)| {
+                                let displacement =
+                                    utils::PROCEDURE_ENVIRONMENT_DISPLACEMENT
+                                        + 8 * index as i64;
+                                let offset = offset(displacement);
+                                mset(closure.clone(), offset, opand)
+                            },
+                        );
+                        let sets = [set_label, set_arity]
+                            .into_iter()
+                            .chain(set_opands)
+                            .collect::<Vec<_>>();
+                        let closure =
+                            self::Value::triv(self::Triv::opand(closure));
+                        sets.into_iter()
+                            .rev()
+                            .fold(closure, |body, set| then(set, body))
+                    })
+                })
+            })
+        }
+
+        // The closure is the first opand, and is not counted by the arity.
+        //
+        // if (opand & 7) == 2
+        // then let arity = mref(opand, 6)
+        //      in if arity == n
+        //         then let label = mref(opand, -2) in call label opands
+        //         else arity-mismatch
+        // else type-error
+        fn specify_call(
+            opand: self::Opand,
+            opands: Vec<self::Opand>,
+        ) -> self::Tail {
+            let type_error = error(utils::RuntimeError::type_error);
+            let type_error = self::Tail::value(type_error);
+            // No literal is a procedure.
+            if let self::Opand::int64(..) = opand {
+                return type_error;
+            }
+            let arity = opands.len() as i64 - 1;
+            let offset = |displacement| {
+                self::Opand::int64(displacement - utils::PROCEDURE_TAG)
+            };
+            let label = mref(
+                opand.clone(),
+                offset(utils::PROCEDURE_LABEL_DISPLACEMENT),
+            );
+            let tail = bind_tail(label, |label| {
+                let triv = self::Triv::opand(label);
+                self::Tail::call { triv, opands }
+            });
+            let arity_mismatch = error(utils::RuntimeError::arity_mismatch);
+            let arity_mismatch = self::Tail::value(arity_mismatch);
+            let actual = mref(
+                opand.clone(),
+                offset(utils::PROCEDURE_ARITY_DISPLACEMENT),
+            );
+            let tail = bind_tail(actual, |actual| {
+                let pred = self::Pred::relop {
+                    relop: utils::Relop::eq,
+                    opand1: actual,
+                    opand2: self::Opand::int64(arity),
+                };
+                let tail1 = Box::new(tail);
+                let tail2 = Box::new(arity_mismatch);
+                self::Tail::r#if { pred, tail1, tail2 }
+            });
+            let masked = utils::Aloc::fresh();
+            let mask = self::Value::binop {
+                binop: utils::Binop::bitwise_and,
+                opand1: opand,
+                opand2: self::Opand::int64(utils::PROCEDURE_MASK),
+            };
+            let bindings = [(masked.clone(), mask)].into_iter().collect();
+            let pred = self::Pred::relop {
+                relop: utils::Relop::eq,
+                opand1: self::Opand::aloc(masked),
+                opand2: self::Opand::int64(utils::PROCEDURE_TAG),
+            };
+            let pred = Box::new(pred);
+            let pred = self::Pred::r#let { bindings, pred };
+            let tail1 = Box::new(tail);
+            let tail2 = Box::new(type_error);
+            self::Tail::r#if { pred, tail1, tail2 }
+        }
+
+        fn check_pair(opand: self::Opand, value: self::Value) -> self::Value {
+            let type_error = error(utils::RuntimeError::type_error);
+            check_tag(
+                opand,
+                utils::PAIR_MASK,
+                utils::PAIR_TAG,
+                value,
+                type_error,
+            )
+        }
+
+        fn check_vector(opand: self::Opand, value: self::Value) -> self::Value {
+            let type_error = error(utils::RuntimeError::type_error);
+            check_tag(
+                opand,
+                utils::VECTOR_MASK,
+                utils::VECTOR_TAG,
+                value,
+                type_error,
+            )
+        }
+
+        fn check_fixnum(opand: self::Opand, value: self::Value) -> self::Value {
+            let type_error = error(utils::RuntimeError::type_error);
+            check_tag(
+                opand,
+                utils::FIXNUM_MASK,
+                utils::FIXNUM_TAG,
+                value,
+                type_error,
+            )
+        }
+
+        // if (opand & mask) == tag then value1 else value2
+        fn check_tag(
+            opand: self::Opand,
+            mask: i64,
+            tag: i64,
+            value1: self::Value,
+            value2: self::Value,
+        ) -> self::Value {
+            let mask = self::Opand::int64(mask);
+            bind(fold(utils::Binop::bitwise_and, opand, mask), |masked| {
+                check_eq(masked, tag, value1, value2)
+            })
+        }
+
+        // if opand == int64 then value1 else value2
+        fn check_eq(
+            opand: self::Opand,
+            int64: i64,
+            value1: self::Value,
+            value2: self::Value,
+        ) -> self::Value {
+            let int64 = self::Opand::int64(int64);
+            check_relop(utils::Relop::eq, opand, int64, value1, value2)
+        }
+
+        // if opand1 relop opand2 then value1 else value2
+        fn check_relop(
+            relop: utils::Relop,
+            opand1: self::Opand,
+            opand2: self::Opand,
+            value1: self::Value,
+            value2: self::Value,
+        ) -> self::Value {
+            match (opand1, opand2) {
+                (self::Opand::int64(int64_1), self::Opand::int64(int64_2)) => {
+                    match relop.eval(int64_1, int64_2) {
+                        true => value1,
+                        false => value2,
+                    }
+                },
+                (opand1, opand2) => {
+                    let pred = self::Pred::relop {
+                        relop,
+                        opand1,
+                        opand2,
+                    };
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    self::Value::r#if {
+                        pred,
+                        value1,
+                        value2,
+                    }
+                },
+            }
+        }
+
+        // Binds the value to a fresh aloc for the body, unless it is an opand.
+        fn bind(
+            value: self::Value,
+            body: impl FnOnce(self::Opand) -> self::Value,
+        ) -> self::Value {
+            match value {
+                self::Value::triv(self::Triv::opand(opand)) => body(opand),
+                value => {
+                    let aloc = utils::Aloc::fresh();
+                    let body = body(self::Opand::aloc(aloc.clone()));
+                    let bindings = [(aloc, value)].into_iter().collect();
+                    let value = Box::new(body);
+                    self::Value::r#let { bindings, value }
+                },
+            }
+        }
+
+        fn fold(
+            binop: utils::Binop,
+            opand1: self::Opand,
+            opand2: self::Opand,
+        ) -> self::Value {
+            let int64 = match (&opand1, &opand2) {
+                (self::Opand::int64(int64_1), self::Opand::int64(int64_2)) => {
+                    binop.checked_eval(*int64_1, *int64_2)
+                },
+                _ => None,
+            };
+            match int64 {
+                Some(int64) => datum_word(int64),
+                // A binop which overflows is left in place, so that it still
+                // traps in the checked overflow mode.
+                None => self::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                },
+            }
+        }
+
+        // Evaluates the value for its effect, discarding the result.
+        fn then(value: self::Value, body: self::Value) -> self::Value {
+            bind(value, |_| body)
+        }
+
+        // Binds the value to a fresh aloc for the tail.
+        fn bind_tail(
+            value: self::Value,
+            body: impl FnOnce(self::Opand) -> self::Tail,
+        ) -> self::Tail {
+            let aloc = utils::Aloc::fresh();
+            let body = body(self::Opand::aloc(aloc.clone()));
+            let bindings = [(aloc, value)].into_iter().collect();
+            let tail = Box::new(body);
+            self::Tail::r#let { bindings, tail }
+        }
+
+        fn mref(opand1: self::Opand, opand2: self::Opand) -> self::Value {
+            self::Value::mref { opand1, opand2 }
+        }
+
+        fn mset(
+            opand1: self::Opand,
+            opand2: self::Opand,
+            opand3: self::Opand,
+        ) -> self::Value {
+            self::Value::mset {
+                opand1,
+                opand2,
+                opand3,
+            }
+        }
+
+        fn error(error: utils::RuntimeError) -> self::Value {
+            datum_word(error.code())
+        }
+
+        fn datum(datum: utils::Datum) -> self::Value {
+            datum_word(datum.repr())
+        }
+
+        fn datum_word(int64: i64) -> self::Value {
+            self::Value::triv(self::Triv::opand(self::Opand::int64(int64)))
+        }
+
+        let p = specify_p(p);
+        Self(p)
+    }
+
+    /// EliminateCommonSubexpressions: ValuesUniqueLang -> ValuesUniqueLang
+    ///
+    /// ### Purpose:
+    /// Replaces each binop whose result is already bound to an aloc in scope
+    /// with a reference to that aloc.
+    ///
+    /// ### Notes:
+    /// A binding is in scope within the body of its let, which it dominates.
+    /// Binops are pure, so the same binop of the same opands always has the
+    /// same result, unless one of the alocs involved is assigned; binops on
+    /// assigned alocs are therefore never reused. The opands of commutative
+    /// binops are matched in either order. Lambda bodies start with no binops
+    /// in scope, so that no lambda captures an aloc it did not before.
+    pub fn eliminate_common_subexpressions(self) -> Self {
+        type Key = (utils::Binop, self::Opand, self::Opand);
+        type Available = HashMap<Key, utils::Aloc>;
+
+        fn eliminate_p(p: self::P) -> self::P {
+            match p {
+                self::P::module { lambdas, tail } => {
+                    let mut assigned = utils::AlocSet::default();
+                    lambdas
+                        .iter()
+                        .map(|lambda| &lambda.tail)
+                        .chain(std::iter::once(&tail))
+                        .for_each(|tail| assigned_tail(tail, &mut assigned));
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|Lambda { label, args, tail }| {
+                            let available = Available::default();
+                            let tail =
+                                eliminate_tail(tail, &available, &assigned);
+                            Lambda { label, args, tail }
+                        })
+                        .collect();
+                    let available = Available::default();
+                    let tail = eliminate_tail(tail, &available, &assigned);
+                    self::P::module { lambdas, tail }
+                },
+            }
+        }
+
+        fn eliminate_tail(
+            tail: self::Tail,
+            available: &Available,
+            assigned: &utils::AlocSet,
+        ) -> self::Tail {
+            match tail {
+                self::Tail::value(value) => {
+                    let value = eliminate_value(value, available, assigned);
+                    self::Tail::value(value)
+                },
+                self::Tail::r#let { bindings, tail } => {
+                    let (bindings, available) =
+                        eliminate_bindings(bindings, available, assigned);
+                    let tail = eliminate_tail(*tail, &available, assigned);
+                    let tail = Box::new(tail);
+                    self::Tail::r#let { bindings, tail }
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let pred = eliminate_pred(pred, available, assigned);
+                    let tail1 = eliminate_tail(*tail1, available, assigned);
+                    let tail2 = eliminate_tail(*tail2, available, assigned);
+                    let tail1 = Box::new(tail1);
+                    let tail2 = Box::new(tail2);
+                    self::Tail::r#if { pred, tail1, tail2 }
+                },
+                tail => tail,
+            }
+        }
 
-#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
-pub struct ValuesUniqueLang(pub self::P);
+        fn eliminate_pred(
+            pred: self::Pred,
+            available: &Available,
+            assigned: &utils::AlocSet,
+        ) -> self::Pred {
+            match pred {
+                self::Pred::not(pred) => {
+                    let pred = eliminate_pred(*pred, available, assigned);
+                    self::Pred::not(Box::new(pred))
+                },
+                self::Pred::r#let { bindings, pred } => {
+                    let (bindings, available) =
+                        eliminate_bindings(bindings, available, assigned);
+                    let pred = eliminate_pred(*pred, &available, assigned);
+                    let pred = Box::new(pred);
+                    self::Pred::r#let { bindings, pred }
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let pred1 = eliminate_pred(*pred1, available, assigned);
+                    let pred2 = eliminate_pred(*pred2, available, assigned);
+                    let pred3 = eliminate_pred(*pred3, available, assigned);
+                    let pred1 = Box::new(pred1);
+                    let pred2 = Box::new(pred2);
+                    let pred3 = Box::new(pred3);
+                    self::Pred::r#if {
+                        pred1,
+                        pred2,
+                        pred3,
+                    }
+                },
+                pred => pred,
+            }
+        }
+
+        fn eliminate_value(
+            value: self::Value,
+            available: &Available,
+            assigned: &utils::AlocSet,
+        ) -> self::Value {
+            match value {
+                self::Value::binop {
+                    binop,
+                    opand1,
+                    opand2,
+                } => match lookup(binop, &opand1, &opand2, available) {
+                    Some(aloc) => {
+                        let opand = self::Opand::aloc(aloc.clone());
+                        self::Value::triv(self::Triv::opand(opand))
+                    },
+                    None => self::Value::binop {
+                        binop,
+                        opand1,
+                        opand2,
+                    },
+                },
+                self::Value::r#let { bindings, value } => {
+                    let (bindings, available) =
+                        eliminate_bindings(bindings, available, assigned);
+                    let value = eliminate_value(*value, &available, assigned);
+                    let value = Box::new(value);
+                    self::Value::r#let { bindings, value }
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let pred = eliminate_pred(pred, available, assigned);
+                    let value1 = eliminate_value(*value1, available, assigned);
+                    let value2 = eliminate_value(*value2, available, assigned);
+                    let value1 = Box::new(value1);
+                    let value2 = Box::new(value2);
+                    self::Value::r#if {
+                        pred,
+                        value1,
+                        value2,
+                    }
+                },
+                self::Value::lambda { args, tail } => {
+                    let available = Available::default();
+                    let tail = eliminate_tail(*tail, &available, assigned);
+                    let tail = Box::new(tail);
+                    self::Value::lambda { args, tail }
+                },
+                self::Value::set { aloc, value } => {
+                    let value = eliminate_value(*value, available, assigned);
+                    let value = Box::new(value);
+                    self::Value::set { aloc, value }
+                },
+                self::Value::r#while { pred, value } => {
+                    let pred = eliminate_pred(pred, available, assigned);
+                    let value = eliminate_value(*value, available, assigned);
+                    let value = Box::new(value);
+                    self::Value::r#while { pred, value }
+                },
+                self::Value::switch {
+                    opand,
+                    table,
+                    values,
+                } => {
+                    let values = values
+                        .into_iter()
+                        .map(|value| {
+                            eliminate_value(value, available, assigned)
+                        })
+                        .collect();
+                    self::Value::switch {
+                        opand,
+                        table,
+                        values,
+                    }
+                },
+                value => value,
+            }
+        }
+
+        // The bindings of a let are parallel, so each is eliminated in the
+        // outer scope; those which remain binops are available in the body.
+        fn eliminate_bindings(
+            bindings: self::Bindings,
+            available: &Available,
+            assigned: &utils::AlocSet,
+        ) -> (self::Bindings, Available) {
+            let mut inner = available.clone();
+            let bindings = bindings
+                .into_iter()
+                .map(|(aloc, value)| {
+                    let value = eliminate_value(value, available, assigned);
+                    if let self::Value::binop {
+                        binop,
+                        opand1,
+                        opand2,
+                    } = &value
+                    {
+                        let reusable = [opand1, opand2]
+                            .into_iter()
+                            .filter_map(|opand| match opand {
+                                self::Opand::aloc(aloc) => Some(aloc),
+                                self::Opand::int64(_) => None,
+                            })
+                            .chain(std::iter::once(&aloc))
+                            .all(|aloc| !assigned.contains(aloc));
+                        if reusable {
+                            let key = (*binop, opand1.clone(), opand2.clone());
+                            inner.entry(key).or_insert_with(|| aloc.clone());
+                        }
+                    };
+                    (aloc, value)
+                })
+                .collect();
+            (bindings, inner)
+        }
+
+        fn lookup<'a>(
+            binop: utils::Binop,
+            opand1: &self::Opand,
+            opand2: &self::Opand,
+            available: &'a Available,
+        ) -> Option<&'a utils::Aloc> {
+            let commutative = matches!(
+                binop,
+                utils::Binop::plus
+                    | utils::Binop::multiply
+                    | utils::Binop::bitwise_and
+                    | utils::Binop::bitwise_ior
+                    | utils::Binop::bitwise_xor
+            );
+            let key = (binop, opand1.clone(), opand2.clone());
+            available.get(&key).or_else(|| match commutative {
+                true => {
+                    let key = (binop, opand2.clone(), opand1.clone());
+                    available.get(&key)
+                },
+                false => None,
+            })
+        }
+
+        fn assigned_tail(tail: &self::Tail, assigned: &mut utils::AlocSet) {
+            match tail {
+                self::Tail::value(value) => assigned_value(value, assigned),
+                self::Tail::r#let { bindings, tail } => {
+                    bindings
+                        .values()
+                        .for_each(|value| assigned_value(value, assigned));
+                    assigned_tail(tail, assigned)
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    assigned_pred(pred, assigned);
+                    assigned_tail(tail1, assigned);
+                    assigned_tail(tail2, assigned)
+                },
+                self::Tail::call { .. } => (),
+            }
+        }
+
+        fn assigned_pred(pred: &self::Pred, assigned: &mut utils::AlocSet) {
+            match pred {
+                self::Pred::not(pred) => assigned_pred(pred, assigned),
+                self::Pred::r#let { bindings, pred } => {
+                    bindings
+                        .values()
+                        .for_each(|value| assigned_value(value, assigned));
+                    assigned_pred(pred, assigned)
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    assigned_pred(pred1, assigned);
+                    assigned_pred(pred2, assigned);
+                    assigned_pred(pred3, assigned)
+                },
+                _ => (),
+            }
+        }
+
+        fn assigned_value(value: &self::Value, assigned: &mut utils::AlocSet) {
+            match value {
+                self::Value::r#let { bindings, value } => {
+                    bindings
+                        .values()
+                        .for_each(|value| assigned_value(value, assigned));
+                    assigned_value(value, assigned)
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    assigned_pred(pred, assigned);
+                    assigned_value(value1, assigned);
+                    assigned_value(value2, assigned)
+                },
+                self::Value::lambda { tail, .. } => {
+                    assigned_tail(tail, assigned)
+                },
+                self::Value::set { aloc, value } => {
+                    assigned.insert(aloc.clone());
+                    assigned_value(value, assigned)
+                },
+                self::Value::r#while { pred, value } => {
+                    assigned_pred(pred, assigned);
+                    assigned_value(value, assigned)
+                },
+                self::Value::switch { values, .. } => values
+                    .iter()
+                    .for_each(|value| assigned_value(value, assigned)),
+                _ => (),
+            }
+        }
+
+        let Self(p) = self;
+        let p = eliminate_p(p);
+        Self(p)
+    }
 
-impl ValuesUniqueLang {
     /// OptimizeLetBindings: ValuesUniqueLang -> ValuesUniqueLang
     ///
     /// ### Purpose:
@@ -82,6 +2469,7 @@ impl ValuesUniqueLang {
                     opand1,
                     opand2,
                 },
+                self::Pred::opand(opand) => target::Pred::opand(opand),
                 self::Pred::r#true => target::Pred::r#true,
                 self::Pred::r#false => target::Pred::r#false,
                 self::Pred::not(pred) => {
@@ -127,6 +2515,45 @@ impl ValuesUniqueLang {
                     opand1,
                     opand2,
                 },
+                self::Value::primop { .. }
+                | self::Value::make_closure { .. }
+                | self::Value::closure_ref { .. } => {
+                    unreachable!(
+                        "Primops and closures are lowered by \
+                         specify-representation."
+                    )
+                },
+                self::Value::lambda { .. } => {
+                    unreachable!("Lambdas are converted by convert-closures.")
+                },
+                self::Value::alloc { opand } => target::Value::alloc { opand },
+                self::Value::mref { opand1, opand2 } => {
+                    target::Value::mref { opand1, opand2 }
+                },
+                self::Value::switch {
+                    opand,
+                    table,
+                    values,
+                } => {
+                    let values = values.into_iter().map(seq_value).collect();
+                    target::Value::switch {
+                        opand,
+                        table,
+                        values,
+                    }
+                },
+                value @ (self::Value::set { .. }
+                | self::Value::mset { .. }
+                | self::Value::r#while { .. }) => {
+                    let effect = seq_effect(value);
+                    let void = target::Opand::int64(utils::VOID);
+                    let value = target::Value::triv(target::Triv::opand(void));
+                    let value = Box::new(value);
+                    target::Value::begin {
+                        effects: vec![effect],
+                        value,
+                    }
+                },
                 self::Value::r#let { bindings, value } => {
                     let effects = seq_bindings(bindings);
                     let value = seq_value(*value);
@@ -152,6 +2579,70 @@ impl ValuesUniqueLang {
             }
         }
 
+        // Sequentializes a value which is evaluated only for its effect.
+        fn seq_effect(value: self::Value) -> target::Effect {
+            match value {
+                self::Value::set { aloc, value } => {
+                    let value = seq_value(*value);
+                    target::Effect::set_aloc_value { aloc, value }
+                },
+                self::Value::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => target::Effect::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                },
+                self::Value::r#let { bindings, value } => {
+                    let mut effects = seq_bindings(bindings);
+                    effects.push(seq_effect(*value));
+                    target::Effect::begin(effects)
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let pred = seq_pred(pred);
+                    let effect1 = seq_effect(*value1);
+                    let effect2 = seq_effect(*value2);
+                    let effect1 = Box::new(effect1);
+                    let effect2 = Box::new(effect2);
+                    target::Effect::r#if {
+                        pred,
+                        effect1,
+                        effect2,
+                    }
+                },
+                self::Value::r#while { pred, value } => {
+                    let pred = seq_pred(pred);
+                    let effect = seq_effect(*value);
+                    let effect = Box::new(effect);
+                    target::Effect::r#while { pred, effect }
+                },
+                self::Value::switch {
+                    opand,
+                    table,
+                    values,
+                } => {
+                    let effects = values.into_iter().map(seq_effect).collect();
+                    target::Effect::switch {
+                        opand,
+                        table,
+                        effects,
+                    }
+                },
+                self::Value::triv(..) => target::Effect::begin(vec![]),
+                value => {
+                    let aloc = utils::Aloc::fresh();
+                    let value = seq_value(value);
+                    target::Effect::set_aloc_value { aloc, value }
+                },
+            }
+        }
+
         fn seq_bindings(bindings: self::Bindings) -> Vec<target::Effect> {
             bindings
                 .into_iter()
@@ -166,3 +2657,251 @@ impl ValuesUniqueLang {
         target::ImpMfLang(p)
     }
 }
+
+impl Tail {
+    /// ### Purpose:
+    /// The alocs that are referenced in the tail, but not bound in it.
+    pub fn free_alocs(&self) -> utils::AlocSet {
+        fn free_tail(tail: &self::Tail) -> utils::AlocSet {
+            match tail {
+                self::Tail::value(value) => free_value(value),
+                self::Tail::r#let { bindings, tail } => {
+                    free_let(bindings, free_tail(tail))
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let mut alocs = free_pred(pred);
+                    alocs.extend(free_tail(tail1));
+                    alocs.extend(free_tail(tail2));
+                    alocs
+                },
+                self::Tail::call { triv, opands } => {
+                    let mut alocs = free_opands(opands.iter());
+                    if let self::Triv::opand(opand) = triv {
+                        alocs.extend(free_opands([opand]));
+                    }
+                    alocs
+                },
+            }
+        }
+
+        fn free_pred(pred: &self::Pred) -> utils::AlocSet {
+            match pred {
+                self::Pred::relop { opand1, opand2, .. } => {
+                    free_opands([opand1, opand2])
+                },
+                self::Pred::opand(opand) => free_opands([opand]),
+                self::Pred::r#true | self::Pred::r#false => {
+                    utils::AlocSet::default()
+                },
+                self::Pred::not(pred) => free_pred(pred),
+                self::Pred::r#let { bindings, pred } => {
+                    free_let(bindings, free_pred(pred))
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let mut alocs = free_pred(pred1);
+                    alocs.extend(free_pred(pred2));
+                    alocs.extend(free_pred(pred3));
+                    alocs
+                },
+            }
+        }
+
+        fn free_value(value: &self::Value) -> utils::AlocSet {
+            match value {
+                self::Value::triv(self::Triv::opand(opand)) => {
+                    free_opands([opand])
+                },
+                self::Value::triv(self::Triv::label(..)) => {
+                    utils::AlocSet::default()
+                },
+                self::Value::binop { opand1, opand2, .. }
+                | self::Value::mref { opand1, opand2 } => {
+                    free_opands([opand1, opand2])
+                },
+                self::Value::primop { opands, .. }
+                | self::Value::make_closure { opands, .. } => {
+                    free_opands(opands.iter())
+                },
+                self::Value::alloc { opand }
+                | self::Value::closure_ref { opand, .. } => {
+                    free_opands([opand])
+                },
+                self::Value::mset {
+                    opand1,
+                    opand2,
+                    opand3,
+                } => free_opands([opand1, opand2, opand3]),
+                self::Value::r#let { bindings, value } => {
+                    free_let(bindings, free_value(value))
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    let mut alocs = free_pred(pred);
+                    alocs.extend(free_value(value1));
+                    alocs.extend(free_value(value2));
+                    alocs
+                },
+                self::Value::lambda { args, tail } => {
+                    let mut alocs = free_tail(tail);
+                    args.iter().for_each(|aloc| {
+                        alocs.remove(aloc);
+                    });
+                    alocs
+                },
+                self::Value::set { aloc, value } => {
+                    let mut alocs = free_value(value);
+                    alocs.insert(aloc.clone());
+                    alocs
+                },
+                self::Value::r#while { pred, value } => {
+                    let mut alocs = free_pred(pred);
+                    alocs.extend(free_value(value));
+                    alocs
+                },
+                self::Value::switch { opand, values, .. } => {
+                    let mut alocs = free_opands([opand]);
+                    values
+                        .iter()
+                        .for_each(|value| alocs.extend(free_value(value)));
+                    alocs
+                },
+            }
+        }
+
+        // The alocs are unique, so the bound values may refer to each other.
+        fn free_let(
+            bindings: &self::Bindings,
+            mut alocs: utils::AlocSet,
+        ) -> utils::AlocSet {
+            bindings
+                .values()
+                .for_each(|value| alocs.extend(free_value(value)));
+            bindings.keys().for_each(|aloc| {
+                alocs.remove(aloc);
+            });
+            alocs
+        }
+
+        fn free_opands<'a>(
+            opands: impl IntoIterator<Item = &'a self::Opand>,
+        ) -> utils::AlocSet {
+            opands
+                .into_iter()
+                .filter_map(|opand| match opand {
+                    self::Opand::aloc(aloc) => Some(aloc.clone()),
+                    self::Opand::int64(..) => None,
+                })
+                .collect()
+        }
+
+        free_tail(self)
+    }
+    /// ### Purpose:
+    /// The labels that are referenced in the tail.
+    pub fn labels(&self) -> HashSet<utils::Label> {
+        fn labels_tail(tail: &self::Tail, labels: &mut HashSet<utils::Label>) {
+            match tail {
+                self::Tail::value(value) => labels_value(value, labels),
+                self::Tail::r#let { bindings, tail } => {
+                    labels_bindings(bindings, labels);
+                    labels_tail(tail, labels);
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    labels_pred(pred, labels);
+                    labels_tail(tail1, labels);
+                    labels_tail(tail2, labels);
+                },
+                self::Tail::call { triv, .. } => labels_triv(triv, labels),
+            }
+        }
+
+        fn labels_pred(pred: &self::Pred, labels: &mut HashSet<utils::Label>) {
+            match pred {
+                self::Pred::relop { .. }
+                | self::Pred::opand(..)
+                | self::Pred::r#true
+                | self::Pred::r#false => (),
+                self::Pred::not(pred) => labels_pred(pred, labels),
+                self::Pred::r#let { bindings, pred } => {
+                    labels_bindings(bindings, labels);
+                    labels_pred(pred, labels);
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    labels_pred(pred1, labels);
+                    labels_pred(pred2, labels);
+                    labels_pred(pred3, labels);
+                },
+            }
+        }
+
+        fn labels_value(
+            value: &self::Value,
+            labels: &mut HashSet<utils::Label>,
+        ) {
+            match value {
+                self::Value::triv(triv) => labels_triv(triv, labels),
+                self::Value::make_closure { label, .. } => {
+                    labels.insert(label.clone());
+                },
+                self::Value::r#let { bindings, value } => {
+                    labels_bindings(bindings, labels);
+                    labels_value(value, labels);
+                },
+                self::Value::r#if {
+                    pred,
+                    value1,
+                    value2,
+                } => {
+                    labels_pred(pred, labels);
+                    labels_value(value1, labels);
+                    labels_value(value2, labels);
+                },
+                self::Value::lambda { tail, .. } => labels_tail(tail, labels),
+                self::Value::set { value, .. } => labels_value(value, labels),
+                self::Value::r#while { pred, value } => {
+                    labels_pred(pred, labels);
+                    labels_value(value, labels);
+                },
+                self::Value::switch { values, .. } => values
+                    .iter()
+                    .for_each(|value| labels_value(value, labels)),
+                self::Value::binop { .. }
+                | self::Value::primop { .. }
+                | self::Value::alloc { .. }
+                | self::Value::mref { .. }
+                | self::Value::mset { .. }
+                | self::Value::closure_ref { .. } => (),
+            }
+        }
+
+        fn labels_bindings(
+            bindings: &self::Bindings,
+            labels: &mut HashSet<utils::Label>,
+        ) {
+            bindings
+                .values()
+                .for_each(|value| labels_value(value, labels));
+        }
+
+        fn labels_triv(triv: &self::Triv, labels: &mut HashSet<utils::Label>) {
+            if let self::Triv::label(label) = triv {
+                labels.insert(label.clone());
+            }
+        }
+
+        let mut labels = HashSet::new();
+        labels_tail(self, &mut labels);
+        labels
+    }
+}
@@ -1 +1,1115 @@
+use serial_test::serial;
 
+use crate::imperative_abstractions::values_unique_lang as source;
+use crate::utils;
+
+fn int64(int64: i64) -> source::Value {
+    source::Value::triv(source::Triv::opand(source::Opand::int64(int64)))
+}
+
+fn aloc(aloc: &utils::Aloc) -> source::Opand {
+    source::Opand::aloc(aloc.clone())
+}
+
+fn bind(
+    aloc: &utils::Aloc,
+    value: source::Value,
+    body: source::Value,
+) -> source::Value {
+    source::Value::r#let {
+        bindings: [(aloc.clone(), value)].into_iter().collect(),
+        value: Box::new(body),
+    }
+}
+
+// if (opand & mask) == tag then value1 else value2, with the masked opand
+// bound to the aloc.
+fn check_tag(
+    tag_aloc: &utils::Aloc,
+    opand: source::Opand,
+    (mask, tag): (i64, i64),
+    value1: source::Value,
+    value2: source::Value,
+) -> source::Value {
+    let masked = source::Value::binop {
+        binop: utils::Binop::bitwise_and,
+        opand1: opand,
+        opand2: source::Opand::int64(mask),
+    };
+    let value = source::Value::r#if {
+        pred: source::Pred::relop {
+            relop: utils::Relop::eq,
+            opand1: aloc(tag_aloc),
+            opand2: source::Opand::int64(tag),
+        },
+        value1: Box::new(value1),
+        value2: Box::new(value2),
+    };
+    bind(tag_aloc, masked, value)
+}
+
+#[test]
+#[serial]
+fn specify_constants() {
+    utils::reset_all_indices();
+
+    let fixnum = |int64| utils::Datum::fixnum(int64).repr();
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::r#if {
+            pred: source::Pred::r#true,
+            tail1: Box::new(source::Tail::value(source::Value::binop {
+                binop: utils::Binop::multiply,
+                opand1: source::Opand::int64(fixnum(6)),
+                opand2: source::Opand::int64(fixnum(7)),
+            })),
+            tail2: Box::new(source::Tail::value(source::Value::primop {
+                primop: utils::Primop::fixnum_p,
+                opands: vec![source::Opand::int64(utils::EMPTY)],
+            })),
+        },
+    });
+    let actual = p.specify_representation();
+
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::r#if {
+            pred: source::Pred::r#true,
+            tail1: Box::new(source::Tail::value(int64(fixnum(42)))),
+            tail2: Box::new(source::Tail::value(int64(utils::FALSE))),
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn specify_checked_binop() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh();
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![source::Lambda {
+            label: utils::Label::new(),
+            args: vec![x.clone()],
+            tail: source::Tail::value(source::Value::binop {
+                binop: utils::Binop::plus,
+                opand1: source::Opand::aloc(x.clone()),
+                opand2: source::Opand::int64(utils::Datum::fixnum(1).repr()),
+            }),
+        }],
+        tail: source::Tail::value(int64(0)),
+    });
+    let actual = p.specify_representation();
+
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh();
+    let label = utils::Label::new();
+    let tag = utils::Aloc::fresh();
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![source::Lambda {
+            label,
+            args: vec![x.clone()],
+            tail: source::Tail::value(source::Value::r#let {
+                bindings: vec![(
+                    tag.clone(),
+                    source::Value::binop {
+                        binop: utils::Binop::bitwise_and,
+                        opand1: source::Opand::aloc(x.clone()),
+                        opand2: source::Opand::int64(utils::FIXNUM_MASK),
+                    },
+                )]
+                .into_iter()
+                .collect(),
+                value: Box::new(source::Value::r#if {
+                    pred: source::Pred::relop {
+                        relop: utils::Relop::eq,
+                        opand1: source::Opand::aloc(tag),
+                        opand2: source::Opand::int64(utils::FIXNUM_TAG),
+                    },
+                    value1: Box::new(source::Value::binop {
+                        binop: utils::Binop::plus,
+                        opand1: source::Opand::aloc(x),
+                        opand2: source::Opand::int64(8),
+                    }),
+                    value2: Box::new(int64(
+                        utils::RuntimeError::type_error.code(),
+                    )),
+                }),
+            }),
+        }],
+        tail: source::Tail::value(int64(0)),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn convert_lambda_to_closure() {
+    utils::reset_all_indices();
+
+    let y = utils::Aloc::fresh();
+    let x = utils::Aloc::fresh();
+    let f = utils::Aloc::fresh();
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::r#let {
+            bindings: [(y.clone(), int64(8)), (
+                f.clone(),
+                source::Value::lambda {
+                    args: vec![x.clone()],
+                    tail: Box::new(source::Tail::value(source::Value::binop {
+                        binop: utils::Binop::plus,
+                        opand1: source::Opand::aloc(x.clone()),
+                        opand2: source::Opand::aloc(y.clone()),
+                    })),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            tail: Box::new(source::Tail::call {
+                triv: source::Triv::opand(source::Opand::aloc(f.clone())),
+                opands: vec![source::Opand::int64(16)],
+            }),
+        },
+    });
+    let actual = p.convert_closures();
+
+    utils::reset_all_indices();
+
+    let y = utils::Aloc::fresh();
+    let x = utils::Aloc::fresh();
+    let f = utils::Aloc::fresh();
+    let label = utils::Label::new_with_name("lambda");
+    let closure = utils::Aloc::fresh_with_name("closure");
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![source::Lambda {
+            label: label.clone(),
+            args: vec![closure.clone(), x.clone()],
+            tail: source::Tail::r#let {
+                bindings: [(y.clone(), source::Value::closure_ref {
+                    opand: source::Opand::aloc(closure),
+                    index: 0,
+                })]
+                .into_iter()
+                .collect(),
+                tail: Box::new(source::Tail::value(source::Value::binop {
+                    binop: utils::Binop::plus,
+                    opand1: source::Opand::aloc(x),
+                    opand2: source::Opand::aloc(y.clone()),
+                })),
+            },
+        }],
+        tail: source::Tail::r#let {
+            bindings: [(y.clone(), int64(8)), (
+                f.clone(),
+                source::Value::make_closure {
+                    label,
+                    arity: 1,
+                    opands: vec![source::Opand::aloc(y)],
+                },
+            )]
+            .into_iter()
+            .collect(),
+            tail: Box::new(source::Tail::call {
+                triv: source::Triv::opand(source::Opand::aloc(f.clone())),
+                opands: vec![
+                    source::Opand::aloc(f),
+                    source::Opand::int64(16),
+                ],
+            }),
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn specify_call_through_literal() {
+    utils::reset_all_indices();
+
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::call {
+            triv: source::Triv::opand(source::Opand::int64(0)),
+            opands: vec![source::Opand::int64(0)],
+        },
+    });
+    let actual = p.specify_representation();
+
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(int64(
+            utils::RuntimeError::type_error.code(),
+        )),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn convert_captured_assignment() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh();
+    let f = utils::Aloc::fresh();
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::r#let {
+            bindings: [(x.clone(), int64(1))].into_iter().collect(),
+            tail: Box::new(source::Tail::r#let {
+                bindings: [(f.clone(), source::Value::lambda {
+                    args: vec![],
+                    tail: Box::new(source::Tail::value(source::Value::triv(
+                        source::Triv::opand(source::Opand::aloc(x.clone())),
+                    ))),
+                })]
+                .into_iter()
+                .collect(),
+                tail: Box::new(source::Tail::value(source::Value::set {
+                    aloc: x,
+                    value: Box::new(int64(2)),
+                })),
+            }),
+        },
+    });
+    let actual = p.convert_assignments();
+
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh();
+    let f = utils::Aloc::fresh();
+    let cell = utils::Aloc::fresh();
+    let set = utils::Aloc::fresh();
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::r#let {
+            bindings: [(x.clone(), source::Value::r#let {
+                bindings: [(cell.clone(), source::Value::alloc {
+                    opand: source::Opand::int64(8),
+                })]
+                .into_iter()
+                .collect(),
+                value: Box::new(source::Value::r#let {
+                    bindings: [(set, source::Value::mset {
+                        opand1: source::Opand::aloc(cell.clone()),
+                        opand2: source::Opand::int64(0),
+                        opand3: source::Opand::int64(1),
+                    })]
+                    .into_iter()
+                    .collect(),
+                    value: Box::new(source::Value::triv(source::Triv::opand(
+                        source::Opand::aloc(cell),
+                    ))),
+                }),
+            })]
+            .into_iter()
+            .collect(),
+            tail: Box::new(source::Tail::r#let {
+                bindings: [(f, source::Value::lambda {
+                    args: vec![],
+                    tail: Box::new(source::Tail::value(source::Value::mref {
+                        opand1: source::Opand::aloc(x.clone()),
+                        opand2: source::Opand::int64(0),
+                    })),
+                })]
+                .into_iter()
+                .collect(),
+                tail: Box::new(source::Tail::value(source::Value::mset {
+                    opand1: source::Opand::aloc(x),
+                    opand2: source::Opand::int64(0),
+                    opand3: source::Opand::int64(2),
+                })),
+            }),
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn eliminate_unreachable_procedures() {
+    utils::reset_all_indices();
+
+    let [f, g, h, k, m] =
+        ["f", "g", "h", "k", "m"].map(utils::Label::new_with_name);
+    // Calls the label in tail position, or returns it if it is none.
+    let call = |label: Option<&utils::Label>| match label {
+        Some(label) => source::Tail::call {
+            triv: source::Triv::label(label.clone()),
+            opands: vec![],
+        },
+        None => source::Tail::value(int64(0)),
+    };
+    let lambda = |label: &utils::Label, tail| source::Lambda {
+        label: label.clone(),
+        args: vec![],
+        tail,
+    };
+    // f calls g, which uses h as a value; k is a root, and m is dead.
+    let lambdas = || {
+        vec![
+            lambda(&f, call(Some(&g))),
+            lambda(
+                &g,
+                source::Tail::value(source::Value::triv(source::Triv::label(
+                    h.clone(),
+                ))),
+            ),
+            lambda(&h, call(None)),
+            lambda(&k, call(None)),
+            lambda(&m, call(Some(&m))),
+        ]
+    };
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: lambdas(),
+        tail: call(Some(&f)),
+    });
+    let roots = std::slice::from_ref(&k);
+    let (actual, removed) = p.eliminate_dead_procedures(roots);
+
+    let mut expected_lambdas = lambdas();
+    expected_lambdas.pop();
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: expected_lambdas,
+        tail: call(Some(&f)),
+    });
+
+    assert_eq!(actual, expected);
+    assert_eq!(removed, vec![m]);
+}
+
+#[test]
+#[serial]
+fn inline_small_procedures() {
+    utils::reset_all_indices();
+
+    let [inc, spin] = ["inc", "spin"].map(utils::Label::new_with_name);
+    let x = utils::Aloc::fresh_with_name("x");
+    let n = utils::Aloc::fresh_with_name("n");
+    let add_one = |x: &utils::Aloc| {
+        source::Tail::value(source::Value::binop {
+            binop: utils::Binop::plus,
+            opand1: source::Opand::aloc(x.clone()),
+            opand2: source::Opand::int64(1),
+        })
+    };
+    let call = |label: &utils::Label, opand| source::Tail::call {
+        triv: source::Triv::label(label.clone()),
+        opands: vec![opand],
+    };
+    // inc is small enough to be inlined, but spin calls itself.
+    let lambdas = || {
+        vec![
+            source::Lambda {
+                label: inc.clone(),
+                args: vec![x.clone()],
+                tail: add_one(&x),
+            },
+            source::Lambda {
+                label: spin.clone(),
+                args: vec![n.clone()],
+                tail: call(&spin, source::Opand::aloc(n.clone())),
+            },
+        ]
+    };
+    let tail = |tail1| source::Tail::r#if {
+        pred: source::Pred::r#true,
+        tail1: Box::new(tail1),
+        tail2: Box::new(call(&spin, source::Opand::int64(0))),
+    };
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: lambdas(),
+        tail: tail(call(&inc, source::Opand::int64(5))),
+    });
+    let actual = p.inline_procedures(16);
+
+    let fresh_x = utils::Aloc {
+        name: "x".into(),
+        index: 2,
+    };
+    let bindings = [(fresh_x.clone(), int64(5))].into_iter().collect();
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: lambdas(),
+        tail: tail(source::Tail::r#let {
+            bindings,
+            tail: Box::new(add_one(&fresh_x)),
+        }),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn inline_never_unrolls_self_recursion() {
+    utils::reset_all_indices();
+
+    let count = utils::Label::new_with_name("count");
+    let [n, m] = ["n", "m"].map(utils::Aloc::fresh_with_name);
+    let call = |opand| source::Tail::call {
+        triv: source::Triv::label(count.clone()),
+        opands: vec![opand],
+    };
+    let p = || {
+        source::ValuesUniqueLang(source::P::module {
+            lambdas: vec![source::Lambda {
+                label: count.clone(),
+                args: vec![n.clone()],
+                tail: source::Tail::r#let {
+                    bindings: [(m.clone(), source::Value::binop {
+                        binop: utils::Binop::plus,
+                        opand1: aloc(&n),
+                        opand2: source::Opand::int64(1),
+                    })]
+                    .into_iter()
+                    .collect(),
+                    tail: Box::new(call(aloc(&m))),
+                },
+            }],
+            tail: call(source::Opand::int64(0)),
+        })
+    };
+    let actual = p().inline_procedures(usize::MAX);
+
+    assert_eq!(actual, p());
+}
+
+#[test]
+#[serial]
+fn inline_mutually_recursive_procedures() {
+    utils::reset_all_indices();
+
+    let [even, odd] = ["even", "odd"].map(utils::Label::new_with_name);
+    let [a, b] = ["a", "b"].map(utils::Aloc::fresh_with_name);
+    let call = |label: &utils::Label, opand| source::Tail::call {
+        triv: source::Triv::label(label.clone()),
+        opands: vec![opand],
+    };
+    let odd_lambda = || source::Lambda {
+        label: odd.clone(),
+        args: vec![b.clone()],
+        tail: call(&even, aloc(&b)),
+    };
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![
+            source::Lambda {
+                label: even.clone(),
+                args: vec![a.clone()],
+                tail: call(&odd, aloc(&a)),
+            },
+            odd_lambda(),
+        ],
+        tail: call(&even, source::Opand::int64(7)),
+    });
+    let actual = p.inline_procedures(usize::MAX);
+
+    // odd is inlined into even, after which even calls itself, and so is
+    // never inlined.
+    let fresh_b = utils::Aloc {
+        name: "b".into(),
+        index: 2,
+    };
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![
+            source::Lambda {
+                label: even.clone(),
+                args: vec![a.clone()],
+                tail: source::Tail::r#let {
+                    bindings: [(
+                        fresh_b.clone(),
+                        source::Value::triv(source::Triv::opand(aloc(&a))),
+                    )]
+                    .into_iter()
+                    .collect(),
+                    tail: Box::new(call(&even, aloc(&fresh_b))),
+                },
+            },
+            odd_lambda(),
+        ],
+        tail: call(&even, source::Opand::int64(7)),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn inline_same_procedure_twice() {
+    utils::reset_all_indices();
+
+    let inc = utils::Label::new_with_name("inc");
+    let [x, y] = ["x", "y"].map(utils::Aloc::fresh_with_name);
+    let add_one = |x: &utils::Aloc, y: &utils::Aloc| {
+        bind(
+            y,
+            source::Value::binop {
+                binop: utils::Binop::plus,
+                opand1: aloc(x),
+                opand2: source::Opand::int64(1),
+            },
+            source::Value::triv(source::Triv::opand(aloc(y))),
+        )
+    };
+    let call = |int64| source::Tail::call {
+        triv: source::Triv::label(inc.clone()),
+        opands: vec![source::Opand::int64(int64)],
+    };
+    let tail = |tail1, tail2| source::Tail::r#if {
+        pred: source::Pred::r#true,
+        tail1: Box::new(tail1),
+        tail2: Box::new(tail2),
+    };
+    let lambdas = || {
+        vec![source::Lambda {
+            label: inc.clone(),
+            args: vec![x.clone()],
+            tail: source::Tail::value(add_one(&x, &y)),
+        }]
+    };
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: lambdas(),
+        tail: tail(call(1), call(2)),
+    });
+    let actual = p.inline_procedures(usize::MAX);
+
+    // Each copy of the body binds its own fresh alocs.
+    let [x1, y1, x2, y2] = [("x", 2), ("y", 3), ("x", 4), ("y", 5)]
+        .map(|(name, index)| utils::Aloc {
+            name: name.into(),
+            index,
+        });
+    let copy = |x: &utils::Aloc, y: &utils::Aloc, int64| source::Tail::r#let {
+        bindings: [(x.clone(), self::int64(int64))].into_iter().collect(),
+        tail: Box::new(source::Tail::value(add_one(x, y))),
+    };
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: lambdas(),
+        tail: tail(copy(&x1, &y1, 1), copy(&x2, &y2, 2)),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn inline_only_within_budget() {
+    utils::reset_all_indices();
+
+    let inc = utils::Label::new_with_name("inc");
+    let x = utils::Aloc::fresh_with_name("x");
+    let add_one = |x: &utils::Aloc| {
+        source::Tail::value(source::Value::binop {
+            binop: utils::Binop::plus,
+            opand1: aloc(x),
+            opand2: source::Opand::int64(1),
+        })
+    };
+    let lambdas = || {
+        vec![source::Lambda {
+            label: inc.clone(),
+            args: vec![x.clone()],
+            tail: add_one(&x),
+        }]
+    };
+    let p = || {
+        source::ValuesUniqueLang(source::P::module {
+            lambdas: lambdas(),
+            tail: source::Tail::call {
+                triv: source::Triv::label(inc.clone()),
+                opands: vec![source::Opand::int64(5)],
+            },
+        })
+    };
+
+    // The body of inc is a tail and a binop, two nodes in all.
+    assert_eq!(p().inline_procedures(1), p());
+
+    let actual = p().inline_procedures(2);
+    let fresh_x = utils::Aloc {
+        name: "x".into(),
+        index: 1,
+    };
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: lambdas(),
+        tail: source::Tail::r#let {
+            bindings: [(fresh_x.clone(), int64(5))].into_iter().collect(),
+            tail: Box::new(add_one(&fresh_x)),
+        },
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn eliminate_repeated_binop() {
+    utils::reset_all_indices();
+
+    let [a, b, x, y] = ["a", "b", "x", "y"].map(utils::Aloc::fresh_with_name);
+    let aloc = |aloc: &utils::Aloc| source::Opand::aloc(aloc.clone());
+    let multiply = |opand1, opand2| source::Value::binop {
+        binop: utils::Binop::multiply,
+        opand1,
+        opand2,
+    };
+    let r#let = |aloc: &utils::Aloc, value, tail| source::Tail::r#let {
+        bindings: [(aloc.clone(), value)].into_iter().collect(),
+        tail: Box::new(tail),
+    };
+    let body = |value| {
+        r#let(
+            &x,
+            multiply(aloc(&a), aloc(&b)),
+            r#let(&y, value, source::Tail::value(multiply(aloc(&x), aloc(&y)))),
+        )
+    };
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: body(multiply(aloc(&b), aloc(&a))),
+    });
+    let actual = p.eliminate_common_subexpressions();
+
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: body(source::Value::triv(source::Triv::opand(aloc(&x)))),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn binop_on_assigned_aloc_is_not_eliminated() {
+    utils::reset_all_indices();
+
+    let [a, b, x, y, z, w] =
+        ["a", "b", "x", "y", "z", "w"].map(utils::Aloc::fresh_with_name);
+    let plus_one = |a: &utils::Aloc| source::Value::binop {
+        binop: utils::Binop::plus,
+        opand1: aloc(a),
+        opand2: source::Opand::int64(1),
+    };
+    let assign = || source::Value::set {
+        aloc: a.clone(),
+        value: Box::new(int64(2)),
+    };
+    // a is assigned between the two occurrences of a + 1, whereas b is not.
+    let body = |value| {
+        let value = bind(&z, assign(), value);
+        bind(&x, plus_one(&a), bind(&y, plus_one(&b), value))
+    };
+    let tail = |value1, value2| {
+        source::Tail::value(body(bind(&w, value1, value2)))
+    };
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: tail(plus_one(&a), plus_one(&b)),
+    });
+    let actual = p.eliminate_common_subexpressions();
+
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: tail(
+            plus_one(&a),
+            source::Value::triv(source::Triv::opand(aloc(&y))),
+        ),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn binop_is_not_eliminated_outside_its_scope() {
+    utils::reset_all_indices();
+
+    let [a, b, x, f] = ["a", "b", "x", "f"].map(utils::Aloc::fresh_with_name);
+    let multiply = || source::Value::binop {
+        binop: utils::Binop::multiply,
+        opand1: aloc(&a),
+        opand2: aloc(&b),
+    };
+    let lambda = || source::Value::lambda {
+        args: vec![],
+        tail: Box::new(source::Tail::value(multiply())),
+    };
+    let x_value = || source::Value::triv(source::Triv::opand(aloc(&x)));
+    // Neither the other branch nor the body of the lambda is within the let
+    // which binds x.
+    let tail = || source::Tail::r#if {
+        pred: source::Pred::r#true,
+        tail1: Box::new(source::Tail::value(bind(
+            &x,
+            multiply(),
+            bind(&f, lambda(), x_value()),
+        ))),
+        tail2: Box::new(source::Tail::value(multiply())),
+    };
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: tail(),
+    });
+    let actual = p.eliminate_common_subexpressions();
+
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: tail(),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn only_commutative_binops_match_swapped_opands() {
+    utils::reset_all_indices();
+
+    let [a, b, x, y, u, v] =
+        ["a", "b", "x", "y", "u", "v"].map(utils::Aloc::fresh_with_name);
+    let binop = |binop, opand1: &utils::Aloc, opand2: &utils::Aloc| {
+        source::Value::binop {
+            binop,
+            opand1: aloc(opand1),
+            opand2: aloc(opand2),
+        }
+    };
+    let body = |value1, value2| {
+        bind(
+            &x,
+            binop(utils::Binop::plus, &a, &b),
+            bind(
+                &y,
+                binop(utils::Binop::minus, &a, &b),
+                bind(&u, value1, bind(&v, value2, int64(0))),
+            ),
+        )
+    };
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(body(
+            binop(utils::Binop::plus, &b, &a),
+            binop(utils::Binop::minus, &b, &a),
+        )),
+    });
+    let actual = p.eliminate_common_subexpressions();
+
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(body(
+            source::Value::triv(source::Triv::opand(aloc(&x))),
+            binop(utils::Binop::minus, &b, &a),
+        )),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn overflowing_literal_binop_is_not_folded() {
+    utils::reset_all_indices();
+
+    let max = utils::Datum::fixnum((1 << 60) - 1).repr();
+    let one = utils::Datum::fixnum(1).repr();
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::binop {
+            binop: utils::Binop::plus,
+            opand1: source::Opand::int64(max),
+            opand2: source::Opand::int64(one),
+        }),
+    });
+    let actual = p.specify_representation();
+
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::binop {
+            binop: utils::Binop::plus,
+            opand1: source::Opand::int64(max),
+            opand2: source::Opand::int64(one),
+        }),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn specify_cons() {
+    utils::reset_all_indices();
+
+    let fixnum = |int64| utils::Datum::fixnum(int64).repr();
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::primop {
+            primop: utils::Primop::cons,
+            opands: vec![
+                source::Opand::int64(fixnum(1)),
+                source::Opand::int64(fixnum(2)),
+            ],
+        }),
+    });
+    let actual = p.specify_representation();
+
+    utils::reset_all_indices();
+
+    let ptr = utils::Aloc::fresh();
+    let pair = utils::Aloc::fresh();
+    let set_cdr = utils::Aloc::fresh();
+    let set_car = utils::Aloc::fresh();
+    let mset = |displacement, int64| source::Value::mset {
+        opand1: aloc(&pair),
+        opand2: source::Opand::int64(displacement - utils::PAIR_TAG),
+        opand3: source::Opand::int64(int64),
+    };
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(bind(
+            &ptr,
+            source::Value::alloc {
+                opand: source::Opand::int64(utils::PAIR_SIZE),
+            },
+            bind(
+                &pair,
+                source::Value::binop {
+                    binop: utils::Binop::plus,
+                    opand1: aloc(&ptr),
+                    opand2: source::Opand::int64(utils::PAIR_TAG),
+                },
+                bind(
+                    &set_car,
+                    mset(utils::CAR_DISPLACEMENT, fixnum(1)),
+                    bind(
+                        &set_cdr,
+                        mset(utils::CDR_DISPLACEMENT, fixnum(2)),
+                        source::Value::triv(source::Triv::opand(aloc(&pair))),
+                    ),
+                ),
+            ),
+        )),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn specify_car_and_cdr() {
+    for (primop, displacement) in [
+        (utils::Primop::car, utils::CAR_DISPLACEMENT),
+        (utils::Primop::cdr, utils::CDR_DISPLACEMENT),
+    ] {
+        utils::reset_all_indices();
+
+        let pair = utils::Aloc::fresh();
+        let p = source::ValuesUniqueLang(source::P::module {
+            lambdas: vec![],
+            tail: source::Tail::value(source::Value::primop {
+                primop,
+                opands: vec![aloc(&pair)],
+            }),
+        });
+        let actual = p.specify_representation();
+
+        utils::reset_all_indices();
+
+        let pair = utils::Aloc::fresh();
+        let tag = utils::Aloc::fresh();
+        let expected = source::ValuesUniqueLang(source::P::module {
+            lambdas: vec![],
+            tail: source::Tail::value(check_tag(
+                &tag,
+                aloc(&pair),
+                (utils::PAIR_MASK, utils::PAIR_TAG),
+                source::Value::mref {
+                    opand1: aloc(&pair),
+                    opand2: source::Opand::int64(
+                        displacement - utils::PAIR_TAG,
+                    ),
+                },
+                int64(utils::RuntimeError::type_error.code()),
+            )),
+        });
+
+        assert_eq!(actual, expected);
+    }
+}
+
+#[test]
+#[serial]
+fn specify_make_vector() {
+    utils::reset_all_indices();
+
+    let length = utils::Datum::fixnum(2).repr();
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::primop {
+            primop: utils::Primop::make_vector,
+            opands: vec![source::Opand::int64(length)],
+        }),
+    });
+    let actual = p.specify_representation();
+
+    utils::reset_all_indices();
+
+    let ptr = utils::Aloc::fresh();
+    let vector = utils::Aloc::fresh();
+    let set_length = utils::Aloc::fresh();
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(bind(
+            &ptr,
+            source::Value::alloc {
+                opand: source::Opand::int64(
+                    length + utils::VECTOR_BASE_DISPLACEMENT,
+                ),
+            },
+            bind(
+                &vector,
+                source::Value::binop {
+                    binop: utils::Binop::plus,
+                    opand1: aloc(&ptr),
+                    opand2: source::Opand::int64(utils::VECTOR_TAG),
+                },
+                bind(
+                    &set_length,
+                    source::Value::mset {
+                        opand1: aloc(&vector),
+                        opand2: source::Opand::int64(
+                            utils::VECTOR_LENGTH_DISPLACEMENT
+                                - utils::VECTOR_TAG,
+                        ),
+                        opand3: source::Opand::int64(length),
+                    },
+                    source::Value::triv(source::Triv::opand(aloc(&vector))),
+                ),
+            ),
+        )),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn specify_make_vector_of_negative_length() {
+    utils::reset_all_indices();
+
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::primop {
+            primop: utils::Primop::make_vector,
+            opands: vec![source::Opand::int64(
+                utils::Datum::fixnum(-1).repr(),
+            )],
+        }),
+    });
+    let actual = p.specify_representation();
+
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(int64(
+            utils::RuntimeError::out_of_bounds.code(),
+        )),
+    });
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn specify_vector_ref() {
+    utils::reset_all_indices();
+
+    let vector = utils::Aloc::fresh();
+    let index = utils::Aloc::fresh();
+    let p = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(source::Value::primop {
+            primop: utils::Primop::vector_ref,
+            opands: vec![aloc(&vector), aloc(&index)],
+        }),
+    });
+    let actual = p.specify_representation();
+
+    utils::reset_all_indices();
+
+    let vector = utils::Aloc::fresh();
+    let index = utils::Aloc::fresh();
+    let length = utils::Aloc::fresh();
+    let offset = utils::Aloc::fresh();
+    let index_tag = utils::Aloc::fresh();
+    let vector_tag = utils::Aloc::fresh();
+    let out_of_bounds = || int64(utils::RuntimeError::out_of_bounds.code());
+    let type_error = || int64(utils::RuntimeError::type_error.code());
+    let relop = |relop, opand1, opand2, value1, value2| source::Value::r#if {
+        pred: source::Pred::relop {
+            relop,
+            opand1,
+            opand2,
+        },
+        value1: Box::new(value1),
+        value2: Box::new(value2),
+    };
+    let element = bind(
+        &offset,
+        source::Value::binop {
+            binop: utils::Binop::plus,
+            opand1: aloc(&index),
+            opand2: source::Opand::int64(
+                utils::VECTOR_BASE_DISPLACEMENT - utils::VECTOR_TAG,
+            ),
+        },
+        source::Value::mref {
+            opand1: aloc(&vector),
+            opand2: aloc(&offset),
+        },
+    );
+    let in_bounds = bind(
+        &length,
+        source::Value::mref {
+            opand1: aloc(&vector),
+            opand2: source::Opand::int64(
+                utils::VECTOR_LENGTH_DISPLACEMENT - utils::VECTOR_TAG,
+            ),
+        },
+        relop(
+            utils::Relop::lt,
+            aloc(&index),
+            source::Opand::int64(0),
+            out_of_bounds(),
+            relop(
+                utils::Relop::lt,
+                aloc(&index),
+                aloc(&length),
+                element,
+                out_of_bounds(),
+            ),
+        ),
+    );
+    let expected = source::ValuesUniqueLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::value(check_tag(
+            &vector_tag,
+            aloc(&vector),
+            (utils::VECTOR_MASK, utils::VECTOR_TAG),
+            check_tag(
+                &index_tag,
+                aloc(&index),
+                (utils::FIXNUM_MASK, utils::FIXNUM_TAG),
+                in_bounds,
+                type_error(),
+            ),
+            type_error(),
+        )),
+    });
+
+    assert_eq!(actual, expected);
+}
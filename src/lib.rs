@@ -7,12 +7,11 @@ macro_rules! make_begins {
             0 => $end,
             _ => {
                 let t = if let $lang::$atom::begin {
-                    effects: mut t_effects,
+                    effects: t_effects,
                     $t,
                 } = $end
                 {
-                    t_effects.extend($effects);
-                    $effects = t_effects;
+                    $effects.extend(t_effects);
                     $t
                 } else {
                     Box::new($end)
@@ -28,8 +27,8 @@ macro_rules! make_begins {
 }
 
 macro_rules! thrush {
-    ($p:ident => $($phase:expr)=>*) => ({
-        $(let $p = $phase($p)?;)*
+    ($p:ident, $config:ident, $options:ident => $($phase:expr)=>*) => ({
+        $(let $p = $phase($p, $config, $options)?;)*
         Ok($p)
     });
 }
@@ -46,12 +45,73 @@ type Source = crate::imperative_abstractions::Source;
 
 type Target = crate::x64::Target;
 
-pub fn compile(p: Source) -> Result<Target, String> {
+pub fn compile(
+    p: Source,
+    config: &utils::TargetConfig,
+    options: &utils::CompileOptions,
+) -> Result<Target, String> {
+    config.validate()?;
+    let p = p.link_prelude();
     thrush!(
-        p
+        p, config, options
             => imperative_abstractions::compile
             => register_allocation::compile
             => structured_control_flow::compile
             => x64::compile
     )
 }
+
+/// ### Purpose:
+/// Compiles a module of a program of several modules to a Paren-x64 unit,
+/// which link combines with the units of the other modules.
+pub fn compile_module(
+    module: imperative_abstractions::values_lang::Module,
+    config: &utils::TargetConfig,
+    options: &utils::CompileOptions,
+) -> Result<x64::paren_x64::Unit, String> {
+    config.validate()?;
+    let imperative_abstractions::values_lang::Module {
+        name,
+        provides,
+        requires,
+        p,
+    } = module.check_module()?;
+    let symbols = |names: Vec<utils::Name>| {
+        names
+            .into_iter()
+            .map(|name| (name, utils::Label::new_with_name(name)))
+            .collect::<x64::paren_x64::Symbols>()
+    };
+    let exports = symbols(provides);
+    let imports = symbols(requires);
+    let labels = exports.clone().into_iter().chain(imports.clone()).collect();
+    let p = imperative_abstractions::values_lang::ValuesLang(p);
+    let p = imperative_abstractions::compile_with_labels(
+        p,
+        &labels,
+        config,
+        options,
+    )?;
+    let p = register_allocation::compile(p, config, options)?;
+    let p = structured_control_flow::compile(p, config, options)?;
+    let x64::paren_x64::ParenX64(p) = p;
+    Ok(x64::paren_x64::Unit {
+        name,
+        exports,
+        imports,
+        p,
+    })
+}
+
+/// ### Purpose:
+/// Links the units of a program of several modules, the first of which is
+/// the one the program starts in, and compiles the result to x64.
+pub fn link(
+    units: Vec<x64::paren_x64::Unit>,
+    config: &utils::TargetConfig,
+    options: &utils::CompileOptions,
+) -> Result<Target, String> {
+    config.validate()?;
+    let p = x64::paren_x64::ParenX64::link_units(units)?;
+    x64::compile(p, config, options)
+}
@@ -13,9 +13,9 @@ pub enum P {
 #[derive(PartialEq, Eq)]
 #[cfg_attr(test, derive(Debug))]
 pub struct Lambda {
-    label: utils::Label,
-    info: utils::Info<super::target::Loc>,
-    tail: Tail,
+    pub label: utils::Label,
+    pub info: utils::Info<super::target::Loc>,
+    pub tail: Tail,
 }
 
 #[derive(PartialEq, Eq)]
@@ -31,6 +31,17 @@ pub enum Tail {
         tail1: Box<Self>,
         tail2: Box<Self>,
     },
+    /// Runs the tail, which may jump back to its start by the label. The
+    /// label is local to the procedure.
+    r#loop {
+        label: utils::Label,
+        tail: Box<Self>,
+    },
+    /// Jumps to the trg, with the locs holding the values which it reads.
+    jump {
+        trg: Trg,
+        locs: Vec<Rloc>,
+    },
 }
 
 #[derive(PartialEq, Eq)]
@@ -67,15 +78,63 @@ pub enum Effect {
         binop: utils::Binop,
         triv: Triv,
     },
+    /// Sets the aloc to the address of the label.
+    set_aloc_label {
+        aloc: utils::Aloc,
+        label: utils::Label,
+    },
+    /// Reads an argument which the caller passed in the rloc.
+    set_aloc_rloc {
+        aloc: utils::Aloc,
+        rloc: Rloc,
+    },
+    /// Passes an argument to a procedure in the rloc.
+    set_rloc_triv {
+        rloc: Rloc,
+        triv: Triv,
+    },
+    set_aloc_alloc_triv {
+        aloc: utils::Aloc,
+        triv: Triv,
+    },
+    set_aloc_mref_aloc_triv {
+        aloc: utils::Aloc,
+        base: utils::Aloc,
+        index: Triv,
+    },
+    mset_aloc_triv_triv {
+        aloc: utils::Aloc,
+        index: Triv,
+        triv: Triv,
+    },
     begin(Vec<Self>),
     r#if {
         pred: Pred,
         effect1: Box<Self>,
         effect2: Box<Self>,
     },
+    r#while {
+        pred: Pred,
+        effect: Box<Self>,
+    },
+    /// Sets the aloc to a jump table, which holds the address of each label.
+    set_aloc_jump_table {
+        aloc: utils::Aloc,
+        labels: Vec<utils::Label>,
+    },
+    /// Jumps through the table to the label at the index in the aloc, or to
+    /// the last label if the index is outside the table. Each label marks the
+    /// effect at the same position, after which the switch is done.
+    switch {
+        aloc: utils::Aloc,
+        table: utils::Aloc,
+        length: usize,
+        labels: Vec<utils::Label>,
+        effects: Vec<Self>,
+    },
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(test, derive(Debug))]
 pub enum Triv {
     int64(i64),
@@ -94,12 +153,25 @@ pub enum Loc {
     rloc(Rloc),
 }
 
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
 pub enum Rloc {
     reg(utils::Reg),
     fvar(utils::Fvar),
 }
 
+impl From<Rloc> for super::target::Loc {
+    fn from(rloc: Rloc) -> Self {
+        match rloc {
+            Rloc::reg(reg) => Self::reg(reg),
+            Rloc::fvar(fvar) => Self::fvar(fvar),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
 pub enum Trg {
     label(utils::Label),
-    loc(Loc),
+    aloc(utils::Aloc),
 }
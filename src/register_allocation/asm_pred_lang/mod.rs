@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 
 pub use self::data::*;
+use crate::register_allocation::AllocStats;
 use crate::structured_control_flow::nested_asm_lang as target;
 use crate::utils;
 
@@ -17,25 +18,41 @@ impl AsmPredLang {
     /// ### Purpose:
     /// Compiles Asm-lang v2 to Asm-lang v2/locals, analysing which abstract
     /// locations are used in the program and decorating the program with the
-    /// set of variables in an info field.
+    /// set of variables in an info field. Each procedure is decorated with its
+    /// own set.
     pub fn uncover_locals(self) -> Self {
         let Self(p) = self;
 
         fn uncover_p(p: self::P) -> self::P {
             match p {
-                self::P::module { tail, .. } => {
-                    let locals = utils::AlocSet::default();
-                    let locals = uncover_tail(&tail, locals);
-                    let locals = Some(locals);
-                    let info = utils::Info {
-                        locals,
-                        ..Default::default()
-                    };
-                    self::P::module { info, tail }
+                self::P::module { lambdas, tail, .. } => {
+                    let info = uncover_info(&tail);
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, tail, .. }| {
+                            let info = uncover_info(&tail);
+                            self::Lambda { label, info, tail }
+                        })
+                        .collect();
+                    self::P::module {
+                        info,
+                        lambdas,
+                        tail,
+                    }
                 },
             }
         }
 
+        fn uncover_info(tail: &self::Tail) -> utils::Info<target::Loc> {
+            let locals = utils::AlocSet::default();
+            let locals = uncover_tail(tail, locals);
+            let locals = Some(locals);
+            utils::Info {
+                locals,
+                ..Default::default()
+            }
+        }
+
         fn uncover_tail(
             tail: &self::Tail,
             locals: utils::AlocSet,
@@ -44,12 +61,21 @@ impl AsmPredLang {
                 self::Tail::halt(triv) => uncover_triv(triv, locals),
                 self::Tail::begin { effects, tail } => {
                     let locals = uncover_effects(effects, locals);
-                    uncover_tail(&tail, locals)
+                    uncover_tail(tail, locals)
                 },
                 self::Tail::r#if { pred, tail1, tail2 } => {
                     let locals = uncover_pred(pred, locals);
-                    let locals = uncover_tail(&tail1, locals);
-                    uncover_tail(&tail2, locals)
+                    let locals = uncover_tail(tail1, locals);
+                    uncover_tail(tail2, locals)
+                },
+                self::Tail::r#loop { tail, .. } => uncover_tail(tail, locals),
+                self::Tail::jump { trg, .. } => match trg {
+                    self::Trg::label(..) => locals,
+                    self::Trg::aloc(aloc) => {
+                        let mut locals = locals;
+                        locals.insert(aloc.clone());
+                        locals
+                    },
                 },
             }
         }
@@ -59,28 +85,31 @@ impl AsmPredLang {
             locals: utils::AlocSet,
         ) -> utils::AlocSet {
             match pred {
-                self::Pred::relop { .. }
-                | self::Pred::r#true
-                | self::Pred::r#false => locals,
+                self::Pred::relop { aloc, triv, .. } => {
+                    let mut locals = locals;
+                    locals.insert(aloc.clone());
+                    uncover_triv(triv, locals)
+                },
+                self::Pred::r#true | self::Pred::r#false => locals,
                 self::Pred::begin { effects, pred } => {
                     let locals = uncover_effects(effects, locals);
-                    uncover_pred(&pred, locals)
+                    uncover_pred(pred, locals)
                 },
-                self::Pred::not(pred) => uncover_pred(&pred, locals),
+                self::Pred::not(pred) => uncover_pred(pred, locals),
                 self::Pred::r#if {
                     pred1,
                     pred2,
                     pred3,
                 } => {
-                    let locals = uncover_pred(&pred1, locals);
-                    let locals = uncover_pred(&pred2, locals);
-                    uncover_pred(&pred3, locals)
+                    let locals = uncover_pred(pred1, locals);
+                    let locals = uncover_pred(pred2, locals);
+                    uncover_pred(pred3, locals)
                 },
             }
         }
 
         fn uncover_effects(
-            effects: &Vec<self::Effect>,
+            effects: &[self::Effect],
             locals: utils::AlocSet,
         ) -> utils::AlocSet {
             effects
@@ -100,6 +129,28 @@ impl AsmPredLang {
                     locals.insert(aloc.clone());
                     uncover_triv(triv, locals)
                 },
+                self::Effect::set_aloc_alloc_triv { aloc, triv } => {
+                    locals.insert(aloc.clone());
+                    uncover_triv(triv, locals)
+                },
+                self::Effect::set_aloc_label { aloc, .. }
+                | self::Effect::set_aloc_rloc { aloc, .. } => {
+                    locals.insert(aloc.clone());
+                    locals
+                },
+                self::Effect::set_rloc_triv { triv, .. } => {
+                    uncover_triv(triv, locals)
+                },
+                self::Effect::set_aloc_mref_aloc_triv { aloc, base, index } => {
+                    locals.insert(aloc.clone());
+                    locals.insert(base.clone());
+                    uncover_triv(index, locals)
+                },
+                self::Effect::mset_aloc_triv_triv { aloc, index, triv } => {
+                    locals.insert(aloc.clone());
+                    let locals = uncover_triv(index, locals);
+                    uncover_triv(triv, locals)
+                },
                 self::Effect::begin(effects) => {
                     uncover_effects(effects, locals)
                 },
@@ -109,8 +160,26 @@ impl AsmPredLang {
                     effect2,
                 } => {
                     let locals = uncover_pred(pred, locals);
-                    let locals = uncover_effect(&effect1, locals);
-                    uncover_effect(&effect2, locals)
+                    let locals = uncover_effect(effect1, locals);
+                    uncover_effect(effect2, locals)
+                },
+                self::Effect::r#while { pred, effect } => {
+                    let locals = uncover_pred(pred, locals);
+                    uncover_effect(effect, locals)
+                },
+                self::Effect::set_aloc_jump_table { aloc, .. } => {
+                    locals.insert(aloc.clone());
+                    locals
+                },
+                self::Effect::switch {
+                    aloc,
+                    table,
+                    effects,
+                    ..
+                } => {
+                    locals.insert(aloc.clone());
+                    locals.insert(table.clone());
+                    uncover_effects(effects, locals)
                 },
             }
         }
@@ -120,7 +189,7 @@ impl AsmPredLang {
             mut locals: utils::AlocSet,
         ) -> utils::AlocSet {
             match triv {
-                self::Triv::int64(..) => HashSet::new(),
+                self::Triv::int64(..) => locals,
                 self::Triv::aloc(aloc) => {
                     locals.insert(aloc.clone());
                     locals
@@ -132,6 +201,191 @@ impl AsmPredLang {
         Self(p)
     }
 
+    /// ### Purpose:
+    /// Finds the abstract locations which are only ever set to one constant,
+    /// and decorates each procedure with its own in the constants info field.
+    ///
+    /// ### Notes:
+    /// An abstract location which is the destination of a binop, the left
+    /// operand of a relop, or the base of a memory operation is not a
+    /// constant, and neither is the trg of a jump or the index or table of a
+    /// switch, which must all stay locations.
+    pub fn uncover_constants(self) -> Self {
+        let Self(p) = self;
+
+        fn uncover_p(p: self::P) -> self::P {
+            match p {
+                self::P::module {
+                    info,
+                    lambdas,
+                    tail,
+                } => {
+                    let info = uncover_info(info, &tail);
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, info, tail }| {
+                            let info = uncover_info(info, &tail);
+                            self::Lambda { label, info, tail }
+                        })
+                        .collect();
+                    self::P::module {
+                        info,
+                        lambdas,
+                        tail,
+                    }
+                },
+            }
+        }
+
+        fn uncover_info(
+            info: utils::Info<target::Loc>,
+            tail: &self::Tail,
+        ) -> utils::Info<target::Loc> {
+            let constants = constants_tail(tail, HashMap::default())
+                .into_iter()
+                .filter_map(|(aloc, int64)| int64.map(|int64| (aloc, int64)))
+                .collect();
+            let constants = Some(constants);
+            utils::Info { constants, ..info }
+        }
+
+        // Maps each abstract location to the constant it is always set to, or
+        // to `None` if it is set to anything else.
+        fn constants_tail(
+            tail: &self::Tail,
+            constants: HashMap<utils::Aloc, Option<i64>>,
+        ) -> HashMap<utils::Aloc, Option<i64>> {
+            match tail {
+                self::Tail::halt(..) => constants,
+                self::Tail::begin { effects, tail } => {
+                    let constants = constants_effects(effects, constants);
+                    constants_tail(tail, constants)
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let constants = constants_pred(pred, constants);
+                    let constants = constants_tail(tail1, constants);
+                    constants_tail(tail2, constants)
+                },
+                self::Tail::r#loop { tail, .. } => {
+                    constants_tail(tail, constants)
+                },
+                // The trg of a jump must stay a location.
+                self::Tail::jump { trg, .. } => {
+                    let mut constants = constants;
+                    if let self::Trg::aloc(aloc) = trg {
+                        constants.insert(aloc.clone(), None);
+                    }
+                    constants
+                },
+            }
+        }
+
+        fn constants_pred(
+            pred: &self::Pred,
+            mut constants: HashMap<utils::Aloc, Option<i64>>,
+        ) -> HashMap<utils::Aloc, Option<i64>> {
+            match pred {
+                self::Pred::relop { aloc, .. } => {
+                    constants.insert(aloc.clone(), None);
+                    constants
+                },
+                self::Pred::r#true | self::Pred::r#false => constants,
+                self::Pred::not(pred) => constants_pred(pred, constants),
+                self::Pred::begin { effects, pred } => {
+                    let constants = constants_effects(effects, constants);
+                    constants_pred(pred, constants)
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let constants = constants_pred(pred1, constants);
+                    let constants = constants_pred(pred2, constants);
+                    constants_pred(pred3, constants)
+                },
+            }
+        }
+
+        fn constants_effects(
+            effects: &[self::Effect],
+            constants: HashMap<utils::Aloc, Option<i64>>,
+        ) -> HashMap<utils::Aloc, Option<i64>> {
+            effects.iter().fold(constants, |constants, effect| {
+                constants_effect(effect, constants)
+            })
+        }
+
+        fn constants_effect(
+            effect: &self::Effect,
+            mut constants: HashMap<utils::Aloc, Option<i64>>,
+        ) -> HashMap<utils::Aloc, Option<i64>> {
+            match effect {
+                self::Effect::set_aloc_triv {
+                    aloc,
+                    triv: self::Triv::int64(int64),
+                } => {
+                    let int64 = match constants.get(aloc) {
+                        None => Some(*int64),
+                        Some(prev) => prev.filter(|prev| prev == int64),
+                    };
+                    constants.insert(aloc.clone(), int64);
+                    constants
+                },
+                self::Effect::set_aloc_triv { aloc, .. }
+                | self::Effect::set_aloc_binop_aloc_triv { aloc, .. }
+                | self::Effect::set_aloc_alloc_triv { aloc, .. }
+                | self::Effect::mset_aloc_triv_triv { aloc, .. }
+                | self::Effect::set_aloc_label { aloc, .. }
+                | self::Effect::set_aloc_rloc { aloc, .. } => {
+                    constants.insert(aloc.clone(), None);
+                    constants
+                },
+                self::Effect::set_rloc_triv { .. } => constants,
+                // The base of a memory operation must stay a location.
+                self::Effect::set_aloc_mref_aloc_triv { aloc, base, .. } => {
+                    constants.insert(aloc.clone(), None);
+                    constants.insert(base.clone(), None);
+                    constants
+                },
+                self::Effect::begin(effects) => {
+                    constants_effects(effects, constants)
+                },
+                self::Effect::r#if {
+                    pred,
+                    effect1,
+                    effect2,
+                } => {
+                    let constants = constants_pred(pred, constants);
+                    let constants = constants_effect(effect1, constants);
+                    constants_effect(effect2, constants)
+                },
+                self::Effect::r#while { pred, effect } => {
+                    let constants = constants_pred(pred, constants);
+                    constants_effect(effect, constants)
+                },
+                self::Effect::set_aloc_jump_table { aloc, .. } => {
+                    constants.insert(aloc.clone(), None);
+                    constants
+                },
+                // The index and the table of a jump must stay locations.
+                self::Effect::switch {
+                    aloc,
+                    table,
+                    effects,
+                    ..
+                } => {
+                    constants.insert(aloc.clone(), None);
+                    constants.insert(table.clone(), None);
+                    constants_effects(effects, constants)
+                },
+            }
+        }
+
+        let p = uncover_p(p);
+        Self(p)
+    }
+
     /// ### Purpose:
     /// Compiles Asm-lang v2/locals to Asm-lang v2/assignments, by assigning
     /// each abstract location from the locals info field to a fresh frame
@@ -141,7 +395,11 @@ impl AsmPredLang {
 
         fn assign_p(p: self::P) -> self::P {
             match p {
-                self::P::module { info, tail } => {
+                self::P::module {
+                    info,
+                    lambdas,
+                    tail,
+                } => {
                     let utils::Info { locals, .. } = info;
                     let locals = locals.unwrap();
                     let mut locals_as_vec = locals.iter().collect::<Vec<_>>();
@@ -163,7 +421,11 @@ impl AsmPredLang {
                         ..info /* undead_out,
                                 * conflicts, */
                     };
-                    self::P::module { info, tail }
+                    self::P::module {
+                        info,
+                        lambdas,
+                        tail,
+                    }
                 },
             }
         }
@@ -175,28 +437,71 @@ impl AsmPredLang {
     /// ### Purpose:
     /// Performs undeadness analysis, decorating the program with undead-set
     /// tree. Only the info field of the program is modified.
+    ///
+    /// ### Notes:
+    /// The undead-set tree of an if holds the node of its pred, followed by
+    /// the nodes of its two branches. The pred is reached from the alocs
+    /// undead on entry to either branch. A constant pred has the node of the
+    /// alocs undead after it, like any other pred.
+    ///
+    /// The undead-set tree of a while holds the node of its pred, followed by
+    /// the node of its effect. Both are
+    /// computed from the fixpoint of the loop, so an aloc which is used in a
+    /// later iteration is undead throughout the effect.
+    ///
+    /// The undead-set tree of a switch holds the node of each of its effects,
+    /// followed by the set of alocs undead after the jump through the table.
+    ///
+    /// The undead-set tree of a loop holds the node of its tail, computed from
+    /// the fixpoint of the loop like a while. A jump has the node of the alocs
+    /// undead after it; before it, its trg is undead, along with the alocs
+    /// undead on entry to the loop which it jumps back to, if any.
     pub fn undead_analysis(self) -> Self {
         let Self(p) = self;
 
         fn undead_p(p: self::P) -> self::P {
-            let ust = utils::Tree::new();
             match p {
-                self::P::module { info, tail } => {
-                    let last = utils::AlocSet::default();
-                    let (utils::Tree { nodes }, _) =
-                        undead_tail(&tail, ust, last);
-                    let undead_out = nodes.get(0).unwrap().clone();
-                    let undead_out = Some(undead_out);
-                    let info = utils::Info { undead_out, ..info };
-                    self::P::module { info, tail }
+                self::P::module {
+                    info,
+                    lambdas,
+                    tail,
+                } => {
+                    let info = undead_info(info, &tail);
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, info, tail }| {
+                            let info = undead_info(info, &tail);
+                            self::Lambda { label, info, tail }
+                        })
+                        .collect();
+                    self::P::module {
+                        info,
+                        lambdas,
+                        tail,
+                    }
                 },
             }
         }
 
+        fn undead_info(
+            info: utils::Info<target::Loc>,
+            tail: &self::Tail,
+        ) -> utils::Info<target::Loc> {
+            let ust = utils::Tree::new();
+            let last = utils::AlocSet::default();
+            let loops = HashMap::default();
+            let (utils::Tree { nodes }, _) =
+                undead_tail(tail, ust, last, &loops);
+            let undead_out = nodes.first().unwrap().clone();
+            let undead_out = Some(undead_out);
+            utils::Info { undead_out, ..info }
+        }
+
         fn undead_tail(
             tail: &self::Tail,
             mut ust: utils::Tree,
             last: utils::AlocSet,
+            loops: &HashMap<utils::Label, utils::AlocSet>,
         ) -> (utils::Tree, utils::AlocSet) {
             match tail {
                 self::Tail::halt(triv) => {
@@ -209,7 +514,8 @@ impl AsmPredLang {
                 },
                 self::Tail::begin { effects, tail } => {
                     let sub_ust = utils::Tree::new();
-                    let (sub_ust, last) = undead_tail(&tail, sub_ust, last);
+                    let (sub_ust, last) =
+                        undead_tail(tail, sub_ust, last, loops);
                     let (sub_ust, last) =
                         undead_effects(effects, sub_ust, last);
                     let node = utils::Node::tree { tree: sub_ust };
@@ -217,17 +523,78 @@ impl AsmPredLang {
                     (ust, last)
                 },
                 self::Tail::r#if { pred, tail1, tail2 } => {
-                    let sub_ust = utils::Tree::new();
-                    let (sub_ust, last) = undead_pred(pred, sub_ust, last);
-                    let (sub_ust, last) = undead_tail(&tail1, sub_ust, last);
-                    let (sub_ust, last) = undead_tail(&tail2, sub_ust, last);
+                    let (ust1, last1) = undead_tail(
+                        tail1,
+                        utils::Tree::new(),
+                        last.clone(),
+                        loops,
+                    );
+                    let (ust2, last2) =
+                        undead_tail(tail2, utils::Tree::new(), last, loops);
+                    let last = last1.union(&last2).cloned().collect();
+                    let (sub_ust, last) =
+                        undead_pred(pred, utils::Tree::new(), last);
+                    ust.push_on(undead_if(sub_ust, ust1, ust2));
+                    (ust, last)
+                },
+                // The tail is reached both on entry and along each jump back
+                // to the label, so what is undead before it is found by
+                // iterating to a fixpoint.
+                self::Tail::r#loop { label, tail } => {
+                    let undead_loop = |entry: utils::AlocSet, ust| {
+                        let mut loops = loops.clone();
+                        loops.insert(label.clone(), entry);
+                        undead_tail(tail, ust, last.clone(), &loops)
+                    };
+                    let mut entry = utils::AlocSet::default();
+                    loop {
+                        let (_, next) =
+                            undead_loop(entry.clone(), utils::Tree::new());
+                        match next.is_subset(&entry) {
+                            true => break,
+                            false => entry.extend(next),
+                        }
+                    }
+                    let (sub_ust, last) =
+                        undead_loop(entry, utils::Tree::new());
                     let node = utils::Node::tree { tree: sub_ust };
                     ust.push_on(node);
                     (ust, last)
                 },
+                self::Tail::jump { trg, .. } => {
+                    let node = utils::Node::alocs {
+                        alocs: last.clone(),
+                    };
+                    ust.push_on(node);
+                    let mut last = last;
+                    match trg {
+                        self::Trg::label(label) => {
+                            if let Some(entry) = loops.get(label) {
+                                last.extend(entry.iter().cloned());
+                            }
+                        },
+                        self::Trg::aloc(aloc) => {
+                            last.insert(aloc.clone());
+                        },
+                    };
+                    (ust, last)
+                },
             }
         }
 
+        fn undead_if(
+            pred: utils::Tree,
+            branch1: utils::Tree,
+            branch2: utils::Tree,
+        ) -> utils::Node {
+            let nodes = vec![pred, branch1, branch2]
+                .into_iter()
+                .flat_map(|utils::Tree { nodes }| nodes)
+                .collect();
+            let tree = utils::Tree { nodes };
+            utils::Node::tree { tree }
+        }
+
         fn undead_pred(
             pred: &self::Pred,
             mut ust: utils::Tree,
@@ -236,26 +603,34 @@ impl AsmPredLang {
             match pred {
                 self::Pred::begin { effects, pred } => {
                     let sub_ust = utils::Tree::new();
+                    let (sub_ust, last) = undead_pred(pred, sub_ust, last);
                     let (sub_ust, last) =
                         undead_effects(effects, sub_ust, last);
-                    let (sub_ust, last) = undead_pred(&pred, sub_ust, last);
                     let node = utils::Node::tree { tree: sub_ust };
                     ust.push_on(node);
                     (ust, last)
                 },
-                self::Pred::r#true | self::Pred::r#false => (ust, last),
-                self::Pred::not(pred) => undead_pred(&pred, ust, last),
+                self::Pred::r#true | self::Pred::r#false => {
+                    let node = utils::Node::alocs {
+                        alocs: last.clone(),
+                    };
+                    ust.push_on(node);
+                    (ust, last)
+                },
+                self::Pred::not(pred) => undead_pred(pred, ust, last),
                 self::Pred::r#if {
                     pred1,
                     pred2,
                     pred3,
                 } => {
-                    let sub_ust = utils::Tree::new();
-                    let (sub_ust, last) = undead_pred(&pred1, sub_ust, last);
-                    let (sub_ust, last) = undead_pred(&pred2, sub_ust, last);
-                    let (sub_ust, last) = undead_pred(&pred3, sub_ust, last);
-                    let node = utils::Node::tree { tree: sub_ust };
-                    ust.push_on(node);
+                    let (ust2, last2) =
+                        undead_pred(pred2, utils::Tree::new(), last.clone());
+                    let (ust3, last3) =
+                        undead_pred(pred3, utils::Tree::new(), last);
+                    let last = last2.union(&last3).cloned().collect();
+                    let (ust1, last) =
+                        undead_pred(pred1, utils::Tree::new(), last);
+                    ust.push_on(undead_if(ust1, ust2, ust3));
                     (ust, last)
                 },
 
@@ -264,7 +639,7 @@ impl AsmPredLang {
                         alocs: last.clone(),
                     };
                     ust.push_on(node);
-                    last.remove(aloc);
+                    last.insert(aloc.clone());
                     let last = undead_triv(triv, last);
                     (ust, last)
                 },
@@ -272,11 +647,11 @@ impl AsmPredLang {
         }
 
         fn undead_effects(
-            effects: &Vec<self::Effect>,
+            effects: &[self::Effect],
             ust: utils::Tree,
             last: utils::AlocSet,
         ) -> (utils::Tree, utils::AlocSet) {
-            effects.into_iter().rev().fold(
+            effects.iter().rev().fold(
                 (ust, last),
                 |(curr_ust, curr_last), effect| {
                     undead_effect(effect, curr_ust, curr_last)
@@ -299,14 +674,61 @@ impl AsmPredLang {
                     let last = undead_triv(triv, last);
                     (ust, last)
                 },
+                // The aloc is both read and written by the binop.
                 self::Effect::set_aloc_binop_aloc_triv {
                     aloc, triv, ..
                 } => {
+                    let node = utils::Node::alocs {
+                        alocs: last.clone(),
+                    };
+                    ust.push_on(node);
+                    last.insert(aloc.clone());
+                    let last = undead_triv(triv, last);
+                    (ust, last)
+                },
+                self::Effect::set_aloc_alloc_triv { aloc, triv } => {
+                    let node = utils::Node::alocs {
+                        alocs: last.clone(),
+                    };
+                    ust.push_on(node);
+                    last.remove(aloc);
+                    let last = undead_triv(triv, last);
+                    (ust, last)
+                },
+                self::Effect::set_aloc_label { aloc, .. }
+                | self::Effect::set_aloc_rloc { aloc, .. } => {
+                    let node = utils::Node::alocs {
+                        alocs: last.clone(),
+                    };
+                    ust.push_on(node);
+                    last.remove(aloc);
+                    (ust, last)
+                },
+                self::Effect::set_rloc_triv { triv, .. } => {
+                    let node = utils::Node::alocs {
+                        alocs: last.clone(),
+                    };
+                    ust.push_on(node);
+                    let last = undead_triv(triv, last);
+                    (ust, last)
+                },
+                self::Effect::set_aloc_mref_aloc_triv { aloc, base, index } => {
                     let node = utils::Node::alocs {
                         alocs: last.clone(),
                     };
                     ust.push_on(node);
                     last.remove(aloc);
+                    last.insert(base.clone());
+                    let last = undead_triv(index, last);
+                    (ust, last)
+                },
+                self::Effect::mset_aloc_triv_triv { aloc, index, triv } => {
+                    let node = utils::Node::alocs {
+                        alocs: last.clone(),
+                    };
+                    ust.push_on(node);
+                    last.insert(aloc.clone());
+                    let last = undead_triv(index, last);
                     let last = undead_triv(triv, last);
                     (ust, last)
                 },
@@ -324,16 +746,79 @@ impl AsmPredLang {
                     effect1,
                     effect2,
                 } => {
-                    let sub_ust = utils::Tree::new();
-                    let (sub_ust, last) = undead_pred(pred, sub_ust, last);
+                    let (ust1, last1) = undead_effect(
+                        effect1,
+                        utils::Tree::new(),
+                        last.clone(),
+                    );
+                    let (ust2, last2) =
+                        undead_effect(effect2, utils::Tree::new(), last);
+                    let last = last1.union(&last2).cloned().collect();
                     let (sub_ust, last) =
-                        undead_effect(&effect1, sub_ust, last);
+                        undead_pred(pred, utils::Tree::new(), last);
+                    ust.push_on(undead_if(sub_ust, ust1, ust2));
+                    (ust, last)
+                },
+                // The pred is reached both on entry and along the back-edge
+                // from the end of the effect, so what is undead before it
+                // is found by iterating to a fixpoint.
+                self::Effect::r#while { pred, effect } => {
+                    let undead_loop = |entry: utils::AlocSet, ust| {
+                        let (ust, body) = undead_effect(effect, ust, entry);
+                        let after = body.union(&last).cloned().collect();
+                        undead_pred(pred, ust, after)
+                    };
+                    let mut entry = utils::AlocSet::default();
+                    loop {
+                        let (_, next) =
+                            undead_loop(entry.clone(), utils::Tree::new());
+                        match next.is_subset(&entry) {
+                            true => break,
+                            false => entry.extend(next),
+                        }
+                    }
                     let (sub_ust, last) =
-                        undead_effect(&effect2, sub_ust, last);
+                        undead_loop(entry, utils::Tree::new());
                     let node = utils::Node::tree { tree: sub_ust };
                     ust.push_on(node);
                     (ust, last)
                 },
+                self::Effect::set_aloc_jump_table { aloc, .. } => {
+                    let node = utils::Node::alocs {
+                        alocs: last.clone(),
+                    };
+                    ust.push_on(node);
+                    last.remove(aloc);
+                    (ust, last)
+                },
+                // Every effect is reached by the jump through the table, which
+                // overwrites the aloc. The node of the jump follows the nodes
+                // of the effects.
+                self::Effect::switch {
+                    aloc,
+                    table,
+                    effects,
+                    ..
+                } => {
+                    let (mut sub_ust, mut undead) = effects.iter().rev().fold(
+                        (utils::Tree::new(), utils::AlocSet::default()),
+                        |(sub_ust, mut undead), effect| {
+                            let (sub_ust, entry) =
+                                undead_effect(effect, sub_ust, last.clone());
+                            undead.extend(entry);
+                            (sub_ust, undead)
+                        },
+                    );
+                    let node = utils::Node::alocs {
+                        alocs: undead.clone(),
+                    };
+                    sub_ust.nodes.push(node);
+                    let node = utils::Node::tree { tree: sub_ust };
+                    ust.push_on(node);
+                    undead.insert(aloc.clone());
+                    undead.insert(table.clone());
+                    (ust, undead)
+                },
             }
         }
 
@@ -363,34 +848,55 @@ impl AsmPredLang {
         fn conf_p(p: self::P) -> self::P {
             match p {
                 self::P::module {
-                    info:
-                        utils::Info {
-                            undead_out,
-                            locals,
-                            assignment,
-                            ..
-                        },
+                    info,
+                    lambdas,
                     tail,
                 } => {
-                    let locals = locals.unwrap();
-                    let undead_out = undead_out.unwrap();
-                    let conflicts = utils::Graph::new(&locals);
-                    let conflicts = conf_tail(&tail, &undead_out, conflicts);
-                    let locals = Some(locals);
-                    let undead_out = Some(undead_out);
-                    let conflicts = Some(conflicts);
-                    let info = utils::Info {
-                        undead_out,
-                        locals,
-                        conflicts,
-                        assignment,
-                    };
-
-                    self::P::module { info, tail }
+                    let info = conf_info(info, &tail);
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, info, tail }| {
+                            let info = conf_info(info, &tail);
+                            self::Lambda { label, info, tail }
+                        })
+                        .collect();
+                    self::P::module {
+                        info,
+                        lambdas,
+                        tail,
+                    }
                 },
             }
         }
 
+        fn conf_info(
+            utils::Info {
+                undead_out,
+                locals,
+                assignment,
+                constants,
+                rematerialised,
+                ..
+            }: utils::Info<target::Loc>,
+            tail: &self::Tail,
+        ) -> utils::Info<target::Loc> {
+            let locals = locals.unwrap();
+            let undead_out = undead_out.unwrap();
+            let conflicts = utils::Graph::new(&locals);
+            let conflicts = conf_tail(tail, &undead_out, conflicts);
+            let locals = Some(locals);
+            let undead_out = Some(undead_out);
+            let conflicts = Some(conflicts);
+            utils::Info {
+                undead_out,
+                locals,
+                conflicts,
+                assignment,
+                constants,
+                rematerialised,
+            }
+        }
+
         fn conf_tail(
             tail: &self::Tail,
             ust: &utils::Node,
@@ -399,14 +905,21 @@ impl AsmPredLang {
             match tail {
                 self::Tail::halt { .. } => conflicts,
                 self::Tail::begin { effects, tail } => {
-                    let conflicts = conf_tail(&tail, ust, conflicts);
+                    let node = ust.to_tree_panic().last().unwrap();
+                    let conflicts = conf_tail(tail, node, conflicts);
                     conf_effects(effects, ust, conflicts)
                 },
                 self::Tail::r#if { pred, tail1, tail2 } => {
-                    let conflicts = conf_pred(pred, ust, conflicts);
-                    let conflicts = conf_tail(tail1, ust, conflicts);
-                    conf_tail(tail2, ust, conflicts)
+                    let nodes = ust.to_tree_panic();
+                    let conflicts = conf_pred(pred, &nodes[0], conflicts);
+                    let conflicts = conf_tail(tail1, &nodes[1], conflicts);
+                    conf_tail(tail2, &nodes[2], conflicts)
+                },
+                self::Tail::r#loop { tail, .. } => {
+                    let node = ust.to_tree_panic().first().unwrap();
+                    conf_tail(tail, node, conflicts)
                 },
+                self::Tail::jump { .. } => conflicts,
             }
         }
 
@@ -417,20 +930,22 @@ impl AsmPredLang {
         ) -> utils::Graph {
             match pred {
                 self::Pred::begin { effects, pred } => {
+                    let node = ust.to_tree_panic().last().unwrap();
                     let conflicts = conf_effects(effects, ust, conflicts);
-                    conf_pred(pred, ust, conflicts)
+                    conf_pred(pred, node, conflicts)
                 },
                 self::Pred::r#if {
                     pred1,
                     pred2,
                     pred3,
                 } => {
-                    let conflicts = conf_pred(&pred1, ust, conflicts);
-                    let conflicts = conf_pred(&pred2, ust, conflicts);
-                    conf_pred(&pred3, ust, conflicts)
+                    let nodes = ust.to_tree_panic();
+                    let conflicts = conf_pred(pred1, &nodes[0], conflicts);
+                    let conflicts = conf_pred(pred2, &nodes[1], conflicts);
+                    conf_pred(pred3, &nodes[2], conflicts)
                 },
                 self::Pred::r#true | self::Pred::r#false => conflicts,
-                self::Pred::not(pred) => conf_pred(&pred, ust, conflicts),
+                self::Pred::not(pred) => conf_pred(pred, ust, conflicts),
                 self::Pred::relop { aloc, triv, .. } => {
                     let mut alocs = ust.to_alocs_panic().clone();
                     alocs.remove(aloc);
@@ -442,12 +957,12 @@ impl AsmPredLang {
         }
 
         fn conf_effects(
-            effects: &Vec<self::Effect>,
+            effects: &[self::Effect],
             ust: &utils::Node,
             conflicts: utils::Graph,
         ) -> utils::Graph {
             let nodes = ust.to_tree_panic();
-            effects.into_iter().zip(nodes).fold(
+            effects.iter().zip(nodes).fold(
                 conflicts,
                 |curr_conflicts, (effect, node)| {
                     conf_effect(effect, node, curr_conflicts)
@@ -477,6 +992,17 @@ impl AsmPredLang {
                     conflicts.insert_alocs(aloc.clone(), alocs);
                     conflicts
                 },
+                self::Effect::set_aloc_alloc_triv { aloc, .. }
+                | self::Effect::set_aloc_mref_aloc_triv { aloc, .. }
+                | self::Effect::set_aloc_label { aloc, .. }
+                | self::Effect::set_aloc_rloc { aloc, .. } => {
+                    let mut alocs = ust.to_alocs_panic().clone();
+                    alocs.remove(aloc);
+                    conflicts.insert_alocs(aloc.clone(), alocs);
+                    conflicts
+                },
+                self::Effect::set_rloc_triv { .. } => conflicts,
+                self::Effect::mset_aloc_triv_triv { .. } => conflicts,
                 self::Effect::begin(effects) => {
                     conf_effects(effects, ust, conflicts)
                 },
@@ -485,9 +1011,44 @@ impl AsmPredLang {
                     effect1,
                     effect2,
                 } => {
-                    let conflicts = conf_pred(pred, ust, conflicts);
-                    let conflicts = conf_effect(effect1, ust, conflicts);
-                    conf_effect(effect2, ust, conflicts)
+                    let nodes = ust.to_tree_panic();
+                    let conflicts = conf_pred(pred, &nodes[0], conflicts);
+                    let conflicts = conf_effect(effect1, &nodes[1], conflicts);
+                    conf_effect(effect2, &nodes[2], conflicts)
+                },
+                self::Effect::r#while { pred, effect } => {
+                    let nodes = ust.to_tree_panic();
+                    let node = nodes.last().unwrap();
+                    let conflicts = conf_effect(effect, node, conflicts);
+                    let node = nodes.first().unwrap();
+                    conf_pred(pred, node, conflicts)
+                },
+                self::Effect::set_aloc_jump_table { aloc, .. } => {
+                    let mut alocs = ust.to_alocs_panic().clone();
+                    alocs.remove(aloc);
+                    conflicts.insert_alocs(aloc.clone(), alocs);
+                    conflicts
+                },
+                // The jump overwrites the aloc while the table is still read.
+                self::Effect::switch {
+                    aloc,
+                    table,
+                    effects,
+                    ..
+                } => {
+                    let nodes = ust.to_tree_panic();
+                    let mut conflicts = effects.iter().zip(nodes).fold(
+                        conflicts,
+                        |conflicts, (effect, node)| {
+                            conf_effect(effect, node, conflicts)
+                        },
+                    );
+                    let mut alocs =
+                        nodes.last().unwrap().to_alocs_panic().clone();
+                    alocs.insert(table.clone());
+                    alocs.remove(aloc);
+                    conflicts.insert_alocs(aloc.clone(), alocs);
+                    conflicts
                 },
             }
         }
@@ -514,17 +1075,32 @@ impl AsmPredLang {
     /// each of the abstract location declared in the locals set into a
     /// register, and if one cannot be found, assigns it a frame variable
     /// instead.
-    pub fn assign_registers(self) -> Self {
+    ///
+    /// ### Notes:
+    /// An abstract location in the constants info field, which
+    /// uncover-constants fills in, is not spilled. It is recorded in the
+    /// rematerialised info field instead, and replace-locations re-emits the
+    /// constant at each of its uses.
+    ///
+    /// Each procedure is allocated on its own. The physical locations through
+    /// which a procedure passes or receives arguments are never assigned to
+    /// its abstract locations, so that no argument is overwritten before it is
+    /// read.
+    ///
+    /// The locals and conflicts info fields are kept alongside the
+    /// assignment, so that alloc-stats and conflicts-to-dot can still read
+    /// them afterwards.
+    pub fn assign_registers(self, config: &utils::TargetConfig) -> Self {
         let Self(p) = self;
 
         fn get_assignable_registers_from_assignments(
             assignments: &utils::Assignments<target::Loc>,
-            current_assignable_registers: &HashSet<utils::Reg>,
+            assignable_registers: &[utils::Reg],
             _: usize,
         ) -> Vec<utils::Reg> {
             let locs = assignments.values().collect::<HashSet<&target::Loc>>();
-            let registers = current_assignable_registers
-                .into_iter()
+            let registers = assignable_registers
+                .iter()
                 .filter_map(|reg| {
                     let reg = *reg;
                     let loc = target::Loc::reg(reg);
@@ -542,7 +1118,7 @@ impl AsmPredLang {
         fn lowest_order_aloc(
             utils::Graph { graph }: &utils::Graph,
         ) -> utils::Aloc {
-            let mut conflicts = graph.into_iter().collect::<Vec<_>>();
+            let mut conflicts = graph.iter().collect::<Vec<_>>();
             conflicts.sort_by(|(_, alocs1), (_, alocs2)| {
                 let length1 = alocs1.len();
                 let length2 = alocs2.len();
@@ -552,37 +1128,158 @@ impl AsmPredLang {
             (*aloc).clone()
         }
 
-        fn assign_p(p: self::P) -> self::P {
-            let (current_assignable_registers, k) = {
-                let registers = utils::Reg::current_assignable_registers();
-                let length = registers.len();
-                (registers, length)
-            };
-
+        fn assign_p(p: self::P, config: &utils::TargetConfig) -> self::P {
             match p {
-                self::P::module { info, tail } => {
-                    let utils::Info {
-                        locals, conflicts, ..
-                    } = info;
-                    let locals = locals.unwrap();
-                    let conflicts = conflicts.unwrap();
-                    let assignment =
-                        HashMap::<utils::Aloc, target::Loc>::default();
-                    let assignment = recursive_assign(
-                        locals,
-                        conflicts,
-                        assignment,
-                        &current_assignable_registers,
-                        k,
-                    );
-                    let assignment = Some(assignment);
-                    let info = utils::Info {
-                        locals: None,
-                        conflicts: None,
-                        assignment,
-                        ..info
-                    };
-                    self::P::module { info, tail }
+                self::P::module {
+                    info,
+                    lambdas,
+                    tail,
+                } => {
+                    let info = assign_info(info, &tail, config);
+                    let lambdas = lambdas
+                        .into_iter()
+                        .map(|self::Lambda { label, info, tail }| {
+                            let info = assign_info(info, &tail, config);
+                            self::Lambda { label, info, tail }
+                        })
+                        .collect();
+                    self::P::module {
+                        info,
+                        lambdas,
+                        tail,
+                    }
+                },
+            }
+        }
+
+        fn assign_info(
+            info: utils::Info<target::Loc>,
+            tail: &self::Tail,
+            config: &utils::TargetConfig,
+        ) -> utils::Info<target::Loc> {
+            let rlocs = rlocs_tail(tail, HashSet::default());
+            let assignable_registers = config
+                .assignable_registers
+                .iter()
+                .copied()
+                .filter(|reg| !rlocs.contains(&target::Loc::reg(*reg)))
+                .collect::<Vec<_>>();
+            let k = assignable_registers.len();
+            let locals = info.locals.clone().unwrap();
+            let conflicts = info.conflicts.clone().unwrap();
+            let constants = info.constants.clone().unwrap_or_default();
+            let assignment = HashMap::<utils::Aloc, target::Loc>::default();
+            let rematerialised = HashMap::<utils::Aloc, i64>::default();
+            let (assignment, rematerialised) = recursive_assign(
+                locals,
+                conflicts,
+                (assignment, rematerialised),
+                &constants,
+                (&assignable_registers, &rlocs),
+                k,
+            );
+            let assignment = Some(assignment);
+            let rematerialised = Some(rematerialised);
+            utils::Info {
+                assignment,
+                rematerialised,
+                ..info
+            }
+        }
+
+        // Collects the physical locations which the body reads or writes.
+        fn rlocs_tail(
+            tail: &self::Tail,
+            rlocs: HashSet<target::Loc>,
+        ) -> HashSet<target::Loc> {
+            match tail {
+                self::Tail::halt(..) => rlocs,
+                self::Tail::begin { effects, tail } => {
+                    let rlocs = rlocs_effects(effects, rlocs);
+                    rlocs_tail(tail, rlocs)
+                },
+                self::Tail::r#if { pred, tail1, tail2 } => {
+                    let rlocs = rlocs_pred(pred, rlocs);
+                    let rlocs = rlocs_tail(tail1, rlocs);
+                    rlocs_tail(tail2, rlocs)
+                },
+                self::Tail::r#loop { tail, .. } => rlocs_tail(tail, rlocs),
+                self::Tail::jump { locs, .. } => {
+                    locs.iter().fold(rlocs, |mut rlocs, rloc| {
+                        rlocs.insert(rloc.clone().into());
+                        rlocs
+                    })
+                },
+            }
+        }
+
+        fn rlocs_pred(
+            pred: &self::Pred,
+            rlocs: HashSet<target::Loc>,
+        ) -> HashSet<target::Loc> {
+            match pred {
+                self::Pred::relop { .. }
+                | self::Pred::r#true
+                | self::Pred::r#false => rlocs,
+                self::Pred::not(pred) => rlocs_pred(pred, rlocs),
+                self::Pred::begin { effects, pred } => {
+                    let rlocs = rlocs_effects(effects, rlocs);
+                    rlocs_pred(pred, rlocs)
+                },
+                self::Pred::r#if {
+                    pred1,
+                    pred2,
+                    pred3,
+                } => {
+                    let rlocs = rlocs_pred(pred1, rlocs);
+                    let rlocs = rlocs_pred(pred2, rlocs);
+                    rlocs_pred(pred3, rlocs)
+                },
+            }
+        }
+
+        fn rlocs_effects(
+            effects: &[self::Effect],
+            rlocs: HashSet<target::Loc>,
+        ) -> HashSet<target::Loc> {
+            effects
+                .iter()
+                .fold(rlocs, |rlocs, effect| rlocs_effect(effect, rlocs))
+        }
+
+        fn rlocs_effect(
+            effect: &self::Effect,
+            mut rlocs: HashSet<target::Loc>,
+        ) -> HashSet<target::Loc> {
+            match effect {
+                self::Effect::set_aloc_rloc { rloc, .. }
+                | self::Effect::set_rloc_triv { rloc, .. } => {
+                    rlocs.insert(rloc.clone().into());
+                    rlocs
+                },
+                self::Effect::set_aloc_triv { .. }
+                | self::Effect::set_aloc_binop_aloc_triv { .. }
+                | self::Effect::set_aloc_label { .. }
+                | self::Effect::set_aloc_alloc_triv { .. }
+                | self::Effect::set_aloc_mref_aloc_triv { .. }
+                | self::Effect::mset_aloc_triv_triv { .. }
+                | self::Effect::set_aloc_jump_table { .. } => rlocs,
+                self::Effect::begin(effects)
+                | self::Effect::switch { effects, .. } => {
+                    rlocs_effects(effects, rlocs)
+                },
+                self::Effect::r#if {
+                    pred,
+                    effect1,
+                    effect2,
+                } => {
+                    let rlocs = rlocs_pred(pred, rlocs);
+                    let rlocs = rlocs_effect(effect1, rlocs);
+                    rlocs_effect(effect2, rlocs)
+                },
+                self::Effect::r#while { pred, effect } => {
+                    let rlocs = rlocs_pred(pred, rlocs);
+                    rlocs_effect(effect, rlocs)
                 },
             }
         }
@@ -590,10 +1287,18 @@ impl AsmPredLang {
         fn recursive_assign(
             mut locals: utils::AlocSet,
             mut conflicts: utils::Graph,
-            assignments: utils::Assignments<target::Loc>,
-            current_assignable_registers: &HashSet<utils::Reg>,
+            assignments: (
+                utils::Assignments<target::Loc>,
+                HashMap<utils::Aloc, i64>,
+            ),
+            constants: &HashMap<utils::Aloc, i64>,
+            (assignable_registers, rlocs): (
+                &[utils::Reg],
+                &HashSet<target::Loc>,
+            ),
             k: usize,
-        ) -> utils::Assignments<target::Loc> {
+        ) -> (utils::Assignments<target::Loc>, HashMap<utils::Aloc, i64>)
+        {
             let empty_locals = locals.is_empty();
             match empty_locals {
                 true => assignments,
@@ -601,28 +1306,43 @@ impl AsmPredLang {
                     let aloc = lowest_order_aloc(&conflicts);
                     locals.remove(&aloc);
                     conflicts.remove_node(&aloc);
-                    let mut assignments = recursive_assign(
-                        locals,
-                        conflicts,
-                        assignments,
-                        current_assignable_registers,
-                        k,
-                    );
-                    let loc = get_assignable_registers_from_assignments(
+                    let (mut assignments, mut rematerialised) =
+                        recursive_assign(
+                            locals,
+                            conflicts,
+                            assignments,
+                            constants,
+                            (assignable_registers, rlocs),
+                            k,
+                        );
+                    let reg = get_assignable_registers_from_assignments(
                         &assignments,
-                        &current_assignable_registers,
+                        assignable_registers,
                         k,
                     )
-                    .get(0)
-                    .map(|reg| target::Loc::reg(*reg))
-                    .unwrap_or(target::Loc::fvar(utils::Fvar::fresh()));
-                    assignments.insert(aloc, loc);
-                    assignments
+                    .first()
+                    .copied();
+                    match (reg, constants.get(&aloc)) {
+                        (Some(reg), _) => {
+                            assignments.insert(aloc, target::Loc::reg(reg));
+                        },
+                        (None, Some(int64)) => {
+                            rematerialised.insert(aloc, *int64);
+                        },
+                        (None, None) => {
+                            let loc = std::iter::repeat_with(utils::Fvar::fresh)
+                                .map(target::Loc::fvar)
+                                .find(|loc| !rlocs.contains(loc))
+                                .unwrap();
+                            assignments.insert(aloc, loc);
+                        },
+                    };
+                    (assignments, rematerialised)
                 },
             }
         }
 
-        let p = assign_p(p);
+        let p = assign_p(p, config);
         Self(p)
     }
 
@@ -630,58 +1350,150 @@ impl AsmPredLang {
     /// Compiles Asm-lang v2/assignments to Nested-asm-lang v2, replaced each
     /// abstract location with its assigned physical location from the
     /// assignment info field.
+    ///
+    /// ### Notes:
+    /// Each loop becomes a procedure of its own, which is jumped to in place of
+    /// the loop.
     pub fn replace_locations(self) -> target::NestedAsmLang {
+        let (p, _) = self.replace_locations_with_moves();
+        p
+    }
+
+    /// ### Purpose:
+    /// Performs replace-locations, returning the number of moves it drops
+    /// alongside the program. A move is dropped when its source and
+    /// destination were assigned the same location.
+    pub fn replace_locations_with_moves(
+        self,
+    ) -> (target::NestedAsmLang, usize) {
         let Self(p) = self;
 
-        fn replace_p(p: self::P) -> target::P {
+        fn replace_p(p: self::P, moves: &mut usize) -> target::P {
             match p {
                 self::P::module {
-                    info: utils::Info { assignment, .. },
+                    info,
+                    lambdas: bodies,
                     tail,
                 } => {
-                    let assignment = assignment.unwrap();
-                    let tail = Box::new(tail);
-                    let tail = replace_tail(*tail, &assignment);
-                    target::P::module(tail)
+                    let mut lambdas = vec![];
+                    let tail = replace_body(info, tail, &mut lambdas, moves);
+                    bodies.into_iter().for_each(
+                        |self::Lambda { label, info, tail }| {
+                            let tail =
+                                replace_body(info, tail, &mut lambdas, moves);
+                            lambdas.push(target::Lambda { label, tail });
+                        },
+                    );
+                    target::P::module { lambdas, tail }
                 },
             }
         }
 
+        fn replace_body(
+            utils::Info {
+                assignment,
+                rematerialised,
+                ..
+            }: utils::Info<target::Loc>,
+            tail: self::Tail,
+            lambdas: &mut Vec<target::Lambda>,
+            moves: &mut usize,
+        ) -> target::Tail {
+            let assignment = assignment.unwrap();
+            let rematerialised = rematerialised.unwrap_or_default();
+            replace_tail(tail, &assignment, &rematerialised, lambdas, moves)
+        }
+
         fn replace_tail(
             tail: self::Tail,
             assignment: &utils::Assignments<target::Loc>,
+            rematerialised: &HashMap<utils::Aloc, i64>,
+            lambdas: &mut Vec<target::Lambda>,
+            moves: &mut usize,
         ) -> target::Tail {
             match tail {
                 self::Tail::halt(triv) => {
-                    let triv = replace_triv(triv, assignment);
+                    let triv = replace_triv(triv, assignment, rematerialised);
                     target::Tail::halt(triv)
                 },
                 self::Tail::begin { effects, tail } => {
-                    let effects = replace_effects(effects, assignment);
-                    let tail = replace_tail(*tail, assignment);
+                    let effects = replace_effects(
+                        effects,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    let tail = replace_tail(
+                        *tail,
+                        assignment,
+                        rematerialised,
+                        lambdas,
+                        moves,
+                    );
                     let tail = Box::new(tail);
                     target::Tail::begin { effects, tail }
                 },
                 self::Tail::r#if { pred, tail1, tail2 } => {
-                    let pred = replace_pred(pred, assignment);
-                    let tail1 = replace_tail(*tail1, assignment);
-                    let tail2 = replace_tail(*tail2, assignment);
+                    let pred = replace_pred(
+                        pred,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    let tail1 = replace_tail(
+                        *tail1,
+                        assignment,
+                        rematerialised,
+                        lambdas,
+                        moves,
+                    );
+                    let tail2 = replace_tail(
+                        *tail2,
+                        assignment,
+                        rematerialised,
+                        lambdas,
+                        moves,
+                    );
                     let tail1 = Box::new(tail1);
                     let tail2 = Box::new(tail2);
                     target::Tail::r#if { pred, tail1, tail2 }
                 },
+                self::Tail::r#loop { label, tail } => {
+                    let tail = replace_tail(
+                        *tail,
+                        assignment,
+                        rematerialised,
+                        lambdas,
+                        moves,
+                    );
+                    let trg = target::Trg::label(label.clone());
+                    lambdas.push(target::Lambda { label, tail });
+                    target::Tail::jump(trg)
+                },
+                self::Tail::jump { trg, .. } => {
+                    let trg = match trg {
+                        self::Trg::label(label) => target::Trg::label(label),
+                        self::Trg::aloc(aloc) => {
+                            let loc = assignment.get(&aloc).cloned().unwrap();
+                            target::Trg::loc(loc)
+                        },
+                    };
+                    target::Tail::jump(trg)
+                },
             }
         }
 
         fn replace_pred(
             pred: self::Pred,
             assignment: &utils::Assignments<target::Loc>,
+            rematerialised: &HashMap<utils::Aloc, i64>,
+            moves: &mut usize,
         ) -> target::Pred {
             match pred {
                 self::Pred::relop { relop, aloc, triv } => {
                     let loc =
-                        assignment.get(&aloc).map(target::Loc::clone).unwrap();
-                    let triv = replace_triv(triv, assignment);
+                        assignment.get(&aloc).cloned().unwrap();
+                    let triv = replace_triv(triv, assignment, rematerialised);
                     target::Pred::relop { relop, loc, triv }
                 },
                 self::Pred::r#if {
@@ -689,9 +1501,12 @@ impl AsmPredLang {
                     pred2,
                     pred3,
                 } => {
-                    let pred1 = replace_pred(*pred1, assignment);
-                    let pred2 = replace_pred(*pred2, assignment);
-                    let pred3 = replace_pred(*pred3, assignment);
+                    let pred1 =
+                        replace_pred(*pred1, assignment, rematerialised, moves);
+                    let pred2 =
+                        replace_pred(*pred2, assignment, rematerialised, moves);
+                    let pred3 =
+                        replace_pred(*pred3, assignment, rematerialised, moves);
                     let pred1 = Box::new(pred1);
                     let pred2 = Box::new(pred2);
                     let pred3 = Box::new(pred3);
@@ -701,12 +1516,31 @@ impl AsmPredLang {
                         pred3,
                     }
                 },
-                self::Pred::not(pred) => replace_pred(*pred, assignment),
+                self::Pred::not(pred) => {
+                    let pred = replace_pred(
+                        *pred,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    let pred = Box::new(pred);
+                    target::Pred::not(pred)
+                },
                 self::Pred::r#true => target::Pred::r#true,
                 self::Pred::r#false => target::Pred::r#false,
                 self::Pred::begin { effects, pred } => {
-                    let effects = replace_effects(effects, assignment);
-                    let pred = replace_pred(*pred, assignment);
+                    let effects = replace_effects(
+                        effects,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    let pred = replace_pred(
+                        *pred,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
                     let pred = Box::new(pred);
                     target::Pred::begin { effects, pred }
                 },
@@ -716,23 +1550,41 @@ impl AsmPredLang {
         fn replace_effects(
             effects: Vec<self::Effect>,
             assignment: &utils::Assignments<target::Loc>,
+            rematerialised: &HashMap<utils::Aloc, i64>,
+            moves: &mut usize,
         ) -> Vec<target::Effect> {
             effects
                 .into_iter()
-                .map(|effect| replace_effect(effect, assignment))
+                .map(|effect| {
+                    replace_effect(effect, assignment, rematerialised, moves)
+                })
                 .collect::<Vec<_>>()
         }
 
         fn replace_effect(
             effect: self::Effect,
             assignment: &utils::Assignments<target::Loc>,
+            rematerialised: &HashMap<utils::Aloc, i64>,
+            moves: &mut usize,
         ) -> target::Effect {
             match effect {
+                self::Effect::set_aloc_triv { aloc, .. }
+                    if rematerialised.contains_key(&aloc) =>
+                {
+                    target::Effect::begin(vec![])
+                },
                 self::Effect::set_aloc_triv { aloc, triv } => {
                     let loc =
-                        assignment.get(&aloc).map(target::Loc::clone).unwrap();
-                    let triv = replace_triv(triv, assignment);
-                    target::Effect::set { loc, triv }
+                        assignment.get(&aloc).cloned().unwrap();
+                    let triv = replace_triv(triv, assignment, rematerialised);
+                    match triv {
+                        // loc <- loc
+                        target::Triv::loc(ref loc2) if *loc2 == loc => {
+                            *moves += 1;
+                            target::Effect::begin(vec![])
+                        },
+                        triv => target::Effect::set { loc, triv },
+                    }
                 },
                 self::Effect::set_aloc_binop_aloc_triv {
                     aloc,
@@ -740,12 +1592,50 @@ impl AsmPredLang {
                     triv,
                 } => {
                     let loc =
-                        assignment.get(&aloc).map(target::Loc::clone).unwrap();
-                    let triv = replace_triv(triv, assignment);
+                        assignment.get(&aloc).cloned().unwrap();
+                    let triv = replace_triv(triv, assignment, rematerialised);
                     target::Effect::set_binop { loc, binop, triv }
                 },
+                self::Effect::set_aloc_label { aloc, label } => {
+                    let loc = assignment.get(&aloc).cloned().unwrap();
+                    target::Effect::set_label { loc, label }
+                },
+                self::Effect::set_aloc_rloc { aloc, rloc } => {
+                    let loc = assignment.get(&aloc).cloned().unwrap();
+                    let triv = target::Triv::loc(rloc.into());
+                    target::Effect::set { loc, triv }
+                },
+                self::Effect::set_rloc_triv { rloc, triv } => {
+                    let loc = rloc.into();
+                    let triv = replace_triv(triv, assignment, rematerialised);
+                    target::Effect::set { loc, triv }
+                },
+                self::Effect::set_aloc_alloc_triv { aloc, triv } => {
+                    let loc = assignment.get(&aloc).cloned().unwrap();
+                    let triv = replace_triv(triv, assignment, rematerialised);
+                    target::Effect::set_alloc { loc, triv }
+                },
+                self::Effect::set_aloc_mref_aloc_triv { aloc, base, index } => {
+                    let loc = assignment.get(&aloc).cloned().unwrap();
+                    let base = assignment.get(&base).cloned().unwrap();
+                    let index =
+                        replace_triv(index, assignment, rematerialised);
+                    target::Effect::set_mref { loc, base, index }
+                },
+                self::Effect::mset_aloc_triv_triv { aloc, index, triv } => {
+                    let loc = assignment.get(&aloc).cloned().unwrap();
+                    let index =
+                        replace_triv(index, assignment, rematerialised);
+                    let triv = replace_triv(triv, assignment, rematerialised);
+                    target::Effect::mset { loc, index, triv }
+                },
                 self::Effect::begin(effects) => {
-                    let effects = replace_effects(effects, assignment);
+                    let effects = replace_effects(
+                        effects,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
                     target::Effect::begin(effects)
                 },
                 self::Effect::r#if {
@@ -753,9 +1643,24 @@ impl AsmPredLang {
                     effect1,
                     effect2,
                 } => {
-                    let pred = replace_pred(pred, assignment);
-                    let effect1 = replace_effect(*effect1, assignment);
-                    let effect2 = replace_effect(*effect2, assignment);
+                    let pred = replace_pred(
+                        pred,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    let effect1 = replace_effect(
+                        *effect1,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    let effect2 = replace_effect(
+                        *effect2,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
                     let effect1 = Box::new(effect1);
                     let effect2 = Box::new(effect2);
                     target::Effect::r#if {
@@ -764,24 +1669,185 @@ impl AsmPredLang {
                         effect2,
                     }
                 },
+                self::Effect::r#while { pred, effect } => {
+                    let pred = replace_pred(
+                        pred,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    let effect = replace_effect(
+                        *effect,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    let effect = Box::new(effect);
+                    target::Effect::r#while { pred, effect }
+                },
+                self::Effect::set_aloc_jump_table { aloc, labels } => {
+                    let loc = assignment.get(&aloc).cloned().unwrap();
+                    target::Effect::set_jump_table { loc, labels }
+                },
+                self::Effect::switch {
+                    aloc,
+                    table,
+                    length,
+                    labels,
+                    effects,
+                } => {
+                    let loc = assignment.get(&aloc).cloned().unwrap();
+                    let table = assignment.get(&table).cloned().unwrap();
+                    let effects = replace_effects(
+                        effects,
+                        assignment,
+                        rematerialised,
+                        moves,
+                    );
+                    target::Effect::switch {
+                        loc,
+                        table,
+                        length,
+                        labels,
+                        effects,
+                    }
+                },
             }
         }
 
         fn replace_triv(
             triv: self::Triv,
             assignment: &utils::Assignments<target::Loc>,
+            rematerialised: &HashMap<utils::Aloc, i64>,
         ) -> target::Triv {
             match triv {
                 self::Triv::int64(int64) => target::Triv::int64(int64),
-                self::Triv::aloc(aloc) => {
-                    let loc =
-                        assignment.get(&aloc).map(target::Loc::clone).unwrap();
-                    target::Triv::loc(loc)
+                self::Triv::aloc(aloc) => match rematerialised.get(&aloc) {
+                    Some(int64) => target::Triv::int64(*int64),
+                    None => {
+                        let loc = assignment
+                            .get(&aloc)
+                            .cloned()
+                            .unwrap();
+                        target::Triv::loc(loc)
+                    },
                 },
             }
         }
 
-        let p = replace_p(p);
-        target::NestedAsmLang(p)
+        let mut moves = 0;
+        let p = replace_p(p, &mut moves);
+        (target::NestedAsmLang(p), moves)
+    }
+
+    /// ### Purpose:
+    /// Summarises the register allocation of the program. The info field must
+    /// have been decorated by conflict-analysis and assign-registers.
+    ///
+    /// ### Notes:
+    /// The counts of each procedure are added to those of the main body, while
+    /// the largest clique and frame are the largest of any of them. The
+    /// eliminated moves are left at zero, as they are counted by
+    /// replace-locations.
+    pub fn alloc_stats(&self) -> AllocStats {
+        let Self(p) = self;
+
+        fn stats_p(p: &self::P) -> AllocStats {
+            match p {
+                self::P::module { info, lambdas, .. } => lambdas.iter().fold(
+                    stats_body(info),
+                    |stats, self::Lambda { info, .. }| {
+                        let other = stats_body(info);
+                        AllocStats {
+                            locals: stats.locals + other.locals,
+                            conflict_edges: stats.conflict_edges
+                                + other.conflict_edges,
+                            max_clique: stats.max_clique.max(other.max_clique),
+                            spilled: stats.spilled + other.spilled,
+                            frame_size: stats.frame_size.max(other.frame_size),
+                            moves_eliminated: stats.moves_eliminated
+                                + other.moves_eliminated,
+                            rematerialised: stats.rematerialised
+                                + other.rematerialised,
+                        }
+                    },
+                ),
+            }
+        }
+
+        fn stats_body(
+            utils::Info {
+                locals,
+                conflicts,
+                assignment,
+                rematerialised,
+                ..
+            }: &utils::Info<target::Loc>,
+        ) -> AllocStats {
+            let locals = locals.as_ref().unwrap();
+            let conflicts = conflicts.as_ref().unwrap();
+            let assignment = assignment.as_ref().unwrap();
+            let fvars = assignment
+                .values()
+                .filter_map(|loc| match loc {
+                    target::Loc::fvar(fvar) => Some(fvar),
+                    target::Loc::reg(..) => None,
+                })
+                .collect::<Vec<_>>();
+            let frame_size = fvars
+                .iter()
+                .map(|utils::Fvar(index)| (index + 1) * 8)
+                .max()
+                .unwrap_or_default();
+            AllocStats {
+                locals: locals.len(),
+                conflict_edges: conflicts.edges().len(),
+                max_clique: conflicts.max_clique_estimate(),
+                spilled: fvars.len(),
+                frame_size,
+                moves_eliminated: 0,
+                rematerialised: rematerialised
+                    .as_ref()
+                    .map_or(0, HashMap::len),
+            }
+        }
+
+        stats_p(p)
+    }
+
+    /// ### Purpose:
+    /// Renders the conflict graph of the program in Graphviz DOT format,
+    /// colouring each abstract location by its assigned location once
+    /// assign-registers has run. Returns `None` before conflict-analysis.
+    pub fn conflicts_to_dot(&self) -> Option<String> {
+        let Self(p) = self;
+
+        match p {
+            self::P::module {
+                info:
+                    utils::Info {
+                        conflicts,
+                        assignment,
+                        ..
+                    },
+                ..
+            } => conflicts
+                .as_ref()
+                .map(|conflicts| conflicts.to_dot(assignment.as_ref())),
+        }
+    }
+
+    /// ### Purpose:
+    /// Renders the undead-set tree of the program in Graphviz DOT format.
+    /// Returns `None` before undead-analysis.
+    pub fn undead_to_dot(&self) -> Option<String> {
+        let Self(p) = self;
+
+        match p {
+            self::P::module {
+                info: utils::Info { undead_out, .. },
+                ..
+            } => undead_out.as_ref().map(utils::Node::to_dot),
+        }
     }
 }
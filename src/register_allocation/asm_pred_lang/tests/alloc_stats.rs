@@ -0,0 +1,78 @@
+use serial_test::serial;
+
+use crate::register_allocation::asm_pred_lang as source;
+use crate::register_allocation::AllocStats;
+use crate::structured_control_flow::nested_asm_lang as target;
+use crate::utils;
+
+#[test]
+#[serial]
+fn basic() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh();
+    let y = utils::Aloc::fresh();
+    let z = utils::Aloc::fresh();
+
+    let program = source::AsmPredLang(source::P::module {
+        info: utils::Info {
+            locals: Some(
+                vec![x.clone(), y.clone(), z.clone()].into_iter().collect(),
+            ),
+            conflicts: Some(utils::Graph::new_with_graph(&[
+                (x.clone(), std::slice::from_ref(&z)),
+                (y.clone(), std::slice::from_ref(&z)),
+                (z.clone(), &[x.clone(), y.clone()]),
+            ])),
+            assignment: Some(
+                vec![
+                    (x.clone(), target::Loc::reg(utils::Reg::rbx)),
+                    (y.clone(), target::Loc::reg(utils::Reg::rbx)),
+                    (z.clone(), target::Loc::fvar(utils::Fvar(1))),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        },
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![
+                source::Effect::set_aloc_triv {
+                    aloc: x.clone(),
+                    triv: source::Triv::int64(42),
+                },
+                source::Effect::set_aloc_triv {
+                    aloc: y.clone(),
+                    triv: source::Triv::aloc(x.clone()),
+                },
+                source::Effect::set_aloc_triv {
+                    aloc: z.clone(),
+                    triv: source::Triv::aloc(y.clone()),
+                },
+            ],
+            tail: Box::new(source::Tail::halt(source::Triv::aloc(z.clone()))),
+        },
+    });
+
+    let stats = program.alloc_stats();
+    let (_, moves_eliminated) = program.replace_locations_with_moves();
+
+    utils::reset_all_indices();
+
+    assert_eq!(
+        AllocStats {
+            moves_eliminated,
+            ..stats
+        },
+        AllocStats {
+            locals: 3,
+            conflict_edges: 2,
+            max_clique: 2,
+            spilled: 1,
+            frame_size: 16,
+            moves_eliminated: 1,
+            rematerialised: 0,
+        },
+    );
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serial_test::serial;
 
 use crate::register_allocation::asm_pred_lang as source;
@@ -19,6 +21,7 @@ fn basic() {
             )])),
             ..Default::default()
         },
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![source::Effect::set_aloc_triv {
                 aloc: aloc.clone(),
@@ -30,7 +33,8 @@ fn basic() {
         },
     });
 
-    let source::AsmPredLang(p) = program.assign_registers();
+    let source::AsmPredLang(p) =
+        program.assign_registers(&utils::TargetConfig::default());
 
     match p {
         source::P::module {
@@ -50,7 +54,10 @@ fn basic() {
 #[test]
 #[serial]
 fn basic_without_registers() {
-    utils::Reg::set_current_assignable_registers(vec![].into_iter().collect());
+    let config = utils::TargetConfig {
+        assignable_registers: vec![],
+        ..Default::default()
+    };
 
     let aloc = utils::Aloc::fresh();
 
@@ -63,6 +70,7 @@ fn basic_without_registers() {
             )])),
             ..Default::default()
         },
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![source::Effect::set_aloc_triv {
                 aloc: aloc.clone(),
@@ -74,7 +82,7 @@ fn basic_without_registers() {
         },
     });
 
-    let source::AsmPredLang(p) = program.assign_registers();
+    let source::AsmPredLang(p) = program.assign_registers(&config);
 
     utils::reset_all_indices();
 
@@ -95,6 +103,146 @@ fn basic_without_registers() {
     utils::reset_all_indices();
 }
 
+#[test]
+#[serial]
+fn spill_without_registers() {
+    utils::reset_all_indices();
+    let config = utils::TargetConfig {
+        assignable_registers: vec![],
+        ..Default::default()
+    };
+
+    let aloc = utils::Aloc::fresh();
+
+    let program = source::AsmPredLang(source::P::module {
+        info: utils::Info {
+            locals: Some(vec![aloc.clone()].into_iter().collect()),
+            conflicts: Some(utils::Graph::new_with_graph(&[(
+                aloc.clone(),
+                &[],
+            )])),
+            ..Default::default()
+        },
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![
+                source::Effect::set_aloc_triv {
+                    aloc: aloc.clone(),
+                    triv: source::Triv::int64(42),
+                },
+                source::Effect::set_aloc_binop_aloc_triv {
+                    aloc: aloc.clone(),
+                    binop: utils::Binop::plus,
+                    triv: source::Triv::int64(1),
+                },
+            ],
+            tail: Box::new(source::Tail::halt(source::Triv::aloc(
+                aloc.clone(),
+            ))),
+        },
+    });
+
+    let source::AsmPredLang(p) =
+        program.uncover_constants().assign_registers(&config);
+
+    utils::reset_all_indices();
+
+    match p {
+        source::P::module {
+            info:
+                utils::Info {
+                    assignment,
+                    rematerialised,
+                    ..
+                },
+            ..
+        } => {
+            assert_eq!(
+                assignment.unwrap(),
+                vec![(aloc, target::Loc::fvar(utils::Fvar::fresh()))]
+                    .into_iter()
+                    .collect(),
+            );
+            assert_eq!(rematerialised.unwrap(), HashMap::default());
+        },
+    };
+
+    utils::reset_all_indices();
+}
+
+#[test]
+#[serial]
+fn rematerialise_without_registers() {
+    utils::reset_all_indices();
+    let config = utils::TargetConfig {
+        assignable_registers: vec![],
+        ..Default::default()
+    };
+
+    // The constant does not fit in 32 bits.
+    let int64 = 1 << 40;
+
+    let aloc = utils::Aloc::fresh();
+
+    let program = source::AsmPredLang(source::P::module {
+        info: utils::Info {
+            locals: Some(vec![aloc.clone()].into_iter().collect()),
+            conflicts: Some(utils::Graph::new_with_graph(&[(
+                aloc.clone(),
+                &[],
+            )])),
+            ..Default::default()
+        },
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![source::Effect::set_aloc_triv {
+                aloc: aloc.clone(),
+                triv: source::Triv::int64(int64),
+            }],
+            tail: Box::new(source::Tail::halt(source::Triv::aloc(
+                aloc.clone(),
+            ))),
+        },
+    });
+
+    let program = program.uncover_constants().assign_registers(&config);
+
+    match &program {
+        source::AsmPredLang(source::P::module {
+            info:
+                utils::Info {
+                    assignment,
+                    rematerialised,
+                    ..
+                },
+            ..
+        }) => {
+            assert_eq!(assignment.as_ref().unwrap(), &HashMap::default());
+            assert_eq!(
+                rematerialised.as_ref().unwrap(),
+                &vec![(aloc.clone(), int64)].into_iter().collect(),
+            );
+        },
+    };
+
+    let target::NestedAsmLang(p) = program.replace_locations();
+
+    utils::reset_all_indices();
+
+    assert_eq!(
+        p,
+        target::P::module {
+            lambdas: vec![],
+            tail: target::Tail::begin {
+                effects: vec![target::Effect::begin(vec![])],
+                tail: Box::new(target::Tail::halt(target::Triv::int64(
+                    int64,
+                ))),
+            },
+        },
+    );
+}
+
 #[test]
 #[serial]
 #[ignore = "Registers get randomly assigned..."]
@@ -110,6 +258,7 @@ fn intermediary() {
             )])),
             ..Default::default()
         },
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![source::Effect::set_aloc_triv {
                 aloc: aloc.clone(),
@@ -121,7 +270,8 @@ fn intermediary() {
         },
     });
 
-    let source::AsmPredLang(p) = program.assign_registers();
+    let source::AsmPredLang(p) =
+        program.assign_registers(&utils::TargetConfig::default());
 
     match p {
         source::P::module {
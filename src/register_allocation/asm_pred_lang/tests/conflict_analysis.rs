@@ -27,6 +27,7 @@ fn basic() {
             }),
             ..Default::default()
         },
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![],
             tail: Box::new(source::Tail::halt(source::Triv::aloc(
@@ -1,3 +1,5 @@
+mod alloc_stats;
 mod assign_registers;
 mod conflict_analysis;
+mod replace_locations;
 mod undead_analysis;
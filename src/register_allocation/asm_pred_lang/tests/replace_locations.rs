@@ -0,0 +1,51 @@
+use serial_test::serial;
+
+use crate::register_allocation::asm_pred_lang as source;
+use crate::structured_control_flow::nested_asm_lang as target;
+use crate::utils;
+
+#[test]
+#[serial]
+fn not_pred_is_kept() {
+    let aloc = utils::Aloc::fresh();
+
+    let program = source::AsmPredLang(source::P::module {
+        info: utils::Info {
+            assignment: Some(
+                vec![(aloc.clone(), target::Loc::reg(utils::Reg::rax))]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        },
+        lambdas: vec![],
+        tail: source::Tail::r#if {
+            pred: source::Pred::not(Box::new(source::Pred::relop {
+                relop: utils::Relop::gt,
+                aloc,
+                triv: source::Triv::int64(0),
+            })),
+            tail1: Box::new(source::Tail::halt(source::Triv::int64(1))),
+            tail2: Box::new(source::Tail::halt(source::Triv::int64(2))),
+        },
+    });
+
+    let actual = program.replace_locations();
+
+    let expected = target::NestedAsmLang(target::P::module {
+        lambdas: vec![],
+        tail: target::Tail::r#if {
+            pred: target::Pred::not(Box::new(target::Pred::relop {
+                relop: utils::Relop::gt,
+                loc: target::Loc::reg(utils::Reg::rax),
+                triv: target::Triv::int64(0),
+            })),
+            tail1: Box::new(target::Tail::halt(target::Triv::int64(1))),
+            tail2: Box::new(target::Tail::halt(target::Triv::int64(2))),
+        },
+    });
+
+    assert_eq!(actual, expected);
+
+    utils::reset_all_indices();
+}
@@ -10,6 +10,7 @@ use crate::utils;
 fn basic_recursion_depth_0() {
     let program = source::AsmPredLang(source::P::module {
         info: utils::Info::default(),
+        lambdas: vec![],
         tail: source::Tail::halt(source::Triv::int64(10)),
     });
 
@@ -30,6 +31,7 @@ fn basic_recursion_depth_0() {
 fn basic_recursion_depth_1() {
     let program = source::AsmPredLang(source::P::module {
         info: utils::Info::default(),
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![],
             tail: Box::new(source::Tail::halt(source::Triv::int64(10))),
@@ -57,6 +59,7 @@ fn basic_recursion_depth_1() {
 fn basic_recursion_depth_2() {
     let program = source::AsmPredLang(source::P::module {
         info: utils::Info::default(),
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![],
             tail: Box::new(source::Tail::begin {
@@ -93,6 +96,7 @@ fn basic_begins_with_triv_ref() {
 
     let program = source::AsmPredLang(source::P::module {
         info: utils::Info::default(),
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![source::Effect::set_aloc_triv {
                 aloc: aloc.clone(),
@@ -135,6 +139,7 @@ fn basic_begins_with_multiple_effects() {
 
     let program = source::AsmPredLang(source::P::module {
         info: utils::Info::default(),
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![
                 source::Effect::set_aloc_triv {
@@ -188,6 +193,7 @@ fn basic_begins_with_multiple_effects_2() {
 
     let program = source::AsmPredLang(source::P::module {
         info: utils::Info::default(),
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![
                 source::Effect::set_aloc_triv {
@@ -247,6 +253,7 @@ fn intermediary_begins_with_empty_nested_begin() {
 
     let program = source::AsmPredLang(source::P::module {
         info: utils::Info::default(),
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![source::Effect::begin(vec![])],
             tail: Box::new(source::Tail::halt(source::Triv::aloc(aloc))),
@@ -285,6 +292,7 @@ fn intermediary_begins_with_nested_begin() {
 
     let program = source::AsmPredLang(source::P::module {
         info: utils::Info::default(),
+        lambdas: vec![],
         tail: source::Tail::begin {
             effects: vec![source::Effect::begin(vec![
                 source::Effect::set_aloc_triv {
@@ -326,3 +334,282 @@ fn intermediary_begins_with_nested_begin() {
 
     utils::reset_all_indices();
 }
+
+#[test]
+#[serial]
+fn binop_reads_its_aloc() {
+    let aloc = utils::Aloc::fresh();
+
+    let program = source::AsmPredLang(source::P::module {
+        info: utils::Info::default(),
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![
+                source::Effect::set_aloc_triv {
+                    aloc: aloc.clone(),
+                    triv: source::Triv::int64(10),
+                },
+                source::Effect::set_aloc_binop_aloc_triv {
+                    aloc: aloc.clone(),
+                    binop: utils::Binop::plus,
+                    triv: source::Triv::int64(1),
+                },
+            ],
+            tail: Box::new(source::Tail::halt(source::Triv::aloc(
+                aloc.clone(),
+            ))),
+        },
+    });
+
+    let source::AsmPredLang(p) = program.undead_analysis();
+
+    match p {
+        source::P::module {
+            info: utils::Info { undead_out, .. },
+            ..
+        } => {
+            assert_eq!(undead_out.unwrap(), utils::Node::tree {
+                tree: utils::Tree {
+                    nodes: vec![
+                        utils::Node::alocs {
+                            alocs: vec![aloc.clone()].into_iter().collect()
+                        },
+                        utils::Node::alocs {
+                            alocs: vec![aloc].into_iter().collect()
+                        },
+                        utils::Node::alocs {
+                            alocs: HashSet::default()
+                        },
+                    ],
+                }
+            })
+        },
+    }
+
+    utils::reset_all_indices();
+}
+
+#[test]
+#[serial]
+fn relop_reads_its_aloc() {
+    let aloc = utils::Aloc::fresh();
+
+    let program = source::AsmPredLang(source::P::module {
+        info: utils::Info::default(),
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![source::Effect::set_aloc_triv {
+                aloc: aloc.clone(),
+                triv: source::Triv::int64(10),
+            }],
+            tail: Box::new(source::Tail::r#if {
+                pred: source::Pred::relop {
+                    relop: utils::Relop::gt,
+                    aloc: aloc.clone(),
+                    triv: source::Triv::int64(0),
+                },
+                tail1: Box::new(source::Tail::halt(source::Triv::int64(1))),
+                tail2: Box::new(source::Tail::halt(source::Triv::int64(2))),
+            }),
+        },
+    });
+
+    let source::AsmPredLang(p) = program.undead_analysis();
+
+    let alocs = vec![aloc].into_iter().collect::<HashSet<_>>();
+    match p {
+        source::P::module {
+            info: utils::Info { undead_out, .. },
+            ..
+        } => {
+            assert_eq!(undead_out.unwrap(), utils::Node::tree {
+                tree: utils::Tree {
+                    nodes: vec![
+                        utils::Node::alocs {
+                            alocs: alocs.clone()
+                        },
+                        utils::Node::tree {
+                            tree: utils::Tree {
+                                nodes: vec![
+                                    utils::Node::alocs {
+                                        alocs: HashSet::default()
+                                    },
+                                    utils::Node::alocs {
+                                        alocs: HashSet::default()
+                                    },
+                                    utils::Node::alocs {
+                                        alocs: HashSet::default()
+                                    },
+                                ],
+                            }
+                        },
+                    ],
+                }
+            })
+        },
+    }
+
+    utils::reset_all_indices();
+}
+
+#[test]
+#[serial]
+fn if_joins_the_alocs_undead_in_either_branch() {
+    let a = utils::Aloc::fresh();
+    let b = utils::Aloc::fresh();
+
+    let program = source::AsmPredLang(source::P::module {
+        info: utils::Info::default(),
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![
+                source::Effect::set_aloc_triv {
+                    aloc: a.clone(),
+                    triv: source::Triv::int64(1),
+                },
+                source::Effect::set_aloc_triv {
+                    aloc: b.clone(),
+                    triv: source::Triv::int64(2),
+                },
+            ],
+            tail: Box::new(source::Tail::r#if {
+                pred: source::Pred::relop {
+                    relop: utils::Relop::gt,
+                    aloc: a.clone(),
+                    triv: source::Triv::int64(0),
+                },
+                tail1: Box::new(source::Tail::halt(source::Triv::aloc(
+                    b.clone(),
+                ))),
+                tail2: Box::new(source::Tail::halt(source::Triv::int64(0))),
+            }),
+        },
+    });
+
+    let source::AsmPredLang(p) = program.undead_analysis();
+
+    let set = |alocs: Vec<&utils::Aloc>| utils::Node::alocs {
+        alocs: alocs.into_iter().cloned().collect(),
+    };
+    match p {
+        source::P::module {
+            info: utils::Info { undead_out, .. },
+            ..
+        } => {
+            assert_eq!(undead_out.unwrap(), utils::Node::tree {
+                tree: utils::Tree {
+                    nodes: vec![
+                        set(vec![&a]),
+                        set(vec![&a, &b]),
+                        utils::Node::tree {
+                            tree: utils::Tree {
+                                nodes: vec![
+                                    set(vec![&b]),
+                                    set(vec![]),
+                                    set(vec![]),
+                                ],
+                            }
+                        },
+                    ],
+                }
+            })
+        },
+    }
+
+    utils::reset_all_indices();
+}
+
+#[test]
+#[serial]
+fn while_keeps_loop_carried_alocs_undead() {
+    let sum = utils::Aloc::fresh();
+    let index = utils::Aloc::fresh();
+
+    let program = source::AsmPredLang(source::P::module {
+        info: utils::Info::default(),
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![
+                source::Effect::set_aloc_triv {
+                    aloc: sum.clone(),
+                    triv: source::Triv::int64(0),
+                },
+                source::Effect::set_aloc_triv {
+                    aloc: index.clone(),
+                    triv: source::Triv::int64(1),
+                },
+                source::Effect::r#while {
+                    pred: source::Pred::relop {
+                        relop: utils::Relop::lte,
+                        aloc: index.clone(),
+                        triv: source::Triv::int64(10),
+                    },
+                    effect: Box::new(source::Effect::begin(vec![
+                        source::Effect::set_aloc_binop_aloc_triv {
+                            aloc: sum.clone(),
+                            binop: utils::Binop::plus,
+                            triv: source::Triv::aloc(index.clone()),
+                        },
+                        source::Effect::set_aloc_binop_aloc_triv {
+                            aloc: index.clone(),
+                            binop: utils::Binop::plus,
+                            triv: source::Triv::int64(1),
+                        },
+                    ])),
+                },
+            ],
+            tail: Box::new(source::Tail::halt(source::Triv::aloc(
+                sum.clone(),
+            ))),
+        },
+    });
+
+    let source::AsmPredLang(p) = program.undead_analysis();
+
+    let both = vec![sum.clone(), index].into_iter().collect::<HashSet<_>>();
+    match p {
+        source::P::module {
+            info: utils::Info { undead_out, .. },
+            ..
+        } => {
+            assert_eq!(undead_out.unwrap(), utils::Node::tree {
+                tree: utils::Tree {
+                    nodes: vec![
+                        utils::Node::alocs {
+                            alocs: vec![sum].into_iter().collect()
+                        },
+                        utils::Node::alocs {
+                            alocs: both.clone()
+                        },
+                        utils::Node::tree {
+                            tree: utils::Tree {
+                                nodes: vec![
+                                    utils::Node::alocs {
+                                        alocs: both.clone()
+                                    },
+                                    utils::Node::tree {
+                                        tree: utils::Tree {
+                                            nodes: vec![
+                                                utils::Node::alocs {
+                                                    alocs: both.clone()
+                                                },
+                                                utils::Node::alocs {
+                                                    alocs: both
+                                                },
+                                            ],
+                                        }
+                                    },
+                                ],
+                            }
+                        },
+                        utils::Node::alocs {
+                            alocs: HashSet::default()
+                        },
+                    ],
+                }
+            })
+        },
+    };
+
+    utils::reset_all_indices();
+}
@@ -1,15 +1,67 @@
 pub mod asm_pred_lang;
 
+use crate::utils;
+
 pub type Source = asm_pred_lang::AsmPredLang;
 
 pub type Target = crate::structured_control_flow::Source;
 
-pub fn compile(p: Source) -> Result<Target, String> {
+/// ### Purpose:
+/// Summarises the quality of a register allocation, so that regressions in
+/// the allocator can be tracked across compiler changes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AllocStats {
+    /// The number of abstract locations in the program.
+    pub locals: usize,
+
+    /// The number of edges in the conflict graph.
+    pub conflict_edges: usize,
+
+    /// The size of the largest clique found in the conflict graph; a lower
+    /// bound on the number of registers needed to avoid spilling.
+    pub max_clique: usize,
+
+    /// The number of abstract locations assigned to a frame variable.
+    pub spilled: usize,
+
+    /// The extent of the frame in bytes, as addressed by the frame variables
+    /// in the assignment.
+    pub frame_size: usize,
+
+    /// The number of moves between abstract locations that were assigned the
+    /// same physical location, and were therefore dropped.
+    pub moves_eliminated: usize,
+
+    /// The number of constant abstract locations that were re-emitted at each
+    /// of their uses instead of being spilled.
+    pub rematerialised: usize,
+}
+
+pub fn compile(
+    p: Source,
+    config: &utils::TargetConfig,
+    _: &utils::CompileOptions,
+) -> Result<Target, String> {
+    compile_with_stats(p, config).map(|(p, _)| p)
+}
+
+pub fn compile_with_stats(
+    p: Source,
+    config: &utils::TargetConfig,
+) -> Result<(Target, AllocStats), String> {
     let p = p
         .uncover_locals()
+        .uncover_constants()
         .undead_analysis()
         .conflict_analysis()
-        .assign_registers()
-        .replace_locations();
-    Ok(p)
+        .assign_registers(config);
+    let stats = p.alloc_stats();
+    let (p, moves_eliminated) = p.replace_locations_with_moves();
+    Ok((
+        p,
+        AllocStats {
+            moves_eliminated,
+            ..stats
+        },
+    ))
 }
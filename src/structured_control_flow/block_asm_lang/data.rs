@@ -1,13 +1,16 @@
 use crate::utils;
 
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub enum P {
     module(Vec<B>),
 }
 
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub enum B {
     define_label_tail { label: utils::Label, tail: Tail },
 }
 
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub enum Tail {
     halt(Opand),
     jump(Trg),
@@ -36,6 +39,20 @@ pub enum Effect {
         binop: utils::Binop,
         opand: Opand,
     },
+    set_alloc {
+        loc: Loc,
+        opand: Opand,
+    },
+    set_mref {
+        loc: Loc,
+        base: Loc,
+        index: Opand,
+    },
+    mset {
+        loc: Loc,
+        index: Opand,
+        triv: Triv,
+    },
 }
 
 pub type Triv = super::target::Triv;
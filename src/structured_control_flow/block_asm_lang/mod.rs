@@ -2,15 +2,124 @@ pub mod data;
 #[cfg(test)]
 mod tests;
 
+use std::collections::HashMap;
+use std::collections::HashSet;
+
 pub use self::data::*;
 use crate::structured_control_flow::para_asm_lang as target;
+use crate::utils;
 
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub struct BlockAsmLang(pub self::P);
 
 impl BlockAsmLang {
+    /// ### Purpose:
+    /// Reorders the basic blocks of the program so that the likely successor
+    /// of each block is placed directly after it, allowing flatten-program to
+    /// replace the jump between them with a fall-through.
+    ///
+    /// ### Notes:
+    /// The first block is the entry of the program and stays first. Blocks
+    /// are laid out in greedy traces: a trace follows the target of a jump,
+    /// or the false target of an if (then the true target, if the false one
+    /// is already placed), until it reaches a block that is already placed.
+    /// The next trace starts at the first unplaced block in the original
+    /// order.
+    pub fn order_blocks(self) -> Self {
+        let Self(p) = self;
+
+        fn order_p(p: self::P) -> self::P {
+            match p {
+                self::P::module(bs) => {
+                    let labels = bs
+                        .iter()
+                        .map(|b| match b {
+                            self::B::define_label_tail { label, .. } => {
+                                label.clone()
+                            },
+                        })
+                        .collect::<Vec<_>>();
+
+                    let mut blocks = bs
+                        .into_iter()
+                        .map(|b| match b {
+                            self::B::define_label_tail {
+                                ref label, ..
+                            } => (label.clone(), b),
+                        })
+                        .collect::<HashMap<_, _>>();
+
+                    let mut placed = HashSet::<utils::Label>::default();
+                    let mut order = vec![];
+
+                    labels.iter().for_each(|label| {
+                        let mut next = Some(label.clone());
+                        while let Some(label) = next.take() {
+                            if placed.insert(label.clone()) {
+                                let successors = blocks
+                                    .get(&label)
+                                    .map(successors_b)
+                                    .unwrap_or_default();
+                                next = successors.into_iter().find(|label| {
+                                    blocks.contains_key(label)
+                                        && !placed.contains(label)
+                                });
+                                order.push(label);
+                            }
+                        }
+                    });
+
+                    let bs = order
+                        .into_iter()
+                        .filter_map(|label| blocks.remove(&label))
+                        .collect();
+
+                    self::P::module(bs)
+                },
+            }
+        }
+
+        fn successors_b(b: &self::B) -> Vec<utils::Label> {
+            match b {
+                self::B::define_label_tail { tail, .. } => {
+                    successors_tail(tail)
+                },
+            }
+        }
+
+        // The successors of a tail, in order of preference for being placed
+        // directly after it.
+        fn successors_tail(tail: &self::Tail) -> Vec<utils::Label> {
+            match tail {
+                self::Tail::halt(..) => vec![],
+                self::Tail::jump(trg) => successors_trg(trg),
+                self::Tail::begin { tail, .. } => successors_tail(tail),
+                self::Tail::r#if { trg1, trg2, .. } => successors_trg(trg2)
+                    .into_iter()
+                    .chain(successors_trg(trg1))
+                    .collect(),
+            }
+        }
+
+        fn successors_trg(trg: &self::Trg) -> Vec<utils::Label> {
+            match trg {
+                self::Trg::label(label) => vec![label.clone()],
+                self::Trg::loc(..) => vec![],
+            }
+        }
+
+        let p = order_p(p);
+        Self(p)
+    }
+
     /// ### Purpose:
     /// Compile Block-asm-lang v4 to Para-asm-lang v4 by flattening basic blocks
     /// into labeled instructions.
+    ///
+    /// ### Notes:
+    /// A jump to the block that directly follows is elided, and an if whose
+    /// true target directly follows is inverted, so that control falls through
+    /// to the next block instead.
     pub fn flatten_program(self) -> target::ParaAsmLang {
         let Self(p) = self;
 
@@ -25,14 +134,39 @@ impl BlockAsmLang {
         }
 
         fn flatten_bs(bs: Vec<self::B>) -> Vec<target::S> {
-            bs.into_iter().map(flatten_b).flatten().collect()
+            let next_labels = bs
+                .iter()
+                .skip(1)
+                .map(|b| match b {
+                    self::B::define_label_tail { label, .. } => {
+                        Some(label.clone())
+                    },
+                })
+                .chain(std::iter::once(None))
+                .collect::<Vec<_>>();
+
+            bs.into_iter()
+                .zip(next_labels)
+                .flat_map(|(b, next_label)| flatten_b(b, next_label.as_ref()))
+                .collect()
         }
 
-        fn flatten_b(b: self::B) -> Vec<target::S> {
+        fn flatten_b(
+            b: self::B,
+            next_label: Option<&utils::Label>,
+        ) -> Vec<target::S> {
             match b {
                 self::B::define_label_tail { label, tail } => {
-                    flatten_tail(tail)
-                        .into_iter()
+                    let ss = flatten_tail(tail, next_label);
+
+                    // A block which only falls through still needs an
+                    // instruction to carry its label.
+                    let ss = match ss.is_empty() {
+                        true => vec![target::S::nop],
+                        false => ss,
+                    };
+
+                    ss.into_iter()
                         .enumerate()
                         .map(|(index, s)| match index {
                             0usize => target::S::with_label {
@@ -46,22 +180,28 @@ impl BlockAsmLang {
             }
         }
 
-        fn flatten_tail(tail: self::Tail) -> Vec<target::S> {
+        fn flatten_tail(
+            tail: self::Tail,
+            next_label: Option<&utils::Label>,
+        ) -> Vec<target::S> {
             match tail {
                 self::Tail::halt(opand) => {
                     let instr = target::S::halt(opand);
 
                     vec![instr]
                 },
-                self::Tail::jump(trg) => {
-                    let instr = target::S::jump(trg);
+                self::Tail::jump(trg) => match is_next(&trg, next_label) {
+                    true => vec![],
+                    false => {
+                        let instr = target::S::jump(trg);
 
-                    vec![instr]
+                        vec![instr]
+                    },
                 },
                 self::Tail::begin { effects, tail } => {
                     let mut ss = flatten_effects(effects);
 
-                    let ss_tail = flatten_tail(*tail);
+                    let ss_tail = flatten_tail(*tail, next_label);
 
                     ss.extend(ss_tail);
 
@@ -73,18 +213,56 @@ impl BlockAsmLang {
                     opand,
                     trg1,
                     trg2,
-                } => {
-                    let instr1 = target::S::compare_jump {
-                        loc,
-                        opand,
-                        relop,
-                        trg: trg1,
-                    };
+                } => match (
+                    is_next(&trg1, next_label),
+                    is_next(&trg2, next_label),
+                ) {
+                    // compare-jump trg1, fall through to trg2
+                    (_, true) => {
+                        let instr = target::S::compare_jump {
+                            loc,
+                            opand,
+                            relop,
+                            trg: trg1,
+                        };
+
+                        vec![instr]
+                    },
+
+                    // compare-jump (not relop) trg2, fall through to trg1
+                    (true, false) => {
+                        let instr = target::S::compare_jump {
+                            loc,
+                            opand,
+                            relop: !relop,
+                            trg: trg2,
+                        };
 
-                    let instr2 = target::S::jump(trg2);
+                        vec![instr]
+                    },
+
+                    (false, false) => {
+                        let instr1 = target::S::compare_jump {
+                            loc,
+                            opand,
+                            relop,
+                            trg: trg1,
+                        };
+
+                        let instr2 = target::S::jump(trg2);
+
+                        vec![instr1, instr2]
+                    },
+                },
+            }
+        }
 
-                    vec![instr1, instr2]
+        fn is_next(trg: &self::Trg, next_label: Option<&utils::Label>) -> bool {
+            match (trg, next_label) {
+                (self::Trg::label(label), Some(next_label)) => {
+                    label == next_label
                 },
+                _ => false,
             }
         }
 
@@ -100,6 +278,15 @@ impl BlockAsmLang {
                 self::Effect::set_binop { loc, binop, opand } => {
                     target::S::set_loc_binop_opand { loc, binop, opand }
                 },
+                self::Effect::set_alloc { loc, opand } => {
+                    target::S::set_loc_alloc_opand { loc, opand }
+                },
+                self::Effect::set_mref { loc, base, index } => {
+                    target::S::set_loc_mref_loc_opand { loc, base, index }
+                },
+                self::Effect::mset { loc, index, triv } => {
+                    target::S::mset_loc_opand_triv { loc, index, triv }
+                },
             }
         }
 
@@ -107,4 +294,50 @@ impl BlockAsmLang {
 
         target::ParaAsmLang(p)
     }
+
+    /// ### Purpose:
+    /// Renders the control-flow graph of the program as a Graphviz digraph,
+    /// with one node per basic block and one edge per jump. The edges of an
+    /// if are labelled with the relop under which each target is taken.
+    pub fn to_dot(&self) -> String {
+        let Self(p) = self;
+
+        fn dot_p(p: &self::P) -> Vec<(&utils::Label, Vec<utils::CfgEdge<'_>>)> {
+            match p {
+                self::P::module(bs) => bs.iter().map(dot_b).collect(),
+            }
+        }
+
+        fn dot_b(b: &self::B) -> (&utils::Label, Vec<utils::CfgEdge<'_>>) {
+            match b {
+                self::B::define_label_tail { label, tail } => {
+                    (label, dot_tail(tail))
+                },
+            }
+        }
+
+        fn dot_tail(tail: &self::Tail) -> Vec<utils::CfgEdge<'_>> {
+            match tail {
+                self::Tail::halt(..) => vec![utils::CfgEdge::halt],
+                self::Tail::jump(trg) => vec![dot_edge(trg, "jump".into())],
+                self::Tail::begin { tail, .. } => dot_tail(tail),
+                self::Tail::r#if {
+                    relop, trg1, trg2, ..
+                } => {
+                    let label1 = format!("{:?}", relop);
+                    let label2 = format!("{:?}", !*relop);
+                    vec![dot_edge(trg1, label1), dot_edge(trg2, label2)]
+                },
+            }
+        }
+
+        fn dot_edge(trg: &self::Trg, label: String) -> utils::CfgEdge<'_> {
+            match trg {
+                self::Trg::label(trg) => utils::CfgEdge::label { trg, label },
+                self::Trg::loc(..) => utils::CfgEdge::indirect { label },
+            }
+        }
+
+        utils::cfg_to_dot(dot_p(p))
+    }
 }
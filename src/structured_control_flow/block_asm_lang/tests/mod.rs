@@ -1 +1,142 @@
+use serial_test::serial;
 
+use crate::structured_control_flow::block_asm_lang as source;
+use crate::structured_control_flow::para_asm_lang as target;
+use crate::utils;
+
+#[test]
+#[serial]
+fn order_blocks_follows_jumps() {
+    utils::reset_all_indices();
+
+    let main = utils::Label::new_with_name("main");
+    let label1 = utils::Label::new_with_name("one");
+    let label2 = utils::Label::new_with_name("two");
+
+    let actual = source::BlockAsmLang(source::P::module(vec![
+        source::B::define_label_tail {
+            label: main.clone(),
+            tail: source::Tail::jump(source::Trg::label(label2.clone())),
+        },
+        source::B::define_label_tail {
+            label: label1.clone(),
+            tail: source::Tail::halt(source::Opand::int64(1)),
+        },
+        source::B::define_label_tail {
+            label: label2.clone(),
+            tail: source::Tail::jump(source::Trg::label(label1.clone())),
+        },
+    ]))
+    .order_blocks();
+
+    utils::reset_all_indices();
+
+    let expected = source::BlockAsmLang(source::P::module(vec![
+        source::B::define_label_tail {
+            label: main,
+            tail: source::Tail::jump(source::Trg::label(label2.clone())),
+        },
+        source::B::define_label_tail {
+            label: label2,
+            tail: source::Tail::jump(source::Trg::label(label1.clone())),
+        },
+        source::B::define_label_tail {
+            label: label1,
+            tail: source::Tail::halt(source::Opand::int64(1)),
+        },
+    ]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn flatten_program_falls_through() {
+    utils::reset_all_indices();
+
+    let main = utils::Label::new_with_name("main");
+    let label = utils::Label::new_with_name("next");
+
+    let actual = source::BlockAsmLang(source::P::module(vec![
+        source::B::define_label_tail {
+            label: main.clone(),
+            tail: source::Tail::jump(source::Trg::label(label.clone())),
+        },
+        source::B::define_label_tail {
+            label: label.clone(),
+            tail: source::Tail::halt(source::Opand::int64(0)),
+        },
+    ]))
+    .flatten_program();
+
+    utils::reset_all_indices();
+
+    let expected = target::ParaAsmLang(target::P::begin(vec![
+        target::S::with_label {
+            label: main,
+            s: Box::new(target::S::nop),
+        },
+        target::S::with_label {
+            label,
+            s: Box::new(target::S::halt(target::Opand::int64(0))),
+        },
+    ]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn flatten_program_inverts_if() {
+    utils::reset_all_indices();
+
+    let main = utils::Label::new_with_name("main");
+    let label_true = utils::Label::new_with_name("true");
+    let label_false = utils::Label::new_with_name("false");
+
+    let actual = source::BlockAsmLang(source::P::module(vec![
+        source::B::define_label_tail {
+            label: main.clone(),
+            tail: source::Tail::r#if {
+                relop: utils::Relop::gt,
+                loc: source::Loc::reg(utils::Reg::rax),
+                opand: source::Opand::int64(5),
+                trg1: source::Trg::label(label_true.clone()),
+                trg2: source::Trg::label(label_false.clone()),
+            },
+        },
+        source::B::define_label_tail {
+            label: label_true.clone(),
+            tail: source::Tail::halt(source::Opand::int64(1)),
+        },
+        source::B::define_label_tail {
+            label: label_false.clone(),
+            tail: source::Tail::halt(source::Opand::int64(0)),
+        },
+    ]))
+    .flatten_program();
+
+    utils::reset_all_indices();
+
+    let expected = target::ParaAsmLang(target::P::begin(vec![
+        target::S::with_label {
+            label: main,
+            s: Box::new(target::S::compare_jump {
+                loc: target::Loc::reg(utils::Reg::rax),
+                opand: target::Opand::int64(5),
+                relop: utils::Relop::lte,
+                trg: target::Trg::label(label_false.clone()),
+            }),
+        },
+        target::S::with_label {
+            label: label_true,
+            s: Box::new(target::S::halt(target::Opand::int64(1))),
+        },
+        target::S::with_label {
+            label: label_false,
+            s: Box::new(target::S::halt(target::Opand::int64(0))),
+        },
+    ]));
+
+    assert_eq!(actual, expected);
+}
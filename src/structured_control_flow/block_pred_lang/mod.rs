@@ -4,6 +4,7 @@ mod tests;
 
 pub use self::data::*;
 use crate::structured_control_flow::block_asm_lang as target;
+use crate::utils;
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub struct BlockPredLang(pub self::P);
@@ -79,4 +80,59 @@ impl BlockPredLang {
 
         target::BlockAsmLang(p)
     }
+
+    /// ### Purpose:
+    /// Renders the control-flow graph of the program as a Graphviz digraph,
+    /// with one node per basic block and one edge per jump. The edges of an
+    /// if are labelled with the relop under which each target is taken.
+    pub fn to_dot(&self) -> String {
+        let Self(p) = self;
+
+        fn dot_p(p: &self::P) -> Vec<(&utils::Label, Vec<utils::CfgEdge<'_>>)> {
+            match p {
+                self::P::module(bs) => bs.iter().map(dot_b).collect(),
+            }
+        }
+
+        fn dot_b(b: &self::B) -> (&utils::Label, Vec<utils::CfgEdge<'_>>) {
+            match b {
+                self::B::define { label, tail } => (label, dot_tail(tail)),
+            }
+        }
+
+        fn dot_tail(tail: &self::Tail) -> Vec<utils::CfgEdge<'_>> {
+            match tail {
+                self::Tail::halt(..) => vec![utils::CfgEdge::halt],
+                self::Tail::jump(trg) => vec![dot_edge(trg, "jump".into())],
+                self::Tail::begin { tail, .. } => dot_tail(tail),
+                self::Tail::r#if { pred, trg1, trg2 } => {
+                    let (label1, label2) = dot_pred(pred);
+                    vec![dot_edge(trg1, label1), dot_edge(trg2, label2)]
+                },
+            }
+        }
+
+        fn dot_pred(pred: &self::Pred) -> (String, String) {
+            match pred {
+                self::Pred::relop { relop, .. } => {
+                    (format!("{:?}", relop), format!("{:?}", !*relop))
+                },
+                self::Pred::r#true => ("true".into(), "never".into()),
+                self::Pred::r#false => ("never".into(), "false".into()),
+                self::Pred::not(pred) => {
+                    let (label1, label2) = dot_pred(pred);
+                    (label2, label1)
+                },
+            }
+        }
+
+        fn dot_edge(trg: &self::Trg, label: String) -> utils::CfgEdge<'_> {
+            match trg {
+                self::Trg::label(trg) => utils::CfgEdge::label { trg, label },
+                self::Trg::loc(..) => utils::CfgEdge::indirect { label },
+            }
+        }
+
+        utils::cfg_to_dot(dot_p(p))
+    }
 }
@@ -1 +1,55 @@
+use serial_test::serial;
 
+use crate::structured_control_flow::block_pred_lang as source;
+use crate::utils;
+
+#[test]
+#[serial]
+fn cfg_to_dot() {
+    utils::reset_all_indices();
+
+    let main = utils::Label::new_with_name("main");
+    let label_true = utils::Label::new_with_name("true");
+    let label_false = utils::Label::new_with_name("false");
+
+    let program = source::BlockPredLang(source::P::module(vec![
+        source::B::define {
+            label: main,
+            tail: source::Tail::r#if {
+                pred: source::Pred::relop {
+                    relop: utils::Relop::gt,
+                    loc: source::Loc::reg(utils::Reg::rax),
+                    opand: source::Opand::int64(5),
+                },
+                trg1: source::Trg::label(label_true.clone()),
+                trg2: source::Trg::label(label_false.clone()),
+            },
+        },
+        source::B::define {
+            label: label_true,
+            tail: source::Tail::halt(source::Opand::int64(1)),
+        },
+        source::B::define {
+            label: label_false,
+            tail: source::Tail::halt(source::Opand::int64(0)),
+        },
+    ]));
+
+    let actual = program.to_dot();
+
+    utils::reset_all_indices();
+
+    let expected = "digraph cfg {
+    \"halt\" [shape=doublecircle];
+    \"indirect\" [shape=diamond];
+    \"L.main.0\" [shape=box];
+    \"L.main.0\" -> \"L.true.1\" [label=\"gt\"];
+    \"L.main.0\" -> \"L.false.2\" [label=\"lte\"];
+    \"L.true.1\" [shape=box];
+    \"L.true.1\" -> \"halt\";
+    \"L.false.2\" [shape=box];
+    \"L.false.2\" -> \"halt\";
+}";
+
+    assert_eq!(actual, expected);
+}
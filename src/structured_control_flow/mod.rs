@@ -4,17 +4,24 @@ pub mod nested_asm_lang;
 pub mod para_asm_lang;
 pub mod paren_x64_fvars;
 
+use crate::utils;
+
 pub type Source = nested_asm_lang::NestedAsmLang;
 
 pub type Target = crate::x64::Source;
 
-pub fn compile(p: Source) -> Result<Target, String> {
+pub fn compile(
+    p: Source,
+    config: &utils::TargetConfig,
+    options: &utils::CompileOptions,
+) -> Result<Target, String> {
     let p = p
         .optimize_predicates()
         .expose_basic_blocks()
         .resolve_predicates()
+        .order_blocks()
         .flatten_program()
-        .patch_instructions()
-        .implement_fvars();
+        .patch_instructions(config, options)
+        .implement_fvars(config);
     Ok(p)
 }
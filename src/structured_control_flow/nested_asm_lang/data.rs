@@ -2,7 +2,13 @@ use crate::utils;
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub enum P {
-    module(Tail),
+    module { lambdas: Vec<Lambda>, tail: Tail },
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct Lambda {
+    pub label: utils::Label,
+    pub tail: Tail,
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
@@ -38,6 +44,7 @@ pub enum Tail {
         tail1: Box<Self>,
         tail2: Box<Self>,
     },
+    jump(Trg),
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
@@ -51,14 +58,54 @@ pub enum Effect {
         binop: utils::Binop,
         triv: Triv,
     },
+    /// Sets the loc to the address of the label.
+    set_label {
+        loc: Loc,
+        label: utils::Label,
+    },
+    set_alloc {
+        loc: Loc,
+        triv: Triv,
+    },
+    set_mref {
+        loc: Loc,
+        base: Loc,
+        index: Triv,
+    },
+    mset {
+        loc: Loc,
+        index: Triv,
+        triv: Triv,
+    },
     begin(Vec<Self>),
     r#if {
         pred: Pred,
         effect1: Box<Self>,
         effect2: Box<Self>,
     },
+    r#while {
+        pred: Pred,
+        effect: Box<Self>,
+    },
+    /// Sets the loc to a jump table, which holds the address of each label.
+    set_jump_table {
+        loc: Loc,
+        labels: Vec<utils::Label>,
+    },
+    /// Jumps through the table to the label at the index in the loc, or to
+    /// the last label if the index is outside the table. Each label marks the
+    /// effect at the same position, after which the switch is done.
+    switch {
+        loc: Loc,
+        table: Loc,
+        length: usize,
+        labels: Vec<utils::Label>,
+        effects: Vec<Self>,
+    },
 }
 
 pub type Loc = super::target::Loc;
 
 pub type Triv = super::target::Opand;
+
+pub type Trg = super::target::Trg;
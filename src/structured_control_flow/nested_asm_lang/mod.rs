@@ -19,18 +19,35 @@ impl NestedAsmLang {
 
     /// ### Purpose:
     /// Compile the Nested-asm-lang v4 to Block-pred-lang v4, eliminating all
-    /// nested expressions by generating fresh basic blocks and jumps.
+    /// nested expressions by generating fresh basic blocks and jumps. The main
+    /// block is the entry of the program, and is therefore placed first. Each
+    /// procedure becomes a block with its own label.
+    ///
+    /// ### Notes:
+    /// A while becomes a loop block which tests the pred, and a body block
+    /// which ends with a jump back to the loop block.
+    ///
+    /// A jump table is allocated on the heap and filled with its labels. A
+    /// switch checks that its index is within the table, and then jumps to
+    /// the label read from the table at that index. Each of its effects is a
+    /// block which ends with a jump to the block after the switch.
     pub fn expose_basic_blocks(self) -> target::BlockPredLang {
         let Self(p) = self;
 
         fn expose_p(p: self::P) -> target::P {
             match p {
-                self::P::module(tail) => {
+                self::P::module { lambdas, tail } => {
                     let bs = vec![];
                     let (tail, mut bs) = expose_tail(tail, bs);
                     let label = utils::Label::new_with_name("main");
                     let b = target::B::define { label, tail };
-                    bs.push(b);
+                    bs.insert(0, b);
+                    let bs = lambdas.into_iter().fold(bs, |bs, lambda| {
+                        let self::Lambda { label, tail } = lambda;
+                        let (tail, mut bs) = expose_tail(tail, bs);
+                        bs.push(target::B::define { label, tail });
+                        bs
+                    });
                     target::P::module(bs)
                 },
             }
@@ -42,6 +59,7 @@ impl NestedAsmLang {
         ) -> (target::Tail, Vec<target::B>) {
             match tail {
                 self::Tail::halt(triv) => (target::Tail::halt(triv), bs),
+                self::Tail::jump(trg) => (target::Tail::jump(trg), bs),
                 self::Tail::begin { effects, tail } => {
                     let (tail, bs) = expose_tail(*tail, bs);
                     expose_effects(effects, tail, bs)
@@ -91,6 +109,36 @@ impl NestedAsmLang {
                         make_begins!((effects, tail) => target::Tail::tail);
                     (tail, bs)
                 },
+                self::Effect::set_label { loc, label } => {
+                    let triv = target::Triv::label(label);
+                    let effect = target::Effect::set { loc, triv };
+                    let mut effects = vec![effect];
+                    let tail =
+                        make_begins!((effects, tail) => target::Tail::tail);
+                    (tail, bs)
+                },
+                self::Effect::set_alloc { loc, triv } => {
+                    let effect = target::Effect::set_alloc { loc, opand: triv };
+                    let mut effects = vec![effect];
+                    let tail =
+                        make_begins!((effects, tail) => target::Tail::tail);
+                    (tail, bs)
+                },
+                self::Effect::set_mref { loc, base, index } => {
+                    let effect = target::Effect::set_mref { loc, base, index };
+                    let mut effects = vec![effect];
+                    let tail =
+                        make_begins!((effects, tail) => target::Tail::tail);
+                    (tail, bs)
+                },
+                self::Effect::mset { loc, index, triv } => {
+                    let triv = target::Triv::opand(triv);
+                    let effect = target::Effect::mset { loc, index, triv };
+                    let mut effects = vec![effect];
+                    let tail =
+                        make_begins!((effects, tail) => target::Tail::tail);
+                    (tail, bs)
+                },
                 self::Effect::begin(effects) => {
                     expose_effects(effects, tail, bs)
                 },
@@ -122,6 +170,127 @@ impl NestedAsmLang {
                     bs.push(b2);
                     expose_pred(pred, label_true, label_false, bs)
                 },
+                // loop: if pred then body else final
+                // body: effect; jump loop
+                self::Effect::r#while { pred, effect } => {
+                    let r#loop = utils::Label::new_with_name("loop");
+                    let body = utils::Label::new_with_name("body");
+                    let r#final = utils::Label::new_with_name("final");
+                    let b = target::B::define {
+                        label: r#final.clone(),
+                        tail,
+                    };
+                    bs.push(b);
+                    let tail = target::Tail::jump(target::Trg::label(
+                        r#loop.clone(),
+                    ));
+                    let (tail_body, bs) = expose_effect(*effect, tail, bs);
+                    let (tail_loop, mut bs) =
+                        expose_pred(pred, body.clone(), r#final, bs);
+                    let b1 = target::B::define {
+                        label: r#loop.clone(),
+                        tail: tail_loop,
+                    };
+                    let b2 = target::B::define {
+                        label: body,
+                        tail: tail_body,
+                    };
+                    bs.push(b1);
+                    bs.push(b2);
+                    (target::Tail::jump(target::Trg::label(r#loop)), bs)
+                },
+                self::Effect::set_jump_table { loc, labels } => {
+                    let opand = target::Opand::int64(8 * labels.len() as i64);
+                    let effect = target::Effect::set_alloc {
+                        loc: loc.clone(),
+                        opand,
+                    };
+                    let mut effects = vec![effect];
+                    effects.extend(labels.into_iter().enumerate().map(
+                        |(index, label)| target::Effect::mset {
+                            loc: loc.clone(),
+                            index: target::Opand::int64(8 * index as i64),
+                            triv: target::Triv::label(label),
+                        },
+                    ));
+                    let tail =
+                        make_begins!((effects, tail) => target::Tail::tail);
+                    (tail, bs)
+                },
+                // if loc < 0 then default else check
+                // check: if loc >= length then default else dispatch
+                // dispatch: loc <- loc << 3; loc <- mref table loc; jump loc
+                self::Effect::switch {
+                    loc,
+                    table,
+                    length,
+                    labels,
+                    effects,
+                } => {
+                    let r#final = utils::Label::new_with_name("final");
+                    let b = target::B::define {
+                        label: r#final.clone(),
+                        tail,
+                    };
+                    bs.push(b);
+                    let tail = target::Tail::jump(target::Trg::label(r#final));
+                    let default = labels.last().unwrap().clone();
+                    let mut bs = labels.into_iter().zip(effects).fold(
+                        bs,
+                        |bs, (label, effect)| {
+                            let (tail, mut bs) =
+                                expose_effect(effect, tail.clone(), bs);
+                            bs.push(target::B::define { label, tail });
+                            bs
+                        },
+                    );
+                    let check = utils::Label::new_with_name("check");
+                    let dispatch = utils::Label::new_with_name("dispatch");
+                    let effect1 = target::Effect::set_binop {
+                        loc: loc.clone(),
+                        binop: utils::Binop::shift_left,
+                        opand: target::Opand::int64(3),
+                    };
+                    let effect2 = target::Effect::set_mref {
+                        loc: loc.clone(),
+                        base: table,
+                        index: target::Opand::loc(loc.clone()),
+                    };
+                    let mut effects = vec![effect1, effect2];
+                    let tail =
+                        target::Tail::jump(target::Trg::loc(loc.clone()));
+                    let tail_dispatch =
+                        make_begins!((effects, tail) => target::Tail::tail);
+                    let tail_check = target::Tail::r#if {
+                        pred: target::Pred::relop {
+                            relop: utils::Relop::gte,
+                            loc: loc.clone(),
+                            opand: target::Opand::int64(length as i64),
+                        },
+                        trg1: target::Trg::label(default.clone()),
+                        trg2: target::Trg::label(dispatch.clone()),
+                    };
+                    let b1 = target::B::define {
+                        label: check.clone(),
+                        tail: tail_check,
+                    };
+                    let b2 = target::B::define {
+                        label: dispatch,
+                        tail: tail_dispatch,
+                    };
+                    bs.push(b1);
+                    bs.push(b2);
+                    let tail = target::Tail::r#if {
+                        pred: target::Pred::relop {
+                            relop: utils::Relop::lt,
+                            loc,
+                            opand: target::Opand::int64(0),
+                        },
+                        trg1: target::Trg::label(default),
+                        trg2: target::Trg::label(check),
+                    };
+                    (tail, bs)
+                },
             }
         }
 
@@ -130,7 +299,7 @@ impl NestedAsmLang {
             tail: target::Tail,
             bs: Vec<target::B>,
         ) -> (target::Tail, Vec<target::B>) {
-            effects.into_iter().fold((tail, bs), |(tail, bs), effect| {
+            effects.into_iter().rev().fold((tail, bs), |(tail, bs), effect| {
                 expose_effect(effect, tail, bs)
             })
         }
@@ -199,8 +368,6 @@ impl NestedAsmLang {
                 },
 
                 self::Pred::begin { effects, pred } => {
-                    let label_true = utils::Label::new_with_name("true");
-                    let label_false = utils::Label::new_with_name("false");
                     let (tail, bs) =
                         expose_pred(*pred, label_true, label_false, bs);
                     expose_effects(effects, tail, bs)
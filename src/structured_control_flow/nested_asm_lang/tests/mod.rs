@@ -7,9 +7,12 @@ use crate::utils;
 #[test]
 #[serial]
 fn basic_halt() {
-    let program = source::NestedAsmLang(source::P::module(source::Tail::halt(
-        source::Triv::int64(5),
-    )));
+    utils::reset_all_indices();
+
+    let program = source::NestedAsmLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::halt(source::Triv::int64(5)),
+    });
 
     let actual = program.expose_basic_blocks();
 
@@ -27,11 +30,15 @@ fn basic_halt() {
 #[test]
 #[serial]
 fn with_no_effects() {
-    let program =
-        source::NestedAsmLang(source::P::module(source::Tail::begin {
+    utils::reset_all_indices();
+
+    let program = source::NestedAsmLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::begin {
             effects: vec![],
             tail: Box::new(source::Tail::halt(source::Triv::int64(5))),
-        }));
+        },
+    });
 
     let actual = program.expose_basic_blocks();
 
@@ -49,14 +56,18 @@ fn with_no_effects() {
 #[test]
 #[serial]
 fn with_effect() {
-    let program =
-        source::NestedAsmLang(source::P::module(source::Tail::begin {
+    utils::reset_all_indices();
+
+    let program = source::NestedAsmLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::begin {
             effects: vec![source::Effect::set {
                 loc: source::Loc::reg(utils::Reg::r10),
                 triv: source::Triv::int64(5),
             }],
             tail: Box::new(source::Tail::halt(source::Triv::int64(5))),
-        }));
+        },
+    });
 
     let actual = program.expose_basic_blocks();
 
@@ -80,8 +91,11 @@ fn with_effect() {
 #[test]
 #[serial]
 fn nested_begin_with_no_effects() {
-    let program =
-        source::NestedAsmLang(source::P::module(source::Tail::begin {
+    utils::reset_all_indices();
+
+    let program = source::NestedAsmLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::begin {
             effects: vec![
                 source::Effect::set {
                     loc: source::Loc::reg(utils::Reg::r10),
@@ -90,7 +104,8 @@ fn nested_begin_with_no_effects() {
                 source::Effect::begin(vec![]),
             ],
             tail: Box::new(source::Tail::halt(source::Triv::int64(5))),
-        }));
+        },
+    });
 
     let actual = program.expose_basic_blocks();
 
@@ -114,8 +129,11 @@ fn nested_begin_with_no_effects() {
 #[test]
 #[serial]
 fn nested_begin() {
-    let program =
-        source::NestedAsmLang(source::P::module(source::Tail::begin {
+    utils::reset_all_indices();
+
+    let program = source::NestedAsmLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::begin {
             effects: vec![
                 source::Effect::set {
                     loc: source::Loc::reg(utils::Reg::r10),
@@ -133,7 +151,8 @@ fn nested_begin() {
                 ]),
             ],
             tail: Box::new(source::Tail::halt(source::Triv::int64(5))),
-        }));
+        },
+    });
 
     let actual = program.expose_basic_blocks();
 
@@ -163,3 +182,250 @@ fn nested_begin() {
 
     assert_eq!(actual, expected);
 }
+
+#[test]
+#[serial]
+fn effects_before_begin_tail() {
+    utils::reset_all_indices();
+
+    let program = source::NestedAsmLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![
+                source::Effect::set {
+                    loc: source::Loc::reg(utils::Reg::r10),
+                    triv: source::Triv::int64(1),
+                },
+                source::Effect::set {
+                    loc: source::Loc::reg(utils::Reg::r11),
+                    triv: source::Triv::int64(2),
+                },
+            ],
+            tail: Box::new(source::Tail::begin {
+                effects: vec![source::Effect::set {
+                    loc: source::Loc::reg(utils::Reg::r12),
+                    triv: source::Triv::int64(3),
+                }],
+                tail: Box::new(source::Tail::halt(source::Triv::int64(5))),
+            }),
+        },
+    });
+
+    let actual = program.expose_basic_blocks();
+
+    utils::reset_all_indices();
+
+    let expected =
+        target::BlockPredLang(target::P::module(vec![target::B::define {
+            label: utils::Label::new_with_name("main"),
+            tail: target::Tail::begin {
+                effects: vec![
+                    target::Effect::set {
+                        loc: target::Loc::reg(utils::Reg::r10),
+                        triv: target::Triv::opand(target::Opand::int64(1)),
+                    },
+                    target::Effect::set {
+                        loc: target::Loc::reg(utils::Reg::r11),
+                        triv: target::Triv::opand(target::Opand::int64(2)),
+                    },
+                    target::Effect::set {
+                        loc: target::Loc::reg(utils::Reg::r12),
+                        triv: target::Triv::opand(target::Opand::int64(3)),
+                    },
+                ],
+                tail: Box::new(target::Tail::halt(target::Opand::int64(5))),
+            },
+        }]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn if_with_begin_pred() {
+    utils::reset_all_indices();
+
+    let program = source::NestedAsmLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::r#if {
+            pred: source::Pred::begin {
+                effects: vec![source::Effect::set {
+                    loc: source::Loc::reg(utils::Reg::r10),
+                    triv: source::Triv::int64(1),
+                }],
+                pred: Box::new(source::Pred::relop {
+                    relop: utils::Relop::gt,
+                    loc: source::Loc::reg(utils::Reg::r10),
+                    triv: source::Triv::int64(0),
+                }),
+            },
+            tail1: Box::new(source::Tail::halt(source::Triv::int64(1))),
+            tail2: Box::new(source::Tail::halt(source::Triv::int64(2))),
+        },
+    });
+
+    let actual = program.expose_basic_blocks();
+
+    utils::reset_all_indices();
+
+    let label_true = utils::Label::new_with_name("true");
+    let label_false = utils::Label::new_with_name("false");
+    let expected = target::BlockPredLang(target::P::module(vec![
+        target::B::define {
+            label: utils::Label::new_with_name("main"),
+            tail: target::Tail::begin {
+                effects: vec![target::Effect::set {
+                    loc: target::Loc::reg(utils::Reg::r10),
+                    triv: target::Triv::opand(target::Opand::int64(1)),
+                }],
+                tail: Box::new(target::Tail::r#if {
+                    pred: target::Pred::relop {
+                        relop: utils::Relop::gt,
+                        loc: target::Loc::reg(utils::Reg::r10),
+                        opand: target::Opand::int64(0),
+                    },
+                    trg1: target::Trg::label(label_true.clone()),
+                    trg2: target::Trg::label(label_false.clone()),
+                }),
+            },
+        },
+        target::B::define {
+            label: label_true,
+            tail: target::Tail::halt(target::Opand::int64(1)),
+        },
+        target::B::define {
+            label: label_false,
+            tail: target::Tail::halt(target::Opand::int64(2)),
+        },
+    ]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn while_loop() {
+    utils::reset_all_indices();
+
+    let config = utils::TargetConfig::default();
+    let sum = source::Loc::reg(utils::Reg::r8);
+    let index = source::Loc::reg(utils::Reg::r9);
+    let program = source::NestedAsmLang(source::P::module {
+        lambdas: vec![],
+        tail: source::Tail::begin {
+            effects: vec![
+                source::Effect::set {
+                    loc: sum.clone(),
+                    triv: source::Triv::int64(0),
+                },
+                source::Effect::set {
+                    loc: index.clone(),
+                    triv: source::Triv::int64(1),
+                },
+                source::Effect::r#while {
+                    pred: source::Pred::relop {
+                        relop: utils::Relop::lte,
+                        loc: index.clone(),
+                        triv: source::Triv::int64(10),
+                    },
+                    effect: Box::new(source::Effect::begin(vec![
+                        source::Effect::set_binop {
+                            loc: sum.clone(),
+                            binop: utils::Binop::plus,
+                            triv: source::Triv::loc(index.clone()),
+                        },
+                        source::Effect::set_binop {
+                            loc: index,
+                            binop: utils::Binop::plus,
+                            triv: source::Triv::int64(1),
+                        },
+                    ])),
+                },
+            ],
+            tail: Box::new(source::Tail::halt(source::Triv::loc(sum))),
+        },
+    });
+
+    let actual = program
+        .expose_basic_blocks()
+        .resolve_predicates()
+        .order_blocks()
+        .flatten_program()
+        .patch_instructions(&config, &utils::CompileOptions::default())
+        .implement_fvars(&config)
+        .link_paren_x64()
+        .interp_loop(&config);
+
+    assert_eq!(actual, 55);
+}
+
+#[test]
+#[serial]
+fn switch_through_jump_table() {
+    utils::reset_all_indices();
+
+    let run = |index| {
+        utils::reset_all_indices();
+
+        let config = utils::TargetConfig::default();
+        let table = source::Loc::reg(utils::Reg::r8);
+        let loc = source::Loc::reg(utils::Reg::r9);
+        let result = source::Loc::reg(utils::Reg::r13);
+        let labels = ["one", "two", "default"]
+            .map(utils::Label::new_with_name)
+            .to_vec();
+        let set_result = |int64| source::Effect::set {
+            loc: result.clone(),
+            triv: source::Triv::int64(int64),
+        };
+        let program = source::NestedAsmLang(source::P::module {
+            lambdas: vec![],
+            tail: source::Tail::begin {
+                effects: vec![
+                    source::Effect::set_jump_table {
+                        loc: table.clone(),
+                        labels: vec![
+                            labels[0].clone(),
+                            labels[1].clone(),
+                            labels[1].clone(),
+                            labels[2].clone(),
+                        ],
+                    },
+                    source::Effect::set {
+                        loc: loc.clone(),
+                        triv: source::Triv::int64(index),
+                    },
+                    source::Effect::switch {
+                        loc,
+                        table,
+                        length: 4,
+                        labels,
+                        effects: vec![
+                            set_result(10),
+                            set_result(20),
+                            set_result(30),
+                        ],
+                    },
+                ],
+                tail: Box::new(source::Tail::halt(source::Triv::loc(result))),
+            },
+        });
+
+        program
+            .expose_basic_blocks()
+            .resolve_predicates()
+            .order_blocks()
+            .flatten_program()
+            .patch_instructions(&config, &utils::CompileOptions::default())
+            .implement_fvars(&config)
+            .link_paren_x64()
+            .interp_loop(&config)
+    };
+
+    assert_eq!(run(0), 10);
+    assert_eq!(run(1), 20);
+    assert_eq!(run(2), 20);
+    assert_eq!(run(3), 30);
+    assert_eq!(run(4), 30);
+    assert_eq!(run(-1), 30);
+}
@@ -17,6 +17,20 @@ pub enum S {
         binop: utils::Binop,
         opand: Opand,
     },
+    set_loc_mref_loc_opand {
+        loc: Loc,
+        base: Loc,
+        index: Opand,
+    },
+    mset_loc_opand_triv {
+        loc: Loc,
+        index: Opand,
+        triv: Triv,
+    },
+    set_loc_alloc_opand {
+        loc: Loc,
+        opand: Opand,
+    },
     jump(Trg),
     with_label {
         label: utils::Label,
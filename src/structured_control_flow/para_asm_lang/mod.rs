@@ -15,21 +15,55 @@ impl ParaAsmLang {
     /// that have no x64 analogue into a sequence of instructions.
     ///
     /// ### Notes:
-    /// The implementation should use auxiliary registers from
-    /// current-patch-instructions-registers when generating instruction
-    /// sequences, and current-return-value-register for compiling halt.
-    pub fn patch_instructions(self) -> target::ParenX64Fvars {
+    /// The implementation uses the auxiliary registers of the target
+    /// configuration when generating instruction sequences, and its return
+    /// register for compiling halt. Shifts by a location go through rcx, and
+    /// quotient and remainder go through rax and rdx, saving both around the
+    /// division in two frame variables which every division shares.
+    /// Allocation bumps the heap pointer of the target configuration. In
+    /// checked overflow mode, every addition, subtraction and multiplication
+    /// of the source program is followed by a jump to an overflow block,
+    /// which halts with the overflow error code.
+    pub fn patch_instructions(
+        self,
+        config: &utils::TargetConfig,
+        options: &utils::CompileOptions,
+    ) -> target::ParenX64Fvars {
         let Self(p) = self;
 
-        fn patch_p(p: self::P) -> target::P {
+        fn patch_p(
+            p: self::P,
+            config: &utils::TargetConfig,
+            options: &utils::CompileOptions,
+        ) -> target::P {
             match p {
                 self::P::begin(ss) => {
+                    let mut scratch = None;
                     let mut ss = ss
                         .into_iter()
-                        .map(patch_s)
-                        .flatten()
+                        .flat_map(|s| match options.overflow_mode {
+                            utils::OverflowMode::wrapping => {
+                                patch_s(s, &mut scratch, config)
+                            },
+                            utils::OverflowMode::checked => {
+                                check_overflow(s, &mut scratch, config)
+                            },
+                        })
                         .collect::<Vec<_>>();
 
+                    if let utils::OverflowMode::checked = options.overflow_mode
+                    {
+                        ss.push(target::S::with_label {
+                            label: utils::Label::overflow_label(),
+                            s: Box::new(target::S::set_reg_triv {
+                                reg: config.return_register,
+                                triv: target::Triv::int64(
+                                    utils::RuntimeError::overflow.code(),
+                                ),
+                            }),
+                        });
+                    }
+
                     let halt_label = utils::Label::halt_label();
 
                     let halt_instr = target::S::with_label {
@@ -44,10 +78,14 @@ impl ParaAsmLang {
             }
         }
 
-        fn patch_s(s: self::S) -> Vec<target::S> {
+        fn patch_s(
+            s: self::S,
+            scratch: &mut Option<(utils::Fvar, utils::Fvar)>,
+            config: &utils::TargetConfig,
+        ) -> Vec<target::S> {
             match s {
                 self::S::halt(opand) => {
-                    let return_reg = utils::Reg::current_return_reg();
+                    let return_reg = config.return_register;
 
                     let instr1 = match opand {
                         self::Opand::int64(int64) => target::S::set_reg_triv {
@@ -106,7 +144,7 @@ impl ParaAsmLang {
                                     // reg <- int64
                                     // fvar <- reg
                                     || {
-                                        let (aux_reg, _) = utils::Reg::current_auxiliary_registers();
+                                        let (aux_reg, _) = config.auxiliary_registers;
 
                                         let instr1 = target::S::set_reg_triv { reg: aux_reg, triv: target::Triv::int64(int64) };
 
@@ -141,7 +179,7 @@ impl ParaAsmLang {
                                     // reg <- fvar2
                                     // fvar <- reg
                                     self::Loc::fvar(fvar2) => {
-                                        let (aux_reg, _) = utils::Reg::current_auxiliary_registers();
+                                        let (aux_reg, _) = config.auxiliary_registers;
 
                                         let instr1 = target::S::set_reg_loc {
                                             reg: aux_reg,
@@ -171,6 +209,39 @@ impl ParaAsmLang {
                     },
                 },
 
+                // loc <- loc << (int64 & 63)
+                self::S::set_loc_binop_opand {
+                    loc,
+                    binop:
+                        binop @ (utils::Binop::arithmetic_shift_right
+                        | utils::Binop::shift_left),
+                    opand: self::Opand::int64(int64),
+                } if !(0..64).contains(&int64) => {
+                    let s = self::S::set_loc_binop_opand {
+                        loc,
+                        binop,
+                        opand: self::Opand::int64(int64 & 63),
+                    };
+
+                    patch_s(s, scratch, config)
+                },
+
+                self::S::set_loc_binop_opand {
+                    loc,
+                    binop:
+                        binop @ (utils::Binop::arithmetic_shift_right
+                        | utils::Binop::shift_left),
+                    opand: self::Opand::loc(count),
+                } => patch_shift(loc, binop, count, config),
+
+                self::S::set_loc_binop_opand {
+                    loc,
+                    binop:
+                        binop @ (utils::Binop::quotient
+                        | utils::Binop::remainder),
+                    opand,
+                } => patch_divide(loc, binop, opand, scratch, config),
+
                 self::S::set_loc_binop_opand { loc, binop, opand } => {
                     match (loc, opand) {
                         (self::Loc::reg(reg), self::Opand::int64(int64)) => {
@@ -179,8 +250,7 @@ impl ParaAsmLang {
                                 // reg <- reg + aux_reg
                                 || {
                                     let (aux_reg, _) =
-                                        utils::Reg::current_auxiliary_registers(
-                                        );
+                                        config.auxiliary_registers;
 
                                     let instr1 = target::S::set_reg_triv {
                                         reg: aux_reg,
@@ -229,8 +299,7 @@ impl ParaAsmLang {
                                 // fvar <- aux_reg
                                 || {
                                     let (aux_reg, aux_reg_2) =
-                                        utils::Reg::current_auxiliary_registers(
-                                        );
+                                        config.auxiliary_registers;
 
                                     let instr1 = target::S::set_reg_triv {
                                         reg: aux_reg,
@@ -261,8 +330,7 @@ impl ParaAsmLang {
                                 // fvar <- aux_reg
                                 |int32| {
                                     let (aux_reg, _) =
-                                        utils::Reg::current_auxiliary_registers(
-                                        );
+                                        config.auxiliary_registers;
 
                                     let instr1 = target::S::set_reg_loc {
                                         reg: aux_reg,
@@ -293,8 +361,7 @@ impl ParaAsmLang {
                                 // fvar <- aux_reg
                                 self::Loc::reg(reg) => {
                                     let (aux_reg, _) =
-                                        utils::Reg::current_auxiliary_registers(
-                                        );
+                                        config.auxiliary_registers;
 
                                     let instr1 = target::S::set_reg_loc {
                                         reg: aux_reg,
@@ -322,8 +389,7 @@ impl ParaAsmLang {
                                 // fvar <- aux_reg
                                 self::Loc::fvar(fvar2) => {
                                     let (aux_reg, aux_reg_2) =
-                                        utils::Reg::current_auxiliary_registers(
-                                        );
+                                        config.auxiliary_registers;
 
                                     let instr1 = target::S::set_reg_loc {
                                         reg: aux_reg,
@@ -354,6 +420,18 @@ impl ParaAsmLang {
                     }
                 },
 
+                self::S::set_loc_mref_loc_opand { loc, base, index } => {
+                    patch_mref(loc, base, index, config)
+                },
+
+                self::S::mset_loc_opand_triv { loc, index, triv } => {
+                    patch_mset(loc, index, triv, config)
+                },
+
+                self::S::set_loc_alloc_opand { loc, opand } => {
+                    patch_alloc(loc, opand, config)
+                },
+
                 self::S::jump(trg) => match trg {
                     self::Trg::label(label) => {
                         let instr = target::S::jump(target::Trg::label(label));
@@ -367,8 +445,7 @@ impl ParaAsmLang {
                             vec![instr]
                         },
                         self::Loc::fvar(fvar) => {
-                            let (aux_reg, _) =
-                                utils::Reg::current_auxiliary_registers();
+                            let (aux_reg, _) = config.auxiliary_registers;
 
                             let instr1 = target::S::set_reg_loc {
                                 reg: aux_reg,
@@ -383,17 +460,19 @@ impl ParaAsmLang {
                     },
                 },
 
-                self::S::with_label { label, s } => patch_s(*s)
-                    .into_iter()
-                    .enumerate()
-                    .map(|(index, s)| match index {
-                        0usize => target::S::with_label {
-                            label: label.clone(),
-                            s: Box::new(s),
-                        },
-                        _ => s,
-                    })
-                    .collect(),
+                self::S::with_label { label, s } => {
+                    patch_s(*s, scratch, config)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, s)| match index {
+                            0usize => target::S::with_label {
+                                label: label.clone(),
+                                s: Box::new(s),
+                            },
+                            _ => s,
+                        })
+                        .collect()
+                },
 
                 self::S::compare_jump {
                     loc,
@@ -440,8 +519,7 @@ impl ParaAsmLang {
                             vec![instr1, instr2, instr3]
                         },
                         self::Loc::fvar(fvar) => {
-                            let (aux_reg, _) =
-                                utils::Reg::current_auxiliary_registers();
+                            let (aux_reg, _) = config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -484,8 +562,7 @@ impl ParaAsmLang {
                             vec![instr]
                         },
                         self::Loc::fvar(fvar) => {
-                            let (aux_reg, _) =
-                                utils::Reg::current_auxiliary_registers();
+                            let (aux_reg, _) = config.auxiliary_registers;
 
                             let instr1 = target::S::set_reg_loc {
                                 reg: aux_reg,
@@ -528,8 +605,7 @@ impl ParaAsmLang {
                             vec![instr1, instr2, instr3]
                         },
                         (self::Loc::reg(reg2), self::Loc::fvar(fvar3)) => {
-                            let (aux_reg, _) =
-                                utils::Reg::current_auxiliary_registers();
+                            let (aux_reg, _) = config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -556,8 +632,7 @@ impl ParaAsmLang {
                             vec![instr1, instr2, instr3, instr4]
                         },
                         (self::Loc::fvar(fvar2), self::Loc::reg(reg3)) => {
-                            let (aux_reg, _) =
-                                utils::Reg::current_auxiliary_registers();
+                            let (aux_reg, _) = config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -585,7 +660,7 @@ impl ParaAsmLang {
                         },
                         (self::Loc::fvar(fvar2), self::Loc::fvar(fvar3)) => {
                             let (aux_reg, aux_reg_2) =
-                                utils::Reg::current_auxiliary_registers();
+                                config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -623,8 +698,7 @@ impl ParaAsmLang {
                         self::Opand::int64(int64),
                         self::Trg::label(label),
                     ) => {
-                        let (aux_reg, _) =
-                            utils::Reg::current_auxiliary_registers();
+                        let (aux_reg, _) = config.auxiliary_registers;
 
                         let instr1 = target::S::set_reg_loc {
                             reg: aux_reg,
@@ -646,8 +720,7 @@ impl ParaAsmLang {
                         self::Trg::loc(loc),
                     ) => match loc {
                         self::Loc::reg(reg) => {
-                            let (aux_reg, _) =
-                                utils::Reg::current_auxiliary_registers();
+                            let (aux_reg, _) = config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -674,7 +747,7 @@ impl ParaAsmLang {
                         },
                         self::Loc::fvar(fvar3) => {
                             let (aux_reg, aux_reg_2) =
-                                utils::Reg::current_auxiliary_registers();
+                                config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -712,8 +785,7 @@ impl ParaAsmLang {
                         self::Trg::label(label),
                     ) => match loc {
                         self::Loc::reg(reg2) => {
-                            let (aux_reg, _) =
-                                utils::Reg::current_auxiliary_registers();
+                            let (aux_reg, _) = config.auxiliary_registers;
 
                             let instr1 = target::S::set_reg_loc {
                                 reg: aux_reg,
@@ -731,7 +803,7 @@ impl ParaAsmLang {
                         },
                         self::Loc::fvar(fvar2) => {
                             let (aux_reg, aux_reg_2) =
-                                utils::Reg::current_auxiliary_registers();
+                                config.auxiliary_registers;
 
                             let instr1 = target::S::set_reg_loc {
                                 reg: aux_reg,
@@ -759,8 +831,7 @@ impl ParaAsmLang {
                         self::Trg::loc(loc2),
                     ) => match (loc, loc2) {
                         (self::Loc::reg(reg2), self::Loc::reg(reg3)) => {
-                            let (aux_reg, _) =
-                                utils::Reg::current_auxiliary_registers();
+                            let (aux_reg, _) = config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -788,7 +859,7 @@ impl ParaAsmLang {
                         },
                         (self::Loc::reg(reg2), self::Loc::fvar(fvar3)) => {
                             let (aux_reg, aux_reg_2) =
-                                utils::Reg::current_auxiliary_registers();
+                                config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -821,7 +892,7 @@ impl ParaAsmLang {
                         },
                         (self::Loc::fvar(fvar2), self::Loc::reg(reg3)) => {
                             let (aux_reg, aux_reg_2) =
-                                utils::Reg::current_auxiliary_registers();
+                                config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -854,7 +925,7 @@ impl ParaAsmLang {
                         },
                         (self::Loc::fvar(fvar2), self::Loc::fvar(fvar3)) => {
                             let (aux_reg, aux_reg_2) =
-                                utils::Reg::current_auxiliary_registers();
+                                config.auxiliary_registers;
 
                             let label = generate_neg_jump_label();
 
@@ -901,7 +972,427 @@ impl ParaAsmLang {
             }
         }
 
-        let p = patch_p(p);
+        // reg <- reg + opand
+        // jump-if-overflow overflow
+        // ...
+        // overflow: return_reg <- overflow-code
+        //
+        // Only the binops of the source program are checked, not the
+        // arithmetic which patching introduces itself. The jump follows the
+        // whole patched sequence, as storing the result back does not touch
+        // the flags.
+        fn check_overflow(
+            s: self::S,
+            scratch: &mut Option<(utils::Fvar, utils::Fvar)>,
+            config: &utils::TargetConfig,
+        ) -> Vec<target::S> {
+            fn can_overflow(s: &self::S) -> bool {
+                match s {
+                    self::S::set_loc_binop_opand { binop, .. } => {
+                        matches!(
+                            binop,
+                            utils::Binop::plus
+                                | utils::Binop::minus
+                                | utils::Binop::multiply
+                        )
+                    },
+                    self::S::with_label { s, .. } => can_overflow(s),
+                    _ => false,
+                }
+            }
+
+            let can_overflow = can_overflow(&s);
+
+            let mut ss = patch_s(s, scratch, config);
+
+            if can_overflow {
+                ss.push(target::S::jump_if_overflow {
+                    label: utils::Label::overflow_label(),
+                });
+            }
+
+            ss
+        }
+
+        // aux_reg <- loc
+        // aux_reg' <- rcx
+        // rcx <- count
+        // aux_reg <- aux_reg << rcx
+        // rcx <- aux_reg'
+        // loc <- aux_reg
+        fn patch_shift(
+            loc: self::Loc,
+            binop: utils::Binop,
+            count: self::Loc,
+            config: &utils::TargetConfig,
+        ) -> Vec<target::S> {
+            let (aux_reg, aux_reg_2) = config.auxiliary_registers;
+
+            let instr1 = target::S::set_reg_loc {
+                reg: aux_reg,
+                loc: loc.clone(),
+            };
+
+            let instr2 = target::S::set_reg_loc {
+                reg: aux_reg_2,
+                loc: target::Loc::reg(utils::Reg::rcx),
+            };
+
+            let instr3 = target::S::set_reg_loc {
+                reg: utils::Reg::rcx,
+                loc: count,
+            };
+
+            let instr4 = target::S::set_reg_binop_reg_loc {
+                reg: aux_reg,
+                binop,
+                loc: target::Loc::reg(utils::Reg::rcx),
+            };
+
+            let instr5 = target::S::set_reg_loc {
+                reg: utils::Reg::rcx,
+                loc: target::Loc::reg(aux_reg_2),
+            };
+
+            let instr6 = store_aux_reg(loc, aux_reg);
+
+            vec![instr1, instr2, instr3, instr4, instr5, instr6]
+        }
+
+        // aux_reg <- loc
+        // aux_reg' <- opand
+        // fvar_rax <- rax
+        // fvar_rdx <- rdx
+        // compare aux_reg' 0 => div-by-zero
+        // compare aux_reg' -1 => div-by-neg-one
+        // rax <- aux_reg
+        // divide aux_reg'
+        // aux_reg <- rax (quotient) or rdx (remainder)
+        // jump div-done
+        // div-by-zero: aux_reg <- -1 (quotient) or nop (remainder)
+        // jump div-done
+        // div-by-neg-one: aux_reg <- aux_reg * -1 (quotient) or 0 (remainder)
+        // div-done: rax <- fvar_rax
+        // rdx <- fvar_rdx
+        // loc <- aux_reg
+        fn patch_divide(
+            loc: self::Loc,
+            binop: utils::Binop,
+            opand: self::Opand,
+            scratch: &mut Option<(utils::Fvar, utils::Fvar)>,
+            config: &utils::TargetConfig,
+        ) -> Vec<target::S> {
+            let (aux_reg, aux_reg_2) = config.auxiliary_registers;
+
+            let (fvar_rax, fvar_rdx) = *scratch.get_or_insert_with(|| {
+                (utils::Fvar::fresh(), utils::Fvar::fresh())
+            });
+
+            let zero_label = utils::Label::new_with_name("div-by-zero");
+            let neg_one_label = utils::Label::new_with_name("div-by-neg-one");
+            let done_label = utils::Label::new_with_name("div-done");
+
+            let (result_reg, zero_instr, neg_one_instr) = match binop {
+                utils::Binop::quotient => (
+                    utils::Reg::rax,
+                    target::S::set_reg_triv {
+                        reg: aux_reg,
+                        triv: target::Triv::int64(-1),
+                    },
+                    target::S::set_reg_binop_reg_int32 {
+                        reg: aux_reg,
+                        binop: utils::Binop::multiply,
+                        int32: -1,
+                    },
+                ),
+                _ => (
+                    utils::Reg::rdx,
+                    target::S::nop,
+                    target::S::set_reg_triv {
+                        reg: aux_reg,
+                        triv: target::Triv::int64(0),
+                    },
+                ),
+            };
+
+            let load_opand = match opand {
+                self::Opand::int64(int64) => target::S::set_reg_triv {
+                    reg: aux_reg_2,
+                    triv: target::Triv::int64(int64),
+                },
+                self::Opand::loc(loc) => target::S::set_reg_loc {
+                    reg: aux_reg_2,
+                    loc,
+                },
+            };
+
+            vec![
+                target::S::set_reg_loc {
+                    reg: aux_reg,
+                    loc: loc.clone(),
+                },
+                load_opand,
+                target::S::set_fvar_trg {
+                    fvar: fvar_rax,
+                    trg: target::Trg::reg(utils::Reg::rax),
+                },
+                target::S::set_fvar_trg {
+                    fvar: fvar_rdx,
+                    trg: target::Trg::reg(utils::Reg::rdx),
+                },
+                target::S::compare {
+                    reg: aux_reg_2,
+                    opand: target::Opand::int64(0),
+                    relop: utils::Relop::eq,
+                    label: zero_label.clone(),
+                },
+                target::S::compare {
+                    reg: aux_reg_2,
+                    opand: target::Opand::int64(-1),
+                    relop: utils::Relop::eq,
+                    label: neg_one_label.clone(),
+                },
+                target::S::set_reg_loc {
+                    reg: utils::Reg::rax,
+                    loc: target::Loc::reg(aux_reg),
+                },
+                target::S::divide { reg: aux_reg_2 },
+                target::S::set_reg_loc {
+                    reg: aux_reg,
+                    loc: target::Loc::reg(result_reg),
+                },
+                target::S::jump(target::Trg::label(done_label.clone())),
+                target::S::with_label {
+                    label: zero_label,
+                    s: Box::new(zero_instr),
+                },
+                target::S::jump(target::Trg::label(done_label.clone())),
+                target::S::with_label {
+                    label: neg_one_label,
+                    s: Box::new(neg_one_instr),
+                },
+                target::S::with_label {
+                    label: done_label,
+                    s: Box::new(target::S::set_reg_loc {
+                        reg: utils::Reg::rax,
+                        loc: target::Loc::fvar(fvar_rax),
+                    }),
+                },
+                target::S::set_reg_loc {
+                    reg: utils::Reg::rdx,
+                    loc: target::Loc::fvar(fvar_rdx),
+                },
+                store_aux_reg(loc, aux_reg),
+            ]
+        }
+
+        fn store_aux_reg(loc: self::Loc, aux_reg: utils::Reg) -> target::S {
+            match loc {
+                self::Loc::reg(reg) => target::S::set_reg_loc {
+                    reg,
+                    loc: target::Loc::reg(aux_reg),
+                },
+                self::Loc::fvar(fvar) => target::S::set_fvar_trg {
+                    fvar,
+                    trg: target::Trg::reg(aux_reg),
+                },
+            }
+        }
+
+        // aux_reg <- base (if base is an fvar)
+        // aux_reg' <- index (if index is an fvar or does not fit in 32 bits)
+        // reg <- mref aux_reg aux_reg'
+        // loc <- reg (if loc is an fvar)
+        fn patch_mref(
+            loc: self::Loc,
+            base: self::Loc,
+            index: self::Opand,
+            config: &utils::TargetConfig,
+        ) -> Vec<target::S> {
+            let (aux_reg, aux_reg_2) = config.auxiliary_registers;
+
+            let (mut ss, base) = load_reg(base, aux_reg);
+
+            let (index_ss, index) = load_index(index, aux_reg_2);
+
+            ss.extend(index_ss);
+
+            match loc {
+                self::Loc::reg(reg) => {
+                    ss.push(target::S::set_reg_mref { reg, base, index });
+                },
+                loc @ self::Loc::fvar(..) => {
+                    ss.push(target::S::set_reg_mref {
+                        reg: aux_reg,
+                        base,
+                        index,
+                    });
+                    ss.push(store_aux_reg(loc, aux_reg));
+                },
+            }
+
+            ss
+        }
+
+        // aux_reg <- loc (if loc is an fvar)
+        // aux_reg' <- index (if index is an fvar or does not fit in 32 bits)
+        // aux_reg' <- triv (if triv is an fvar or does not fit in 32 bits)
+        // mset aux_reg aux_reg' aux_reg'
+        //
+        // If both the index and the value need a register, the index is added
+        // to the base first, freeing the second auxiliary register.
+        fn patch_mset(
+            loc: self::Loc,
+            index: self::Opand,
+            triv: self::Triv,
+            config: &utils::TargetConfig,
+        ) -> Vec<target::S> {
+            let (aux_reg, aux_reg_2) = config.auxiliary_registers;
+
+            let triv_is_direct = match &triv {
+                self::Triv::opand(opand) => is_direct(opand),
+                self::Triv::label(..) => true,
+            };
+
+            let (mut ss, base, index) = match triv_is_direct || is_direct(&index)
+            {
+                true => {
+                    let (mut ss, base) = load_reg(loc, aux_reg);
+                    let (index_ss, index) = load_index(index, aux_reg_2);
+                    ss.extend(index_ss);
+                    (ss, base, index)
+                },
+                false => {
+                    let mut ss =
+                        vec![target::S::set_reg_loc { reg: aux_reg, loc }];
+                    let (index_ss, _) = load_index(index, aux_reg_2);
+                    ss.extend(index_ss);
+                    ss.push(target::S::set_reg_binop_reg_loc {
+                        reg: aux_reg,
+                        binop: utils::Binop::plus,
+                        loc: target::Loc::reg(aux_reg_2),
+                    });
+                    (ss, aux_reg, utils::Index::int32(0))
+                },
+            };
+
+            let s = match triv {
+                self::Triv::label(label) => target::S::mset_trg {
+                    base,
+                    index,
+                    trg: target::Trg::label(label),
+                },
+                self::Triv::opand(opand) => match load_index(opand, aux_reg_2) {
+                    (_, utils::Index::int32(int32)) => {
+                        target::S::mset_int32 { base, index, int32 }
+                    },
+                    (load_ss, utils::Index::reg(reg)) => {
+                        ss.extend(load_ss);
+                        target::S::mset_trg {
+                            base,
+                            index,
+                            trg: target::Trg::reg(reg),
+                        }
+                    },
+                },
+            };
+
+            ss.push(s);
+
+            ss
+        }
+
+        // aux_reg' <- opand (if opand is an fvar, does not fit in 32 bits or
+        // is loc itself)
+        // loc <- hbp
+        // hbp <- hbp + aux_reg'
+        fn patch_alloc(
+            loc: self::Loc,
+            opand: self::Opand,
+            config: &utils::TargetConfig,
+        ) -> Vec<target::S> {
+            let (_, aux_reg_2) = config.auxiliary_registers;
+
+            let hbp = config.heap_pointer;
+
+            let (mut ss, index) = match opand {
+                self::Opand::loc(opand_loc) if opand_loc == loc => {
+                    let instr = target::S::set_reg_loc {
+                        reg: aux_reg_2,
+                        loc: opand_loc,
+                    };
+                    (vec![instr], utils::Index::reg(aux_reg_2))
+                },
+                opand => load_index(opand, aux_reg_2),
+            };
+
+            ss.push(store_aux_reg(loc, hbp));
+
+            let bump = match index {
+                utils::Index::int32(int32) => {
+                    target::S::set_reg_binop_reg_int32 {
+                        reg: hbp,
+                        binop: utils::Binop::plus,
+                        int32,
+                    }
+                },
+                utils::Index::reg(reg) => target::S::set_reg_binop_reg_loc {
+                    reg: hbp,
+                    binop: utils::Binop::plus,
+                    loc: target::Loc::reg(reg),
+                },
+            };
+
+            ss.push(bump);
+
+            ss
+        }
+
+        // Whether the opand can be used as an operand of a memory operation
+        // without going through a register first.
+        fn is_direct(opand: &self::Opand) -> bool {
+            match opand {
+                self::Opand::int64(int64) => i32::try_from(*int64).is_ok(),
+                self::Opand::loc(loc) => matches!(loc, self::Loc::reg(..)),
+            }
+        }
+
+        fn load_reg(
+            loc: self::Loc,
+            aux_reg: utils::Reg,
+        ) -> (Vec<target::S>, utils::Reg) {
+            match loc {
+                self::Loc::reg(reg) => (vec![], reg),
+                loc @ self::Loc::fvar(..) => {
+                    let instr = target::S::set_reg_loc { reg: aux_reg, loc };
+                    (vec![instr], aux_reg)
+                },
+            }
+        }
+
+        fn load_index(
+            opand: self::Opand,
+            aux_reg: utils::Reg,
+        ) -> (Vec<target::S>, utils::Index) {
+            match opand {
+                self::Opand::int64(int64) => match i32::try_from(int64) {
+                    Ok(int32) => (vec![], utils::Index::int32(int32)),
+                    Err(_) => {
+                        let instr = target::S::set_reg_triv {
+                            reg: aux_reg,
+                            triv: target::Triv::int64(int64),
+                        };
+                        (vec![instr], utils::Index::reg(aux_reg))
+                    },
+                },
+                self::Opand::loc(loc) => {
+                    let (ss, reg) = load_reg(loc, aux_reg);
+                    (ss, utils::Index::reg(reg))
+                },
+            }
+        }
+
+        let p = patch_p(p, config, options);
 
         target::ParenX64Fvars(p)
     }
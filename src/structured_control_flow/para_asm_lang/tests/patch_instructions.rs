@@ -7,8 +7,11 @@ use crate::utils;
 #[test]
 #[serial]
 fn nothing() {
-    let actual =
-        source::ParaAsmLang(source::P::begin(vec![])).patch_instructions();
+    let actual = source::ParaAsmLang(source::P::begin(vec![]))
+        .patch_instructions(
+            &utils::TargetConfig::default(),
+            &utils::CompileOptions::default(),
+        );
 
     utils::reset_all_indices();
 
@@ -29,7 +32,10 @@ fn basic() {
             loc: source::Loc::reg(utils::Reg::r10),
             triv: source::Triv::opand(source::Opand::int64(5)),
         }]))
-        .patch_instructions();
+        .patch_instructions(
+            &utils::TargetConfig::default(),
+            &utils::CompileOptions::default(),
+        );
 
     let expected = target::ParenX64Fvars(target::P::begin(vec![
         target::S::set_reg_triv {
@@ -55,7 +61,10 @@ fn labeled_patch() {
             loc: source::Loc::reg(utils::Reg::r11),
             triv: source::Triv::label(label.clone()),
         }]))
-        .patch_instructions();
+        .patch_instructions(
+            &utils::TargetConfig::default(),
+            &utils::CompileOptions::default(),
+        );
 
     let expected = target::ParenX64Fvars(target::P::begin(vec![
         target::S::set_reg_triv {
@@ -78,17 +87,20 @@ fn patch_fvar_to_fvar_mov() {
 
     let actual =
         source::ParaAsmLang(source::P::begin(vec![source::S::set_loc_triv {
-            loc: source::Loc::fvar(fvar.clone()),
+            loc: source::Loc::fvar(fvar),
             triv: source::Triv::opand(source::Opand::loc(source::Loc::fvar(
-                fvar.clone(),
+                fvar,
             ))),
         }]))
-        .patch_instructions();
+        .patch_instructions(
+            &utils::TargetConfig::default(),
+            &utils::CompileOptions::default(),
+        );
 
     let expected = target::ParenX64Fvars(target::P::begin(vec![
         target::S::set_reg_loc {
             reg: utils::Reg::r10,
-            loc: target::Loc::fvar(fvar.clone()),
+            loc: target::Loc::fvar(fvar),
         },
         target::S::set_fvar_trg {
             fvar,
@@ -102,3 +114,333 @@ fn patch_fvar_to_fvar_mov() {
 
     assert_eq!(actual, expected);
 }
+
+#[test]
+#[serial]
+fn patch_shift_masks_count() {
+    utils::reset_all_indices();
+
+    let actual = source::ParaAsmLang(source::P::begin(vec![
+        source::S::set_loc_binop_opand {
+            loc: source::Loc::reg(utils::Reg::rbx),
+            binop: utils::Binop::shift_left,
+            opand: source::Opand::int64(65),
+        },
+    ]))
+    .patch_instructions(
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    );
+
+    let expected = target::ParenX64Fvars(target::P::begin(vec![
+        target::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::rbx,
+            binop: utils::Binop::shift_left,
+            int32: 1,
+        },
+        target::S::with_label {
+            label: utils::Label::halt_label(),
+            s: Box::new(target::S::nop),
+        },
+    ]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn patch_shift_by_loc_uses_rcx() {
+    utils::reset_all_indices();
+
+    let actual = source::ParaAsmLang(source::P::begin(vec![
+        source::S::set_loc_binop_opand {
+            loc: source::Loc::reg(utils::Reg::rcx),
+            binop: utils::Binop::arithmetic_shift_right,
+            opand: source::Opand::loc(source::Loc::reg(utils::Reg::rbx)),
+        },
+    ]))
+    .patch_instructions(
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    );
+
+    let expected = target::ParenX64Fvars(target::P::begin(vec![
+        target::S::set_reg_loc {
+            reg: utils::Reg::r10,
+            loc: target::Loc::reg(utils::Reg::rcx),
+        },
+        target::S::set_reg_loc {
+            reg: utils::Reg::r11,
+            loc: target::Loc::reg(utils::Reg::rcx),
+        },
+        target::S::set_reg_loc {
+            reg: utils::Reg::rcx,
+            loc: target::Loc::reg(utils::Reg::rbx),
+        },
+        target::S::set_reg_binop_reg_loc {
+            reg: utils::Reg::r10,
+            binop: utils::Binop::arithmetic_shift_right,
+            loc: target::Loc::reg(utils::Reg::rcx),
+        },
+        target::S::set_reg_loc {
+            reg: utils::Reg::rcx,
+            loc: target::Loc::reg(utils::Reg::r11),
+        },
+        target::S::set_reg_loc {
+            reg: utils::Reg::rcx,
+            loc: target::Loc::reg(utils::Reg::r10),
+        },
+        target::S::with_label {
+            label: utils::Label::halt_label(),
+            s: Box::new(target::S::nop),
+        },
+    ]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn patch_divide_has_defined_results() {
+    let cases = [
+        (utils::Binop::quotient, 7, 2, 3),
+        (utils::Binop::remainder, -7, 2, -1),
+        (utils::Binop::quotient, 7, 0, -1),
+        (utils::Binop::remainder, 7, 0, 7),
+        (utils::Binop::quotient, i64::MIN, -1, i64::MIN),
+        (utils::Binop::remainder, i64::MIN, -1, 0),
+    ];
+
+    for (binop, dividend, divisor, result) in cases {
+        utils::reset_all_indices();
+
+        let config = utils::TargetConfig::default();
+
+        let actual = source::ParaAsmLang(source::P::begin(vec![
+            source::S::set_loc_triv {
+                loc: source::Loc::reg(utils::Reg::rax),
+                triv: source::Triv::opand(source::Opand::int64(0)),
+            },
+            source::S::set_loc_triv {
+                loc: source::Loc::reg(utils::Reg::rdx),
+                triv: source::Triv::opand(source::Opand::int64(100)),
+            },
+            source::S::set_loc_triv {
+                loc: source::Loc::reg(utils::Reg::rbx),
+                triv: source::Triv::opand(source::Opand::int64(dividend)),
+            },
+            source::S::set_loc_binop_opand {
+                loc: source::Loc::reg(utils::Reg::rbx),
+                binop,
+                opand: source::Opand::int64(divisor),
+            },
+            source::S::set_loc_binop_opand {
+                loc: source::Loc::reg(utils::Reg::rbx),
+                binop: utils::Binop::plus,
+                opand: source::Opand::loc(source::Loc::reg(utils::Reg::rdx)),
+            },
+            source::S::halt(source::Opand::loc(source::Loc::reg(
+                utils::Reg::rbx,
+            ))),
+        ]))
+        .patch_instructions(&config, &utils::CompileOptions::default())
+        .implement_fvars(&config)
+        .link_paren_x64()
+        .interp_loop(&config);
+
+        assert_eq!(actual, result.wrapping_add(100));
+    }
+}
+
+#[test]
+#[serial]
+fn patch_checked_overflow() {
+    let config = utils::TargetConfig::default();
+    let program = |options: &utils::CompileOptions| {
+        utils::reset_all_indices();
+
+        source::ParaAsmLang(source::P::begin(vec![
+            source::S::set_loc_triv {
+                loc: source::Loc::reg(utils::Reg::rax),
+                triv: source::Triv::opand(source::Opand::int64(i64::MAX)),
+            },
+            source::S::set_loc_binop_opand {
+                loc: source::Loc::reg(utils::Reg::rax),
+                binop: utils::Binop::plus,
+                opand: source::Opand::int64(1),
+            },
+            source::S::halt(source::Opand::loc(source::Loc::reg(
+                utils::Reg::rax,
+            ))),
+        ]))
+        .patch_instructions(&config, options)
+        .implement_fvars(&config)
+        .link_paren_x64()
+        .interp_loop(&config)
+    };
+
+    let wrapping = utils::CompileOptions::default();
+    let checked = utils::CompileOptions {
+        overflow_mode: utils::OverflowMode::checked,
+        ..Default::default()
+    };
+
+    assert_eq!(program(&wrapping), i64::MIN);
+    assert_eq!(program(&checked), utils::RuntimeError::overflow.code());
+}
+
+#[test]
+#[serial]
+fn patch_checked_overflow_ignores_patched_arithmetic() {
+    utils::reset_all_indices();
+
+    let config = utils::TargetConfig::default();
+    let options = utils::CompileOptions {
+        overflow_mode: utils::OverflowMode::checked,
+        ..Default::default()
+    };
+
+    let actual = source::ParaAsmLang(source::P::begin(vec![
+        source::S::set_loc_triv {
+            loc: source::Loc::reg(utils::Reg::rbx),
+            triv: source::Triv::opand(source::Opand::int64(i64::MIN)),
+        },
+        source::S::set_loc_binop_opand {
+            loc: source::Loc::reg(utils::Reg::rbx),
+            binop: utils::Binop::quotient,
+            opand: source::Opand::int64(-1),
+        },
+        source::S::halt(source::Opand::loc(source::Loc::reg(
+            utils::Reg::rbx,
+        ))),
+    ]))
+    .patch_instructions(&config, &options)
+    .implement_fvars(&config)
+    .link_paren_x64()
+    .interp_loop(&config);
+
+    assert_eq!(actual, i64::MIN);
+}
+
+#[test]
+#[serial]
+fn patch_heap_operations() {
+    utils::reset_all_indices();
+
+    let config = utils::TargetConfig::default();
+    let fvar = |index| source::Loc::fvar(utils::Fvar(index));
+    let big = 1 << 40;
+
+    let actual = source::ParaAsmLang(source::P::begin(vec![
+        source::S::set_loc_alloc_opand {
+            loc: source::Loc::reg(utils::Reg::rbx),
+            opand: source::Opand::int64(16),
+        },
+        source::S::set_loc_alloc_opand {
+            loc: fvar(0),
+            opand: source::Opand::int64(16),
+        },
+        source::S::set_loc_triv {
+            loc: fvar(1),
+            triv: source::Triv::opand(source::Opand::int64(big)),
+        },
+        source::S::set_loc_triv {
+            loc: fvar(2),
+            triv: source::Triv::opand(source::Opand::int64(8)),
+        },
+        source::S::mset_loc_opand_triv {
+            loc: source::Loc::reg(utils::Reg::rbx),
+            index: source::Opand::int64(0),
+            triv: source::Triv::opand(source::Opand::int64(42)),
+        },
+        source::S::mset_loc_opand_triv {
+            loc: source::Loc::reg(utils::Reg::rbx),
+            index: source::Opand::loc(fvar(2)),
+            triv: source::Triv::opand(source::Opand::loc(fvar(1))),
+        },
+        source::S::mset_loc_opand_triv {
+            loc: fvar(0),
+            index: source::Opand::loc(fvar(2)),
+            triv: source::Triv::opand(source::Opand::loc(fvar(1))),
+        },
+        source::S::set_loc_mref_loc_opand {
+            loc: source::Loc::reg(utils::Reg::rcx),
+            base: source::Loc::reg(utils::Reg::rbx),
+            index: source::Opand::int64(0),
+        },
+        source::S::set_loc_mref_loc_opand {
+            loc: fvar(3),
+            base: fvar(0),
+            index: source::Opand::loc(fvar(2)),
+        },
+        source::S::set_loc_binop_opand {
+            loc: source::Loc::reg(utils::Reg::rcx),
+            binop: utils::Binop::plus,
+            opand: source::Opand::loc(fvar(3)),
+        },
+        source::S::set_loc_binop_opand {
+            loc: source::Loc::reg(utils::Reg::rcx),
+            binop: utils::Binop::plus,
+            opand: source::Opand::loc(fvar(0)),
+        },
+        source::S::set_loc_mref_loc_opand {
+            loc: fvar(4),
+            base: source::Loc::reg(utils::Reg::rbx),
+            index: source::Opand::loc(fvar(2)),
+        },
+        source::S::set_loc_binop_opand {
+            loc: source::Loc::reg(utils::Reg::rcx),
+            binop: utils::Binop::plus,
+            opand: source::Opand::loc(fvar(4)),
+        },
+        source::S::halt(source::Opand::loc(source::Loc::reg(
+            utils::Reg::rcx,
+        ))),
+    ]))
+    .patch_instructions(&config, &utils::CompileOptions::default())
+    .implement_fvars(&config)
+    .link_paren_x64()
+    .interp_loop(&config);
+
+    assert_eq!(actual, 42 + big + big + 16);
+}
+
+#[test]
+#[serial]
+fn patch_divides_share_scratch_fvars() {
+    utils::reset_all_indices();
+
+    let divide = |binop| source::S::set_loc_binop_opand {
+        loc: source::Loc::reg(utils::Reg::rbx),
+        binop,
+        opand: source::Opand::int64(3),
+    };
+    let target::ParenX64Fvars(target::P::begin(ss)) =
+        source::ParaAsmLang(source::P::begin(vec![
+            source::S::set_loc_triv {
+                loc: source::Loc::reg(utils::Reg::rbx),
+                triv: source::Triv::opand(source::Opand::int64(100)),
+            },
+            divide(utils::Binop::quotient),
+            divide(utils::Binop::remainder),
+            source::S::halt(source::Opand::loc(source::Loc::reg(
+                utils::Reg::rbx,
+            ))),
+        ]))
+        .patch_instructions(
+            &utils::TargetConfig::default(),
+            &utils::CompileOptions::default(),
+        );
+
+    let mut fvars = ss
+        .into_iter()
+        .filter_map(|s| match s {
+            target::S::set_fvar_trg { fvar, .. } => Some(fvar),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    fvars.sort();
+    fvars.dedup();
+
+    assert_eq!(fvars, vec![utils::Fvar(0), utils::Fvar(1)]);
+}
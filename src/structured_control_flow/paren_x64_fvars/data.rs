@@ -33,6 +33,24 @@ pub enum S {
         binop: utils::Binop,
         loc: Loc,
     },
+    divide {
+        reg: utils::Reg,
+    },
+    set_reg_mref {
+        reg: utils::Reg,
+        base: utils::Reg,
+        index: utils::Index,
+    },
+    mset_int32 {
+        base: utils::Reg,
+        index: utils::Index,
+        int32: i32,
+    },
+    mset_trg {
+        base: utils::Reg,
+        index: utils::Index,
+        trg: Trg,
+    },
     with_label {
         label: utils::Label,
         s: Box<Self>,
@@ -44,11 +62,13 @@ pub enum S {
         relop: utils::Relop,
         label: utils::Label,
     },
+    jump_if_overflow {
+        label: utils::Label,
+    },
     nop,
 }
 
-#[derive(Clone, Hash, PartialEq, Eq)]
-#[cfg_attr(test, derive(Debug))]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Loc {
     reg(utils::Reg),
     fvar(utils::Fvar),
@@ -12,33 +12,39 @@ pub struct ParenX64Fvars(pub self::P);
 impl ParenX64Fvars {
     /// ### Purpose:
     /// Compiles the Paren-x64-fvars v2 to Paren-x64 v2 by reifying fvars
-    /// into displacement mode operands. The pass should use
-    /// current-frame-base-pointer-register.
-    pub fn implement_fvars(self) -> target::ParenX64 {
+    /// into displacement mode operands, relative to the frame base pointer of
+    /// the target configuration. Memory operations become moves to and from
+    /// heap addresses.
+    pub fn implement_fvars(
+        self,
+        config: &utils::TargetConfig,
+    ) -> target::ParenX64 {
         let Self(p) = self;
 
-        fn implement_p(p: self::P) -> target::P {
+        fn implement_p(p: self::P, config: &utils::TargetConfig) -> target::P {
             match p {
                 self::P::begin(ss) => {
-                    let ss =
-                        ss.into_iter().map(implement_s).collect::<Vec<_>>();
+                    let ss = ss
+                        .into_iter()
+                        .map(|s| implement_s(s, config))
+                        .collect::<Vec<_>>();
                     target::P::begin(ss)
                 },
             }
         }
 
-        fn implement_s(s: self::S) -> target::S {
+        fn implement_s(s: self::S, config: &utils::TargetConfig) -> target::S {
             match s {
                 self::S::set_fvar_int32 { fvar, int32 } => {
-                    let addr = implement_fvar(fvar);
+                    let addr = implement_fvar(fvar, config);
                     target::S::set_addr_int32 { addr, int32 }
                 },
                 self::S::set_fvar_trg { fvar, trg } => {
-                    let addr = implement_fvar(fvar);
+                    let addr = implement_fvar(fvar, config);
                     target::S::set_addr_trg { addr, trg }
                 },
                 self::S::set_reg_loc { reg, loc } => {
-                    let loc = implement_loc(loc);
+                    let loc = implement_loc(loc, config);
                     target::S::set_reg_loc { reg, loc }
                 },
                 self::S::set_reg_triv { reg, triv } => {
@@ -48,11 +54,25 @@ impl ParenX64Fvars {
                     target::S::set_reg_binop_reg_int32 { reg, binop, int32 }
                 },
                 self::S::set_reg_binop_reg_loc { reg, binop, loc } => {
-                    let loc = implement_loc(loc);
+                    let loc = implement_loc(loc, config);
                     target::S::set_reg_binop_reg_loc { reg, binop, loc }
                 },
+                self::S::divide { reg } => target::S::divide { reg },
+                self::S::set_reg_mref { reg, base, index } => {
+                    let addr = utils::Addr::heap { base, index };
+                    let loc = target::Loc::addr(addr);
+                    target::S::set_reg_loc { reg, loc }
+                },
+                self::S::mset_int32 { base, index, int32 } => {
+                    let addr = utils::Addr::heap { base, index };
+                    target::S::set_addr_int32 { addr, int32 }
+                },
+                self::S::mset_trg { base, index, trg } => {
+                    let addr = utils::Addr::heap { base, index };
+                    target::S::set_addr_trg { addr, trg }
+                },
                 self::S::with_label { label, s } => {
-                    let s = implement_s(*s);
+                    let s = implement_s(*s, config);
                     let s = Box::new(s);
                     target::S::with_label { label, s }
                 },
@@ -68,27 +88,36 @@ impl ParenX64Fvars {
                     relop,
                     label,
                 },
+                self::S::jump_if_overflow { label } => {
+                    target::S::jump_if_overflow { label }
+                },
                 self::S::nop => target::S::nop,
             }
         }
 
-        fn implement_fvar(utils::Fvar(index): utils::Fvar) -> utils::Addr {
-            let fbp = utils::Reg::current_frame_base_pointer();
+        fn implement_fvar(
+            utils::Fvar(index): utils::Fvar,
+            config: &utils::TargetConfig,
+        ) -> utils::Addr {
+            let fbp = config.frame_base_pointer;
             let disp_offset = index * 8;
-            utils::Addr { fbp, disp_offset }
+            utils::Addr::frame { fbp, disp_offset }
         }
 
-        fn implement_loc(loc: self::Loc) -> target::Loc {
+        fn implement_loc(
+            loc: self::Loc,
+            config: &utils::TargetConfig,
+        ) -> target::Loc {
             match loc {
                 self::Loc::reg(reg) => target::Loc::reg(reg),
                 self::Loc::fvar(fvar) => {
-                    let addr = implement_fvar(fvar);
+                    let addr = implement_fvar(fvar, config);
                     target::Loc::addr(addr)
                 },
             }
         }
 
-        let p = implement_p(p);
+        let p = implement_p(p, config);
         target::ParenX64(p)
     }
 }
@@ -7,8 +7,8 @@ use crate::x64::paren_x64 as target;
 #[test]
 #[serial]
 fn empty() {
-    let actual =
-        source::ParenX64Fvars(source::P::begin(vec![])).implement_fvars();
+    let actual = source::ParenX64Fvars(source::P::begin(vec![]))
+        .implement_fvars(&utils::TargetConfig::default());
 
     let expected = target::ParenX64(target::P::begin(vec![]));
 
@@ -29,7 +29,7 @@ fn basic() {
             int32: 5,
         },
     ]))
-    .implement_fvars();
+    .implement_fvars(&utils::TargetConfig::default());
 
     let expected = target::ParenX64(target::P::begin(vec![
         target::S::set_reg_triv {
@@ -55,14 +55,14 @@ fn one_fvar_conversion() {
             int32: 5,
         },
     ]))
-    .implement_fvars();
+    .implement_fvars(&utils::TargetConfig::default());
 
     utils::reset_all_indices();
 
     let expected =
         target::ParenX64(target::P::begin(vec![target::S::set_addr_int32 {
-            addr: utils::Addr {
-                fbp: utils::Reg::current_frame_base_pointer(),
+            addr: utils::Addr::frame {
+                fbp: utils::TargetConfig::default().frame_base_pointer,
                 disp_offset: 0,
             },
             int32: 5,
@@ -84,21 +84,21 @@ fn many_fvar_conversions() {
             int32: 6,
         },
     ]))
-    .implement_fvars();
+    .implement_fvars(&utils::TargetConfig::default());
 
     utils::reset_all_indices();
 
     let expected = target::ParenX64(target::P::begin(vec![
         target::S::set_addr_int32 {
-            addr: utils::Addr {
-                fbp: utils::Reg::current_frame_base_pointer(),
+            addr: utils::Addr::frame {
+                fbp: utils::TargetConfig::default().frame_base_pointer,
                 disp_offset: 0,
             },
             int32: 5,
         },
         target::S::set_addr_int32 {
-            addr: utils::Addr {
-                fbp: utils::Reg::current_frame_base_pointer(),
+            addr: utils::Addr::frame {
+                fbp: utils::TargetConfig::default().frame_base_pointer,
                 disp_offset: 8,
             },
             int32: 6,
@@ -1,8 +1,15 @@
+use std::collections::HashMap;
+
 use serial_test::serial;
 
-use crate::compile;
+use crate::compile_module;
+use crate::imperative_abstractions;
+use crate::link;
 use crate::imperative_abstractions::values_lang as source;
+use crate::register_allocation;
+use crate::structured_control_flow;
 use crate::utils;
+use crate::x64;
 
 #[test]
 #[serial]
@@ -11,13 +18,8 @@ fn basic() {
         lambdas: vec![],
         tail: source::Tail::value(source::Value::triv(source::Triv::int64(9))),
     });
-    let actual = compile(p).unwrap();
-    let expected = "L.main.0:
-\tmov rax, 9
-\tjmp L.done
-L.done:
-";
-    assert_eq!(actual, expected,);
+
+    assert_eq!(run(p, &utils::CompileOptions::default()), fixnum(9));
 }
 
 #[test]
@@ -37,14 +39,8 @@ fn let_bindings_basic() {
             ))),
         }),
     });
-    let actual = compile(p).unwrap();
-    let expected = "L.main.0:
-\tmov r9, 100
-\tmov rax, r9
-\tjmp L.done
-L.done:
-";
-    assert_eq!(actual, expected);
+
+    assert_eq!(run(p, &utils::CompileOptions::default()), fixnum(100));
 }
 
 #[test]
@@ -56,44 +52,289 @@ fn let_bindings_optimzed() {
             100,
         ))),
     });
-    let actual = compile(p).unwrap();
-    utils::reset_all_indices();
-    let expected = "L.main.0:
-\tmov rax, 100
-\tjmp L.done
-L.done:
-";
-    assert_eq!(actual, expected);
+
+    assert_eq!(run(p, &utils::CompileOptions::default()), fixnum(100));
 }
 
 #[test]
 #[serial]
 fn let_bindings_with_operation_basic() {
+    // The operand is an argument of a procedure which is not inlined, so that
+    // the binop is not folded away.
     let p = source::ValuesLang(source::P::module {
-        lambdas: vec![],
-        tail: source::Tail::value(source::Value::r#let {
-            bindings: vec![("x".into(), source::Value::binop_triv_triv {
-                binop: utils::Binop::plus,
-                triv1: source::Triv::int64(100),
-                triv2: source::Triv::int64(101),
-            })]
-            .into_iter()
-            .collect(),
-            value: Box::new(source::Value::triv(source::Triv::name(
-                "x".into(),
-            ))),
-        }),
+        lambdas: vec![source::Lambda {
+            name: "f",
+            args: vec!["y".into()],
+            tail: source::Tail::value(source::Value::r#let {
+                bindings: vec![("x".into(), source::Value::binop_triv_triv {
+                    binop: utils::Binop::plus,
+                    triv1: name("y"),
+                    triv2: source::Triv::int64(101),
+                })]
+                .into_iter()
+                .collect(),
+                value: Box::new(source::Value::triv(name("x"))),
+            }),
+        }],
+        tail: source::Tail::call {
+            name: "f".into(),
+            args: vec![source::Triv::int64(100)],
+        },
     });
-    let actual = compile(p).unwrap();
+    let options = utils::CompileOptions {
+        inline_budget: 0,
+        ..utils::CompileOptions::default()
+    };
+
+    assert_eq!(run(p, &options), fixnum(201));
+}
+
+#[test]
+#[serial]
+fn compile_reports_dead_procedures() {
+    utils::reset_all_indices();
+
+    let lambda = |name: &'static str| source::Lambda {
+        name,
+        args: vec!["x".into()],
+        tail: source::Tail::value(source::Value::triv(source::Triv::name(
+            "x".into(),
+        ))),
+    };
+    let p = source::ValuesLang(source::P::module {
+        lambdas: vec![lambda("f"), lambda("g")],
+        tail: source::Tail::value(source::Value::triv(source::Triv::int64(9))),
+    });
+    let (_, stats) = imperative_abstractions::compile_with_stats(
+        p,
+        &HashMap::default(),
+        &utils::TargetConfig::default(),
+        &utils::CompileOptions::default(),
+    )
+    .unwrap();
+    let dead_procedures = stats
+        .dead_procedures
+        .into_iter()
+        .map(|utils::Label(label)| label)
+        .collect::<Vec<_>>();
+    assert_eq!(dead_procedures, vec!["L.f.0", "L.g.1"]);
+}
+
+fn run(p: source::ValuesLang, options: &utils::CompileOptions) -> i64 {
+    utils::reset_all_indices();
+
+    let config = utils::TargetConfig::default();
+    imperative_abstractions::compile(p.link_prelude(), &config, options)
+        .and_then(|p| register_allocation::compile(p, &config, options))
+        .and_then(|p| structured_control_flow::compile(p, &config, options))
+        .unwrap()
+        .link_paren_x64()
+        .interp_loop(&config)
+}
+
+fn name(name: &'static str) -> source::Triv {
+    source::Triv::name(name.into())
+}
+
+fn fixnum(int64: i64) -> i64 {
+    utils::Datum::fixnum(int64).repr()
+}
+
+#[test]
+#[serial]
+fn mutually_recursive_procedures() {
+    // (define (even n) (if (= n 0) 1 (let ([m (- n 1)]) (odd m))))
+    // (define (odd n) (if (= n 0) 0 (let ([m (- n 1)]) (even m))))
+    let parity = |name_: &'static str, base, other: &'static str| {
+        source::Lambda {
+            name: name_,
+            args: vec!["n".into()],
+            tail: source::Tail::r#if {
+                pred: source::Pred::relop {
+                    relop: utils::Relop::eq,
+                    triv1: name("n"),
+                    triv2: source::Triv::int64(0),
+                },
+                tail1: Box::new(source::Tail::value(source::Value::triv(
+                    source::Triv::int64(base),
+                ))),
+                tail2: Box::new(source::Tail::r#let {
+                    bindings: vec![("m".into(), source::Value::binop_triv_triv {
+                        binop: utils::Binop::minus,
+                        triv1: name("n"),
+                        triv2: source::Triv::int64(1),
+                    })],
+                    tail: Box::new(source::Tail::call {
+                        name: other.into(),
+                        args: vec![name("m")],
+                    }),
+                }),
+            },
+        }
+    };
+    let p = |n| {
+        source::ValuesLang(source::P::module {
+            lambdas: vec![parity("even", 1, "odd"), parity("odd", 0, "even")],
+            tail: source::Tail::call {
+                name: "even".into(),
+                args: vec![source::Triv::int64(n)],
+            },
+        })
+    };
+    let options = utils::CompileOptions {
+        inline_budget: 0,
+        ..utils::CompileOptions::default()
+    };
+
+    assert_eq!(run(p(10), &options), fixnum(1));
+    assert_eq!(run(p(7), &options), fixnum(0));
+}
+
+#[test]
+#[serial]
+fn link_modules_with_provide_and_require() {
     utils::reset_all_indices();
-    let expected = "L.main.0:
-\tmov r9, 100
-\tadd r9, 101
-\tadd rax, r9
-\tjmp L.done
-L.done:
-";
-    assert_eq!(actual, expected);
+
+    let config = utils::TargetConfig::default();
+    let options = utils::CompileOptions::default();
+    // (module main (require double) (double 21))
+    let main = source::Module {
+        name: "main",
+        provides: vec![],
+        requires: vec!["double"],
+        p: source::P::module {
+            lambdas: vec![],
+            tail: source::Tail::call {
+                name: "double".into(),
+                args: vec![source::Triv::int64(21)],
+            },
+        },
+    };
+    // (module lib (provide double) (define (double x) (+ x x)) 0)
+    let lib = source::Module {
+        name: "lib",
+        provides: vec!["double"],
+        requires: vec![],
+        p: source::P::module {
+            lambdas: vec![source::Lambda {
+                name: "double",
+                args: vec!["x".into()],
+                tail: source::Tail::value(source::Value::binop_triv_triv {
+                    binop: utils::Binop::plus,
+                    triv1: name("x"),
+                    triv2: name("x"),
+                }),
+            }],
+            tail: source::Tail::value(source::Value::triv(
+                source::Triv::int64(0),
+            )),
+        },
+    };
+    let units = vec![main, lib]
+        .into_iter()
+        .map(|module| compile_module(module, &config, &options))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(link(units.clone(), &config, &options).is_ok());
+    let actual = x64::paren_x64::ParenX64::link_units(units)
+        .unwrap()
+        .link_paren_x64()
+        .interp_loop(&config);
+
+    assert_eq!(actual, fixnum(42));
+}
+
+#[test]
+#[serial]
+fn call_recursive_prelude_procedures() {
+    let call = |name: &'static str, args: Vec<i64>| {
+        source::ValuesLang(source::P::module {
+            lambdas: vec![],
+            tail: source::Tail::call {
+                name: name.into(),
+                args: args.into_iter().map(source::Triv::int64).collect(),
+            },
+        })
+    };
+    let options = utils::CompileOptions {
+        inline_budget: 0,
+        ..utils::CompileOptions::default()
+    };
+
+    assert_eq!(run(call("fib", vec![10]), &options), fixnum(55));
+    assert_eq!(run(call("fib", vec![0]), &options), fixnum(0));
+    assert_eq!(run(call("gcd", vec![48, 18]), &options), fixnum(6));
+    assert_eq!(run(call("gcd", vec![-12, 8]), &options), fixnum(4));
+    assert_eq!(run(call("expt", vec![3, 4]), &options), fixnum(81));
+    assert_eq!(run(call("expt", vec![2, 0]), &options), fixnum(1));
+}
+
+#[test]
+#[serial]
+fn self_tail_calls_loop() {
+    // (define (sum n a b)
+    //   (if (= n 0) (- a b) (let ([m (- n 1)] [c (+ a n)]) (sum m b c))))
+    let binop = |binop, triv1, triv2| source::Value::binop_triv_triv {
+        binop,
+        triv1,
+        triv2,
+    };
+    let sum = || source::Lambda {
+        name: "sum",
+        args: vec!["n".into(), "a".into(), "b".into()],
+        tail: source::Tail::r#if {
+            pred: source::Pred::relop {
+                relop: utils::Relop::eq,
+                triv1: name("n"),
+                triv2: source::Triv::int64(0),
+            },
+            tail1: Box::new(source::Tail::value(binop(
+                utils::Binop::minus,
+                name("a"),
+                name("b"),
+            ))),
+            tail2: Box::new(source::Tail::r#let {
+                bindings: vec![
+                    (
+                        "m".into(),
+                        binop(
+                            utils::Binop::minus,
+                            name("n"),
+                            source::Triv::int64(1),
+                        ),
+                    ),
+                    (
+                        "c".into(),
+                        binop(utils::Binop::plus, name("a"), name("n")),
+                    ),
+                ],
+                tail: Box::new(source::Tail::call {
+                    name: "sum".into(),
+                    args: vec![name("m"), name("b"), name("c")],
+                }),
+            }),
+        },
+    };
+    let p = |n| {
+        source::ValuesLang(source::P::module {
+            lambdas: vec![sum()],
+            tail: source::Tail::call {
+                name: "sum".into(),
+                args: vec![
+                    source::Triv::int64(n),
+                    source::Triv::int64(0),
+                    source::Triv::int64(0),
+                ],
+            },
+        })
+    };
+    let options = utils::CompileOptions::default();
+
+    // The arguments a and b swap on every call, so that the sums of the odd
+    // and of the even numbers from n down alternate between them.
+    assert_eq!(run(p(0), &options), fixnum(0));
+    assert_eq!(run(p(4), &options), fixnum(2));
+    assert_eq!(run(p(5), &options), fixnum(-3));
 }
 
 // #[test]
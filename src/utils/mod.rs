@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::Debug;
+use std::fmt::Display;
 use std::hash::Hash;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -10,6 +11,9 @@ use itertools::FoldWhile;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 
+#[cfg(test)]
+mod tests;
+
 pub type Name = &'static str;
 
 pub type AlocSet = HashSet<Aloc>;
@@ -18,29 +22,88 @@ pub type Assignments<Loc> = HashMap<Aloc, Loc>;
 
 pub type PcAddr = usize;
 
+pub const FIXNUM_SHIFT: i64 = 3;
+
+pub const FIXNUM_MASK: i64 = 0b111;
+
+pub const FIXNUM_TAG: i64 = 0b000;
+
+pub const BOOLEAN_MASK: i64 = 0b11110111;
+
+pub const BOOLEAN_TAG: i64 = 0b110;
+
+pub const TRUE: i64 = 0b1110;
+
+pub const FALSE: i64 = 0b0110;
+
+pub const EMPTY: i64 = 0b00010110;
+
+pub const VOID: i64 = 0b00011110;
+
+pub const ASCII_CHAR_SHIFT: i64 = 8;
+
+pub const ASCII_CHAR_MASK: i64 = 0xff;
+
+pub const ASCII_CHAR_TAG: i64 = 0b00101110;
+
+pub const ERROR_SHIFT: i64 = 8;
+
+pub const ERROR_MASK: i64 = 0xff;
+
+pub const ERROR_TAG: i64 = 0b00111110;
+
+pub const PAIR_MASK: i64 = 0b111;
+
+pub const PAIR_TAG: i64 = 0b001;
+
+pub const PAIR_SIZE: i64 = 16;
+
+pub const CAR_DISPLACEMENT: i64 = 0;
+
+pub const CDR_DISPLACEMENT: i64 = 8;
+
+pub const VECTOR_MASK: i64 = 0b111;
+
+pub const VECTOR_TAG: i64 = 0b011;
+
+pub const VECTOR_LENGTH_DISPLACEMENT: i64 = 0;
+
+pub const VECTOR_BASE_DISPLACEMENT: i64 = 8;
+
+pub const PROCEDURE_MASK: i64 = 0b111;
+
+pub const PROCEDURE_TAG: i64 = 0b010;
+
+pub const PROCEDURE_LABEL_DISPLACEMENT: i64 = 0;
+
+pub const PROCEDURE_ARITY_DISPLACEMENT: i64 = 8;
+
+pub const PROCEDURE_ENVIRONMENT_DISPLACEMENT: i64 = 16;
+
+const DOT_PALETTE: [&str; 16] = [
+    "lightblue",
+    "lightgreen",
+    "lightpink",
+    "lightsalmon",
+    "khaki",
+    "plum",
+    "palegreen",
+    "lightcyan",
+    "wheat",
+    "thistle",
+    "aquamarine",
+    "lightskyblue",
+    "peachpuff",
+    "lavender",
+    "honeydew",
+    "mistyrose",
+];
+
 lazy_static! {
     static ref FVAR_INDEX: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
     static ref ALOC_INDEX: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
     static ref LABEL_INDEX: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
-    static ref CURRENT_ASSIGNABLE_REGISTERS: Arc<Mutex<HashSet<Reg>>> =
-        Arc::new(Mutex::new(
-            vec![
-                Reg::rax,
-                Reg::rbx,
-                Reg::rcx,
-                Reg::rdx,
-                Reg::rsi,
-                Reg::rdi,
-                Reg::r8,
-                Reg::r9,
-                Reg::r12,
-                Reg::r13,
-                Reg::r14,
-                Reg::r15,
-            ]
-            .into_iter()
-            .collect()
-        ));
+    static ref NAME_INDEX: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
 }
 
 fn fresh_index(asbtract_index: &Arc<Mutex<usize>>) -> usize {
@@ -51,6 +114,12 @@ fn fresh_index(asbtract_index: &Arc<Mutex<usize>>) -> usize {
     index
 }
 
+fn sorted_alocs(alocs: &AlocSet) -> Vec<&Aloc> {
+    let mut alocs = alocs.iter().collect::<Vec<_>>();
+    alocs.sort();
+    alocs
+}
+
 #[cfg(test)]
 fn reset_index(asbtract_index: &Arc<Mutex<usize>>) {
     let mut abstract_index = asbtract_index.lock().unwrap();
@@ -62,6 +131,50 @@ pub fn reset_all_indices() {
     reset_index(&ALOC_INDEX);
     reset_index(&FVAR_INDEX);
     reset_index(&LABEL_INDEX);
+    reset_index(&NAME_INDEX);
+}
+
+/// ### Purpose:
+/// An identifier bound in a program: either a symbol written in its source, or
+/// a gensym which the macro expander made for a binder a macro introduced.
+///
+/// ### Notes:
+/// A gensym never equals a symbol, even one spelled like it, and never equals
+/// another gensym.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Ident {
+    symbol(Name),
+    gensym { name: Name, index: usize },
+}
+
+impl Ident {
+    /// ### Purpose:
+    /// A gensym which is distinct from every other, derived from the name.
+    pub fn fresh(name: Name) -> Self {
+        let index = fresh_index(&NAME_INDEX);
+        Self::gensym { name, index }
+    }
+}
+
+impl From<Name> for Ident {
+    fn from(name: Name) -> Self {
+        Self::symbol(name)
+    }
+}
+
+impl Display for Ident {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::symbol(name) => f.write_str(name),
+            Self::gensym { name, index } => write!(f, "{}.{}", name, index),
+        }
+    }
+}
+
+impl From<Ident> for String {
+    fn from(ident: Ident) -> Self {
+        ident.to_string()
+    }
 }
 
 #[derive(Default, Derivative, Clone, Hash, PartialEq, Eq)]
@@ -123,40 +236,400 @@ pub enum Reg {
     r15,
 }
 
-impl Reg {
-    pub fn current_return_reg() -> Self {
-        Self::rax
+/// ### Purpose:
+/// Describes the register file of the compilation target. A configuration is
+/// passed through every phase of the compiler, so that separate compilations
+/// (and tests) can use different register files.
+///
+/// ### Notes:
+/// The assignable registers are handed out by the register allocator in the
+/// order in which they are listed. The return register and the parameter
+/// registers may also be assignable; the frame base pointer, the heap pointer
+/// and the auxiliary registers may not. The auxiliary registers also may not
+/// be rax, rcx or rdx, which are used implicitly by division and shifts.
+///
+/// The heap pointer is the bump pointer of allocation. The runtime must
+/// initialise it to the base of a zero-filled heap, which is never reused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetConfig {
+    pub assignable_registers: Vec<Reg>,
+    pub parameter_registers: Vec<Reg>,
+    pub return_register: Reg,
+    pub frame_base_pointer: Reg,
+    pub heap_pointer: Reg,
+    pub auxiliary_registers: (Reg, Reg),
+}
+
+impl TargetConfig {
+    pub fn new(
+        assignable_registers: Vec<Reg>,
+        parameter_registers: Vec<Reg>,
+        return_register: Reg,
+        frame_base_pointer: Reg,
+        heap_pointer: Reg,
+        auxiliary_registers: (Reg, Reg),
+    ) -> Result<Self, String> {
+        let config = Self {
+            assignable_registers,
+            parameter_registers,
+            return_register,
+            frame_base_pointer,
+            heap_pointer,
+            auxiliary_registers,
+        };
+        config.validate().map(|()| config)
     }
 
-    pub fn current_frame_base_pointer() -> Self {
-        Self::rbp
+    /// ### Purpose:
+    /// Checks that no register is given two conflicting roles, and that the
+    /// stack pointer is not given any role at all.
+    pub fn validate(&self) -> Result<(), String> {
+        fn find_duplicate(regs: &[Reg]) -> Option<Reg> {
+            regs.iter()
+                .enumerate()
+                .find(|(index, reg)| regs[..*index].contains(reg))
+                .map(|(_, reg)| *reg)
+        }
+
+        let (aux_reg1, aux_reg2) = self.auxiliary_registers;
+        let reserved = [
+            self.frame_base_pointer,
+            self.heap_pointer,
+            aux_reg1,
+            aux_reg2,
+        ];
+        let return_registers = [self.return_register];
+        let roles: [(&str, &[Reg]); 4] = [
+            ("assignable", &self.assignable_registers),
+            ("parameter", &self.parameter_registers),
+            ("return", &return_registers),
+            ("reserved", &reserved),
+        ];
+
+        roles.iter().try_for_each(|(role, regs)| {
+            let has_stack_pointer = regs.contains(&Reg::rsp);
+            match (has_stack_pointer, find_duplicate(regs)) {
+                (true, _) => Err(format!(
+                    "The stack pointer cannot be used as a {} register.",
+                    role
+                )),
+                (false, Some(reg)) => Err(format!(
+                    "The register, '{:?}', is used twice as a {} register.",
+                    reg, role
+                )),
+                (false, None) => Ok(()),
+            }
+        })?;
+
+        roles[..3].iter().try_for_each(|(role, regs)| {
+            match regs.iter().find(|reg| reserved.contains(reg)) {
+                Some(reg) => Err(format!(
+                    "The register, '{:?}', is reserved and cannot be used as a {} register.",
+                    reg, role
+                )),
+                None => Ok(()),
+            }
+        })?;
+
+        [aux_reg1, aux_reg2].iter().try_for_each(|reg| match reg {
+            Reg::rax | Reg::rcx | Reg::rdx => Err(format!(
+                "The register, '{:?}', is used by division and shifts and cannot be an auxiliary register.",
+                reg
+            )),
+            _ => Ok(()),
+        })
     }
+}
 
-    pub fn current_auxiliary_registers() -> (Self, Self) {
-        (Self::r10, Self::r11)
+impl Default for TargetConfig {
+    fn default() -> Self {
+        Self {
+            assignable_registers: vec![
+                Reg::rax,
+                Reg::rbx,
+                Reg::rcx,
+                Reg::rdx,
+                Reg::rsi,
+                Reg::rdi,
+                Reg::r8,
+                Reg::r9,
+                Reg::r13,
+                Reg::r14,
+                Reg::r15,
+            ],
+            parameter_registers: vec![
+                Reg::rdi,
+                Reg::rsi,
+                Reg::rdx,
+                Reg::rcx,
+                Reg::r8,
+                Reg::r9,
+            ],
+            return_register: Reg::rax,
+            frame_base_pointer: Reg::rbp,
+            heap_pointer: Reg::r12,
+            auxiliary_registers: (Reg::r10, Reg::r11),
+        }
     }
+}
+
+/// ### Purpose:
+/// Selects how a program is compiled, independently of the register file it
+/// is compiled for. The options are passed through every phase of the
+/// compiler alongside the target configuration.
+///
+/// ### Notes:
+/// By default, overflowing arithmetic wraps, types are only checked when the
+/// compiled program runs, and the inline budget is
+/// `DEFAULT_INLINE_BUDGET`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// What a compiled program does when its arithmetic overflows.
+    pub overflow_mode: OverflowMode,
+    /// Whether the program is type-checked before it is compiled.
+    pub typing_mode: TypingMode,
+    /// Procedures whose bodies are no larger than the inline budget are
+    /// inlined at their call sites. A budget of zero disables inlining.
+    pub inline_budget: usize,
+}
+
+impl CompileOptions {
+    pub const DEFAULT_INLINE_BUDGET: usize = 16;
+}
 
-    pub fn set_current_assignable_registers(regs: HashSet<Self>) {
-        let mut curr_regs = CURRENT_ASSIGNABLE_REGISTERS.lock().unwrap();
-        *curr_regs = regs;
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            overflow_mode: OverflowMode::default(),
+            typing_mode: TypingMode::default(),
+            inline_budget: Self::DEFAULT_INLINE_BUDGET,
+        }
     }
+}
 
-    pub fn current_assignable_registers() -> HashSet<Self> {
-        CURRENT_ASSIGNABLE_REGISTERS.lock().unwrap().clone()
+/// ### Purpose:
+/// Selects what a compiled program does when a signed 64-bit addition,
+/// subtraction or multiplication overflows.
+///
+/// ### Notes:
+/// In wrapping mode the result wraps, as it does on x64. In checked mode the
+/// program halts with the code of `RuntimeError::overflow` instead.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum OverflowMode {
+    #[default]
+    wrapping,
+    checked,
+}
+
+/// ### Purpose:
+/// Selects whether a program is type-checked before it is compiled.
+///
+/// ### Notes:
+/// In dynamic mode the type of each value is only checked when the compiled
+/// program runs. In static mode the program is also rejected at compile-time
+/// unless it is well-typed.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum TypingMode {
+    #[default]
+    dynamic,
+    r#static,
+}
+
+/// ### Purpose:
+/// Errors detected by a compiled program while it runs. The program halts
+/// with the error's code in the return register.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+    overflow,
+    type_error,
+    out_of_bounds,
+    arity_mismatch,
+}
+
+impl RuntimeError {
+    /// ### Purpose:
+    /// The representation of the error value that the program produces.
+    pub fn code(&self) -> i64 {
+        let code = match self {
+            Self::overflow => 1,
+            Self::type_error => 2,
+            Self::out_of_bounds => 3,
+            Self::arity_mismatch => 4,
+        };
+
+        Datum::error(code).repr()
+    }
+}
+
+/// ### Purpose:
+/// A value of the source language, together with its tagged runtime
+/// representation as a 64-bit word.
+///
+/// ### Notes:
+/// A fixnum is shifted left by three bits, leaving a zero tag, so it must fit
+/// in 61 bits. Every other datum has a non-zero tag in its low bits. Only
+/// false is falsy, so a value is tested by comparing it against false.
+///
+/// Pairs, vectors and procedures are not data, as they live on the heap. They
+/// are represented by their 8-byte aligned address, tagged in its low bits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Datum {
+    fixnum(i64),
+    boolean(bool),
+    empty,
+    void,
+    ascii_char(u8),
+    error(u8),
+}
+
+impl Datum {
+    pub fn repr(&self) -> i64 {
+        match self {
+            Self::fixnum(fixnum) => fixnum << FIXNUM_SHIFT,
+            Self::boolean(true) => TRUE,
+            Self::boolean(false) => FALSE,
+            Self::empty => EMPTY,
+            Self::void => VOID,
+            Self::ascii_char(ascii) => {
+                ((*ascii as i64) << ASCII_CHAR_SHIFT) | ASCII_CHAR_TAG
+            },
+            Self::error(code) => ((*code as i64) << ERROR_SHIFT) | ERROR_TAG,
+        }
+    }
+
+    /// ### Purpose:
+    /// Decodes a runtime representation, or returns None if the word is not
+    /// the representation of any datum.
+    pub fn from_repr(repr: i64) -> Option<Self> {
+        match repr {
+            TRUE => Some(Self::boolean(true)),
+            FALSE => Some(Self::boolean(false)),
+            EMPTY => Some(Self::empty),
+            VOID => Some(Self::void),
+            _ if repr & FIXNUM_MASK == FIXNUM_TAG => {
+                Some(Self::fixnum(repr >> FIXNUM_SHIFT))
+            },
+            _ if repr & ASCII_CHAR_MASK == ASCII_CHAR_TAG => {
+                u8::try_from(repr >> ASCII_CHAR_SHIFT)
+                    .ok()
+                    .map(Self::ascii_char)
+            },
+            _ if repr & ERROR_MASK == ERROR_TAG => {
+                u8::try_from(repr >> ERROR_SHIFT).ok().map(Self::error)
+            },
+            _ => None,
+        }
     }
 }
 
+/// ### Purpose:
+/// The primitive operations on tagged values, other than the binops and
+/// relops. Eq? is the eq relop, since equal data have equal representations.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Primop {
+    fixnum_p,
+    boolean_p,
+    empty_p,
+    void_p,
+    ascii_char_p,
+    error_p,
+    pair_p,
+    vector_p,
+    procedure_p,
+    not,
+    cons,
+    car,
+    cdr,
+    make_vector,
+    vector_length,
+    vector_ref,
+    vector_set,
+}
+
+impl Primop {
+    /// ### Purpose:
+    /// The number of arguments the operation takes.
+    pub fn arity(&self) -> usize {
+        match self {
+            Self::cons | Self::vector_ref => 2,
+            Self::vector_set => 3,
+            _ => 1,
+        }
+    }
+}
+
+/// ### Notes:
+/// A frame address is a displacement below the frame base pointer. A heap
+/// address is a base register plus an index, which is either a constant or a
+/// second register.
 #[derive(Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(test, derive(Debug))]
-pub struct Addr {
-    pub fbp: Reg,
-    pub disp_offset: usize,
+pub enum Addr {
+    frame { fbp: Reg, disp_offset: usize },
+    heap { base: Reg, index: Index },
 }
 
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum Index {
+    int32(i32),
+    reg(Reg),
+}
+
+/// ### Notes:
+/// All operations are on signed 64-bit integers and wrap on overflow, as they
+/// do on x64. Division never traps: a quotient by zero is -1 and a remainder
+/// by zero is the dividend. Shift counts are taken modulo 64.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub enum Binop {
     plus,
+    minus,
     multiply,
+    quotient,
+    remainder,
+    bitwise_and,
+    bitwise_ior,
+    bitwise_xor,
+    arithmetic_shift_right,
+    shift_left,
+}
+
+impl Binop {
+    /// ### Purpose:
+    /// Evaluates the operation with the semantics of the compiled program.
+    /// Every interpreter and constant folder should go through this.
+    pub fn eval(&self, value1: i64, value2: i64) -> i64 {
+        match self {
+            Self::plus => value1.wrapping_add(value2),
+            Self::minus => value1.wrapping_sub(value2),
+            Self::multiply => value1.wrapping_mul(value2),
+            Self::quotient => match value2 {
+                0 => -1,
+                _ => value1.wrapping_div(value2),
+            },
+            Self::remainder => match value2 {
+                0 => value1,
+                _ => value1.wrapping_rem(value2),
+            },
+            Self::bitwise_and => value1 & value2,
+            Self::bitwise_ior => value1 | value2,
+            Self::bitwise_xor => value1 ^ value2,
+            Self::arithmetic_shift_right => value1 >> (value2 & 63),
+            Self::shift_left => value1 << (value2 & 63),
+        }
+    }
+
+    /// ### Purpose:
+    /// Evaluates the operation, returning None if it overflows. Only
+    /// addition, subtraction and multiplication can overflow.
+    pub fn checked_eval(&self, value1: i64, value2: i64) -> Option<i64> {
+        match self {
+            Self::plus => value1.checked_add(value2),
+            Self::minus => value1.checked_sub(value2),
+            Self::multiply => value1.checked_mul(value2),
+            _ => Some(self.eval(value1, value2)),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -169,6 +642,21 @@ pub enum Relop {
     neq,
 }
 
+impl Relop {
+    /// ### Purpose:
+    /// Evaluates the comparison with the semantics of the compiled program.
+    pub fn eval(&self, value1: i64, value2: i64) -> bool {
+        match self {
+            Self::gt => value1 > value2,
+            Self::gte => value1 >= value2,
+            Self::lt => value1 < value2,
+            Self::lte => value1 <= value2,
+            Self::eq => value1 == value2,
+            Self::neq => value1 != value2,
+        }
+    }
+}
+
 impl std::ops::Not for Relop {
     type Output = Self;
 
@@ -187,6 +675,12 @@ impl std::ops::Not for Relop {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct Label(pub String);
 
+impl Default for Label {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Label {
     pub fn new() -> Self {
         let index = fresh_index(&LABEL_INDEX);
@@ -207,7 +701,13 @@ impl Label {
     }
 
     pub fn halt_label() -> Self {
-        let label = format!("L.done");
+        let label = "L.done".to_string();
+
+        Self(label)
+    }
+
+    pub fn overflow_label() -> Self {
+        let label = String::from("L.overflow");
 
         Self(label)
     }
@@ -230,6 +730,8 @@ pub struct Info<Loc> {
     pub assignment: Option<Assignments<Loc>>,
     pub undead_out: Option<Node>,
     pub conflicts: Option<Graph>,
+    pub constants: Option<HashMap<Aloc, i64>>,
+    pub rematerialised: Option<HashMap<Aloc, i64>>,
 }
 
 impl<Loc> Default for Info<Loc> {
@@ -239,6 +741,8 @@ impl<Loc> Default for Info<Loc> {
             assignment: None,
             undead_out: None,
             conflicts: None,
+            constants: None,
+            rematerialised: None,
         }
     }
 }
@@ -269,6 +773,56 @@ impl Node {
             } => nodes,
         }
     }
+
+    /// ### Purpose:
+    /// Renders this undead-set tree as a Graphviz digraph. Leaves are labelled
+    /// with their (sorted) undead-out sets; edges are labelled with the index
+    /// of the child within its parent.
+    pub fn to_dot(&self) -> String {
+        fn dot_node(
+            node: &Node,
+            id: usize,
+            lines: &mut Vec<String>,
+            next_id: &mut usize,
+        ) {
+            match node {
+                Node::alocs { alocs } => {
+                    let alocs = sorted_alocs(alocs)
+                        .into_iter()
+                        .map(|aloc| format!("{:?}", aloc))
+                        .join(", ");
+                    let label = format!("{{{}}}", alocs);
+                    lines.push(format!(
+                        "    n{} [label={:?}, shape=box];",
+                        id, label
+                    ));
+                },
+                Node::tree {
+                    tree: Tree { nodes },
+                } => {
+                    lines.push(format!(
+                        "    n{} [label=\"tree\", shape=point];",
+                        id
+                    ));
+                    nodes.iter().enumerate().for_each(|(index, child)| {
+                        let child_id = *next_id;
+                        *next_id += 1;
+                        lines.push(format!(
+                            "    n{} -> n{} [label=\"{}\"];",
+                            id, child_id, index
+                        ));
+                        dot_node(child, child_id, lines, next_id);
+                    });
+                },
+            }
+        }
+
+        let mut lines = vec!["digraph undead {".to_string()];
+        let mut next_id = 1usize;
+        dot_node(self, 0usize, &mut lines, &mut next_id);
+        lines.push("}".into());
+        lines.join("\n")
+    }
 }
 
 impl Debug for Node {
@@ -285,6 +839,12 @@ pub struct Tree {
     pub nodes: Vec<Node>,
 }
 
+impl Default for Tree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Tree {
     pub fn new() -> Self {
         Self { nodes: vec![] }
@@ -309,33 +869,179 @@ pub struct Graph {
 impl Graph {
     pub fn new(alocs: &AlocSet) -> Self {
         let graph = alocs
-            .into_iter()
+            .iter()
             .map(|aloc| (aloc.clone(), HashSet::default()))
             .collect();
 
         Self { graph }
     }
 
+    /// ### Purpose:
+    /// Adds the alocs to the conflicts of the aloc, keeping the conflicts it
+    /// already has. An aloc that is set more than once conflicts with what is
+    /// undead after each of its sets.
     pub fn insert_alocs(&mut self, aloc: Aloc, alocs: AlocSet) {
-        self.graph.insert(aloc, alocs);
+        self.graph.entry(aloc).or_default().extend(alocs);
     }
 
     pub fn remove_node(&mut self, aloc: &Aloc) {
         let Self { graph } = self;
 
         graph.remove(aloc);
-        graph.into_iter().for_each(|(_, alocs)| {
+        graph.iter_mut().for_each(|(_, alocs)| {
             alocs.remove(aloc);
         });
     }
 
+    /// ### Purpose:
+    /// Lists every conflict exactly once, as a sorted pair of abstract
+    /// locations. Conflict-analysis may record an edge on only one side.
+    pub fn edges(&self) -> Vec<(&Aloc, &Aloc)> {
+        let Self { graph } = self;
+
+        graph
+            .iter()
+            .flat_map(|(aloc1, alocs)| {
+                alocs.iter().map(move |aloc2| match aloc1 <= aloc2 {
+                    true => (aloc1, aloc2),
+                    false => (aloc2, aloc1),
+                })
+            })
+            .filter(|(aloc1, aloc2)| aloc1 != aloc2)
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// ### Purpose:
+    /// Estimates the size of the largest clique in the graph, growing a clique
+    /// greedily from each node in turn (highest degree first). The result is
+    /// a lower bound on the number of registers needed to avoid spilling.
+    pub fn max_clique_estimate(&self) -> usize {
+        let neighbours = self.edges().into_iter().fold(
+            HashMap::<&Aloc, HashSet<&Aloc>>::default(),
+            |mut neighbours, (aloc1, aloc2)| {
+                neighbours.entry(aloc1).or_default().insert(aloc2);
+                neighbours.entry(aloc2).or_default().insert(aloc1);
+                neighbours
+            },
+        );
+        let degree = |aloc: &&Aloc| {
+            neighbours.get(aloc).map(HashSet::len).unwrap_or_default()
+        };
+
+        let mut alocs = self.graph.keys().collect::<Vec<_>>();
+        alocs.sort_by(|aloc1, aloc2| {
+            degree(aloc2).cmp(&degree(aloc1)).then(aloc1.cmp(aloc2))
+        });
+
+        alocs
+            .iter()
+            .map(|aloc| {
+                let mut candidates = neighbours
+                    .get(aloc)
+                    .map(|alocs| alocs.iter().collect::<Vec<_>>())
+                    .unwrap_or_default();
+                candidates.sort_by(|aloc1, aloc2| {
+                    degree(aloc2).cmp(&degree(aloc1)).then(aloc1.cmp(aloc2))
+                });
+                candidates
+                    .into_iter()
+                    .fold(vec![*aloc], |mut clique, candidate| {
+                        let is_adjacent_to_all = clique.iter().all(|member| {
+                            neighbours
+                                .get(member)
+                                .is_some_and(|alocs| alocs.contains(candidate))
+                        });
+                        if is_adjacent_to_all {
+                            clique.push(candidate);
+                        }
+                        clique
+                    })
+                    .len()
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// ### Purpose:
+    /// Renders the conflict graph as an undirected Graphviz graph.
+    ///
+    /// ### Notes:
+    /// When an assignment is given, each node is labelled with its assigned
+    /// location and filled with a colour unique to that location, so that
+    /// abstract locations sharing a register (or spilled to the frame) can be
+    /// spotted at a glance. Conflicts are recorded on only one side by
+    /// conflict-analysis; each edge is emitted once, whichever side lists it.
+    pub fn to_dot<Loc>(&self, assignment: Option<&Assignments<Loc>>) -> String
+    where
+        Loc: Debug,
+    {
+        let Self { graph } = self;
+
+        let mut alocs = graph
+            .iter()
+            .flat_map(|(aloc, alocs)| std::iter::once(aloc).chain(alocs))
+            .collect::<Vec<_>>();
+        alocs.sort();
+        alocs.dedup();
+
+        let colours = assignment
+            .map(|assignment| {
+                assignment
+                    .values()
+                    .map(|loc| format!("{:?}", loc))
+                    .sorted()
+                    .dedup()
+                    .enumerate()
+                    .map(|(index, loc)| {
+                        let colour = DOT_PALETTE[index % DOT_PALETTE.len()];
+                        (loc, colour)
+                    })
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+
+        let nodes = alocs.iter().map(|aloc| {
+            let name = format!("{:?}", aloc);
+            let loc = assignment
+                .and_then(|assignment| assignment.get(*aloc))
+                .map(|loc| format!("{:?}", loc));
+            match loc {
+                Some(loc) => {
+                    let label = format!("{}\n{}", name, loc);
+                    let colour = colours.get(&loc).unwrap();
+                    format!(
+                        "    {:?} [label={:?}, style=filled, fillcolor={:?}];",
+                        name, label, colour
+                    )
+                },
+                None => format!("    {:?};", name),
+            }
+        });
+
+        let edges = self.edges().into_iter().map(|(aloc1, aloc2)| {
+            format!(
+                "    {:?} -- {:?};",
+                format!("{:?}", aloc1),
+                format!("{:?}", aloc2),
+            )
+        });
+
+        std::iter::once("graph conflicts {".to_string())
+            .chain(nodes)
+            .chain(edges)
+            .chain(std::iter::once("}".to_string()))
+            .join("\n")
+    }
+
     #[cfg(test)]
     pub fn new_with_graph(graph: &[(Aloc, &[Aloc])]) -> Self {
-        let graph = graph.into_iter().fold(
+        let graph = graph.iter().fold(
             HashMap::default(),
             |mut graph, (aloc, alocs)| {
                 let aloc_set =
-                    alocs.into_iter().map(Aloc::clone).collect::<HashSet<_>>();
+                    alocs.iter().map(Aloc::clone).collect::<HashSet<_>>();
                 graph.insert(aloc.clone(), aloc_set);
                 graph
             },
@@ -345,6 +1051,48 @@ impl Graph {
     }
 }
 
+/// ### Purpose:
+/// An edge out of a basic block of a control-flow graph, as rendered by
+/// cfg_to_dot. Every edge but a halt is labelled.
+pub enum CfgEdge<'a> {
+    halt,
+    label { trg: &'a Label, label: String },
+    indirect { label: String },
+}
+
+/// ### Purpose:
+/// Renders a control-flow graph as a Graphviz digraph, with one node per
+/// basic block and one edge per jump. Halts all go to a single halt node, and
+/// jumps through a location all go to a single indirect node.
+pub fn cfg_to_dot<'a>(blocks: Vec<(&'a Label, Vec<CfgEdge<'a>>)>) -> String {
+    let header = vec![
+        "digraph cfg {".to_string(),
+        "    \"halt\" [shape=doublecircle];".into(),
+        "    \"indirect\" [shape=diamond];".into(),
+    ];
+    let blocks = blocks.into_iter().flat_map(|(Label(name), edges)| {
+        let node = format!("    {:?} [shape=box];", name);
+        let edges = edges.into_iter().map(move |edge| match edge {
+            CfgEdge::halt => format!("    {:?} -> \"halt\";", name),
+            CfgEdge::label {
+                trg: Label(trg),
+                label,
+            } => format!("    {:?} -> {:?} [label={:?}];", name, trg, label),
+            CfgEdge::indirect { label } => format!(
+                "    {:?} -> \"indirect\" [label={:?}];",
+                name, label
+            ),
+        });
+        std::iter::once(node).chain(edges)
+    });
+
+    header
+        .into_iter()
+        .chain(blocks)
+        .chain(std::iter::once("}".into()))
+        .join("\n")
+}
+
 #[cfg_attr(test, derive(Debug))]
 pub struct LevelledEnv<K, V> {
     next_level: usize,
@@ -403,10 +1151,24 @@ where
         Self { next_level, levels }
     }
 
+    /// ### Purpose:
+    /// The values bound in every level, including those which are shadowed.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.levels.values().flat_map(HashMap::values)
+    }
+
+    /// ### Purpose:
+    /// The value bound in the outermost level, ignoring every level added
+    /// since.
+    pub fn get_outermost(&self, key: &K) -> Option<&V> {
+        self.levels.get(&0).and_then(|map| map.get(key))
+    }
+
     pub fn get(&self, key: &K) -> Option<&V> {
         let Self { next_level, levels } = self;
         let level = next_level - 1;
-        (level..=0)
+        (0..=level)
+            .rev()
             .fold_while(None, |_, level| {
                 levels
                     .get(&level)
@@ -0,0 +1,249 @@
+use serial_test::serial;
+
+use crate::structured_control_flow::nested_asm_lang as target;
+use crate::utils;
+
+#[test]
+#[serial]
+fn graph_to_dot_without_assignment() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh_with_name("x");
+    let y = utils::Aloc::fresh_with_name("y");
+
+    let graph = utils::Graph::new_with_graph(&[
+        (x.clone(), std::slice::from_ref(&y)),
+        (y.clone(), &[]),
+    ]);
+
+    let actual = graph.to_dot::<target::Loc>(None);
+
+    utils::reset_all_indices();
+
+    let expected = "graph conflicts {
+    \"x.0\";
+    \"y.1\";
+    \"x.0\" -- \"y.1\";
+}";
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn graph_to_dot_with_assignment() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh_with_name("x");
+    let y = utils::Aloc::fresh_with_name("y");
+    let z = utils::Aloc::fresh_with_name("z");
+
+    let graph = utils::Graph::new_with_graph(&[
+        (x.clone(), std::slice::from_ref(&y)),
+        (y.clone(), std::slice::from_ref(&x)),
+        (z.clone(), &[]),
+    ]);
+
+    let assignment = vec![
+        (x, target::Loc::reg(utils::Reg::rbx)),
+        (y, target::Loc::fvar(utils::Fvar(0))),
+        (z, target::Loc::reg(utils::Reg::rbx)),
+    ]
+    .into_iter()
+    .collect();
+
+    let actual = graph.to_dot(Some(&assignment));
+
+    utils::reset_all_indices();
+
+    let expected = "graph conflicts {
+    \"x.0\" [label=\"x.0\\nreg(rbx)\", style=filled, fillcolor=\"lightgreen\"];
+    \"y.1\" [label=\"y.1\\nfvar(Fvar(0))\", style=filled, fillcolor=\"lightblue\"];
+    \"z.2\" [label=\"z.2\\nreg(rbx)\", style=filled, fillcolor=\"lightgreen\"];
+    \"x.0\" -- \"y.1\";
+}";
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn undead_tree_to_dot() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh_with_name("x");
+
+    let node = utils::Node::tree {
+        tree: utils::Tree {
+            nodes: vec![
+                utils::Node::alocs {
+                    alocs: vec![x].into_iter().collect(),
+                },
+                utils::Node::alocs {
+                    alocs: utils::AlocSet::default(),
+                },
+            ],
+        },
+    };
+
+    let actual = node.to_dot();
+
+    utils::reset_all_indices();
+
+    let expected = "digraph undead {
+    n0 [label=\"tree\", shape=point];
+    n0 -> n1 [label=\"0\"];
+    n1 [label=\"{x.0}\", shape=box];
+    n0 -> n2 [label=\"1\"];
+    n2 [label=\"{}\", shape=box];
+}";
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn target_config_default_is_valid() {
+    let config = utils::TargetConfig::default();
+
+    assert_eq!(config.validate(), Ok(()));
+}
+
+#[test]
+#[serial]
+fn target_config_rejects_overlaps() {
+    let default = utils::TargetConfig::default();
+
+    let aux_is_assignable = utils::TargetConfig::new(
+        vec![utils::Reg::rbx, utils::Reg::r10],
+        default.parameter_registers.clone(),
+        default.return_register,
+        default.frame_base_pointer,
+        default.heap_pointer,
+        default.auxiliary_registers,
+    );
+    let fbp_is_aux = utils::TargetConfig::new(
+        default.assignable_registers.clone(),
+        default.parameter_registers.clone(),
+        default.return_register,
+        utils::Reg::r11,
+        default.heap_pointer,
+        default.auxiliary_registers,
+    );
+    let duplicate_parameter = utils::TargetConfig::new(
+        default.assignable_registers.clone(),
+        vec![utils::Reg::rdi, utils::Reg::rdi],
+        default.return_register,
+        default.frame_base_pointer,
+        default.heap_pointer,
+        default.auxiliary_registers,
+    );
+    let hbp_is_assignable = utils::TargetConfig::new(
+        vec![utils::Reg::rbx, default.heap_pointer],
+        default.parameter_registers.clone(),
+        default.return_register,
+        default.frame_base_pointer,
+        default.heap_pointer,
+        default.auxiliary_registers,
+    );
+    let stack_pointer_return = utils::TargetConfig::new(
+        default.assignable_registers.clone(),
+        default.parameter_registers.clone(),
+        utils::Reg::rsp,
+        default.frame_base_pointer,
+        default.heap_pointer,
+        default.auxiliary_registers,
+    );
+
+    assert!(aux_is_assignable.is_err());
+    assert!(fbp_is_aux.is_err());
+    assert!(duplicate_parameter.is_err());
+    assert!(hbp_is_assignable.is_err());
+    assert!(stack_pointer_return.is_err());
+}
+
+#[test]
+#[serial]
+fn binop_eval_wraps() {
+    let cases = [
+        (utils::Binop::plus, i64::MAX, 1, i64::MIN),
+        (utils::Binop::minus, i64::MIN, 1, i64::MAX),
+        (utils::Binop::multiply, i64::MIN, -1, i64::MIN),
+        (utils::Binop::quotient, i64::MIN, -1, i64::MIN),
+        (utils::Binop::quotient, 5, 0, -1),
+        (utils::Binop::remainder, 5, 0, 5),
+        (utils::Binop::shift_left, 1, 65, 2),
+    ];
+
+    for (binop, value1, value2, result) in cases {
+        assert_eq!(binop.eval(value1, value2), result);
+    }
+
+    assert_eq!(utils::Binop::plus.checked_eval(i64::MAX, 1), None);
+    assert_eq!(utils::Binop::quotient.checked_eval(5, 0), Some(-1));
+}
+
+#[test]
+#[serial]
+fn datum_repr_round_trips() {
+    let data = [
+        utils::Datum::fixnum(-5),
+        utils::Datum::fixnum(0),
+        utils::Datum::boolean(true),
+        utils::Datum::boolean(false),
+        utils::Datum::empty,
+        utils::Datum::void,
+        utils::Datum::ascii_char(b'a'),
+        utils::Datum::error(2),
+    ];
+
+    for datum in data {
+        assert_eq!(utils::Datum::from_repr(datum.repr()), Some(datum));
+    }
+
+    assert_eq!(utils::Datum::fixnum(5).repr(), 40);
+    assert_eq!(utils::Datum::boolean(true).repr(), utils::TRUE);
+    assert_eq!(utils::Datum::from_repr(0b101), None);
+}
+
+#[test]
+#[serial]
+fn levelled_env_shadows_outer_levels() {
+    let env = utils::LevelledEnv::<utils::Name, i64>::default()
+        .add_level()
+        .insert("x", 1)
+        .insert("y", 2)
+        .add_level()
+        .insert("x", 3);
+
+    assert_eq!(env.get(&"x"), Some(&3));
+    assert_eq!(env.get(&"y"), Some(&2));
+
+    let env = env.remove_level();
+
+    assert_eq!(env.get(&"x"), Some(&1));
+}
+
+#[test]
+#[serial]
+fn graph_insert_alocs_keeps_conflicts() {
+    utils::reset_all_indices();
+
+    let x = utils::Aloc::fresh_with_name("x");
+    let y = utils::Aloc::fresh_with_name("y");
+    let z = utils::Aloc::fresh_with_name("z");
+
+    let mut actual = utils::Graph::new(
+        &[x.clone(), y.clone(), z.clone()].into_iter().collect(),
+    );
+    actual.insert_alocs(x.clone(), [y.clone()].into_iter().collect());
+    actual.insert_alocs(x.clone(), [z.clone()].into_iter().collect());
+
+    let expected = utils::Graph::new_with_graph(&[
+        (x, &[y.clone(), z.clone()]),
+        (y, &[]),
+        (z, &[]),
+    ]);
+
+    assert_eq!(actual, expected);
+}
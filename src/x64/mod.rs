@@ -1,11 +1,17 @@
 pub mod paren_x64;
 pub mod paren_x64_rt;
 
+use crate::utils;
+
 pub type Source = paren_x64::ParenX64;
 
 pub type Target = String;
 
-pub fn compile(p: Source) -> Result<Target, String> {
-    let p = p.generate_x64();
+pub fn compile(
+    p: Source,
+    _: &utils::TargetConfig,
+    _: &utils::CompileOptions,
+) -> Result<Target, String> {
+    let p = p.peephole()?.generate_x64();
     Ok(p)
 }
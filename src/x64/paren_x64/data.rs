@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::utils;
 
 #[derive(Clone)]
@@ -6,6 +8,24 @@ pub enum P {
     begin(Vec<S>),
 }
 
+/// ### Purpose:
+/// A separately compiled module, with the labels of the procedures it
+/// provides (its exports) and of those it requires (its imports).
+///
+/// ### Notes:
+/// The labels of the imports are not defined in the unit; the linker replaces
+/// them with the labels of the units which export them.
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct Unit {
+    pub name: utils::Name,
+    pub exports: Symbols,
+    pub imports: Symbols,
+    pub p: P,
+}
+
+pub type Symbols = HashMap<utils::Name, utils::Label>;
+
 #[derive(Clone)]
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub enum S {
@@ -35,6 +55,12 @@ pub enum S {
         binop: utils::Binop,
         loc: Loc,
     },
+    /// Divides rax by the reg, leaving the quotient in rax and the remainder
+    /// in rdx. Patch-instructions lowers every quotient and remainder binop
+    /// to a divide, so that neither reaches Paren-x64 as a binop.
+    divide {
+        reg: utils::Reg,
+    },
     with_label {
         label: utils::Label,
         s: Box<Self>,
@@ -46,6 +72,9 @@ pub enum S {
         relop: utils::Relop,
         label: utils::Label,
     },
+    jump_if_overflow {
+        label: utils::Label,
+    },
     nop,
 }
 
@@ -63,7 +63,8 @@ impl ParenX64 {
         fn check_jumps(s: &self::S, labels: &LabelStore) -> Result<(), String> {
             match s {
                 self::S::jump(trg) => check_trg(trg, labels),
-                self::S::compare_reg_opand_jump_if { label, .. } => {
+                self::S::compare_reg_opand_jump_if { label, .. }
+                | self::S::jump_if_overflow { label } => {
                     check_label(label, labels)
                 },
                 _ => Ok(()),
@@ -97,12 +98,239 @@ impl ParenX64 {
             }
         }
 
-        check_p(&p)?;
+        check_p(p)?;
         Ok(self)
     }
 
+    /// ### Purpose:
+    /// Simplifies the program by repeatedly rewriting short windows of
+    /// adjacent instructions with the rules of a rule table, until no rule
+    /// applies. Check-labels is re-run on the result.
+    ///
+    /// ### Notes:
+    /// Only the first instruction of a window may carry a label, so that no
+    /// jump can land in the middle of a rewritten sequence. If a rule removes
+    /// a labelled instruction, its label is moved onto the next instruction,
+    /// or kept on a nop if that instruction already has a label of its own.
+    pub fn peephole(self) -> Result<Self, String> {
+        type Rule = fn(&[self::S]) -> Option<(usize, Vec<self::S>)>;
+
+        const WINDOW: usize = 2;
+
+        const RULES: [Rule; 6] = [
+            remove_self_move,
+            remove_nop,
+            forward_store_to_reload,
+            remove_add_zero,
+            remove_multiply_one,
+            remove_jump_to_next,
+        ];
+
+        let Self(p) = self;
+
+        fn peephole_p(p: self::P) -> self::P {
+            match p {
+                self::P::begin(ss) => {
+                    let mut ss = ss;
+                    loop {
+                        let (next_ss, is_changed) = peephole_ss(ss);
+                        ss = next_ss;
+                        match is_changed {
+                            true => (),
+                            false => break,
+                        }
+                    }
+                    self::P::begin(ss)
+                },
+            }
+        }
+
+        fn peephole_ss(ss: Vec<self::S>) -> (Vec<self::S>, bool) {
+            let mut new_ss = vec![];
+            let mut is_changed = false;
+            let mut index = 0usize;
+
+            while index < ss.len() {
+                let (label, s) = split_label(&ss[index]);
+
+                let window = std::iter::once(s.clone())
+                    .chain(ss.iter().skip(index + 1).take(WINDOW - 1).cloned())
+                    .collect::<Vec<_>>();
+
+                let rewrite = RULES.iter().find_map(|rule| rule(&window));
+
+                match (rewrite, label) {
+                    (None, _) => {
+                        new_ss.push(ss[index].clone());
+                        index += 1;
+                    },
+                    (Some((consumed, rewritten)), None) => {
+                        new_ss.extend(rewritten);
+                        index += consumed;
+                        is_changed = true;
+                    },
+                    (Some((consumed, rewritten)), Some(label)) => {
+                        let next_s = ss
+                            .get(index + consumed)
+                            .filter(|s| split_label(s).0.is_none());
+                        match (rewritten.is_empty(), next_s) {
+                            // The label moves onto the next instruction.
+                            (true, Some(next_s)) => {
+                                new_ss.push(with_label(label, next_s.clone()));
+                                index += consumed + 1;
+                                is_changed = true;
+                            },
+                            // The label stays on a nop, which is only a change
+                            // if the removed instructions were not that nop.
+                            (true, None) => {
+                                let is_nop = matches!(s, self::S::nop);
+                                new_ss.push(with_label(label, self::S::nop));
+                                index += consumed;
+                                is_changed |= !(is_nop && consumed == 1);
+                            },
+                            (false, _) => {
+                                let mut rewritten = rewritten.into_iter();
+                                let first = rewritten.next().unwrap();
+                                new_ss.push(with_label(label, first));
+                                new_ss.extend(rewritten);
+                                index += consumed;
+                                is_changed = true;
+                            },
+                        }
+                    },
+                }
+            }
+
+            (new_ss, is_changed)
+        }
+
+        fn split_label(s: &self::S) -> (Option<utils::Label>, &self::S) {
+            match s {
+                self::S::with_label { label, s } => (Some(label.clone()), s),
+                s => (None, s),
+            }
+        }
+
+        fn with_label(label: utils::Label, s: self::S) -> self::S {
+            let s = Box::new(s);
+            self::S::with_label { label, s }
+        }
+
+        // mov reg, reg
+        fn remove_self_move(ss: &[self::S]) -> Option<(usize, Vec<self::S>)> {
+            match ss {
+                [self::S::set_reg_loc {
+                    reg,
+                    loc: self::Loc::reg(reg2),
+                }, ..]
+                    if reg == reg2 =>
+                {
+                    Some((1, vec![]))
+                },
+                _ => None,
+            }
+        }
+
+        fn remove_nop(ss: &[self::S]) -> Option<(usize, Vec<self::S>)> {
+            match ss {
+                [self::S::nop, ..] => Some((1, vec![])),
+                _ => None,
+            }
+        }
+
+        // mov addr, reg
+        // mov reg2, addr
+        fn forward_store_to_reload(
+            ss: &[self::S],
+        ) -> Option<(usize, Vec<self::S>)> {
+            match ss {
+                [store @ self::S::set_addr_trg {
+                    addr,
+                    trg: self::Trg::reg(reg),
+                }, self::S::set_reg_loc {
+                    reg: reg2,
+                    loc: self::Loc::addr(addr2),
+                }, ..]
+                    if addr == addr2 =>
+                {
+                    let reload = self::S::set_reg_loc {
+                        reg: *reg2,
+                        loc: self::Loc::reg(*reg),
+                    };
+                    Some((2, vec![store.clone(), reload]))
+                },
+                _ => None,
+            }
+        }
+
+        // add reg, 0
+        // jo label (optional, as the add cannot overflow)
+        fn remove_add_zero(ss: &[self::S]) -> Option<(usize, Vec<self::S>)> {
+            match ss {
+                [self::S::set_reg_binop_reg_int32 {
+                    binop: utils::Binop::plus,
+                    int32: 0,
+                    ..
+                }, self::S::jump_if_overflow { .. }, ..] => Some((2, vec![])),
+                [self::S::set_reg_binop_reg_int32 {
+                    binop: utils::Binop::plus,
+                    int32: 0,
+                    ..
+                }, ..] => Some((1, vec![])),
+                _ => None,
+            }
+        }
+
+        // imul reg, 1
+        // jo label (optional, as the imul cannot overflow)
+        fn remove_multiply_one(
+            ss: &[self::S],
+        ) -> Option<(usize, Vec<self::S>)> {
+            match ss {
+                [self::S::set_reg_binop_reg_int32 {
+                    binop: utils::Binop::multiply,
+                    int32: 1,
+                    ..
+                }, self::S::jump_if_overflow { .. }, ..] => Some((2, vec![])),
+                [self::S::set_reg_binop_reg_int32 {
+                    binop: utils::Binop::multiply,
+                    int32: 1,
+                    ..
+                }, ..] => Some((1, vec![])),
+                _ => None,
+            }
+        }
+
+        // jmp label
+        // label:
+        fn remove_jump_to_next(
+            ss: &[self::S],
+        ) -> Option<(usize, Vec<self::S>)> {
+            match ss {
+                [self::S::jump(self::Trg::label(label)), next_s, ..] => {
+                    match next_s {
+                        self::S::with_label { label: label2, .. }
+                            if label == label2 =>
+                        {
+                            Some((1, vec![]))
+                        },
+                        _ => None,
+                    }
+                },
+                _ => None,
+            }
+        }
+
+        let p = peephole_p(p);
+        Self(p).check_labels()
+    }
+
     /// ### Purpose:
     /// Generate X64 source code in string form.
+    ///
+    /// ### Notes:
+    /// The count of a shift by an int32 is masked to its low 6 bits, as x64
+    /// does with a count in cl.
     pub fn generate_x64(self) -> String {
         let Self(p) = self;
 
@@ -146,22 +374,46 @@ impl ParenX64 {
 
                     format!("\tmov {:?}, {}", reg, triv)
                 },
+                self::S::set_reg_binop_reg_int32 {
+                    reg,
+                    binop:
+                        binop @ (utils::Binop::arithmetic_shift_right
+                        | utils::Binop::shift_left),
+                    int32,
+                } => {
+                    let binop = generate_binop(binop);
+
+                    // As on x64, only the low 6 bits of the count are used.
+                    format!("\t{} {:?}, {}", binop, reg, int32 & 63)
+                },
                 self::S::set_reg_binop_reg_int32 { reg, binop, int32 } => {
                     let binop = generate_binop(binop);
 
                     format!("\t{} {:?}, {}", binop, reg, int32)
                 },
+                self::S::set_reg_binop_reg_loc {
+                    reg,
+                    binop:
+                        binop @ (utils::Binop::arithmetic_shift_right
+                        | utils::Binop::shift_left),
+                    loc,
+                } => generate_shift(reg, binop, loc),
                 self::S::set_reg_binop_reg_loc { reg, binop, loc } => {
-                    let binop = generate_binop(binop);
-
                     let loc = generate_loc(loc);
 
+                    let binop = generate_binop(binop);
+
                     format!("\t{} {:?}, {}", binop, reg, loc)
                 },
+                self::S::divide { reg } => {
+                    let reg = generate_reg(reg);
+
+                    format!("\tcqo\n\tidiv {}", reg)
+                },
                 self::S::with_label { label, s } => {
                     let label = generate_label(label);
 
-                    let s = generate_s(&s);
+                    let s = generate_s(s);
 
                     format!("{}:\n{}", label, s)
                 },
@@ -188,10 +440,52 @@ impl ParenX64 {
 
                     format!("{}\n{}", cmp_instr, jmp_instr)
                 },
+                self::S::jump_if_overflow { label } => {
+                    let label = generate_label(label);
+
+                    format!("\tjo {}", label)
+                },
                 self::S::nop => "".into(),
             }
         }
 
+        // A count in rcx is used as cl. Otherwise, rcx is saved and the value
+        // is shifted on the stack, so that the reg may itself be rcx:
+        // push rcx
+        // push reg
+        // rcx <- loc
+        // [rsp] <- [rsp] << cl
+        // rcx <- [rsp + 8]
+        // pop reg
+        // rsp <- rsp + 8
+        fn generate_shift(
+            reg: &utils::Reg,
+            binop: &utils::Binop,
+            loc: &self::Loc,
+        ) -> String {
+            let binop = generate_binop(binop);
+
+            match loc {
+                self::Loc::reg(utils::Reg::rcx) => {
+                    format!("\t{} {:?}, cl", binop, reg)
+                },
+                loc => {
+                    let loc = generate_loc(loc);
+
+                    [
+                        "\tpush rcx".to_string(),
+                        format!("\tpush {:?}", reg),
+                        format!("\tmov rcx, {}", loc),
+                        format!("\t{} QWORD [rsp], cl", binop),
+                        "\tmov rcx, QWORD [rsp + 8]".into(),
+                        format!("\tpop {:?}", reg),
+                        "\tadd rsp, 8".into(),
+                    ]
+                    .join("\n")
+                },
+            }
+        }
+
         fn generate_triv(triv: &self::Triv) -> String {
             match triv {
                 self::Triv::trg(trg) => generate_trg(trg),
@@ -214,12 +508,39 @@ impl ParenX64 {
             format!("{:#?}", reg)
         }
 
-        fn generate_addr(
-            utils::Addr { fbp, disp_offset }: &utils::Addr,
-        ) -> String {
-            let fbp = generate_reg(fbp);
+        fn generate_addr(addr: &utils::Addr) -> String {
+            match addr {
+                utils::Addr::frame { fbp, disp_offset } => {
+                    let fbp = generate_reg(fbp);
 
-            format!("QWORD [{} - {}]", fbp, disp_offset)
+                    format!("QWORD [{} - {}]", fbp, disp_offset)
+                },
+                utils::Addr::heap {
+                    base,
+                    index: utils::Index::int32(int32),
+                } => {
+                    let base = generate_reg(base);
+
+                    match int32.is_negative() {
+                        true => format!(
+                            "QWORD [{} - {}]",
+                            base,
+                            int32.unsigned_abs()
+                        ),
+                        false => format!("QWORD [{} + {}]", base, int32),
+                    }
+                },
+                utils::Addr::heap {
+                    base,
+                    index: utils::Index::reg(reg),
+                } => {
+                    let base = generate_reg(base);
+
+                    let reg = generate_reg(reg);
+
+                    format!("QWORD [{} + {}]", base, reg)
+                },
+            }
         }
 
         fn generate_opand(opand: &self::Opand) -> String {
@@ -239,7 +560,18 @@ impl ParenX64 {
         fn generate_binop(binop: &utils::Binop) -> String {
             match binop {
                 utils::Binop::plus => "add",
+                utils::Binop::minus => "sub",
                 utils::Binop::multiply => "imul",
+                utils::Binop::bitwise_and => "and",
+                utils::Binop::bitwise_ior => "or",
+                utils::Binop::bitwise_xor => "xor",
+                utils::Binop::arithmetic_shift_right => "sar",
+                utils::Binop::shift_left => "shl",
+                utils::Binop::quotient | utils::Binop::remainder => {
+                    unreachable!(
+                        "Division is lowered to divide by patch-instructions."
+                    )
+                },
             }
             .into()
         }
@@ -259,6 +591,200 @@ impl ParenX64 {
         generate_p(&p)
     }
 
+    /// ### Purpose:
+    /// Links separately compiled units into one program, which starts with
+    /// the first unit. Every symbol must be exported by at most one unit, and
+    /// every import must be exported by some unit.
+    ///
+    /// ### Notes:
+    /// The labels of each unit are namespaced by its name, so that the labels
+    /// of separate compilations cannot collide. The halt and overflow labels
+    /// are shared by all units: their definitions are removed from the units,
+    /// and made once at the end of the program. The first unit jumps to the
+    /// halt label when it ends, so that the code of the other units only runs
+    /// when it is called.
+    pub fn link_units(units: Vec<self::Unit>) -> Result<Self, String> {
+        // The unit which exports each symbol, and its label once linked.
+        type SymbolTable = HashMap<utils::Name, (utils::Name, utils::Label)>;
+        type LabelMap = HashMap<utils::Label, utils::Label>;
+
+        fn link_units(units: Vec<self::Unit>) -> Result<self::P, String> {
+            let table = collect_exports(&units)?;
+            let halt_label = utils::Label::halt_label();
+            let overflow_label = utils::Label::overflow_label();
+            let mut overflow_s = None;
+            let mut ss = vec![];
+            units.into_iter().enumerate().try_for_each(|(index, unit)| {
+                let imports = resolve_imports(&unit, &table)?;
+                let self::Unit { name, p, .. } = unit;
+                let self::P::begin(unit_ss) = p;
+                unit_ss.into_iter().for_each(|s| match s {
+                    self::S::with_label { label, .. }
+                        if label == halt_label => {},
+                    self::S::with_label { label, s }
+                        if label == overflow_label =>
+                    {
+                        overflow_s
+                            .get_or_insert(self::S::with_label { label, s });
+                    },
+                    s => ss.push(link_s(s, name, &imports)),
+                });
+                if index == 0 {
+                    let trg = self::Trg::label(halt_label.clone());
+                    ss.push(self::S::jump(trg));
+                };
+                Ok::<_, String>(())
+            })?;
+            ss.extend(overflow_s);
+            ss.push(self::S::with_label {
+                label: halt_label,
+                s: Box::new(self::S::nop),
+            });
+            Ok(self::P::begin(ss))
+        }
+
+        fn collect_exports(
+            units: &[self::Unit],
+        ) -> Result<SymbolTable, String> {
+            let mut names = HashSet::new();
+            units.iter().try_fold(SymbolTable::default(), |mut table, unit| {
+                match names.insert(unit.name) {
+                    true => Ok(()),
+                    false => Err(format!(
+                        "The module, '{}', is linked more than once.",
+                        unit.name
+                    )),
+                }?;
+                let mut exports = unit.exports.iter().collect::<Vec<_>>();
+                exports.sort_by_key(|(symbol, _)| **symbol);
+                exports.into_iter().try_for_each(|(symbol, label)| {
+                    let label = namespace(label, unit.name);
+                    match table.insert(symbol, (unit.name, label)) {
+                        None => Ok(()),
+                        Some((other_name, _)) => Err(format!(
+                            "The symbol, '{}', is provided by both '{}' and \
+                             '{}'.",
+                            symbol, other_name, unit.name
+                        )),
+                    }
+                })?;
+                Ok(table)
+            })
+        }
+
+        // Maps the label of each import of the unit to the label of its
+        // export.
+        fn resolve_imports(
+            unit: &self::Unit,
+            table: &SymbolTable,
+        ) -> Result<LabelMap, String> {
+            let mut imports = unit.imports.iter().collect::<Vec<_>>();
+            imports.sort_by_key(|(symbol, _)| **symbol);
+            imports
+                .into_iter()
+                .map(|(symbol, label)| match table.get(symbol) {
+                    Some((_, export)) => Ok((label.clone(), export.clone())),
+                    None => Err(format!(
+                        "The symbol, '{}', required by '{}', is not provided \
+                         by any module.",
+                        symbol, unit.name
+                    )),
+                })
+                .collect()
+        }
+
+        fn namespace(
+            label: &utils::Label,
+            name: utils::Name,
+        ) -> utils::Label {
+            let is_shared = *label == utils::Label::halt_label()
+                || *label == utils::Label::overflow_label();
+            match is_shared {
+                true => label.clone(),
+                false => {
+                    let utils::Label(label) = label;
+                    let label = label.strip_prefix("L.").unwrap_or(label);
+                    utils::Label(format!("L.{}.{}", name, label))
+                },
+            }
+        }
+
+        fn link_label(
+            label: utils::Label,
+            name: utils::Name,
+            imports: &LabelMap,
+        ) -> utils::Label {
+            match imports.get(&label) {
+                Some(export) => export.clone(),
+                None => namespace(&label, name),
+            }
+        }
+
+        fn link_s(
+            s: self::S,
+            name: utils::Name,
+            imports: &LabelMap,
+        ) -> self::S {
+            match s {
+                self::S::set_addr_trg { addr, trg } => {
+                    let trg = link_trg(trg, name, imports);
+                    self::S::set_addr_trg { addr, trg }
+                },
+                self::S::set_reg_triv {
+                    reg,
+                    triv: self::Triv::trg(trg),
+                } => {
+                    let trg = link_trg(trg, name, imports);
+                    let triv = self::Triv::trg(trg);
+                    self::S::set_reg_triv { reg, triv }
+                },
+                self::S::with_label { label, s } => {
+                    let label = link_label(label, name, imports);
+                    let s = Box::new(link_s(*s, name, imports));
+                    self::S::with_label { label, s }
+                },
+                self::S::jump(trg) => {
+                    self::S::jump(link_trg(trg, name, imports))
+                },
+                self::S::compare_reg_opand_jump_if {
+                    reg,
+                    opand,
+                    relop,
+                    label,
+                } => {
+                    let label = link_label(label, name, imports);
+                    self::S::compare_reg_opand_jump_if {
+                        reg,
+                        opand,
+                        relop,
+                        label,
+                    }
+                },
+                self::S::jump_if_overflow { label } => {
+                    let label = link_label(label, name, imports);
+                    self::S::jump_if_overflow { label }
+                },
+                s => s,
+            }
+        }
+
+        fn link_trg(
+            trg: self::Trg,
+            name: utils::Name,
+            imports: &LabelMap,
+        ) -> self::Trg {
+            match trg {
+                self::Trg::reg(..) => trg,
+                self::Trg::label(label) => {
+                    self::Trg::label(link_label(label, name, imports))
+                },
+            }
+        }
+
+        let p = link_units(units)?;
+        Self(p).check_labels()
+    }
+
     /// ### Purpose:
     /// Compiles Paren-x64 v4 to Paren-x64-rt v4 by resolving all labels to
     /// their position in the instruction sequence.
@@ -323,7 +849,7 @@ impl ParenX64 {
                 },
                 self::S::set_reg_loc { reg, loc } => target::S::set_reg_loc {
                     reg,
-                    loc: loc.into(),
+                    loc,
                 },
                 self::S::set_reg_triv { reg, triv } => {
                     let triv = link_triv(triv, labels);
@@ -336,9 +862,10 @@ impl ParenX64 {
                     target::S::set_reg_binop_reg_loc {
                         reg,
                         binop,
-                        loc: loc.into(),
+                        loc,
                     }
                 },
+                self::S::divide { reg } => target::S::divide { reg },
                 self::S::with_label { s, .. } => {
                     let s = *s;
                     link_s(s, labels)
@@ -357,11 +884,16 @@ impl ParenX64 {
 
                     target::S::compare_reg_opand_jump_if {
                         reg,
-                        opand: opand.into(),
+                        opand,
                         relop,
                         pc_addr,
                     }
                 },
+                self::S::jump_if_overflow { label } => {
+                    let pc_addr = *labels.get(&label).unwrap();
+
+                    target::S::jump_if_overflow { pc_addr }
+                },
                 self::S::nop => target::S::nop,
             }
         }
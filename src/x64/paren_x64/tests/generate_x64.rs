@@ -190,3 +190,112 @@ L.finish.3:
 
     assert_eq!(actual, expected);
 }
+
+#[test]
+#[serial]
+fn shift_and_divide() {
+    let actual = source::ParenX64(source::P::begin(vec![
+        source::S::set_reg_binop_reg_loc {
+            reg: utils::Reg::r10,
+            binop: utils::Binop::arithmetic_shift_right,
+            loc: source::Loc::reg(utils::Reg::rcx),
+        },
+        source::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::r10,
+            binop: utils::Binop::bitwise_xor,
+            int32: 3,
+        },
+        source::S::divide {
+            reg: utils::Reg::r11,
+        },
+    ]))
+    .generate_x64();
+
+    let expected: String = "\tsar r10, cl
+\txor r10, 3
+\tcqo
+\tidiv r11"
+        .into();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn heap_addresses() {
+    let addr = |index| utils::Addr::heap {
+        base: utils::Reg::r12,
+        index,
+    };
+
+    let actual = source::ParenX64(source::P::begin(vec![
+        source::S::set_reg_loc {
+            reg: utils::Reg::rax,
+            loc: source::Loc::addr(addr(utils::Index::int32(-1))),
+        },
+        source::S::set_addr_int32 {
+            addr: addr(utils::Index::int32(7)),
+            int32: 0,
+        },
+        source::S::set_addr_trg {
+            addr: addr(utils::Index::reg(utils::Reg::rbx)),
+            trg: source::Trg::reg(utils::Reg::rax),
+        },
+    ]))
+    .generate_x64();
+
+    let expected: String = "\tmov rax, QWORD [r12 - 1]
+\tmov QWORD [r12 + 7], 0
+\tmov QWORD [r12 + rbx], rax"
+        .into();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn shift_by_loc_other_than_rcx() {
+    let actual = source::ParenX64(source::P::begin(vec![
+        source::S::set_reg_binop_reg_loc {
+            reg: utils::Reg::rcx,
+            binop: utils::Binop::shift_left,
+            loc: source::Loc::reg(utils::Reg::rbx),
+        },
+    ]))
+    .generate_x64();
+
+    let expected: String = "\tpush rcx
+\tpush rcx
+\tmov rcx, rbx
+\tshl QWORD [rsp], cl
+\tmov rcx, QWORD [rsp + 8]
+\tpop rcx
+\tadd rsp, 8"
+        .into();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn shift_count_is_masked() {
+    let actual = source::ParenX64(source::P::begin(vec![
+        source::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::rax,
+            binop: utils::Binop::shift_left,
+            int32: 65,
+        },
+        source::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::rax,
+            binop: utils::Binop::arithmetic_shift_right,
+            int32: -1,
+        },
+    ]))
+    .generate_x64();
+
+    let expected: String = "\tshl rax, 1
+\tsar rax, 63"
+        .into();
+
+    assert_eq!(actual, expected);
+}
@@ -0,0 +1,102 @@
+use serial_test::serial;
+
+use crate::utils;
+use crate::x64::paren_x64 as source;
+
+fn label(label: &str) -> utils::Label {
+    utils::Label(label.into())
+}
+
+fn with_label(label: &str, s: source::S) -> source::S {
+    source::S::with_label {
+        label: self::label(label),
+        s: Box::new(s),
+    }
+}
+
+fn jump(label: &str) -> source::S {
+    source::S::jump(source::Trg::label(self::label(label)))
+}
+
+fn set_rax(int64: i64) -> source::S {
+    source::S::set_reg_triv {
+        reg: utils::Reg::rax,
+        triv: source::Triv::int64(int64),
+    }
+}
+
+// Calls inc, which the library provides. Both units use the labels L.main.0
+// and L.inc.0, as if compiled separately.
+fn main_unit() -> source::Unit {
+    source::Unit {
+        name: "main",
+        exports: source::Symbols::default(),
+        imports: [("inc", label("L.inc.0"))].into_iter().collect(),
+        p: source::P::begin(vec![
+            with_label("L.main.0", set_rax(1)),
+            jump("L.inc.0"),
+            with_label("L.done", source::S::nop),
+        ]),
+    }
+}
+
+fn lib_unit() -> source::Unit {
+    source::Unit {
+        name: "lib",
+        exports: [("inc", label("L.inc.0"))].into_iter().collect(),
+        imports: source::Symbols::default(),
+        p: source::P::begin(vec![
+            with_label("L.main.0", set_rax(0)),
+            with_label("L.inc.0", source::S::set_reg_binop_reg_int32 {
+                reg: utils::Reg::rax,
+                binop: utils::Binop::plus,
+                int32: 1,
+            }),
+            jump("L.done"),
+            with_label("L.done", source::S::nop),
+        ]),
+    }
+}
+
+#[test]
+#[serial]
+fn namespaces_labels_and_resolves_imports() {
+    let actual = source::ParenX64::link_units(vec![main_unit(), lib_unit()]);
+
+    let expected = source::ParenX64(source::P::begin(vec![
+        with_label("L.main.main.0", set_rax(1)),
+        jump("L.lib.inc.0"),
+        jump("L.done"),
+        with_label("L.lib.main.0", set_rax(0)),
+        with_label("L.lib.inc.0", source::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::rax,
+            binop: utils::Binop::plus,
+            int32: 1,
+        }),
+        jump("L.done"),
+        with_label("L.done", source::S::nop),
+    ]));
+
+    assert_eq!(actual, Ok(expected));
+}
+
+#[test]
+#[serial]
+fn reports_undefined_symbols() {
+    let actual = source::ParenX64::link_units(vec![main_unit()]);
+
+    assert!(actual.is_err());
+}
+
+#[test]
+#[serial]
+fn reports_duplicate_symbols() {
+    let lib2 = source::Unit {
+        name: "lib2",
+        ..lib_unit()
+    };
+    let actual =
+        source::ParenX64::link_units(vec![main_unit(), lib_unit(), lib2]);
+
+    assert!(actual.is_err());
+}
@@ -1,3 +1,5 @@
 mod check_labels;
 mod generate_x64;
 mod link_paren_x84;
+mod link_units;
+mod peephole;
@@ -0,0 +1,178 @@
+use serial_test::serial;
+
+use crate::utils;
+use crate::x64::paren_x64 as source;
+
+#[test]
+#[serial]
+fn removes_redundant_instructions() {
+    let actual = source::ParenX64(source::P::begin(vec![
+        source::S::set_reg_loc {
+            reg: utils::Reg::rax,
+            loc: source::Loc::reg(utils::Reg::rax),
+        },
+        source::S::nop,
+        source::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::rbx,
+            binop: utils::Binop::plus,
+            int32: 0,
+        },
+        source::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::rbx,
+            binop: utils::Binop::multiply,
+            int32: 1,
+        },
+        source::S::set_reg_triv {
+            reg: utils::Reg::rax,
+            triv: source::Triv::int64(10),
+        },
+    ]))
+    .peephole()
+    .unwrap();
+
+    let expected =
+        source::ParenX64(source::P::begin(vec![source::S::set_reg_triv {
+            reg: utils::Reg::rax,
+            triv: source::Triv::int64(10),
+        }]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn forwards_store_to_reload() {
+    let addr = utils::Addr::frame {
+        fbp: utils::Reg::rbp,
+        disp_offset: 8,
+    };
+
+    let actual = source::ParenX64(source::P::begin(vec![
+        source::S::set_addr_trg {
+            addr: addr.clone(),
+            trg: source::Trg::reg(utils::Reg::r10),
+        },
+        source::S::set_reg_loc {
+            reg: utils::Reg::r10,
+            loc: source::Loc::addr(addr.clone()),
+        },
+        source::S::set_addr_trg {
+            addr: addr.clone(),
+            trg: source::Trg::reg(utils::Reg::r10),
+        },
+        source::S::set_reg_loc {
+            reg: utils::Reg::r11,
+            loc: source::Loc::addr(addr.clone()),
+        },
+    ]))
+    .peephole()
+    .unwrap();
+
+    let expected = source::ParenX64(source::P::begin(vec![
+        source::S::set_addr_trg {
+            addr: addr.clone(),
+            trg: source::Trg::reg(utils::Reg::r10),
+        },
+        source::S::set_addr_trg {
+            addr,
+            trg: source::Trg::reg(utils::Reg::r10),
+        },
+        source::S::set_reg_loc {
+            reg: utils::Reg::r11,
+            loc: source::Loc::reg(utils::Reg::r10),
+        },
+    ]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn keeps_labels_of_removed_instructions() {
+    utils::reset_all_indices();
+
+    let main = utils::Label::new_with_name("main");
+    let label = utils::Label::new_with_name("next");
+    let halt_label = utils::Label::halt_label();
+
+    let actual = source::ParenX64(source::P::begin(vec![
+        source::S::with_label {
+            label: main.clone(),
+            s: Box::new(source::S::nop),
+        },
+        source::S::jump(source::Trg::label(label.clone())),
+        source::S::with_label {
+            label: label.clone(),
+            s: Box::new(source::S::set_reg_triv {
+                reg: utils::Reg::rax,
+                triv: source::Triv::int64(10),
+            }),
+        },
+        source::S::jump(source::Trg::label(halt_label.clone())),
+        source::S::with_label {
+            label: halt_label.clone(),
+            s: Box::new(source::S::nop),
+        },
+    ]))
+    .peephole()
+    .unwrap();
+
+    utils::reset_all_indices();
+
+    let expected = source::ParenX64(source::P::begin(vec![
+        source::S::with_label {
+            label: main,
+            s: Box::new(source::S::nop),
+        },
+        source::S::with_label {
+            label,
+            s: Box::new(source::S::set_reg_triv {
+                reg: utils::Reg::rax,
+                triv: source::Triv::int64(10),
+            }),
+        },
+        source::S::with_label {
+            label: halt_label,
+            s: Box::new(source::S::nop),
+        },
+    ]));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[serial]
+fn removes_overflow_check_of_add_zero() {
+    let overflow = utils::Label::overflow_label();
+
+    let actual = source::ParenX64(source::P::begin(vec![
+        source::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::rbx,
+            binop: utils::Binop::plus,
+            int32: 0,
+        },
+        source::S::jump_if_overflow {
+            label: overflow.clone(),
+        },
+        source::S::with_label {
+            label: overflow,
+            s: Box::new(source::S::set_reg_triv {
+                reg: utils::Reg::rax,
+                triv: source::Triv::int64(10),
+            }),
+        },
+    ]))
+    .peephole()
+    .unwrap();
+
+    let expected =
+        source::ParenX64(source::P::begin(vec![source::S::with_label {
+            label: utils::Label::overflow_label(),
+            s: Box::new(source::S::set_reg_triv {
+                reg: utils::Reg::rax,
+                triv: source::Triv::int64(10),
+            }),
+        }]));
+
+    assert_eq!(actual, expected);
+}
@@ -35,6 +35,9 @@ pub enum S {
         binop: utils::Binop,
         loc: Loc,
     },
+    divide {
+        reg: utils::Reg,
+    },
     jump_trg(Trg),
     compare_reg_opand_jump_if {
         reg: utils::Reg,
@@ -42,6 +45,9 @@ pub enum S {
         relop: utils::Relop,
         pc_addr: utils::PcAddr,
     },
+    jump_if_overflow {
+        pc_addr: utils::PcAddr,
+    },
     nop,
 }
 
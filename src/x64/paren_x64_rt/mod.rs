@@ -2,7 +2,6 @@ pub mod data;
 #[cfg(test)]
 mod tests;
 
-use std::cmp::Ordering;
 use std::collections::HashMap;
 
 pub use self::data::*;
@@ -13,12 +12,22 @@ pub struct ParenX64Rt(pub self::P);
 impl ParenX64Rt {
     /// ### Purpose:
     /// Interpret the ParenX64Rt program as a value, returning the final value
-    /// of rax.
-    pub fn interp_loop(self) -> i64 {
+    /// of the return register of the target configuration.
+    ///
+    /// ### Notes:
+    /// Arithmetic follows `utils::Binop::eval`. As on x64, binops and
+    /// comparisons set an overflow flag, which is read by jump-if-overflow.
+    /// The heap pointer starts at address zero, and the heap is zero-filled.
+    /// A heap address wraps as on x64, and must lie below the heap pointer.
+    /// A register which has not been set reads as zero, since the division
+    /// lowering saves rax and rdx whether or not they hold a value.
+    pub fn interp_loop(self, config: &utils::TargetConfig) -> i64 {
         type RegEnv = HashMap<utils::Reg, i64>;
 
         type AddrEnv = HashMap<utils::Addr, i64>;
 
+        type HeapEnv = HashMap<i64, i64>;
+
         enum Control {
             next,
             jump { pc_addr: utils::PcAddr },
@@ -26,26 +35,30 @@ impl ParenX64Rt {
 
         let Self(p) = self;
 
-        fn interp_p(p: self::P) -> i64 {
+        fn interp_p(p: self::P, config: &utils::TargetConfig) -> i64 {
             let mut reg_env = RegEnv::default();
 
             let mut addr_env = AddrEnv::default();
 
-            let mut pc_addr = utils::PcAddr::default();
+            let mut heap_env = HeapEnv::default();
 
-            match p {
-                self::P::begin(ss) => {
-                    let max_pc_addr = ss.len();
+            reg_env.insert(config.heap_pointer, 0);
 
-                    loop {
-                        match pc_addr.cmp(&max_pc_addr) {
-                            Ordering::Less => (),
-                            Ordering::Equal | Ordering::Greater => break,
-                        };
+            let mut pc_addr = utils::PcAddr::default();
 
-                        let s = ss.get(pc_addr).unwrap();
+            let mut overflow = false;
 
-                        let control = interp_s(s, &mut reg_env, &mut addr_env);
+            match p {
+                self::P::begin(ss) => {
+                    while let Some(s) = ss.get(pc_addr) {
+                        let control = interp_s(
+                            s,
+                            &mut reg_env,
+                            &mut addr_env,
+                            &mut heap_env,
+                            &mut overflow,
+                            config.heap_pointer,
+                        );
                         match control {
                             Control::next => pc_addr += 1usize,
                             Control::jump {
@@ -54,8 +67,8 @@ impl ParenX64Rt {
                         }
                     }
 
-                    let return_reg = utils::Reg::current_return_reg();
-                    reg_env.get(&return_reg).map(i64::clone).unwrap()
+                    let return_reg = config.return_register;
+                    reg_env.get(&return_reg).copied().unwrap()
                 },
             }
         }
@@ -64,15 +77,33 @@ impl ParenX64Rt {
             s: &self::S,
             reg_env: &mut RegEnv,
             addr_env: &mut AddrEnv,
+            heap_env: &mut HeapEnv,
+            overflow: &mut bool,
+            heap_pointer: utils::Reg,
         ) -> Control {
             match s {
                 self::S::set_addr_int32 { addr, int32 } => {
-                    addr_env.insert(addr.clone(), *int32 as i64);
+                    let value = *int32 as i64;
+                    set_addr(
+                        addr,
+                        value,
+                        reg_env,
+                        addr_env,
+                        heap_env,
+                        heap_pointer,
+                    );
                     Control::next
                 },
                 self::S::set_addr_trg { addr, trg } => {
                     let value = get_from_trg(trg, reg_env);
-                    addr_env.insert(addr.clone(), value);
+                    set_addr(
+                        addr,
+                        value,
+                        reg_env,
+                        addr_env,
+                        heap_env,
+                        heap_pointer,
+                    );
                     Control::next
                 },
                 self::S::set_reg_triv { reg, triv } => {
@@ -81,26 +112,49 @@ impl ParenX64Rt {
                     Control::next
                 },
                 self::S::set_reg_loc { reg, loc } => {
-                    let value = get_from_loc(loc, reg_env, addr_env);
+                    let value = get_from_loc(
+                        loc,
+                        reg_env,
+                        addr_env,
+                        heap_env,
+                        heap_pointer,
+                    );
                     reg_env.insert(*reg, value);
                     Control::next
                 },
                 self::S::set_reg_binop_reg_int32 { reg, binop, int32 } => {
                     let value1 = get_from_reg(reg, reg_env);
                     let value2 = *int32 as i64;
-                    let value = bin_operate(binop, value1, value2);
+                    let value = bin_operate(binop, value1, value2, overflow);
 
                     reg_env.insert(*reg, value);
                     Control::next
                 },
                 self::S::set_reg_binop_reg_loc { reg, binop, loc } => {
                     let value1 = get_from_reg(reg, reg_env);
-                    let value2 = get_from_loc(loc, reg_env, addr_env);
-                    let value = bin_operate(binop, value1, value2);
+                    let value2 = get_from_loc(
+                        loc,
+                        reg_env,
+                        addr_env,
+                        heap_env,
+                        heap_pointer,
+                    );
+                    let value = bin_operate(binop, value1, value2, overflow);
 
                     reg_env.insert(*reg, value);
                     Control::next
                 },
+                self::S::divide { reg } => {
+                    let value1 = get_from_reg(&utils::Reg::rax, reg_env);
+                    let value2 = get_from_reg(reg, reg_env);
+                    let quotient = utils::Binop::quotient.eval(value1, value2);
+                    let remainder =
+                        utils::Binop::remainder.eval(value1, value2);
+
+                    reg_env.insert(utils::Reg::rax, quotient);
+                    reg_env.insert(utils::Reg::rdx, remainder);
+                    Control::next
+                },
                 self::S::jump_trg(trg) => {
                     let value: usize =
                         get_from_trg(trg, reg_env).try_into().unwrap();
@@ -114,25 +168,58 @@ impl ParenX64Rt {
                 } => {
                     let value1 = get_from_reg(reg, reg_env);
                     let value2 = get_from_opand(opand, reg_env);
+                    *overflow = value1.checked_sub(value2).is_none();
 
-                    let should_jump = rel_operate(relop, value1, value2);
+                    let should_jump = relop.eval(value1, value2);
                     let pc_addr = *pc_addr;
                     match should_jump {
                         true => Control::jump { pc_addr },
                         false => Control::next,
                     }
                 },
+                self::S::jump_if_overflow { pc_addr } => match *overflow {
+                    true => Control::jump { pc_addr: *pc_addr },
+                    false => Control::next,
+                },
                 self::S::nop => Control::next,
             }
         }
 
+        fn set_addr(
+            addr: &utils::Addr,
+            value: i64,
+            reg_env: &RegEnv,
+            addr_env: &mut AddrEnv,
+            heap_env: &mut HeapEnv,
+            heap_pointer: utils::Reg,
+        ) {
+            match addr {
+                utils::Addr::frame { .. } => {
+                    addr_env.insert(addr.clone(), value);
+                },
+                utils::Addr::heap { base, index } => {
+                    let address =
+                        heap_address(base, index, reg_env, heap_pointer);
+                    heap_env.insert(address, value);
+                },
+            }
+        }
+
         fn get_from_loc(
             loc: &self::Loc,
             reg_env: &RegEnv,
             addr_env: &AddrEnv,
+            heap_env: &HeapEnv,
+            heap_pointer: utils::Reg,
         ) -> i64 {
             match loc {
-                self::Loc::addr(addr) => get_from_addr(addr, addr_env),
+                self::Loc::addr(addr) => get_from_addr(
+                    addr,
+                    reg_env,
+                    addr_env,
+                    heap_env,
+                    heap_pointer,
+                ),
                 self::Loc::reg(reg) => get_from_reg(reg, reg_env),
             }
         }
@@ -145,11 +232,47 @@ impl ParenX64Rt {
         }
 
         fn get_from_reg(reg: &utils::Reg, reg_env: &RegEnv) -> i64 {
-            reg_env.get(reg).map(i64::clone).unwrap()
+            reg_env.get(reg).copied().unwrap_or_default()
         }
 
-        fn get_from_addr(addr: &utils::Addr, addr_env: &AddrEnv) -> i64 {
-            addr_env.get(addr).map(i64::clone).unwrap()
+        fn get_from_addr(
+            addr: &utils::Addr,
+            reg_env: &RegEnv,
+            addr_env: &AddrEnv,
+            heap_env: &HeapEnv,
+            heap_pointer: utils::Reg,
+        ) -> i64 {
+            match addr {
+                utils::Addr::frame { .. } => {
+                    addr_env.get(addr).copied().unwrap()
+                },
+                utils::Addr::heap { base, index } => {
+                    let address =
+                        heap_address(base, index, reg_env, heap_pointer);
+                    heap_env.get(&address).copied().unwrap_or_default()
+                },
+            }
+        }
+
+        fn heap_address(
+            base: &utils::Reg,
+            index: &utils::Index,
+            reg_env: &RegEnv,
+            heap_pointer: utils::Reg,
+        ) -> i64 {
+            let base = get_from_reg(base, reg_env);
+            let index = match index {
+                utils::Index::int32(int32) => *int32 as i64,
+                utils::Index::reg(reg) => get_from_reg(reg, reg_env),
+            };
+            let address = base.wrapping_add(index);
+            let end = get_from_reg(&heap_pointer, reg_env);
+            assert!(
+                (0..end).contains(&address),
+                "The heap address, '{}', is outside of the heap.",
+                address
+            );
+            address
         }
 
         fn get_from_triv(triv: &self::Triv, reg_env: &RegEnv) -> i64 {
@@ -166,24 +289,16 @@ impl ParenX64Rt {
             }
         }
 
-        fn bin_operate(binop: &utils::Binop, value1: i64, value2: i64) -> i64 {
-            match binop {
-                utils::Binop::plus => value1 + value2,
-                utils::Binop::multiply => value1 * value2,
-            }
-        }
-
-        fn rel_operate(relop: &utils::Relop, value1: i64, value2: i64) -> bool {
-            match relop {
-                utils::Relop::gt => value1 < value2,
-                utils::Relop::gte => value1 <= value2,
-                utils::Relop::lt => value1 > value2,
-                utils::Relop::lte => value1 >= value2,
-                utils::Relop::eq => value1 == value2,
-                utils::Relop::neq => value1 != value2,
-            }
+        fn bin_operate(
+            binop: &utils::Binop,
+            value1: i64,
+            value2: i64,
+            overflow: &mut bool,
+        ) -> i64 {
+            *overflow = binop.checked_eval(value1, value2).is_none();
+            binop.eval(value1, value2)
         }
 
-        interp_p(p)
+        interp_p(p, config)
     }
 }
@@ -1,5 +1,9 @@
 use serial_test::serial;
 
+use crate::imperative_abstractions;
+use crate::imperative_abstractions::values_lang;
+use crate::register_allocation;
+use crate::structured_control_flow;
 use crate::utils;
 use crate::x64::paren_x64_rt as source;
 
@@ -10,7 +14,7 @@ use crate::x64::paren_x64_rt as source;
 fn empty_program() {
     let program = source::ParenX64Rt(source::P::begin(vec![]));
 
-    let _ = program.interp_loop();
+    let _ = program.interp_loop(&utils::TargetConfig::default());
 }
 
 #[test]
@@ -23,7 +27,7 @@ fn single_halt() {
             source::Trg::pc_addr(1),
         )]));
 
-    let _ = program.interp_loop();
+    let _ = program.interp_loop(&utils::TargetConfig::default());
 }
 
 #[test]
@@ -37,7 +41,7 @@ fn basic() {
         source::S::nop,
     ]));
 
-    let result = program.interp_loop();
+    let result = program.interp_loop(&utils::TargetConfig::default());
 
     assert_eq!(result, 10);
 }
@@ -58,7 +62,7 @@ fn jump_over_set() {
         source::S::nop,
     ]));
 
-    let result = program.interp_loop();
+    let result = program.interp_loop(&utils::TargetConfig::default());
 
     assert_eq!(result, 30);
 }
@@ -83,7 +87,202 @@ fn jump_over_two_sets() {
         source::S::nop,
     ]));
 
-    let result = program.interp_loop();
+    let result = program.interp_loop(&utils::TargetConfig::default());
 
     assert_eq!(result, 10);
 }
+
+#[test]
+#[serial]
+fn divide_sets_rax_and_rdx() {
+    let program = source::ParenX64Rt(source::P::begin(vec![
+        source::S::set_reg_triv {
+            reg: utils::Reg::rax,
+            triv: source::Triv::int64(-17),
+        },
+        source::S::set_reg_triv {
+            reg: utils::Reg::rbx,
+            triv: source::Triv::int64(5),
+        },
+        source::S::divide {
+            reg: utils::Reg::rbx,
+        },
+        source::S::set_reg_binop_reg_int32 {
+            reg: utils::Reg::rax,
+            binop: utils::Binop::multiply,
+            int32: 100,
+        },
+        source::S::set_reg_binop_reg_loc {
+            reg: utils::Reg::rax,
+            binop: utils::Binop::minus,
+            loc: source::Loc::reg(utils::Reg::rdx),
+        },
+    ]));
+
+    let result = program.interp_loop(&utils::TargetConfig::default());
+
+    assert_eq!(result, -298);
+}
+
+#[test]
+#[serial]
+fn compare_jumps_when_relop_holds() {
+    let program = source::ParenX64Rt(source::P::begin(vec![
+        source::S::set_reg_triv {
+            reg: utils::Reg::rax,
+            triv: source::Triv::int64(1),
+        },
+        source::S::compare_reg_opand_jump_if {
+            reg: utils::Reg::rax,
+            opand: source::Opand::int64(0),
+            relop: utils::Relop::gt,
+            pc_addr: 3,
+        },
+        source::S::set_reg_triv {
+            reg: utils::Reg::rax,
+            triv: source::Triv::int64(2),
+        },
+        source::S::nop,
+    ]));
+
+    let result = program.interp_loop(&utils::TargetConfig::default());
+
+    assert_eq!(result, 1);
+}
+
+// Compiles the Values-lang value down to Paren-x64, and interprets it.
+#[test]
+#[serial]
+#[should_panic(expected = "outside of the heap")]
+fn heap_address_wraps_outside_of_heap() {
+    let program = source::ParenX64Rt(source::P::begin(vec![
+        source::S::set_reg_triv {
+            reg: utils::Reg::rbx,
+            triv: source::Triv::int64(i64::MAX),
+        },
+        source::S::set_reg_loc {
+            reg: utils::Reg::rax,
+            loc: source::Loc::addr(utils::Addr::heap {
+                base: utils::Reg::rbx,
+                index: utils::Index::int32(8),
+            }),
+        },
+    ]));
+
+    let _ = program.interp_loop(&utils::TargetConfig::default());
+}
+
+fn run(value: values_lang::Value) -> i64 {
+    run_tail(values_lang::Tail::value(value))
+}
+
+fn run_tail(tail: values_lang::Tail) -> i64 {
+    utils::reset_all_indices();
+
+    let config = utils::TargetConfig::default();
+    let options = utils::CompileOptions::default();
+    let p = values_lang::ValuesLang(values_lang::P::module {
+        lambdas: vec![],
+        tail,
+    });
+    imperative_abstractions::compile(p, &config, &options)
+        .and_then(|p| register_allocation::compile(p, &config, &options))
+        .and_then(|p| structured_control_flow::compile(p, &config, &options))
+        .unwrap()
+        .link_paren_x64()
+        .interp_loop(&config)
+}
+
+fn primop(
+    primop: utils::Primop,
+    trivs: Vec<values_lang::Triv>,
+) -> values_lang::Value {
+    values_lang::Value::primop_trivs { primop, trivs }
+}
+
+fn name(name: utils::Name) -> values_lang::Triv {
+    values_lang::Triv::name(name.into())
+}
+
+fn fixnum(int64: i64) -> i64 {
+    utils::Datum::fixnum(int64).repr()
+}
+
+#[test]
+#[serial]
+fn read_back_cons() {
+    let int64 = values_lang::Triv::int64;
+    let pair = |accessor| values_lang::Value::r#let {
+        bindings: vec![(
+            "p".into(),
+            primop(utils::Primop::cons, vec![int64(1), int64(2)]),
+        )],
+        value: Box::new(primop(accessor, vec![name("p")])),
+    };
+
+    assert_eq!(run(pair(utils::Primop::car)), fixnum(1));
+    assert_eq!(run(pair(utils::Primop::cdr)), fixnum(2));
+}
+
+#[test]
+#[serial]
+fn read_back_vector_ref() {
+    let int64 = values_lang::Triv::int64;
+    let vector = |index| values_lang::Value::r#let {
+        bindings: vec![(
+            "v".into(),
+            primop(utils::Primop::make_vector, vec![int64(2)]),
+        )],
+        value: Box::new(values_lang::Value::begin {
+            values: vec![primop(utils::Primop::vector_set, vec![
+                name("v"),
+                int64(1),
+                int64(7),
+            ])],
+            value: Box::new(primop(utils::Primop::vector_ref, vec![
+                name("v"),
+                int64(index),
+            ])),
+        }),
+    };
+
+    assert_eq!(run(vector(1)), fixnum(7));
+    assert_eq!(run(vector(0)), fixnum(0));
+    assert_eq!(
+        run(vector(2)),
+        utils::RuntimeError::out_of_bounds.code()
+    );
+    assert_eq!(
+        run(vector(-1)),
+        utils::RuntimeError::out_of_bounds.code()
+    );
+}
+
+#[test]
+#[serial]
+fn closure_captures_free_variable() {
+    // (let ([y 5]) (let ([f (lambda (x) (+ x y))]) (f 3)))
+    let int64 = values_lang::Triv::int64;
+    let lambda = values_lang::Value::lambda {
+        args: vec!["x".into()],
+        tail: Box::new(values_lang::Tail::value(
+            values_lang::Value::binop_triv_triv {
+                binop: utils::Binop::plus,
+                triv1: name("x"),
+                triv2: name("y"),
+            },
+        )),
+    };
+    let tail = values_lang::Tail::r#let {
+        bindings: vec![("y".into(), values_lang::Value::triv(int64(5)))],
+        tail: Box::new(values_lang::Tail::r#let {
+            bindings: vec![("f".into(), lambda)],
+            tail: Box::new(values_lang::Tail::call {
+                name: "f".into(),
+                args: vec![int64(3)],
+            }),
+        }),
+    };
+
+    assert_eq!(run_tail(tail), fixnum(8));
+}